@@ -1,13 +1,49 @@
-use crate::core::cache;
 use clap::Subcommand;
+use notarai::cache::CacheExport;
+use notarai::{Error, Project};
 use std::path::{Path, PathBuf};
 
 #[derive(Subcommand)]
 pub enum CacheAction {
-    /// Delete the cache database
+    /// Delete all reconciliation records from the cache
     Clear,
     /// Show cache status
     Status,
+    /// Record files as reconciled against a spec
+    Mark {
+        /// Spec the files were reconciled against
+        #[arg(long)]
+        spec: String,
+        /// Files to record, relative to the project root
+        #[arg(required = true)]
+        files: Vec<String>,
+    },
+    /// Report which files changed since they were last marked for a spec
+    Check {
+        /// Spec whose records to check against
+        #[arg(long)]
+        spec: String,
+        /// Files to check, relative to the project root
+        #[arg(required = true)]
+        files: Vec<String>,
+        /// Rehash every file instead of trusting size and mtime
+        #[arg(long)]
+        verify: bool,
+    },
+    /// Remove records for files and specs that no longer exist
+    Prune,
+    /// Write the cache to a portable JSON file
+    Export {
+        /// Destination file
+        path: PathBuf,
+    },
+    /// Load records from a file written by `cache export`
+    Import {
+        /// File to import
+        path: PathBuf,
+    },
+    /// Seed the cache from reconciliation_state.json for files that still match
+    Restore,
 }
 
 pub fn run(action: CacheAction) -> i32 {
@@ -15,25 +51,29 @@ pub fn run(action: CacheAction) -> i32 {
     match action {
         CacheAction::Status => run_status(&root),
         CacheAction::Clear => run_clear(&root),
+        CacheAction::Mark { spec, files } => run_mark(&root, &spec, &files),
+        CacheAction::Check {
+            spec,
+            files,
+            verify,
+        } => run_check(&root, &spec, &files, verify),
+        CacheAction::Prune => run_prune(&root),
+        CacheAction::Export { path } => run_export(&root, &path),
+        CacheAction::Import { path } => run_import(&root, &path),
+        CacheAction::Restore => run_restore(&root),
     }
 }
 
 fn run_status(root: &Path) -> i32 {
-    let db_path = cache::db_path(root);
-    if !db_path.exists() {
-        println!("Cache DB: {} (not initialized)", db_path.display());
-        println!("Entries: 0");
-        return 0;
-    }
-    let conn = match cache::open_cache_db(root) {
-        Ok(c) => c,
-        Err(e) => {
-            eprintln!("Error: {e}");
-            return 1;
+    let project = Project::open(root);
+    let db_path = project.cache_path();
+    match project.cache_status() {
+        Ok(None) => {
+            println!("Cache DB: {} (not initialized)", db_path.display());
+            println!("Entries: 0");
+            0
         }
-    };
-    match cache::status(&conn) {
-        Ok((count, newest)) => {
+        Ok(Some((count, newest))) => {
             println!("Cache DB: {}", db_path.display());
             println!("Entries: {count}");
             if let Some(ts) = newest {
@@ -49,15 +89,169 @@ fn run_status(root: &Path) -> i32 {
 }
 
 fn run_clear(root: &Path) -> i32 {
-    let db_path = cache::db_path(root);
-    if db_path.exists() {
-        if let Err(e) = std::fs::remove_file(&db_path) {
-            eprintln!("Error: could not delete cache DB: {e}");
-            return 1;
+    match Project::open(root).clear_cache() {
+        Ok(None) => {
+            println!("Cache not initialized");
+            0
+        }
+        Ok(Some(_)) => {
+            println!("Cache cleared");
+            0
+        }
+        Err(e) => {
+            eprintln!("Error: could not clear cache: {e}");
+            1
+        }
+    }
+}
+
+fn run_mark(root: &Path, spec: &str, files: &[String]) -> i32 {
+    match Project::open(root).mark_reconciled(spec, files) {
+        Ok(marked) => {
+            for (path, error) in &marked.failed {
+                eprintln!("Warning: could not hash {path}: {error}");
+            }
+            println!(
+                "Marked {} file(s) for {}",
+                marked.updated,
+                notarai::normalize_rel(spec)
+            );
+            0
+        }
+        Err(e) => {
+            eprintln!("Error: {e}");
+            1
         }
-        println!("Cache cleared");
+    }
+}
+
+/// Print `changed` or `unchanged` for each file. Exits 1 if any file changed,
+/// 2 on error.
+fn run_check(root: &Path, spec: &str, files: &[String], verify: bool) -> i32 {
+    let files = match Project::open(root).changed_files(spec, files, verify) {
+        Ok(files) => files,
+        Err(e) => {
+            eprintln!("Error: {e}");
+            return 2;
+        }
+    };
+    for (rel, changed) in &files {
+        let label = if *changed { "changed" } else { "unchanged" };
+        println!("{label:<9}  {rel}");
+    }
+    if files.iter().any(|(_, changed)| *changed) {
+        1
     } else {
-        println!("Cache not initialized");
+        0
+    }
+}
+
+fn run_prune(root: &Path) -> i32 {
+    match Project::open(root).prune_cache() {
+        Ok(None) => {
+            println!("Cache not initialized");
+            0
+        }
+        Ok(Some((files, specs))) => {
+            println!("Pruned {files} record(s) and {specs} spec(s)");
+            0
+        }
+        Err(e) => {
+            eprintln!("Error: {e}");
+            1
+        }
     }
+}
+
+fn run_export(root: &Path, path: &Path) -> i32 {
+    let export = match Project::open(root).export_cache() {
+        Ok(e) => e,
+        Err(e) => {
+            eprintln!("Error: {e}");
+            return 1;
+        }
+    };
+    let json = match serde_json::to_string_pretty(&export) {
+        Ok(j) => j,
+        Err(e) => {
+            eprintln!("Error: could not serialize cache: {e}");
+            return 1;
+        }
+    };
+    if let Err(e) = std::fs::write(path, json + "\n") {
+        eprintln!("Error: could not write {}: {e}", path.display());
+        return 1;
+    }
+    let records: usize = export.specs.iter().map(|s| s.files.len()).sum();
+    println!(
+        "Exported {records} record(s) for {} spec(s) to {}",
+        export.specs.len(),
+        path.display()
+    );
     0
 }
+
+fn run_import(root: &Path, path: &Path) -> i32 {
+    let content = match std::fs::read_to_string(path) {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("Error: could not read {}: {e}", path.display());
+            return 1;
+        }
+    };
+    let export: CacheExport = match serde_json::from_str(&content) {
+        Ok(e) => e,
+        Err(e) => {
+            eprintln!("Error: invalid cache export: {e}");
+            return 1;
+        }
+    };
+    match Project::open(root).import_cache(&export) {
+        Ok(count) => {
+            println!(
+                "Imported {count} record(s) for {} spec(s)",
+                export.specs.len()
+            );
+            0
+        }
+        Err(e) => {
+            eprintln!("Error: {e}");
+            1
+        }
+    }
+}
+
+fn run_restore(root: &Path) -> i32 {
+    match Project::open(root).restore_cache() {
+        Ok(summary) => {
+            println!(
+                "Restored {} record(s) for {} spec(s)",
+                summary.files, summary.specs
+            );
+            if summary.stale_files > 0 {
+                println!(
+                    "Skipped {} file(s) changed since they were reconciled",
+                    summary.stale_files
+                );
+            }
+            if summary.stale_specs > 0 {
+                println!(
+                    "Skipped {} spec(s) changed or removed since the snapshot",
+                    summary.stale_specs
+                );
+            }
+            if summary.kept_specs > 0 {
+                println!("Kept local records for {} spec(s)", summary.kept_specs);
+            }
+            0
+        }
+        Err(Error::MissingState) => {
+            eprintln!("Error: no reconciliation state found; nothing to restore");
+            1
+        }
+        Err(e) => {
+            eprintln!("Error: {e}");
+            1
+        }
+    }
+}
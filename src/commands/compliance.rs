@@ -0,0 +1,147 @@
+use clap::Subcommand;
+use notarai::compliance::{self, Change, FrameworkRows, Removal};
+use notarai::{LoadedSpec, Project};
+use std::path::PathBuf;
+
+#[derive(Subcommand)]
+pub enum ComplianceAction {
+    /// Print the framework, control and satisfying-statement matrix, flagging unsatisfied controls
+    Report {
+        /// Spec files to report on (default: every spec with compliance frameworks)
+        specs: Vec<String>,
+        /// Print the matrix as JSON instead of Markdown
+        #[arg(long)]
+        json: bool,
+    },
+    /// Fail when an invariant or constraint cited by a compliance control was deleted or reworded since a git revision
+    Check {
+        /// Spec files to check (default: every spec with compliance frameworks at the base revision)
+        specs: Vec<String>,
+        /// Git revision to compare against
+        #[arg(long, default_value = "main")]
+        base: String,
+        /// Git revision to check (default: the working tree)
+        #[arg(long)]
+        head: Option<String>,
+        /// Print the findings as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+pub fn run(action: ComplianceAction) -> i32 {
+    let root = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+    let project = Project::open(root);
+
+    match action {
+        ComplianceAction::Report { specs, json } => report(&project, &specs, json),
+        ComplianceAction::Check {
+            specs,
+            base,
+            head,
+            json,
+        } => check(&project, &specs, &base, head.as_deref(), json),
+    }
+}
+
+fn report(project: &Project, specs: &[String], json: bool) -> i32 {
+    let loaded = if specs.is_empty() {
+        project.load_specs().map(|all| {
+            all.into_iter()
+                .filter(|l| compliance::has_frameworks(&l.spec))
+                .collect()
+        })
+    } else {
+        specs.iter().map(|s| project.load_spec(s)).collect()
+    };
+    let loaded: Vec<LoadedSpec> = match loaded {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            eprintln!("Error: {e}");
+            return 1;
+        }
+    };
+
+    let matrices: Vec<(String, Vec<FrameworkRows>)> = loaded
+        .iter()
+        .map(|l| (l.path.clone(), compliance::matrix(&l.spec)))
+        .collect();
+    if json {
+        let report: Vec<serde_json::Value> = matrices
+            .iter()
+            .map(|(path, frameworks)| {
+                serde_json::json!({"spec_path": path, "frameworks": frameworks})
+            })
+            .collect();
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&report).expect("JSON serialization")
+        );
+        return 0;
+    }
+    if matrices.is_empty() {
+        println!("No specs with compliance frameworks.");
+        return 0;
+    }
+    print!("{}", compliance::to_markdown(&matrices));
+    0
+}
+
+/// Exits 0 when no cited statement was removed, 1 when one was, 2 on error.
+fn check(project: &Project, specs: &[String], base: &str, head: Option<&str>, json: bool) -> i32 {
+    let reports = match project.compliance_removals(specs, base, head) {
+        Ok(reports) => reports,
+        Err(e) => {
+            eprintln!("Error: {e}");
+            return 2;
+        }
+    };
+    let code = if reports.iter().all(|(_, r)| r.is_empty()) {
+        0
+    } else {
+        1
+    };
+
+    if json {
+        let report: Vec<serde_json::Value> = reports
+            .iter()
+            .map(|(path, findings)| serde_json::json!({"spec_path": path, "findings": findings}))
+            .collect();
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&report).expect("JSON serialization")
+        );
+        return code;
+    }
+
+    if reports.is_empty() {
+        println!("No specs with compliance frameworks at {base}.");
+        return code;
+    }
+    for (path, findings) in &reports {
+        if findings.is_empty() {
+            println!("PASS {path}");
+            continue;
+        }
+        println!("FAIL {path}");
+        for removal in findings {
+            print_removal(removal);
+        }
+    }
+    code
+}
+
+fn print_removal(removal: &Removal) {
+    let subject = match &removal.control {
+        Some(control) => format!("{} {control}", removal.framework),
+        None => removal.framework.clone(),
+    };
+    let kind = removal.kind.as_str();
+    match (removal.change, &removal.now) {
+        (Change::Reworded, Some(now)) => println!(
+            "  - [{subject}] {kind} reworded: '{}' -> '{now}'",
+            removal.text
+        ),
+        _ => println!("  - [{subject}] {kind} deleted: '{}'", removal.text),
+    }
+}
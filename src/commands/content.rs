@@ -0,0 +1,224 @@
+use clap::Subcommand;
+use notarai::content;
+use notarai::evidence::IssueKind;
+use notarai::{LoadedSpec, Project};
+use std::path::PathBuf;
+
+#[derive(Subcommand)]
+pub enum ContentAction {
+    /// Check the structure of content sections: unknown or unreachable sections, depends_on cycles and ordering, dangling content_ref paths
+    Check {
+        /// Spec files to check (default: every spec with content sections)
+        specs: Vec<String>,
+        /// Print the findings as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Check that evidence sources exist and are governed, and list claims whose source changed since the last reconciliation
+    Evidence {
+        /// Spec files to check (default: every spec with evidence sources)
+        specs: Vec<String>,
+        /// Rehash every cached source instead of trusting unchanged size and mtime
+        #[arg(long)]
+        verify: bool,
+        /// Print the reports as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Print a spec's content sections as a Mermaid flowchart
+    Graph {
+        /// Path to the spec file
+        spec: String,
+    },
+}
+
+pub fn run(action: ContentAction) -> i32 {
+    let root = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+    let project = Project::open(root);
+
+    match action {
+        ContentAction::Check { specs, json } => check(&project, &specs, json),
+        ContentAction::Evidence {
+            specs,
+            verify,
+            json,
+        } => check_evidence(&project, &specs, verify, json),
+        ContentAction::Graph { spec } => graph(&project, &spec),
+    }
+}
+
+/// Exits 0 when no spec has findings, 1 when any does, 2 on error.
+fn check(project: &Project, specs: &[String], json: bool) -> i32 {
+    let loaded = if specs.is_empty() {
+        project.load_specs().map(|all| {
+            all.into_iter()
+                .filter(|l| {
+                    l.spec
+                        .content
+                        .as_ref()
+                        .is_some_and(|c| !c.sections.is_empty())
+                })
+                .collect()
+        })
+    } else {
+        specs.iter().map(|s| project.load_spec(s)).collect()
+    };
+    let loaded: Vec<LoadedSpec> = match loaded {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            eprintln!("Error: {e}");
+            return 2;
+        }
+    };
+
+    let reports: Vec<(String, Vec<content::Finding>)> = loaded
+        .iter()
+        .map(|l| {
+            let findings = l
+                .spec
+                .content
+                .as_ref()
+                .map(|c| content::analyze(c, project.root()))
+                .unwrap_or_default();
+            (l.path.clone(), findings)
+        })
+        .collect();
+    let code = if reports.iter().all(|(_, f)| f.is_empty()) {
+        0
+    } else {
+        1
+    };
+
+    if json {
+        let report: Vec<serde_json::Value> = reports
+            .iter()
+            .map(|(path, findings)| serde_json::json!({"spec_path": path, "findings": findings}))
+            .collect();
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&report).expect("JSON serialization")
+        );
+        return code;
+    }
+
+    if reports.is_empty() {
+        println!("No specs with content sections.");
+        return code;
+    }
+    for (path, findings) in &reports {
+        if findings.is_empty() {
+            println!("PASS {path}");
+            continue;
+        }
+        println!("FAIL {path}");
+        for finding in findings {
+            println!("  - [{}] {}", finding.section, finding.message);
+        }
+    }
+    code
+}
+
+/// Exits 0 when every evidence source is sound and unchanged, 1 otherwise,
+/// 2 on error.
+fn check_evidence(project: &Project, specs: &[String], verify: bool, json: bool) -> i32 {
+    let loaded: notarai::Result<Vec<LoadedSpec>> = if specs.is_empty() {
+        project.load_specs().map(|all| {
+            all.into_iter()
+                .filter(|l| {
+                    l.spec
+                        .content
+                        .iter()
+                        .flat_map(|c| &c.sections)
+                        .flat_map(|s| &s.evidence)
+                        .any(|e| e.source.is_some())
+                })
+                .collect()
+        })
+    } else {
+        specs.iter().map(|s| project.load_spec(s)).collect()
+    };
+    let reports = loaded.and_then(|loaded| {
+        let reports = project.check_evidence(&loaded, verify)?;
+        Ok(loaded
+            .into_iter()
+            .map(|l| l.path)
+            .zip(reports)
+            .collect::<Vec<_>>())
+    });
+    let reports = match reports {
+        Ok(reports) => reports,
+        Err(e) => {
+            eprintln!("Error: {e}");
+            return 2;
+        }
+    };
+    let code = if reports.iter().all(|(_, r)| r.is_clean()) {
+        0
+    } else {
+        1
+    };
+
+    if json {
+        let report: Vec<serde_json::Value> = reports
+            .iter()
+            .map(|(path, report)| {
+                serde_json::json!({
+                    "spec_path": path,
+                    "issues": report.issues,
+                    "changed_claims": report.changed_claims,
+                })
+            })
+            .collect();
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&report).expect("JSON serialization")
+        );
+        return code;
+    }
+
+    if reports.is_empty() {
+        println!("No specs with evidence sources.");
+        return code;
+    }
+    for (path, report) in &reports {
+        if report.is_clean() {
+            println!("PASS {path}");
+            continue;
+        }
+        println!("FAIL {path}");
+        for issue in &report.issues {
+            let problem = match issue.kind {
+                IssueKind::MissingSource => "does not exist",
+                IssueKind::UngovernedSource => "is not governed by any spec",
+            };
+            println!("  - [{}] source {} {problem}", issue.section, issue.source);
+        }
+        for claim in &report.changed_claims {
+            println!(
+                "  - [{}] {} changed: '{}'",
+                claim.section, claim.source, claim.claim
+            );
+        }
+    }
+    code
+}
+
+fn graph(project: &Project, spec: &str) -> i32 {
+    let loaded = match project.load_spec(spec) {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            eprintln!("Error: {e}");
+            return 1;
+        }
+    };
+    match &loaded.spec.content {
+        Some(c) if !c.sections.is_empty() => {
+            print!("{}", content::to_mermaid(c));
+            0
+        }
+        _ => {
+            eprintln!("Error: {} has no content sections", loaded.path);
+            1
+        }
+    }
+}
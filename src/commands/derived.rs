@@ -0,0 +1,102 @@
+use clap::Subcommand;
+use notarai::Project;
+use notarai::derived::{DerivedStatus, Staleness};
+use std::path::{Path, PathBuf};
+
+#[derive(Subcommand)]
+pub enum DerivedAction {
+    /// Report derived artifacts whose pipeline inputs changed since they were produced
+    Check {
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Record the current inputs of every pipeline step's existing outputs, without running the steps
+    Mark {
+        /// Spec files whose pipeline steps to record (default: every spec)
+        specs: Vec<String>,
+    },
+}
+
+pub fn run(action: DerivedAction) -> i32 {
+    let root = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+
+    match action {
+        DerivedAction::Check { json } => check(&root, json),
+        DerivedAction::Mark { specs } => mark(&root, &specs),
+    }
+}
+
+/// Exits 0 when every derived artifact is fresh, 1 otherwise, 2 on error.
+fn check(root: &Path, json: bool) -> i32 {
+    let statuses = Project::open(root).derived_status();
+    let statuses = match statuses {
+        Ok(statuses) => statuses,
+        Err(e) => {
+            eprintln!("Error: {e}");
+            return 2;
+        }
+    };
+    let code = if statuses.iter().all(|s| s.status == Staleness::Fresh) {
+        0
+    } else {
+        1
+    };
+
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&statuses).expect("JSON serialization")
+        );
+        return code;
+    }
+    if statuses.is_empty() {
+        println!("No derived artifacts.");
+        return code;
+    }
+    for status in &statuses {
+        print_status(status);
+    }
+    if statuses.iter().any(|s| s.status == Staleness::Unrecorded) {
+        eprintln!(
+            "hint: run `notarai pipeline run <spec>` to rebuild, or `notarai derived mark` if the outputs are up to date"
+        );
+    }
+    code
+}
+
+fn print_status(status: &DerivedStatus) {
+    let step = status
+        .step
+        .as_deref()
+        .map(|s| format!(" (step {s})"))
+        .unwrap_or_default();
+    match status.status {
+        Staleness::Fresh => println!("fresh       {}{step}", status.path),
+        Staleness::Stale => {
+            println!("STALE       {}{step}", status.path);
+            for input in &status.changed_inputs {
+                println!("  changed: {input}");
+            }
+        }
+        Staleness::Unrecorded => println!("unrecorded  {}{step}", status.path),
+        Staleness::Missing => println!("missing     {}", status.path),
+        Staleness::Unmapped => println!(
+            "unmapped    {} (no pipeline step with an input declares it as output)",
+            status.path
+        ),
+    }
+}
+
+fn mark(root: &Path, specs: &[String]) -> i32 {
+    match Project::open(root).record_derived_inputs(specs) {
+        Ok(count) => {
+            println!("Recorded inputs for {count} output(s)");
+            0
+        }
+        Err(e) => {
+            eprintln!("Error: {e}");
+            1
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use clap::Subcommand;
+use notarai::feedback::{self, MetricResult, MetricStatus, Report};
+use notarai::spec::{Priority, TriggerAction};
+use notarai::{LoadedSpec, Project};
+use std::path::PathBuf;
+
+#[derive(Subcommand)]
+pub enum FeedbackAction {
+    /// Evaluate feedback metrics from their local CSV or JSON sources and report the triggers that fire
+    Check {
+        /// Spec files to check (default: every spec with feedback metrics)
+        specs: Vec<String>,
+        /// Print the reports as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+pub fn run(action: FeedbackAction) -> i32 {
+    let root = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+    let project = Project::open(root);
+
+    match action {
+        FeedbackAction::Check { specs, json } => check(&project, &specs, json),
+    }
+}
+
+/// Exits 0 when no trigger fired, 1 when one did, 2 on error (unreadable
+/// spec, or a trigger that could not be evaluated).
+fn check(project: &Project, specs: &[String], json: bool) -> i32 {
+    let loaded = if specs.is_empty() {
+        project.load_specs().map(|all| {
+            all.into_iter()
+                .filter(|l| {
+                    l.spec
+                        .feedback
+                        .as_ref()
+                        .is_some_and(|f| !f.metrics.is_empty())
+                })
+                .collect()
+        })
+    } else {
+        specs.iter().map(|s| project.load_spec(s)).collect()
+    };
+    let loaded: Vec<LoadedSpec> = match loaded {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            eprintln!("Error: {e}");
+            return 2;
+        }
+    };
+
+    let reports: Vec<Report> = loaded.iter().map(|l| project.check_feedback(l)).collect();
+    let code = if reports.iter().any(|r| !r.errors.is_empty()) {
+        2
+    } else if reports.iter().any(|r| !r.triggered.is_empty()) {
+        1
+    } else {
+        0
+    };
+
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&reports).expect("JSON serialization")
+        );
+        return code;
+    }
+
+    if reports.is_empty() {
+        println!("No specs with feedback metrics.");
+        return code;
+    }
+    for report in &reports {
+        println!("{}", report.spec_path);
+        for metric in &report.metrics {
+            print_metric(metric);
+        }
+        for t in &report.triggered {
+            let action = match t.action {
+                TriggerAction::Reconcile => "reconcile",
+                TriggerAction::Warn => "warn",
+                TriggerAction::Notify => "notify",
+            };
+            let priority = match t.priority {
+                Priority::Low => "low",
+                Priority::Medium => "medium",
+                Priority::High => "high",
+            };
+            println!(
+                "  TRIGGERED    {action} [{priority}] {} {}: {}",
+                t.metric,
+                feedback::operator_name(t.operator),
+                t.message
+            );
+        }
+        for error in &report.errors {
+            println!("  error        {error}");
+        }
+    }
+    code
+}
+
+fn print_metric(metric: &MetricResult) {
+    let status = match metric.status {
+        MetricStatus::Pass => "pass",
+        MetricStatus::Fail => "FAIL",
+        MetricStatus::Unknown => "unknown",
+        MetricStatus::Unavailable => "unavailable",
+    };
+    let Some(value) = metric.value else {
+        let reason = metric.message.as_deref().unwrap_or_default();
+        println!("  {status:<12} {} ({reason})", metric.name);
+        return;
+    };
+    match &metric.threshold {
+        Some(threshold) => println!("  {status:<12} {} = {value} ({threshold})", metric.name),
+        None => println!("  {status:<12} {} = {value}", metric.name),
+    }
+}
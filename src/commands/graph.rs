@@ -0,0 +1,41 @@
+use notarai::Project;
+use notarai::graph::Format;
+use std::path::{Path, PathBuf};
+
+/// Which part of the graph to print.
+pub enum Selection {
+    All,
+    /// A spec and its `subsystems` descendants.
+    Subtree(String),
+    /// A spec and every spec it references, transitively.
+    ReachableFrom(String),
+}
+
+pub fn run(format: Format, artifacts: bool, selection: Selection) -> i32 {
+    let root = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+    match render(&root, format, artifacts, selection) {
+        Ok(out) => {
+            print!("{out}");
+            0
+        }
+        Err(e) => {
+            eprintln!("Error: {e}");
+            1
+        }
+    }
+}
+
+fn render(
+    root: &Path,
+    format: Format,
+    artifacts: bool,
+    selection: Selection,
+) -> notarai::Result<String> {
+    let graph = Project::open(root).graph(artifacts)?;
+    let graph = match selection {
+        Selection::All => graph,
+        Selection::Subtree(spec) => graph.filter(&spec, true)?,
+        Selection::ReachableFrom(spec) => graph.filter(&spec, false)?,
+    };
+    Ok(graph.render(format))
+}
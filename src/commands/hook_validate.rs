@@ -1,4 +1,4 @@
-use crate::core::validator;
+use notarai::validate_spec;
 use std::fs;
 use std::io::{self, Read};
 use std::path::Path;
@@ -64,7 +64,7 @@ pub fn process_hook_input(input: &str, cwd: &Path) -> HookResult {
         }
     };
 
-    let result = validator::validate_spec(&content);
+    let result = validate_spec(&content);
 
     if result.valid {
         HookResult {
@@ -186,6 +186,8 @@ pub fn run(project_root: Option<&Path>) -> i32 {
     setup_skill("notarai-bootstrap", BOOTSTRAP_MD, &claude_dir);
     setup_claude_context(&root);
     setup_gitignore(&root);
+    setup_gitattributes(&root);
+    setup_merge_driver(&root);
     setup_mcp_json(&root);
 
     crate::commands::update::passive_update_hint();
@@ -311,6 +313,85 @@ fn setup_gitignore(project_dir: &Path) {
     println!("Added .notarai/.cache/ to .gitignore");
 }
 
+/// Name of the git merge driver for the reconciliation state file.
+const MERGE_DRIVER: &str = "notarai-state";
+
+fn setup_gitattributes(project_dir: &Path) {
+    let attributes_path = project_dir.join(".gitattributes");
+    let entry = format!(".notarai/reconciliation_state.json merge={MERGE_DRIVER}");
+
+    let existing = if attributes_path.exists() {
+        match fs::read_to_string(&attributes_path) {
+            Ok(c) => c,
+            Err(e) => {
+                eprintln!("Warning: could not read .gitattributes: {e}");
+                return;
+            }
+        }
+    } else {
+        String::new()
+    };
+
+    if existing.lines().any(|line| line == entry) {
+        println!("State merge driver already in .gitattributes");
+        return;
+    }
+
+    let mut content = existing;
+    if !content.is_empty() && !content.ends_with('\n') {
+        content.push('\n');
+    }
+    content.push_str(&entry);
+    content.push('\n');
+
+    if let Err(e) = fs::write(&attributes_path, content) {
+        eprintln!("Warning: could not update .gitattributes: {e}");
+        return;
+    }
+
+    println!("Added state merge driver to .gitattributes");
+}
+
+/// Register `notarai state merge` as the `notarai-state` merge driver in the
+/// repository's local git config. Git config is not committed, so each clone
+/// needs this once; `notarai init` is safe to re-run for that.
+fn setup_merge_driver(project_dir: &Path) {
+    let git = |args: &[&str]| {
+        std::process::Command::new("git")
+            .args(args)
+            .current_dir(project_dir)
+            .output()
+            .ok()
+    };
+    let in_repo = git(&["rev-parse", "--git-dir"]).is_some_and(|o| o.status.success());
+    if !in_repo {
+        println!("Not a git repository; skipped registering the state merge driver");
+        return;
+    }
+
+    let driver = "notarai state merge %O %A %B";
+    let driver_key = format!("merge.{MERGE_DRIVER}.driver");
+    let current = git(&["config", "--local", "--get", &driver_key])
+        .filter(|o| o.status.success())
+        .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string());
+    if current.as_deref() == Some(driver) {
+        println!("State merge driver already registered in .git/config");
+        return;
+    }
+
+    let name_key = format!("merge.{MERGE_DRIVER}.name");
+    for (key, value) in [
+        (name_key.as_str(), "NotarAI reconciliation state"),
+        (driver_key.as_str(), driver),
+    ] {
+        if !git(&["config", "--local", key, value]).is_some_and(|o| o.status.success()) {
+            eprintln!("Warning: could not set {key} in .git/config");
+            return;
+        }
+    }
+    println!("Registered state merge driver in .git/config");
+}
+
 fn setup_mcp_json(project_root: &Path) {
     let mcp_path = project_root.join(".mcp.json");
 
@@ -0,0 +1,57 @@
+use notarai::{Project, time};
+use std::path::{Path, PathBuf};
+
+pub fn run(limit: usize, files: bool, json: bool) -> i32 {
+    let root = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+    show(&root, limit, files, json)
+}
+
+fn show(root: &Path, limit: usize, files: bool, json: bool) -> i32 {
+    let history = match Project::open(root).runs(limit) {
+        Ok(h) => h,
+        Err(e) => {
+            eprintln!("Error: {e}");
+            return 1;
+        }
+    };
+
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&history).expect("JSON serialization")
+        );
+        return 0;
+    }
+
+    if history.is_empty() {
+        println!("No reconciliation runs recorded.");
+        return 0;
+    }
+
+    for (i, run) in history.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        println!("Run {}", run.id);
+        println!("  Started:  {}", time::format_utc(run.started_at));
+        match run.finished_at {
+            Some(ts) => println!("  Finished: {}", time::format_utc(ts)),
+            None => println!("  Finished: (in progress or abandoned)"),
+        }
+        if let Some(ref base) = run.base_ref {
+            println!("  Base:     {base}");
+        }
+        if run.specs.is_empty() {
+            println!("  No specs marked");
+        }
+        for (spec, marked) in &run.specs {
+            println!("  {spec} ({} file(s))", marked.len());
+            if files {
+                for f in marked {
+                    println!("    {f}");
+                }
+            }
+        }
+    }
+    0
+}
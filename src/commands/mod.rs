@@ -1,8 +1,20 @@
 pub mod cache;
+pub mod compliance;
+pub mod content;
+pub mod derived;
+pub mod feedback;
+pub mod graph;
 pub mod hook_validate;
 pub mod init;
+pub mod log;
 pub mod mcp;
+pub mod new;
+pub mod pipeline;
+pub mod resolve;
 pub mod schema_bump;
+pub mod show;
 pub mod state;
+pub mod states;
+pub mod status;
 pub mod update;
 pub mod validate;
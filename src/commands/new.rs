@@ -0,0 +1,31 @@
+use notarai::Project;
+use notarai::scaffold::Registration;
+use notarai::spec::{Domain, Tier};
+use std::path::PathBuf;
+
+pub fn run(name: &str, domain: Domain, tier: Tier, paths: &[String]) -> i32 {
+    let root = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+    if !root.join(".notarai").is_dir() {
+        eprintln!("Error: no .notarai/ directory; run `notarai init` first");
+        return 1;
+    }
+    let created = match Project::open(root).new_spec(name, domain, tier, paths) {
+        Ok(created) => created,
+        Err(e) => {
+            eprintln!("Error: {e}");
+            return 1;
+        }
+    };
+    println!("Created {}", created.path);
+    match created.registration {
+        Registration::NoSystemSpec => println!(
+            "No system spec found; add {} to a spec's subsystems to compose it",
+            created.path
+        ),
+        Registration::Already { .. } => {}
+        Registration::Registered { system, reference } => {
+            println!("Registered {reference} in {system} subsystems");
+        }
+    }
+    0
+}
@@ -0,0 +1,93 @@
+use clap::Subcommand;
+use notarai::Project;
+use notarai::pipeline::{Event, Outcome, RunOptions, StepResult};
+use std::path::PathBuf;
+
+#[derive(Subcommand)]
+pub enum PipelineAction {
+    /// Run a spec's pipeline steps in order, skipping steps whose inputs and outputs are unchanged
+    Run {
+        /// Path to the spec file
+        spec: String,
+        /// Run only this step
+        #[arg(long)]
+        step: Option<String>,
+        /// Show what would run without running anything
+        #[arg(long)]
+        dry_run: bool,
+        /// Print the step results as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+pub fn run(action: PipelineAction) -> i32 {
+    let root = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+    let project = Project::open(root);
+
+    match action {
+        PipelineAction::Run {
+            spec,
+            step,
+            dry_run,
+            json,
+        } => run_pipeline(&project, &spec, RunOptions { dry_run, step }, json),
+    }
+}
+
+/// Exits 0 when no step failed, 1 when one did, 2 when the pipeline could
+/// not be run.
+fn run_pipeline(project: &Project, spec: &str, options: RunOptions, json: bool) -> i32 {
+    let loaded = match project.load_spec(spec) {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            eprintln!("Error: {e}");
+            return 2;
+        }
+    };
+    let results = project.run_pipeline(&loaded, &options, |event| match event {
+        Event::Starting { step, command } => eprintln!("==> {step}: {command}"),
+        Event::Finished(result) if !json => print_result(result),
+        Event::Finished(_) => {}
+    });
+    let results = match results {
+        Ok(results) => results,
+        Err(e) => {
+            eprintln!("Error: {e}");
+            return 2;
+        }
+    };
+    let code = if results.iter().any(|r| r.outcome == Outcome::Failed) {
+        1
+    } else {
+        0
+    };
+
+    if json {
+        let report = serde_json::json!({
+            "spec_path": loaded.path,
+            "dry_run": options.dry_run,
+            "steps": results,
+        });
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&report).expect("JSON serialization")
+        );
+    }
+    code
+}
+
+fn print_result(result: &StepResult) {
+    let status = match result.outcome {
+        Outcome::Ran => "ok",
+        Outcome::UpToDate => "up to date",
+        Outcome::WouldRun => "would run",
+        Outcome::ConditionFalse => "skipped",
+        Outcome::Blocked => "blocked",
+        Outcome::Failed => "FAILED",
+    };
+    match &result.message {
+        Some(message) => println!("{}: {status} ({message})", result.name),
+        None => println!("{}: {status}", result.name),
+    }
+}
@@ -0,0 +1,45 @@
+use notarai::Project;
+use std::path::{Path, PathBuf};
+
+pub fn run(spec: &str, variant: &str, json: bool) -> i32 {
+    let root = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+    resolve(&root, spec, variant, json)
+}
+
+/// Print the effective spec of `variant`. Exits 0 when every override applied
+/// and the result is valid, 1 when an override missed or the result is
+/// invalid, 2 on error.
+fn resolve(root: &Path, spec: &str, variant: &str, json: bool) -> i32 {
+    let report = match Project::open(root).resolve_variant(spec, variant) {
+        Ok(r) => r,
+        Err(e) => {
+            eprintln!("Error: {e}");
+            return 2;
+        }
+    };
+    let code = if report.valid && report.unmatched_overrides.is_empty() {
+        0
+    } else {
+        1
+    };
+
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&report).expect("JSON serialization")
+        );
+        return code;
+    }
+
+    print!(
+        "{}",
+        serde_yaml_ng::to_string(&report.spec).expect("YAML serialization")
+    );
+    for entry in &report.unmatched_overrides {
+        eprintln!("warning: override not applied: {entry}");
+    }
+    for error in &report.errors {
+        eprintln!("error: {error}");
+    }
+    code
+}
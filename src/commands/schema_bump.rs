@@ -1,5 +1,5 @@
-use crate::core::schema;
-use crate::core::validator;
+use notarai::schema;
+use notarai::validate_spec;
 use std::path::{Path, PathBuf};
 use walkdir::WalkDir;
 
@@ -146,7 +146,7 @@ pub fn run(project_root: Option<&Path>) -> i32 {
         return 1;
     }
     let schema_dest = notarai_dir.join("notarai.spec.json");
-    if let Err(e) = std::fs::write(&schema_dest, crate::core::schema::SCHEMA_STR) {
+    if let Err(e) = std::fs::write(&schema_dest, schema::SCHEMA_STR) {
         eprintln!("Error: could not write .notarai/notarai.spec.json: {e}");
         return 1;
     }
@@ -178,7 +178,7 @@ pub fn run(project_root: Option<&Path>) -> i32 {
                 continue;
             }
         };
-        let result = validator::validate_spec(&content);
+        let result = validate_spec(&content);
         if !result.valid {
             eprintln!("FAIL {}", path.display());
             for err in &result.errors {
@@ -219,7 +219,7 @@ mod tests {
             "schema_version: '0.4'\nintent: 'test'\nbehaviors: []\nartifacts: {}\n",
         )
         .unwrap();
-        let updated = update_spec_files(&[spec.clone()], "0.4", "0.5").unwrap();
+        let updated = update_spec_files(std::slice::from_ref(&spec), "0.4", "0.5").unwrap();
         assert_eq!(updated, 1);
         let content = std::fs::read_to_string(&spec).unwrap();
         assert!(content.contains("schema_version: '0.5'"));
@@ -234,7 +234,7 @@ mod tests {
             "schema_version: \"0.4\"\nintent: 'test'\nbehaviors: []\nartifacts: {}\n",
         )
         .unwrap();
-        let updated = update_spec_files(&[spec.clone()], "0.4", "0.5").unwrap();
+        let updated = update_spec_files(std::slice::from_ref(&spec), "0.4", "0.5").unwrap();
         assert_eq!(updated, 1);
         let content = std::fs::read_to_string(&spec).unwrap();
         assert!(content.contains("schema_version: \"0.5\""));
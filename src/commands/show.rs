@@ -0,0 +1,107 @@
+use notarai::{Composition, LoadedSpec, Project, SourcedRule};
+use std::path::PathBuf;
+
+pub fn run(spec: &str, effective: bool, json: bool) -> i32 {
+    let root = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+    show(&Project::open(root), spec, effective, json)
+}
+
+fn show(project: &Project, spec: &str, effective: bool, json: bool) -> i32 {
+    let loaded = match project.load_spec(spec) {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            eprintln!("Error: {e}");
+            return 1;
+        }
+    };
+    let composition = if effective {
+        match project.effective_spec(spec) {
+            Ok(c) => Some(c),
+            Err(e) => {
+                eprintln!("Error: {e}");
+                return 1;
+            }
+        }
+    } else {
+        None
+    };
+
+    if json {
+        let mut report = serde_json::json!({
+            "spec_path": loaded.path,
+            "spec": loaded.spec,
+        });
+        if let Some(c) = &composition {
+            report["effective"] = serde_json::to_value(c).expect("JSON serialization");
+        }
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&report).expect("JSON serialization")
+        );
+        return 0;
+    }
+
+    print_text(&loaded, composition.as_ref());
+    0
+}
+
+fn print_text(loaded: &LoadedSpec, composition: Option<&Composition>) {
+    let spec = &loaded.spec;
+    println!("{}", loaded.path);
+    println!(
+        "  tier: {}  domain: {}",
+        spec.tier().as_str(),
+        spec.domain().as_str()
+    );
+    println!("  intent: {}", spec.intent.trim());
+
+    if !spec.behaviors.is_empty() {
+        println!("\nBehaviors:");
+        for behavior in &spec.behaviors {
+            println!("  - {}", behavior.name);
+        }
+    }
+
+    let own = |rules: &[String]| -> Vec<SourcedRule> {
+        rules
+            .iter()
+            .map(|rule| SourcedRule {
+                rule: rule.clone(),
+                source: loaded.path.clone(),
+            })
+            .collect()
+    };
+    let (constraints, invariants) = match composition {
+        Some(c) => (c.constraints.clone(), c.invariants.clone()),
+        None => (own(&spec.constraints), own(&spec.invariants)),
+    };
+    print_rules("Constraints", &constraints, &loaded.path);
+    print_rules("Invariants", &invariants, &loaded.path);
+
+    if let Some(c) = composition
+        && !c.applied.is_empty()
+    {
+        println!("\nApplies:");
+        for applied in &c.applied {
+            if applied.via == loaded.path {
+                println!("  - {}", applied.path);
+            } else {
+                println!("  - {} (via {})", applied.path, applied.via);
+            }
+        }
+    }
+}
+
+fn print_rules(title: &str, rules: &[SourcedRule], own: &str) {
+    if rules.is_empty() {
+        return;
+    }
+    println!("\n{title}:");
+    for rule in rules {
+        if rule.source == own {
+            println!("  - {}", rule.rule);
+        } else {
+            println!("  - {}  [{}]", rule.rule, rule.source);
+        }
+    }
+}
@@ -1,4 +1,7 @@
 use clap::Subcommand;
+use notarai::state::{merge_states, parse_state, write_state};
+use notarai::{Error, Project};
+use std::path::{Path, PathBuf};
 
 #[derive(Subcommand)]
 pub enum StateAction {
@@ -6,22 +9,43 @@ pub enum StateAction {
     Show,
     /// Reset reconciliation state (next run will be a full reconciliation)
     Reset,
-    /// Snapshot current cache into reconciliation_state.json
-    Snapshot,
+    /// Snapshot current cache into reconciliation_state.json and close the open run
+    Snapshot {
+        /// Replace a state file that cannot be loaded instead of failing
+        #[arg(long)]
+        force: bool,
+    },
+    /// Report drift since the reconciliation baseline, grouped by spec
+    Diff {
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Three-way merge of reconciliation state files, for use as a git merge driver
+    Merge {
+        /// Common ancestor version (git's %O)
+        base: PathBuf,
+        /// Current branch version, overwritten with the result (git's %A)
+        ours: PathBuf,
+        /// Other branch version (git's %B)
+        theirs: PathBuf,
+    },
 }
 
 pub fn run(action: StateAction) -> i32 {
-    let root = std::env::current_dir().unwrap_or_else(|_| std::path::PathBuf::from("."));
+    let root = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
 
     match action {
         StateAction::Show => show(&root),
         StateAction::Reset => reset(&root),
-        StateAction::Snapshot => snapshot(&root),
+        StateAction::Snapshot { force } => snapshot(&root, force),
+        StateAction::Diff { json } => diff(&root, json),
+        StateAction::Merge { base, ours, theirs } => merge(&root, &base, &ours, &theirs),
     }
 }
 
-fn show(root: &std::path::Path) -> i32 {
-    match crate::core::state::load_state(root) {
+fn show(root: &Path) -> i32 {
+    match Project::open(root).load_state() {
         Ok(Some(state)) => {
             let meta = &state.last_reconciliation;
             println!("Last reconciliation: {}", meta.timestamp);
@@ -31,8 +55,20 @@ fn show(root: &std::path::Path) -> i32 {
             if let Some(ref branch) = meta.branch {
                 println!("Branch:              {branch}");
             }
-            println!("Files:               {}", state.file_fingerprints.len());
-            println!("Specs:               {}", state.spec_fingerprints.len());
+            println!(
+                "Files:               {}",
+                state.artifact_fingerprints().len()
+            );
+            println!("Specs:               {}", state.specs.len());
+            for (path, spec) in &state.specs {
+                let hash = spec.git_hash.as_deref().unwrap_or("-");
+                println!(
+                    "  {path}  {}  {}  ({} file(s))",
+                    spec.reconciled_at,
+                    &hash[..hash.len().min(12)],
+                    spec.artifacts.len()
+                );
+            }
             0
         }
         Ok(None) => {
@@ -46,8 +82,8 @@ fn show(root: &std::path::Path) -> i32 {
     }
 }
 
-fn reset(root: &std::path::Path) -> i32 {
-    let path = crate::core::state::state_path(root);
+fn reset(root: &Path) -> i32 {
+    let path = Project::open(root).state_path();
     if path.exists() {
         match std::fs::remove_file(&path) {
             Ok(()) => {
@@ -65,18 +101,27 @@ fn reset(root: &std::path::Path) -> i32 {
     }
 }
 
-fn snapshot(root: &std::path::Path) -> i32 {
-    match crate::core::state::snapshot_from_cache(root) {
-        Ok(state) => match crate::core::state::save_state(root, &state) {
+fn snapshot(root: &Path, force: bool) -> i32 {
+    let project = Project::open(root);
+    let snapshot = if force {
+        project.force_snapshot_state()
+    } else {
+        project.snapshot_state()
+    };
+    match snapshot {
+        Ok(state) => match project.save_state(&state) {
             Ok(()) => {
+                if let Err(e) = project.finish_run() {
+                    eprintln!("Warning: could not close reconciliation run: {e}");
+                }
                 let meta = &state.last_reconciliation;
                 println!("Snapshot saved.");
                 println!("Timestamp: {}", meta.timestamp);
                 if let Some(ref hash) = meta.git_hash {
                     println!("Git hash:  {hash}");
                 }
-                println!("Files:     {}", state.file_fingerprints.len());
-                println!("Specs:     {}", state.spec_fingerprints.len());
+                println!("Files:     {}", state.artifact_fingerprints().len());
+                println!("Specs:     {}", state.specs.len());
                 0
             }
             Err(e) => {
@@ -86,7 +131,119 @@ fn snapshot(root: &std::path::Path) -> i32 {
         },
         Err(e) => {
             eprintln!("Error creating snapshot: {e}");
+            if matches!(e, Error::InvalidState(_) | Error::UnsupportedVersion { .. }) {
+                eprintln!("Pass --force to replace the state file, discarding its contents.");
+            }
             1
         }
     }
 }
+
+/// Print drift since the baseline. Exits 0 when clean, 1 when anything
+/// drifted, 2 on error (including a missing state file).
+fn diff(root: &Path, json: bool) -> i32 {
+    let report = match Project::open(root).state_diff() {
+        Ok(r) => r,
+        Err(e) => {
+            eprintln!("Error: {e}");
+            return 2;
+        }
+    };
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&report).expect("JSON serialization")
+        );
+        return if report.clean { 0 } else { 1 };
+    }
+
+    let baseline = &report.baseline;
+    match &baseline.git_hash {
+        Some(hash) => println!(
+            "Baseline: {} ({})",
+            baseline.timestamp,
+            &hash[..hash.len().min(12)]
+        ),
+        None => println!("Baseline: {}", baseline.timestamp),
+    }
+    if report.clean {
+        println!("No drift since baseline.");
+        return 0;
+    }
+
+    let list = |paths: &[String], label: &str| {
+        for path in paths {
+            println!("  {label:<9} {path}");
+        }
+    };
+    for spec in &report.specs {
+        println!();
+        println!("{}", spec.spec_path);
+        if spec.spec_changed {
+            println!("  spec changed");
+        }
+        list(&spec.added, "added:");
+        list(&spec.modified, "modified:");
+        list(&spec.removed, "removed:");
+    }
+    if !report.removed_specs.is_empty() {
+        println!();
+        println!("Removed specs:");
+        list(&report.removed_specs, "removed:");
+    }
+    if !report.no_longer_governed.is_empty() {
+        println!();
+        println!("No longer governed by any spec:");
+        list(&report.no_longer_governed, "removed:");
+    }
+    1
+}
+
+/// Merge `theirs` into `ours` against `base` and write the result to `ours`.
+///
+/// Exits 0 on a clean merge and 1 when there were true conflicts (the merged
+/// state is still written, keeping the newer side), so git stops for review.
+/// Exits 2 without touching `ours` when an input cannot be read.
+fn merge(root: &Path, base: &Path, ours: &Path, theirs: &Path) -> i32 {
+    let read = |path: &Path| {
+        std::fs::read_to_string(path).map_err(|source| Error::Io {
+            path: path.to_path_buf(),
+            source,
+        })
+    };
+    // git passes an empty file when the ancestor had no state file.
+    let base_state = match read(base) {
+        Ok(content) if content.trim().is_empty() => Ok(None),
+        Ok(content) => parse_state(&content, root).map(Some),
+        Err(e) => Err(e),
+    };
+    let inputs = base_state.and_then(|base| {
+        let ours = read(ours).and_then(|c| parse_state(&c, root))?;
+        let theirs = read(theirs).and_then(|c| parse_state(&c, root))?;
+        Ok((base, ours, theirs))
+    });
+    let (base_state, ours_state, theirs_state) = match inputs {
+        Ok(inputs) => inputs,
+        Err(e) => {
+            eprintln!("Error: {e}");
+            return 2;
+        }
+    };
+
+    let merged = merge_states(base_state.as_ref(), &ours_state, &theirs_state);
+    if let Err(e) = write_state(ours, &merged.state) {
+        eprintln!("Error: {e}");
+        return 2;
+    }
+    if merged.conflicts.is_empty() {
+        return 0;
+    }
+    for conflict in &merged.conflicts {
+        eprintln!("conflict: {conflict}");
+    }
+    eprintln!(
+        "reconciliation_state.json merged with {} conflict(s); review, then reconcile and run `notarai state snapshot`",
+        merged.conflicts.len()
+    );
+    1
+}
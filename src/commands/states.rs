@@ -0,0 +1,118 @@
+use clap::Subcommand;
+use notarai::graph::Format;
+use notarai::spec::Spec;
+use notarai::state_machine;
+use notarai::{LoadedSpec, Project};
+use std::path::PathBuf;
+
+#[derive(Subcommand)]
+pub enum StatesAction {
+    /// Check a spec's state machine: unreachable and dead-end states, ambiguous transitions, behaviors naming undefined states
+    Check {
+        /// Spec files to check (default: every spec with states or state transitions)
+        specs: Vec<String>,
+        /// Print the findings as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Print a spec's state machine as a diagram
+    Graph {
+        /// Path to the spec file
+        spec: String,
+        /// Output format: mermaid, dot or json
+        #[arg(long, default_value = "mermaid")]
+        format: Format,
+    },
+}
+
+pub fn run(action: StatesAction) -> i32 {
+    let root = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+    let project = Project::open(root);
+
+    match action {
+        StatesAction::Check { specs, json } => check(&project, &specs, json),
+        StatesAction::Graph { spec, format } => graph(&project, &spec, format),
+    }
+}
+
+fn has_state_machine(spec: &Spec) -> bool {
+    spec.states.is_some() || spec.behaviors.iter().any(|b| b.state_transition.is_some())
+}
+
+/// Exits 0 when no spec has findings, 1 when any does, 2 on error.
+fn check(project: &Project, specs: &[String], json: bool) -> i32 {
+    let loaded = if specs.is_empty() {
+        project.load_specs().map(|all| {
+            all.into_iter()
+                .filter(|l| has_state_machine(&l.spec))
+                .collect()
+        })
+    } else {
+        specs.iter().map(|s| project.load_spec(s)).collect()
+    };
+    let loaded: Vec<LoadedSpec> = match loaded {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            eprintln!("Error: {e}");
+            return 2;
+        }
+    };
+
+    let reports: Vec<(&str, Vec<state_machine::Finding>)> = loaded
+        .iter()
+        .map(|l| (l.path.as_str(), state_machine::analyze(&l.spec)))
+        .collect();
+    let code = if reports.iter().all(|(_, f)| f.is_empty()) {
+        0
+    } else {
+        1
+    };
+
+    if json {
+        let report: Vec<serde_json::Value> = reports
+            .iter()
+            .map(|(path, findings)| serde_json::json!({"spec_path": path, "findings": findings}))
+            .collect();
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&report).expect("JSON serialization")
+        );
+        return code;
+    }
+
+    if reports.is_empty() {
+        println!("No specs with a state machine.");
+        return code;
+    }
+    for (path, findings) in &reports {
+        if findings.is_empty() {
+            println!("PASS {path}");
+            continue;
+        }
+        println!("FAIL {path}");
+        for finding in findings {
+            println!("  - [{}] {}", finding.subject, finding.message);
+        }
+    }
+    code
+}
+
+fn graph(project: &Project, spec: &str, format: Format) -> i32 {
+    let loaded = match project.load_spec(spec) {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            eprintln!("Error: {e}");
+            return 1;
+        }
+    };
+    match &loaded.spec.states {
+        Some(states) => {
+            print!("{}", state_machine::render(states, format));
+            0
+        }
+        None => {
+            eprintln!("Error: {} has no states block", loaded.path);
+            1
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use notarai::Project;
+use notarai::status::{SpecStatus, Status};
+use notarai::time;
+use std::path::{Path, PathBuf};
+
+pub fn run(json: bool) -> i32 {
+    let root = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+    show(&root, json)
+}
+
+fn show(root: &Path, json: bool) -> i32 {
+    let rows = match Project::open(root).status() {
+        Ok(rows) => rows,
+        Err(e) => {
+            eprintln!("Error: {e}");
+            return 1;
+        }
+    };
+
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&rows).expect("JSON serialization")
+        );
+        return 0;
+    }
+
+    if rows.is_empty() {
+        println!("No specs found in .notarai/");
+        return 0;
+    }
+
+    let now = time::now_secs();
+    let header = [
+        "SPEC",
+        "TIER",
+        "DOMAIN",
+        "STATUS",
+        "CHANGED",
+        "RECONCILED",
+        "COMMIT",
+    ];
+    let table: Vec<[String; 7]> = rows.iter().map(|row| columns(row, now)).collect();
+    let mut widths = header.map(str::len);
+    for cells in &table {
+        for (w, cell) in widths.iter_mut().zip(cells) {
+            *w = (*w).max(cell.len());
+        }
+    }
+    print_row(&header.map(String::from), &widths);
+    for (row, cells) in rows.iter().zip(&table) {
+        print_row(cells, &widths);
+        for error in &row.errors {
+            println!("  error: {error}");
+        }
+    }
+    0
+}
+
+fn columns(row: &SpecStatus, now: i64) -> [String; 7] {
+    let dash = || "-".to_string();
+    let changed = match row.status {
+        Status::Reconciled | Status::ArtifactsChanged => {
+            format!("{}/{}", row.changed_files.len(), row.governed_files)
+        }
+        Status::SpecChanged | Status::NeverReconciled | Status::Invalid => dash(),
+    };
+    let age = row
+        .reconciled_at
+        .as_deref()
+        .and_then(time::parse_utc)
+        .map_or_else(dash, |secs| time::format_age(secs, now));
+    let commit = row
+        .commit
+        .as_deref()
+        .map_or_else(dash, |c| c.chars().take(12).collect());
+    [
+        row.spec_path.clone(),
+        row.tier.map_or_else(dash, |t| t.as_str().to_string()),
+        row.domain.map_or_else(dash, |d| d.as_str().to_string()),
+        row.status.label().to_string(),
+        changed,
+        age,
+        commit,
+    ]
+}
+
+fn print_row(cells: &[String; 7], widths: &[usize; 7]) {
+    let line: Vec<String> = cells
+        .iter()
+        .zip(widths)
+        .map(|(cell, w)| format!("{cell:<w$}"))
+        .collect();
+    println!("{}", line.join("  ").trim_end());
+}
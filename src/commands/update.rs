@@ -1,4 +1,4 @@
-use crate::core::update::{self, InstallMethod, UpdateStatus};
+use crate::update::{self, InstallMethod, UpdateStatus};
 
 fn print_status(status: &UpdateStatus) {
     eprintln!("Current version: {}", status.current);
@@ -1,5 +1,5 @@
-use crate::core::schema;
-use crate::core::validator;
+use notarai::schema;
+use notarai::validate_spec;
 use std::fs;
 use std::path::Path;
 use walkdir::WalkDir;
@@ -51,7 +51,7 @@ fn check_schema_freshness() {
 pub fn run(path: Option<String>) -> i32 {
     check_schema_freshness();
 
-    if let Some(hint) = crate::core::update::check_project_staleness(
+    if let Some(hint) = crate::update::check_project_staleness(
         &std::env::current_dir().unwrap_or_else(|_| std::path::PathBuf::from(".")),
     ) {
         eprintln!("{hint}");
@@ -90,7 +90,7 @@ pub fn run(path: Option<String>) -> i32 {
             }
         };
 
-        let result = validator::validate_spec(&content);
+        let result = validate_spec(&content);
 
         if result.valid {
             println!("PASS {file}");
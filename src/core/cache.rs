@@ -1,6 +1,37 @@
-use rusqlite::{Connection, params};
+use crate::core::normalize::Normalizer;
+use crate::{Error, Result};
+use rayon::prelude::*;
+use rusqlite::{Connection, Transaction, TransactionBehavior, params};
+use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, UNIX_EPOCH};
+
+/// Size and modification time of a file, stored next to its hash so unchanged
+/// files can be recognized without rehashing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileStat {
+    pub size: i64,
+    pub mtime_ns: i64,
+    /// When the stat was taken (epoch seconds). Stored as `stat_at`: a file
+    /// whose mtime is not strictly older may have been rewritten since.
+    pub taken_at: i64,
+}
+
+/// Stat columns as stored on a `file_cache` row; NULL until first recorded.
+struct StoredStat {
+    size: Option<i64>,
+    mtime_ns: Option<i64>,
+    stat_at: Option<i64>,
+}
+
+/// A reconciliation record ready to be written by `upsert_batch`.
+pub struct FileRecord {
+    pub path: String,
+    pub hash: String,
+    pub stat: Option<FileStat>,
+    /// Normalizer `hash` was computed with.
+    pub normalizer: Normalizer,
+}
 
 /// Return the canonical path to the SQLite cache database.
 ///
@@ -15,63 +46,345 @@ pub fn db_path(project_root: &Path) -> PathBuf {
 /// Open (or create) the SQLite cache database at `project_root`.
 ///
 /// Creates the `.notarai/.cache/` directory if it does not exist, opens the
-/// database, and runs the `CREATE TABLE IF NOT EXISTS` migration. Returns a
-/// `Connection` ready for use, or an error string.
+/// database in WAL mode with a `BUSY_TIMEOUT` busy handler, and brings its
+/// schema up to `SCHEMA_VERSION` by running any pending entries of
+/// `MIGRATIONS`. Returns a `Connection` ready for use. A
+/// database written by a newer notarai (a `user_version` above
+/// `SCHEMA_VERSION`) is rejected rather than modified. A newly created
+/// database is seeded from `reconciliation_state.json` (see
+/// `state::restore_cache`).
+///
+/// Several processes (an MCP server, parallel sub-agents, a CLI run) may hold
+/// connections at once: WAL lets readers proceed during a write, and writers
+/// queue on the busy handler. Every write goes through `write_transaction`.
 ///
 /// Note: `Connection` is not `Sync`, so callers must open a new connection per
 /// command invocation -- never store it in a `OnceLock` or shared state.
-pub fn open_cache_db(project_root: &Path) -> Result<Connection, String> {
+pub fn open_cache_db(project_root: &Path) -> Result<Connection> {
+    let fresh = !db_path(project_root).exists();
+    let conn = open_cache_db_no_restore(project_root)?;
+    if fresh {
+        // Cold start (e.g. a fresh clone): inherit the committed baseline. A
+        // state file that cannot be used just leaves the cache cold.
+        if let Ok(Some(state)) = crate::core::state::load_state(project_root) {
+            let _ = crate::core::state::restore_cache(project_root, &conn, &state);
+        }
+    }
+    Ok(conn)
+}
+
+/// `open_cache_db` without seeding a newly created database from the state
+/// file, for callers that restore explicitly.
+pub fn open_cache_db_no_restore(project_root: &Path) -> Result<Connection> {
     let path = db_path(project_root);
     if let Some(parent) = path.parent() {
-        std::fs::create_dir_all(parent)
-            .map_err(|e| format!("could not create cache directory: {e}"))?;
+        std::fs::create_dir_all(parent).map_err(|e| Error::io(parent, e))?;
+    }
+    let conn = Connection::open(&path).map_err(Error::cache("could not open cache DB"))?;
+    conn.busy_timeout(BUSY_TIMEOUT)
+        .map_err(Error::cache("could not set busy timeout"))?;
+    let mode: String = conn
+        .query_row("PRAGMA journal_mode = WAL", [], |row| row.get(0))
+        .map_err(Error::cache("could not enable WAL mode"))?;
+    if !mode.eq_ignore_ascii_case("wal") {
+        return Err(Error::Cache {
+            context: "could not enable WAL mode",
+            source: rusqlite::Error::SqliteFailure(
+                rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_CANTOPEN),
+                Some(format!("journal_mode is {mode}")),
+            ),
+        });
+    }
+    migrate(&conn)?;
+    Ok(conn)
+}
+
+/// How long a connection waits for another writer before giving up.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Begin an immediate (write-locking) transaction.
+///
+/// Taking the write lock up front means a transaction that reads before it
+/// writes never has to upgrade its lock, which in WAL mode fails immediately
+/// with `SQLITE_BUSY` instead of waiting on the busy handler.
+pub fn write_transaction(conn: &Connection) -> Result<Transaction<'_>> {
+    Transaction::new_unchecked(conn, TransactionBehavior::Immediate)
+        .map_err(Error::cache("transaction error"))
+}
+
+/// Current cache schema version, stored in `PRAGMA user_version`.
+pub const SCHEMA_VERSION: i64 = MIGRATIONS.len() as i64;
+
+type Migration = fn(&Connection) -> Result<()>;
+
+/// Ordered schema migrations. Entry `i` upgrades a database from version `i`
+/// to `i + 1`. Never edit a released entry -- append a new one instead.
+const MIGRATIONS: &[Migration] = &[migrate_v1, migrate_v2, migrate_v3, migrate_v4, migrate_v5];
+
+/// Return the schema version recorded in the database.
+pub fn schema_version(conn: &Connection) -> Result<i64> {
+    conn.query_row("PRAGMA user_version", [], |row| row.get(0))
+        .map_err(Error::cache("could not read cache schema version"))
+}
+
+/// Run pending migrations inside a single immediate transaction.
+///
+/// The version is re-read after the write lock is taken, so two processes
+/// opening the same database concurrently apply each migration exactly once.
+fn migrate(conn: &Connection) -> Result<()> {
+    let version = schema_version(conn)?;
+    check_supported(version)?;
+    if version == SCHEMA_VERSION {
+        return Ok(());
+    }
+
+    let tx = write_transaction(conn)?;
+    let version = schema_version(&tx)?;
+    check_supported(version)?;
+    for step in MIGRATIONS.iter().skip(version as usize) {
+        step(&tx)?;
+    }
+    tx.pragma_update(None, "user_version", SCHEMA_VERSION)
+        .map_err(Error::cache("could not record cache schema version"))?;
+    tx.commit().map_err(Error::cache("commit error"))
+}
+
+fn check_supported(version: i64) -> Result<()> {
+    if version > SCHEMA_VERSION {
+        return Err(Error::UnsupportedVersion {
+            what: "cache DB",
+            found: version.to_string(),
+            supported: SCHEMA_VERSION.to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// v1: spec-scoped reconciliation records with size/mtime stat columns.
+///
+/// A `file_cache` table from the original path-only layout (unversioned
+/// databases) is dropped -- its rows cannot be attributed to a spec.
+fn migrate_v1(conn: &Connection) -> Result<()> {
+    let columns = table_columns(conn, "file_cache")?;
+    if !columns.is_empty() && !columns.iter().any(|c| c == "spec_path") {
+        conn.execute_batch("DROP TABLE file_cache;")
+            .map_err(Error::cache("could not drop legacy cache table"))?;
     }
-    let conn = Connection::open(&path).map_err(|e| format!("could not open cache DB: {e}"))?;
     conn.execute_batch(
         "CREATE TABLE IF NOT EXISTS file_cache (
-            path TEXT PRIMARY KEY,
+            spec_path TEXT NOT NULL,
+            path TEXT NOT NULL,
             blake3_hash TEXT NOT NULL,
+            updated_at INTEGER NOT NULL,
+            size INTEGER,
+            mtime_ns INTEGER,
+            stat_at INTEGER,
+            PRIMARY KEY (spec_path, path)
+        );
+        CREATE TABLE IF NOT EXISTS spec_cache (
+            spec_path TEXT PRIMARY KEY,
+            fingerprint TEXT NOT NULL,
             updated_at INTEGER NOT NULL
         );",
     )
-    .map_err(|e| format!("could not initialize cache schema: {e}"))?;
-    Ok(conn)
+    .map_err(Error::cache("could not initialize cache schema"))?;
+    if columns.iter().any(|c| c == "spec_path") && !columns.iter().any(|c| c == "size") {
+        conn.execute_batch(
+            "ALTER TABLE file_cache ADD COLUMN size INTEGER;
+             ALTER TABLE file_cache ADD COLUMN mtime_ns INTEGER;
+             ALTER TABLE file_cache ADD COLUMN stat_at INTEGER;",
+        )
+        .map_err(Error::cache("could not add stat columns"))?;
+    }
+    Ok(())
+}
+
+/// v2: reconciliation run history (see `core::runs`).
+fn migrate_v2(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE reconciliation_runs (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            started_at INTEGER NOT NULL,
+            finished_at INTEGER,
+            base_ref TEXT
+        );
+        CREATE TABLE run_files (
+            run_id INTEGER NOT NULL,
+            spec_path TEXT NOT NULL,
+            path TEXT NOT NULL,
+            PRIMARY KEY (run_id, spec_path, path)
+        );
+        CREATE TABLE run_specs (
+            run_id INTEGER NOT NULL,
+            spec_path TEXT NOT NULL,
+            PRIMARY KEY (run_id, spec_path)
+        );",
+    )
+    .map_err(Error::cache("could not create run history tables"))
+}
+
+/// v3: record the content normalizer each hash was computed with. Existing
+/// records were hashed from raw bytes.
+fn migrate_v3(conn: &Connection) -> Result<()> {
+    conn.execute_batch("ALTER TABLE file_cache ADD COLUMN normalizer TEXT NOT NULL DEFAULT 'raw';")
+        .map_err(Error::cache("could not add normalizer column"))
+}
+
+/// v4: fingerprints of pipeline steps that last ran successfully (see
+/// `core::pipeline`).
+fn migrate_v4(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE pipeline_steps (
+            spec_path TEXT NOT NULL,
+            step TEXT NOT NULL,
+            fingerprint TEXT NOT NULL,
+            updated_at INTEGER NOT NULL,
+            PRIMARY KEY (spec_path, step)
+        );",
+    )
+    .map_err(Error::cache("could not create pipeline step table"))
+}
+
+/// v5: the input hashes each derived output was built from (see
+/// `core::derived`).
+fn migrate_v5(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE derived_inputs (
+            output_path TEXT NOT NULL,
+            input_path TEXT NOT NULL,
+            blake3_hash TEXT NOT NULL,
+            recorded_at INTEGER NOT NULL,
+            PRIMARY KEY (output_path, input_path)
+        );",
+    )
+    .map_err(Error::cache("could not create derived input table"))
+}
+
+/// Return the column names of `table`, or an empty list if it does not exist.
+fn table_columns(conn: &Connection, table: &str) -> Result<Vec<String>> {
+    let mut stmt = conn
+        .prepare("SELECT name FROM pragma_table_info(?1)")
+        .map_err(Error::cache("prepare error"))?;
+    let columns = stmt
+        .query_map(params![table], |row| row.get(0))
+        .map_err(Error::cache("query error"))?
+        .filter_map(|r| r.ok())
+        .collect();
+    Ok(columns)
 }
 
 /// Compute the BLAKE3 hash of a file, returned as a lowercase hex string.
-pub fn hash_file(path: &Path) -> Result<String, String> {
-    let bytes =
-        std::fs::read(path).map_err(|e| format!("could not read {}: {e}", path.display()))?;
-    Ok(blake3::hash(&bytes).to_hex().to_string())
+///
+/// Large files are memory-mapped and hashed with blake3's multithreaded
+/// implementation; small files are read directly. The digest is the same
+/// either way.
+pub fn hash_file(path: &Path) -> Result<String> {
+    let mut hasher = blake3::Hasher::new();
+    hasher
+        .update_mmap_rayon(path)
+        .map_err(|e| Error::io(path, e))?;
+    Ok(hasher.finalize().to_hex().to_string())
+}
+
+/// Hash a file after applying `normalizer` to its content.
+///
+/// `Normalizer::Raw`, and `json` or `notebook` content too large to parse, go
+/// through `hash_file`; `eol` and `text` stream the file through the
+/// normalizer. The digest matches `hash_bytes(&normalizer.apply(content))`.
+pub fn hash_file_with(path: &Path, normalizer: Normalizer) -> Result<String> {
+    let read_error = |e: std::io::Error| Error::io(path, e);
+    match normalizer {
+        Normalizer::Raw => hash_file(path),
+        Normalizer::Json | Normalizer::Notebook => {
+            let size = std::fs::metadata(path).map_err(read_error)?.len();
+            if size > crate::core::normalize::MAX_PARSED_BYTES {
+                return hash_file(path);
+            }
+            let bytes = std::fs::read(path).map_err(read_error)?;
+            Ok(hash_bytes(&normalizer.apply(&bytes)))
+        }
+        Normalizer::Eol | Normalizer::Text => {
+            let file = std::fs::File::open(path).map_err(read_error)?;
+            let mut hasher = blake3::Hasher::new();
+            normalizer
+                .apply_to_writer(std::io::BufReader::new(file), &mut hasher)
+                .map_err(read_error)?;
+            Ok(hasher.finalize().to_hex().to_string())
+        }
+    }
+}
+
+/// Hash many files in parallel, each with its own normalizer. Results are
+/// returned in input order.
+pub fn hash_files(files: &[(&Path, Normalizer)]) -> Vec<Result<String>> {
+    files
+        .par_iter()
+        .map(|(path, normalizer)| hash_file_with(path, *normalizer))
+        .collect()
 }
 
-/// Insert or replace a single file's hash record in the cache.
+/// Fingerprint of a spec file's content, used to scope reconciliation records
+/// to one revision of the spec. Line endings and trailing whitespace are
+/// ignored.
+pub fn spec_fingerprint(content: &[u8]) -> String {
+    hash_bytes(&Normalizer::Text.apply(content))
+}
+
+/// Read a file's size and modification time (nanoseconds since the Unix epoch).
+///
+/// Call this before hashing the file, so `taken_at` predates the content the
+/// hash is computed from. Returns `None` if the file cannot be stat'ed or the
+/// platform does not report modification times.
+pub fn stat_file(path: &Path) -> Option<FileStat> {
+    let taken_at = crate::core::time::now_secs();
+    let meta = std::fs::metadata(path).ok()?;
+    let mtime = meta.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+    Some(FileStat {
+        size: meta.len() as i64,
+        mtime_ns: mtime.as_nanos() as i64,
+        taken_at,
+    })
+}
+
+/// Compute the BLAKE3 hash of an in-memory buffer, returned as a lowercase
+/// hex string. Matches `hash_file` for the same content.
+pub fn hash_bytes(bytes: &[u8]) -> String {
+    blake3::hash(bytes).to_hex().to_string()
+}
+
+/// Insert or replace a single file's reconciliation record for a spec.
 ///
 /// Production code uses `upsert_batch` instead. This is retained for unit tests.
 #[cfg(test)]
-fn upsert(conn: &Connection, rel_path: &str, hash: &str) -> Result<(), String> {
-    let now = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_secs() as i64;
-    conn.execute(
-        "INSERT OR REPLACE INTO file_cache (path, blake3_hash, updated_at) VALUES (?1, ?2, ?3)",
-        params![rel_path, hash, now],
-    )
-    .map_err(|e| format!("upsert failed: {e}"))?;
-    Ok(())
+fn upsert(
+    conn: &Connection,
+    spec_path: &str,
+    spec_fingerprint: &str,
+    rel_path: &str,
+    hash: &str,
+) -> Result<()> {
+    let record = FileRecord {
+        path: rel_path.to_string(),
+        hash: hash.to_string(),
+        stat: None,
+        normalizer: Normalizer::Raw,
+    };
+    upsert_batch(conn, spec_path, spec_fingerprint, &[record]).map(|_| ())
 }
 
-/// Check whether a single file's content has changed since it was last cached.
+/// Check whether a single file's content has changed since it was last
+/// reconciled for a spec.
 ///
 /// Production code uses `check_changed_batch` instead. This is retained for
 /// unit tests.
 #[cfg(test)]
 fn check_changed(
     conn: &Connection,
+    spec_path: &str,
+    spec_fingerprint: &str,
     rel_path: &str,
     abs_path: &Path,
-) -> Result<Option<String>, String> {
+) -> Result<Option<String>> {
     if !abs_path.exists() {
         // Treat absence as changed
         return Ok(Some(String::new()));
@@ -79,8 +392,10 @@ fn check_changed(
     let current_hash = hash_file(abs_path)?;
     let cached: Option<String> = conn
         .query_row(
-            "SELECT blake3_hash FROM file_cache WHERE path = ?1",
-            params![rel_path],
+            "SELECT f.blake3_hash FROM file_cache f
+             JOIN spec_cache s ON s.spec_path = f.spec_path
+             WHERE f.spec_path = ?1 AND f.path = ?2 AND s.fingerprint = ?3",
+            params![spec_path, rel_path, spec_fingerprint],
             |row| row.get(0),
         )
         .ok();
@@ -90,113 +405,499 @@ fn check_changed(
     }
 }
 
-/// Partition files into changed/unchanged using a single transaction and
-/// prepared statement.
+/// Partition files into changed/unchanged for a spec using a single
+/// transaction and prepared statement.
+///
+/// Only records written for `spec_path` while its fingerprint was
+/// `spec_fingerprint` count as reconciled: records from other specs, or from
+/// an earlier revision of this spec, are ignored.
+///
+/// Files whose size and mtime match the record are treated as unchanged
+/// without rehashing, unless the mtime is not strictly older than the moment
+/// that stat was taken (the file may have been rewritten within the same
+/// second without changing its mtime). All other
+/// files with a record are hashed in parallel; when the hash still matches,
+/// the record's stored size and mtime are refreshed with the stat taken
+/// before hashing, so the next call can take the fast path. Pass
+/// `full_verify` to rehash every file regardless.
+///
+/// Files are rehashed with the normalizer recorded alongside their hash, so a
+/// record stays comparable even if the normalizer rules have changed since.
+/// A record made with a normalizer this version does not know counts as
+/// changed.
 ///
 /// Each entry in `files` is `(rel_path, abs_path)`. Returns `(changed, unchanged)`
 /// where `changed` contains the relative paths of files whose current hash differs
 /// from (or is absent from) the cache, and `unchanged` contains paths that match.
+/// Both lists preserve the input order.
 pub fn check_changed_batch(
     conn: &Connection,
+    spec_path: &str,
+    spec_fingerprint: &str,
     files: &[(String, PathBuf)],
-) -> Result<(Vec<String>, Vec<String>), String> {
-    let tx = conn
-        .unchecked_transaction()
-        .map_err(|e| format!("transaction error: {e}"))?;
-    let mut stmt = tx
-        .prepare("SELECT blake3_hash FROM file_cache WHERE path = ?1")
-        .map_err(|e| format!("prepare error: {e}"))?;
+    full_verify: bool,
+) -> Result<(Vec<String>, Vec<String>)> {
+    let mut select = conn
+        .prepare(
+            "SELECT f.blake3_hash, f.size, f.mtime_ns, f.stat_at, f.normalizer FROM file_cache f
+             JOIN spec_cache s ON s.spec_path = f.spec_path
+             WHERE f.spec_path = ?1 AND f.path = ?2 AND s.fingerprint = ?3",
+        )
+        .map_err(Error::cache("prepare error"))?;
 
-    let mut changed = Vec::new();
-    let mut unchanged = Vec::new();
+    // None = changed, Some(true) = unchanged, Some(false) = needs hashing.
+    let mut verdicts: Vec<Option<bool>> = Vec::with_capacity(files.len());
+    let mut to_hash: Vec<(usize, String, FileStat, Normalizer)> = Vec::new();
 
-    for (rel_path, abs_path) in files {
-        if !abs_path.exists() {
-            changed.push(rel_path.clone());
+    for (i, (rel_path, abs_path)) in files.iter().enumerate() {
+        let Some(stat) = stat_file(abs_path) else {
+            verdicts.push(None);
             continue;
+        };
+        let cached: Option<(String, StoredStat, String)> = select
+            .query_row(params![spec_path, rel_path, spec_fingerprint], |row| {
+                let stored = StoredStat {
+                    size: row.get(1)?,
+                    mtime_ns: row.get(2)?,
+                    stat_at: row.get(3)?,
+                };
+                Ok((row.get(0)?, stored, row.get(4)?))
+            })
+            .ok();
+        let Some((hash, stored, normalizer)) = cached else {
+            // No record: changed regardless of content, no need to hash.
+            verdicts.push(None);
+            continue;
+        };
+        let Some(normalizer) = Normalizer::from_name(&normalizer) else {
+            verdicts.push(None);
+            continue;
+        };
+        let fresh = stored.size == Some(stat.size)
+            && stored.mtime_ns == Some(stat.mtime_ns)
+            && stored
+                .stat_at
+                .is_some_and(|at| stat.mtime_ns / 1_000_000_000 < at);
+        if fresh && !full_verify {
+            verdicts.push(Some(true));
+        } else {
+            verdicts.push(Some(false));
+            to_hash.push((i, hash, stat, normalizer));
+        }
+    }
+    drop(select);
+
+    let paths: Vec<(&Path, Normalizer)> = to_hash
+        .iter()
+        .map(|(i, _, _, normalizer)| (files[*i].1.as_path(), *normalizer))
+        .collect();
+    let hashes = hash_files(&paths);
+
+    // Hashing happens outside any transaction so the write lock is only held
+    // for the stat refresh itself. The refresh only applies to a row still
+    // holding the hash it was verified against: a concurrent
+    // `mark_reconciled` may have replaced it meanwhile.
+    let mut refreshed: Vec<(usize, String, FileStat, Normalizer)> = Vec::new();
+    for ((i, cached_hash, stat, normalizer), current) in to_hash.into_iter().zip(hashes) {
+        let current = current?;
+        if current == cached_hash {
+            verdicts[i] = Some(true);
+            refreshed.push((i, cached_hash, stat, normalizer));
+        } else {
+            verdicts[i] = None;
         }
-        let current_hash = hash_file(abs_path)?;
-        let cached: Option<String> = stmt.query_row(params![rel_path], |row| row.get(0)).ok();
-        match cached {
-            Some(h) if h == current_hash => unchanged.push(rel_path.clone()),
-            _ => changed.push(rel_path.clone()),
+    }
+    if !refreshed.is_empty() {
+        let tx = write_transaction(conn)?;
+        let mut refresh = tx
+            .prepare(
+                "UPDATE file_cache SET size = ?1, mtime_ns = ?2, stat_at = ?3
+                 WHERE spec_path = ?4 AND path = ?5 AND blake3_hash = ?6 AND normalizer = ?7",
+            )
+            .map_err(Error::cache("prepare error"))?;
+        for (i, hash, stat, normalizer) in refreshed {
+            refresh
+                .execute(params![
+                    stat.size,
+                    stat.mtime_ns,
+                    stat.taken_at,
+                    spec_path,
+                    &files[i].0,
+                    hash,
+                    normalizer.name(),
+                ])
+                .map_err(Error::cache("stat refresh failed"))?;
         }
+        drop(refresh);
+        tx.commit().map_err(Error::cache("commit error"))?;
     }
 
-    drop(stmt);
-    tx.commit().map_err(|e| format!("commit error: {e}"))?;
+    let mut changed = Vec::new();
+    let mut unchanged = Vec::new();
+    for ((rel_path, _), verdict) in files.iter().zip(verdicts) {
+        if verdict == Some(true) {
+            unchanged.push(rel_path.clone());
+        } else {
+            changed.push(rel_path.clone());
+        }
+    }
     Ok((changed, unchanged))
 }
 
-/// Batch upsert files in a single transaction with a prepared statement.
+/// Batch upsert reconciliation records for a spec in a single transaction
+/// with a prepared statement.
+///
+/// If the stored fingerprint for `spec_path` differs from `spec_fingerprint`,
+/// every existing record for that spec is deleted first -- they were made
+/// against a different revision of the spec. The new fingerprint is then
+/// stored alongside the records.
 ///
-/// Each entry in `files` is `(rel_path, hash)`. Returns the number of
-/// successfully upserted rows.
-pub fn upsert_batch(conn: &Connection, files: &[(String, String)]) -> Result<usize, String> {
-    let now = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_secs() as i64;
-
-    let tx = conn
-        .unchecked_transaction()
-        .map_err(|e| format!("transaction error: {e}"))?;
+/// Returns the number of successfully upserted rows.
+pub fn upsert_batch(
+    conn: &Connection,
+    spec_path: &str,
+    spec_fingerprint: &str,
+    files: &[FileRecord],
+) -> Result<usize> {
+    let tx = write_transaction(conn)?;
+    let now = crate::core::time::now_secs();
+    let count = write_records(&tx, spec_path, spec_fingerprint, files, now)?;
+    tx.commit().map_err(Error::cache("commit error"))?;
+    Ok(count)
+}
+
+/// `upsert_batch`, but recording the spec as marked at `marked_at` (epoch
+/// seconds) instead of now.
+///
+/// Used to restore records from the state file without making the spec look
+/// freshly reconciled to the next snapshot.
+pub fn seed_batch(
+    conn: &Connection,
+    spec_path: &str,
+    spec_fingerprint: &str,
+    files: &[FileRecord],
+    marked_at: i64,
+) -> Result<usize> {
+    let tx = write_transaction(conn)?;
+    let count = write_records(&tx, spec_path, spec_fingerprint, files, marked_at)?;
+    tx.commit().map_err(Error::cache("commit error"))?;
+    Ok(count)
+}
+
+/// Body of `upsert_batch`, run inside a transaction owned by the caller.
+/// `marked_at` is stored as the spec's `updated_at`; file rows use now, and
+/// keep the time their stat was taken as `stat_at`.
+fn write_records(
+    tx: &Connection,
+    spec_path: &str,
+    spec_fingerprint: &str,
+    files: &[FileRecord],
+    marked_at: i64,
+) -> Result<usize> {
+    let now = crate::core::time::now_secs();
+
+    let stored: Option<String> = tx
+        .query_row(
+            "SELECT fingerprint FROM spec_cache WHERE spec_path = ?1",
+            params![spec_path],
+            |row| row.get(0),
+        )
+        .ok();
+    if stored.as_deref() != Some(spec_fingerprint) {
+        tx.execute(
+            "DELETE FROM file_cache WHERE spec_path = ?1",
+            params![spec_path],
+        )
+        .map_err(Error::cache("invalidation failed"))?;
+    }
+    tx.execute(
+        "INSERT OR REPLACE INTO spec_cache (spec_path, fingerprint, updated_at) VALUES (?1, ?2, ?3)",
+        params![spec_path, spec_fingerprint, marked_at],
+    )
+    .map_err(Error::cache("spec fingerprint upsert failed"))?;
+
     let mut stmt = tx
         .prepare(
-            "INSERT OR REPLACE INTO file_cache (path, blake3_hash, updated_at) VALUES (?1, ?2, ?3)",
+            "INSERT OR REPLACE INTO file_cache
+             (spec_path, path, blake3_hash, updated_at, size, mtime_ns, stat_at, normalizer)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
         )
-        .map_err(|e| format!("prepare error: {e}"))?;
+        .map_err(Error::cache("prepare error"))?;
 
     let mut count = 0;
-    for (rel_path, hash) in files {
-        stmt.execute(params![rel_path, hash, now])
-            .map_err(|e| format!("upsert failed: {e}"))?;
+    for record in files {
+        stmt.execute(params![
+            spec_path,
+            record.path,
+            record.hash,
+            now,
+            record.stat.map(|s| s.size),
+            record.stat.map(|s| s.mtime_ns),
+            record.stat.map(|s| s.taken_at),
+            record.normalizer.name(),
+        ])
+        .map_err(Error::cache("upsert failed"))?;
         count += 1;
     }
-
-    drop(stmt);
-    tx.commit().map_err(|e| format!("commit error: {e}"))?;
     Ok(count)
 }
 
-/// Read all cached entries as (rel_path, blake3_hash) pairs.
+/// The records stored for one spec, as read by `read_specs`.
+#[derive(Debug)]
+pub struct SpecRecords {
+    pub spec_path: String,
+    pub fingerprint: String,
+    /// When `mark_reconciled` last wrote records for the spec (epoch seconds).
+    pub marked_at: i64,
+    pub files: Vec<ExportedFile>,
+}
+
+/// Read every spec fingerprint with its file records, ordered by path.
 ///
-/// Used by state snapshotting -- not for hot-path reconciliation.
-pub fn read_all(conn: &Connection) -> Result<Vec<(String, String)>, String> {
-    let mut stmt = conn
-        .prepare("SELECT path, blake3_hash FROM file_cache ORDER BY path")
-        .map_err(|e| format!("prepare error: {e}"))?;
-    let rows = stmt
-        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
-        .map_err(|e| format!("query error: {e}"))?;
-    let mut result = Vec::new();
-    for row in rows {
-        result.push(row.map_err(|e| format!("row error: {e}"))?);
+/// Used by state snapshotting and `cache export` -- not for hot-path
+/// reconciliation.
+pub fn read_specs(conn: &Connection) -> Result<Vec<SpecRecords>> {
+    let mut specs_stmt = conn
+        .prepare("SELECT spec_path, fingerprint, updated_at FROM spec_cache ORDER BY spec_path")
+        .map_err(Error::cache("prepare error"))?;
+    let mut specs: Vec<SpecRecords> = specs_stmt
+        .query_map([], |row| {
+            Ok(SpecRecords {
+                spec_path: row.get(0)?,
+                fingerprint: row.get(1)?,
+                marked_at: row.get(2)?,
+                files: Vec::new(),
+            })
+        })
+        .map_err(Error::cache("query error"))?
+        .collect::<Result<_, _>>()
+        .map_err(Error::cache("row error"))?;
+
+    let mut files_stmt = conn
+        .prepare(
+            "SELECT path, blake3_hash, normalizer FROM file_cache
+             WHERE spec_path = ?1 ORDER BY path",
+        )
+        .map_err(Error::cache("prepare error"))?;
+    for spec in &mut specs {
+        spec.files = files_stmt
+            .query_map(params![spec.spec_path], |row| {
+                Ok(ExportedFile {
+                    path: row.get(0)?,
+                    hash: row.get(1)?,
+                    normalizer: row.get(2)?,
+                })
+            })
+            .map_err(Error::cache("query error"))?
+            .collect::<Result<_, _>>()
+            .map_err(Error::cache("row error"))?;
     }
-    Ok(result)
+    Ok(specs)
 }
 
 /// Return the number of cached entries and the most recent `updated_at` timestamp.
 ///
 /// The timestamp is `None` when the cache is empty.
-pub fn status(conn: &Connection) -> Result<(usize, Option<i64>), String> {
+pub fn status(conn: &Connection) -> Result<(usize, Option<i64>)> {
     let count: i64 = conn
         .query_row("SELECT COUNT(*) FROM file_cache", [], |row| row.get(0))
-        .map_err(|e| format!("count query failed: {e}"))?;
+        .map_err(Error::cache("count query failed"))?;
     let count = count as usize;
     let newest: Option<i64> = conn
         .query_row("SELECT MAX(updated_at) FROM file_cache", [], |row| {
             row.get(0)
         })
-        .map_err(|e| format!("newest query failed: {e}"))?;
+        .map_err(Error::cache("newest query failed"))?;
     Ok((count, newest))
 }
 
+/// Delete every reconciliation record, spec fingerprint, pipeline step
+/// fingerprint and derived input record in one transaction.
+///
+/// The database file stays in place so connections held by other processes
+/// remain valid; run history is kept. Returns the number of file records
+/// removed.
+pub fn clear(conn: &Connection) -> Result<usize> {
+    let tx = write_transaction(conn)?;
+    let removed = tx
+        .execute("DELETE FROM file_cache", [])
+        .map_err(Error::cache("could not clear records"))?;
+    tx.execute("DELETE FROM spec_cache", [])
+        .map_err(Error::cache("could not clear spec fingerprints"))?;
+    tx.execute("DELETE FROM pipeline_steps", [])
+        .map_err(Error::cache("could not clear pipeline step fingerprints"))?;
+    tx.execute("DELETE FROM derived_inputs", [])
+        .map_err(Error::cache("could not clear derived inputs"))?;
+    tx.commit().map_err(Error::cache("commit error"))?;
+    Ok(removed)
+}
+
+/// Remove records that point at files no longer on disk.
+///
+/// A spec whose own file is gone loses its fingerprint and every record made
+/// for it; otherwise only the rows for missing artifact files are deleted.
+/// Runs in a single transaction. Returns `(files_removed, specs_removed)`.
+pub fn prune(conn: &Connection, project_root: &Path) -> Result<(usize, usize)> {
+    let tx = write_transaction(conn)?;
+
+    let spec_paths = query_strings(&tx, "SELECT spec_path FROM spec_cache")?;
+    let mut specs_removed = 0;
+    let mut files_removed = 0;
+    {
+        let mut delete_spec = tx
+            .prepare("DELETE FROM spec_cache WHERE spec_path = ?1")
+            .map_err(Error::cache("prepare error"))?;
+        let mut delete_spec_files = tx
+            .prepare("DELETE FROM file_cache WHERE spec_path = ?1")
+            .map_err(Error::cache("prepare error"))?;
+        for spec_path in spec_paths {
+            if !project_root.join(&spec_path).exists() {
+                delete_spec
+                    .execute(params![spec_path])
+                    .map_err(Error::cache("prune failed"))?;
+                files_removed += delete_spec_files
+                    .execute(params![spec_path])
+                    .map_err(Error::cache("prune failed"))?;
+                specs_removed += 1;
+            }
+        }
+    }
+
+    let paths = query_strings(&tx, "SELECT DISTINCT path FROM file_cache")?;
+    {
+        let mut delete_file = tx
+            .prepare("DELETE FROM file_cache WHERE path = ?1")
+            .map_err(Error::cache("prepare error"))?;
+        for path in paths {
+            if !project_root.join(&path).exists() {
+                files_removed += delete_file
+                    .execute(params![path])
+                    .map_err(Error::cache("prune failed"))?;
+            }
+        }
+    }
+
+    tx.commit().map_err(Error::cache("commit error"))?;
+    Ok((files_removed, specs_removed))
+}
+
+fn query_strings(conn: &Connection, sql: &str) -> Result<Vec<String>> {
+    let mut stmt = conn.prepare(sql).map_err(Error::cache("prepare error"))?;
+    stmt.query_map([], |row| row.get(0))
+        .map_err(Error::cache("query error"))?
+        .collect::<Result<_, _>>()
+        .map_err(Error::cache("row error"))
+}
+
+/// Version of the `cache export` file format.
+pub const EXPORT_FORMAT: u32 = 1;
+
+/// Portable snapshot of the cache written by `cache export`.
+///
+/// Only spec fingerprints and content hashes are included. Sizes and mtimes
+/// are specific to one checkout, so imported records carry no stat and are
+/// verified by hash the first time they are checked.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CacheExport {
+    pub format: u32,
+    pub specs: Vec<ExportedSpec>,
+}
+
+/// The reconciliation records for one spec in a `CacheExport`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExportedSpec {
+    pub spec_path: String,
+    pub fingerprint: String,
+    /// When the records were marked (epoch seconds); absent in exports from
+    /// older versions, which are imported as marked now.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub marked_at: Option<i64>,
+    pub files: Vec<ExportedFile>,
+}
+
+/// One `(path, hash)` record in an `ExportedSpec`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExportedFile {
+    pub path: String,
+    pub hash: String,
+    /// Normalizer the hash was computed with; `raw` when absent.
+    #[serde(default = "raw_normalizer")]
+    pub normalizer: String,
+}
+
+fn raw_normalizer() -> String {
+    Normalizer::Raw.name().to_string()
+}
+
+/// Collect every spec fingerprint and its records, ordered by path.
+pub fn export(conn: &Connection) -> Result<CacheExport> {
+    let specs = read_specs(conn)?
+        .into_iter()
+        .map(|spec| ExportedSpec {
+            spec_path: spec.spec_path,
+            fingerprint: spec.fingerprint,
+            marked_at: Some(spec.marked_at),
+            files: spec.files,
+        })
+        .collect();
+    Ok(CacheExport {
+        format: EXPORT_FORMAT,
+        specs,
+    })
+}
+
+/// Load an export into the cache in a single transaction.
+///
+/// Each spec is written as by `upsert_batch`: if its local fingerprint differs
+/// from the exported one, the local records for that spec are replaced;
+/// otherwise the exported records are merged over them. As with
+/// `seed_batch`, the spec keeps its exported `marked_at`, so the next snapshot
+/// does not take the import for a new reconciliation. Returns the number of
+/// file records written.
+pub fn import(conn: &Connection, export: &CacheExport) -> Result<usize> {
+    if export.format != EXPORT_FORMAT {
+        return Err(Error::UnsupportedVersion {
+            what: "cache export",
+            found: export.format.to_string(),
+            supported: EXPORT_FORMAT.to_string(),
+        });
+    }
+    let tx = write_transaction(conn)?;
+    let mut count = 0;
+    for spec in &export.specs {
+        let records = spec
+            .files
+            .iter()
+            .map(|f| {
+                let normalizer =
+                    Normalizer::from_name(&f.normalizer).ok_or_else(|| Error::UnknownValue {
+                        what: "normalizer",
+                        value: f.normalizer.clone(),
+                        expected: crate::core::normalize::NAMES.join(", "),
+                    })?;
+                Ok(FileRecord {
+                    path: f.path.clone(),
+                    hash: f.hash.clone(),
+                    stat: None,
+                    normalizer,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let marked_at = spec.marked_at.unwrap_or_else(crate::core::time::now_secs);
+        count += write_records(&tx, &spec.spec_path, &spec.fingerprint, &records, marked_at)?;
+    }
+    tx.commit().map_err(Error::cache("commit error"))?;
+    Ok(count)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use tempfile::TempDir;
 
+    const SPEC: &str = ".notarai/a.spec.yaml";
+    const FP: &str = "spec_fp_1";
+
     #[test]
     fn hash_is_consistent() {
         let tmp = TempDir::new().unwrap();
@@ -214,8 +915,8 @@ mod tests {
         std::fs::write(&file, b"hello").unwrap();
         let conn = open_cache_db(tmp.path()).unwrap();
         let hash = hash_file(&file).unwrap();
-        upsert(&conn, file.to_str().unwrap(), &hash).unwrap();
-        let result = check_changed(&conn, file.to_str().unwrap(), &file).unwrap();
+        upsert(&conn, SPEC, FP, file.to_str().unwrap(), &hash).unwrap();
+        let result = check_changed(&conn, SPEC, FP, file.to_str().unwrap(), &file).unwrap();
         assert!(result.is_none(), "expected None for unchanged file");
     }
 
@@ -226,9 +927,9 @@ mod tests {
         std::fs::write(&file, b"hello").unwrap();
         let conn = open_cache_db(tmp.path()).unwrap();
         let hash = hash_file(&file).unwrap();
-        upsert(&conn, file.to_str().unwrap(), &hash).unwrap();
+        upsert(&conn, SPEC, FP, file.to_str().unwrap(), &hash).unwrap();
         std::fs::write(&file, b"world").unwrap();
-        let result = check_changed(&conn, file.to_str().unwrap(), &file).unwrap();
+        let result = check_changed(&conn, SPEC, FP, file.to_str().unwrap(), &file).unwrap();
         assert!(result.is_some(), "expected Some for modified file");
     }
 
@@ -237,22 +938,420 @@ mod tests {
         let tmp = TempDir::new().unwrap();
         let conn = open_cache_db(tmp.path()).unwrap();
         let missing = tmp.path().join("nonexistent.txt");
-        let result = check_changed(&conn, missing.to_str().unwrap(), &missing).unwrap();
+        let result = check_changed(&conn, SPEC, FP, missing.to_str().unwrap(), &missing).unwrap();
         assert!(result.is_some(), "expected Some for missing file");
     }
 
     #[test]
-    fn test_read_all() {
+    fn record_for_one_spec_does_not_apply_to_another() {
+        let tmp = TempDir::new().unwrap();
+        let file = tmp.path().join("shared.txt");
+        std::fs::write(&file, b"shared").unwrap();
+        let conn = open_cache_db(tmp.path()).unwrap();
+        let hash = hash_file(&file).unwrap();
+        upsert(&conn, SPEC, FP, "shared.txt", &hash).unwrap();
+        let other = check_changed(&conn, ".notarai/b.spec.yaml", FP, "shared.txt", &file).unwrap();
+        assert!(other.is_some(), "spec B must not see spec A's record");
+        let own = check_changed(&conn, SPEC, FP, "shared.txt", &file).unwrap();
+        assert!(own.is_none());
+    }
+
+    #[test]
+    fn spec_fingerprint_change_invalidates_records() {
+        let tmp = TempDir::new().unwrap();
+        let a = tmp.path().join("a.txt");
+        let b = tmp.path().join("b.txt");
+        std::fs::write(&a, b"a").unwrap();
+        std::fs::write(&b, b"b").unwrap();
+        let conn = open_cache_db(tmp.path()).unwrap();
+        upsert(&conn, SPEC, FP, "a.txt", &hash_file(&a).unwrap()).unwrap();
+        upsert(&conn, SPEC, FP, "b.txt", &hash_file(&b).unwrap()).unwrap();
+
+        // Checking under a new fingerprint ignores the old records.
+        assert!(
+            check_changed(&conn, SPEC, "spec_fp_2", "a.txt", &a)
+                .unwrap()
+                .is_some()
+        );
+
+        // Marking under the new fingerprint drops records made under the old one.
+        upsert(&conn, SPEC, "spec_fp_2", "a.txt", &hash_file(&a).unwrap()).unwrap();
+        assert!(
+            check_changed(&conn, SPEC, "spec_fp_2", "a.txt", &a)
+                .unwrap()
+                .is_none()
+        );
+        assert!(
+            check_changed(&conn, SPEC, "spec_fp_2", "b.txt", &b)
+                .unwrap()
+                .is_some()
+        );
+    }
+
+    /// Record `file` for SPEC with its current stat, backdating its mtime so
+    /// the fast path is eligible.
+    fn record_with_stat(conn: &Connection, rel: &str, file: &Path) {
+        let past = std::time::SystemTime::now() - Duration::from_secs(10);
+        std::fs::File::options()
+            .write(true)
+            .open(file)
+            .unwrap()
+            .set_modified(past)
+            .unwrap();
+        let record = FileRecord {
+            path: rel.to_string(),
+            stat: stat_file(file),
+            hash: hash_file(file).unwrap(),
+            normalizer: Normalizer::Raw,
+        };
+        upsert_batch(conn, SPEC, FP, &[record]).unwrap();
+    }
+
+    #[test]
+    fn batch_rehashes_files_modified_in_the_second_their_stat_was_taken() {
+        let tmp = TempDir::new().unwrap();
+        let file = tmp.path().join("a.txt");
+        std::fs::write(&file, b"aaaa").unwrap();
+        let conn = open_cache_db(tmp.path()).unwrap();
+        let stat = stat_file(&file).unwrap();
+        let record = FileRecord {
+            path: "a.txt".to_string(),
+            hash: hash_file(&file).unwrap(),
+            // Taken in the same second as the write.
+            stat: Some(FileStat {
+                taken_at: stat.mtime_ns / 1_000_000_000,
+                ..stat
+            }),
+            normalizer: Normalizer::Raw,
+        };
+        upsert_batch(&conn, SPEC, FP, &[record]).unwrap();
+
+        // Rewritten with the same size and mtime: the stat cannot be trusted.
+        let mtime = std::fs::metadata(&file).unwrap().modified().unwrap();
+        std::fs::write(&file, b"bbbb").unwrap();
+        std::fs::File::options()
+            .write(true)
+            .open(&file)
+            .unwrap()
+            .set_modified(mtime)
+            .unwrap();
+        let files = vec![("a.txt".to_string(), file.clone())];
+        let (changed, _) = check_changed_batch(&conn, SPEC, FP, &files, false).unwrap();
+        assert_eq!(changed, vec!["a.txt".to_string()]);
+        let stat_at: i64 = conn
+            .query_row("SELECT stat_at FROM file_cache", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(stat_at, stat.mtime_ns / 1_000_000_000);
+    }
+
+    #[test]
+    fn batch_fast_path_trusts_matching_stat_unless_verifying() {
+        let tmp = TempDir::new().unwrap();
+        let file = tmp.path().join("a.txt");
+        std::fs::write(&file, b"aaaa").unwrap();
+        let conn = open_cache_db(tmp.path()).unwrap();
+        record_with_stat(&conn, "a.txt", &file);
+
+        // Same size, mtime restored: only a full verification notices.
+        let mtime = std::fs::metadata(&file).unwrap().modified().unwrap();
+        std::fs::write(&file, b"bbbb").unwrap();
+        std::fs::File::options()
+            .write(true)
+            .open(&file)
+            .unwrap()
+            .set_modified(mtime)
+            .unwrap();
+
+        let files = vec![("a.txt".to_string(), file.clone())];
+        let (changed, _) = check_changed_batch(&conn, SPEC, FP, &files, false).unwrap();
+        assert!(changed.is_empty(), "fast path should skip rehashing");
+        let (changed, _) = check_changed_batch(&conn, SPEC, FP, &files, true).unwrap();
+        assert_eq!(changed, vec!["a.txt".to_string()]);
+    }
+
+    #[test]
+    fn batch_rehashes_when_stat_differs() {
+        let tmp = TempDir::new().unwrap();
+        let a = tmp.path().join("a.txt");
+        let b = tmp.path().join("b.txt");
+        let c = tmp.path().join("c.txt");
+        std::fs::write(&a, b"a").unwrap();
+        std::fs::write(&b, b"b").unwrap();
+        std::fs::write(&c, b"c").unwrap();
+        let conn = open_cache_db(tmp.path()).unwrap();
+        record_with_stat(&conn, "a.txt", &a);
+        record_with_stat(&conn, "b.txt", &b);
+        record_with_stat(&conn, "c.txt", &c);
+
+        std::fs::write(&a, b"a changed").unwrap();
+        // Touch without changing content.
+        std::fs::write(&b, b"b").unwrap();
+
+        let files: Vec<(String, PathBuf)> = [("a.txt", &a), ("b.txt", &b), ("c.txt", &c)]
+            .iter()
+            .map(|(r, p)| (r.to_string(), (*p).clone()))
+            .collect();
+        let fast = check_changed_batch(&conn, SPEC, FP, &files, false).unwrap();
+        let full = check_changed_batch(&conn, SPEC, FP, &files, true).unwrap();
+        assert_eq!(fast, full);
+        assert_eq!(fast.0, vec!["a.txt".to_string()]);
+        assert_eq!(fast.1, vec!["b.txt".to_string(), "c.txt".to_string()]);
+    }
+
+    #[test]
+    fn hash_file_matches_in_memory_hash_for_large_files() {
+        let tmp = TempDir::new().unwrap();
+        let file = tmp.path().join("big.bin");
+        let data: Vec<u8> = (0..4_000_000u32).map(|i| (i % 251) as u8).collect();
+        std::fs::write(&file, &data).unwrap();
+        assert_eq!(hash_file(&file).unwrap(), hash_bytes(&data));
+    }
+
+    #[test]
+    fn fresh_db_is_at_current_schema_version() {
+        let tmp = TempDir::new().unwrap();
+        let conn = open_cache_db(tmp.path()).unwrap();
+        assert_eq!(schema_version(&conn).unwrap(), SCHEMA_VERSION);
+        drop(conn);
+        // Reopening an up-to-date database is a no-op.
+        let conn = open_cache_db(tmp.path()).unwrap();
+        assert_eq!(schema_version(&conn).unwrap(), SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn unversioned_path_only_db_is_upgraded_in_place() {
+        let tmp = TempDir::new().unwrap();
+        let path = db_path(tmp.path());
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        {
+            let conn = Connection::open(&path).unwrap();
+            conn.execute_batch(
+                "CREATE TABLE file_cache (path TEXT PRIMARY KEY, blake3_hash TEXT NOT NULL, updated_at INTEGER NOT NULL);
+                 INSERT INTO file_cache VALUES ('a.txt', 'h', 0);",
+            )
+            .unwrap();
+        }
+        let conn = open_cache_db(tmp.path()).unwrap();
+        assert_eq!(schema_version(&conn).unwrap(), SCHEMA_VERSION);
+        assert_eq!(status(&conn).unwrap().0, 0);
+        upsert(&conn, SPEC, FP, "a.txt", "h").unwrap();
+        assert_eq!(status(&conn).unwrap().0, 1);
+        assert!(
+            !table_columns(&conn, "reconciliation_runs")
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn v1_db_keeps_records_when_upgraded() {
+        let tmp = TempDir::new().unwrap();
+        let path = db_path(tmp.path());
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        {
+            let conn = Connection::open(&path).unwrap();
+            migrate_v1(&conn).unwrap();
+            conn.pragma_update(None, "user_version", 1).unwrap();
+            conn.execute(
+                "INSERT INTO file_cache (spec_path, path, blake3_hash, updated_at)
+                 VALUES (?1, 'a.txt', 'h', 0)",
+                params![SPEC],
+            )
+            .unwrap();
+        }
+        let conn = open_cache_db(tmp.path()).unwrap();
+        assert_eq!(schema_version(&conn).unwrap(), SCHEMA_VERSION);
+        assert_eq!(status(&conn).unwrap().0, 1);
+        let normalizer: String = conn
+            .query_row("SELECT normalizer FROM file_cache", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(normalizer, "raw");
+    }
+
+    #[test]
+    fn future_schema_version_is_rejected() {
+        let tmp = TempDir::new().unwrap();
+        let path = db_path(tmp.path());
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        {
+            let conn = Connection::open(&path).unwrap();
+            conn.pragma_update(None, "user_version", SCHEMA_VERSION + 1)
+                .unwrap();
+        }
+        let err = open_cache_db(tmp.path()).err().unwrap();
+        assert!(
+            matches!(
+                err,
+                Error::UnsupportedVersion {
+                    what: "cache DB",
+                    ..
+                }
+            ),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_read_specs() {
+        let tmp = TempDir::new().unwrap();
+        let conn = open_cache_db(tmp.path()).unwrap();
+        upsert(&conn, SPEC, FP, "b.txt", "hash_b").unwrap();
+        upsert(&conn, SPEC, FP, "a.txt", "hash_a").unwrap();
+        upsert(&conn, ".notarai/b.spec.yaml", FP, "c.txt", "hash_c").unwrap();
+        upsert(&conn, ".notarai/b.spec.yaml", FP, "a.txt", "hash_a").unwrap();
+        let specs = read_specs(&conn).unwrap();
+        let paths: Vec<_> = specs.iter().map(|s| s.spec_path.as_str()).collect();
+        assert_eq!(paths, [SPEC, ".notarai/b.spec.yaml"]);
+        let files: Vec<_> = specs[0]
+            .files
+            .iter()
+            .map(|f| (f.path.as_str(), f.hash.as_str()))
+            .collect();
+        assert_eq!(files, [("a.txt", "hash_a"), ("b.txt", "hash_b")]);
+        assert_eq!(specs[1].files.len(), 2);
+        assert_eq!(specs[0].fingerprint, FP);
+        assert!(specs[0].marked_at > 0);
+    }
+
+    #[test]
+    fn prune_drops_missing_files_and_specs() {
         let tmp = TempDir::new().unwrap();
+        std::fs::create_dir_all(tmp.path().join(".notarai")).unwrap();
+        std::fs::write(tmp.path().join(SPEC), b"spec").unwrap();
+        std::fs::write(tmp.path().join("kept.txt"), b"kept").unwrap();
         let conn = open_cache_db(tmp.path()).unwrap();
-        upsert(&conn, "a.txt", "hash_a").unwrap();
-        upsert(&conn, "b.txt", "hash_b").unwrap();
-        upsert(&conn, "c.txt", "hash_c").unwrap();
-        let rows = read_all(&conn).unwrap();
-        assert_eq!(rows.len(), 3);
-        // BTreeMap ordering not required here (SQLite ORDER BY path)
-        assert!(rows.iter().any(|(p, h)| p == "a.txt" && h == "hash_a"));
-        assert!(rows.iter().any(|(p, h)| p == "b.txt" && h == "hash_b"));
-        assert!(rows.iter().any(|(p, h)| p == "c.txt" && h == "hash_c"));
+        upsert(&conn, SPEC, FP, "kept.txt", "h1").unwrap();
+        upsert(&conn, SPEC, FP, "gone.txt", "h2").unwrap();
+        upsert(&conn, ".notarai/gone.spec.yaml", FP, "kept.txt", "h1").unwrap();
+
+        assert_eq!(prune(&conn, tmp.path()).unwrap(), (2, 1));
+        let specs = read_specs(&conn).unwrap();
+        assert_eq!(specs.len(), 1);
+        assert_eq!(specs[0].spec_path, SPEC);
+        assert_eq!(specs[0].files.len(), 1);
+        assert_eq!(specs[0].files[0].path, "kept.txt");
+    }
+
+    #[test]
+    fn export_import_round_trip_without_stat() {
+        let src = TempDir::new().unwrap();
+        let file = src.path().join("a.txt");
+        std::fs::write(&file, b"aaaa").unwrap();
+        let conn = open_cache_db(src.path()).unwrap();
+        record_with_stat(&conn, "a.txt", &file);
+        let export = export(&conn).unwrap();
+        assert_eq!(export.specs.len(), 1);
+        assert_eq!(export.specs[0].files.len(), 1);
+
+        let dst = TempDir::new().unwrap();
+        let dst_file = dst.path().join("a.txt");
+        std::fs::write(&dst_file, b"aaaa").unwrap();
+        let dst_conn = open_cache_db(dst.path()).unwrap();
+        assert_eq!(import(&dst_conn, &export).unwrap(), 1);
+        let size: Option<i64> = dst_conn
+            .query_row("SELECT size FROM file_cache", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(size, None);
+        let (changed, unchanged) = check_changed_batch(
+            &dst_conn,
+            SPEC,
+            FP,
+            &[("a.txt".to_string(), dst_file)],
+            false,
+        )
+        .unwrap();
+        assert!(changed.is_empty());
+        assert_eq!(unchanged, vec!["a.txt".to_string()]);
+    }
+
+    #[test]
+    fn import_rejects_unknown_format() {
+        let tmp = TempDir::new().unwrap();
+        let conn = open_cache_db(tmp.path()).unwrap();
+        let export = CacheExport {
+            format: EXPORT_FORMAT + 1,
+            specs: vec![],
+        };
+        assert!(import(&conn, &export).is_err());
+    }
+
+    #[test]
+    fn clear_keeps_db_file_and_empties_records() {
+        let tmp = TempDir::new().unwrap();
+        let conn = open_cache_db(tmp.path()).unwrap();
+        upsert(&conn, SPEC, FP, "a.txt", "h").unwrap();
+        let other = open_cache_db(tmp.path()).unwrap();
+        assert_eq!(clear(&conn).unwrap(), 1);
+        assert!(db_path(tmp.path()).exists());
+        assert_eq!(status(&other).unwrap(), (0, None));
+        assert!(read_specs(&other).unwrap().is_empty());
+    }
+
+    #[test]
+    fn concurrent_writers_do_not_lose_records() {
+        let tmp = TempDir::new().unwrap();
+        let root = tmp.path().to_path_buf();
+        open_cache_db(&root).unwrap();
+        let handles: Vec<_> = (0..8)
+            .map(|t| {
+                let root = root.clone();
+                std::thread::spawn(move || {
+                    let conn = open_cache_db(&root).unwrap();
+                    for i in 0..20 {
+                        let spec = format!(".notarai/s{}.spec.yaml", t % 2);
+                        let record = FileRecord {
+                            path: format!("t{t}_{i}.txt"),
+                            hash: "h".to_string(),
+                            stat: None,
+                            normalizer: Normalizer::Raw,
+                        };
+                        upsert_batch(&conn, &spec, FP, &[record]).unwrap();
+                        crate::core::runs::record_marks(&conn, &spec, &[]).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for h in handles {
+            h.join().unwrap();
+        }
+        let conn = open_cache_db(&root).unwrap();
+        assert_eq!(status(&conn).unwrap().0, 160);
+    }
+
+    #[test]
+    fn check_rehashes_with_the_recorded_normalizer() {
+        let tmp = TempDir::new().unwrap();
+        let file = tmp.path().join("a.rs");
+        std::fs::write(&file, b"fn a() {}\n").unwrap();
+        let conn = open_cache_db(tmp.path()).unwrap();
+        let record = FileRecord {
+            path: "a.rs".to_string(),
+            hash: hash_file_with(&file, Normalizer::Text).unwrap(),
+            stat: stat_file(&file),
+            normalizer: Normalizer::Text,
+        };
+        upsert_batch(&conn, SPEC, FP, &[record]).unwrap();
+        let files = vec![("a.rs".to_string(), file.clone())];
+
+        std::fs::write(&file, b"fn a() {}   \r\n").unwrap();
+        let (changed, _) = check_changed_batch(&conn, SPEC, FP, &files, true).unwrap();
+        assert!(changed.is_empty());
+
+        conn.execute("UPDATE file_cache SET normalizer = 'future'", [])
+            .unwrap();
+        let (changed, _) = check_changed_batch(&conn, SPEC, FP, &files, true).unwrap();
+        assert_eq!(changed, vec!["a.rs".to_string()]);
+    }
+
+    #[test]
+    fn spec_fingerprint_ignores_line_endings() {
+        assert_eq!(
+            spec_fingerprint(b"intent: x\n"),
+            spec_fingerprint(b"intent: x  \r\n")
+        );
+        assert_ne!(
+            spec_fingerprint(b"intent: x\n"),
+            spec_fingerprint(b"intent: y\n")
+        );
     }
 }
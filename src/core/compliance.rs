@@ -0,0 +1,428 @@
+//! Compliance control matrix and the guard against removing the statements
+//! that satisfy a control.
+//!
+//! A spec's `compliance.frameworks` cite invariants and constraints by exact
+//! text, per control or for a whole framework. The matrix lists each control
+//! with the statements it cites and whether the spec still has them; a
+//! control is unsatisfied when it cites nothing the spec has. The guard
+//! compares a spec with its revision at a git ref and reports every cited
+//! statement that was deleted or reworded since.
+
+use crate::core::mcp_tools::normalize_rel;
+use crate::core::spec::{SatisfiedBy, Spec};
+use serde::Serialize;
+use similar::TextDiff;
+use std::path::Path;
+
+/// Edits that keep at least this share of a statement's characters count as
+/// a rewording rather than a deletion.
+const REWORD_SIMILARITY: f32 = 0.6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StatementKind {
+    Invariant,
+    Constraint,
+}
+
+impl StatementKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            StatementKind::Invariant => "invariant",
+            StatementKind::Constraint => "constraint",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Statement {
+    pub kind: StatementKind,
+    pub text: String,
+    /// Whether the spec has the statement among its own invariants or
+    /// constraints.
+    pub present: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ControlRow {
+    /// The control id, or `None` for a framework-level `satisfied_by`.
+    pub id: Option<String>,
+    pub satisfied_by: Vec<Statement>,
+    /// True when at least one cited statement is present.
+    pub satisfied: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FrameworkRows {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub level: Option<String>,
+    pub controls: Vec<ControlRow>,
+}
+
+/// The control matrix of `spec`, in declaration order. A framework-level
+/// `satisfied_by` comes before the framework's controls.
+pub fn matrix(spec: &Spec) -> Vec<FrameworkRows> {
+    let Some(compliance) = &spec.compliance else {
+        return Vec::new();
+    };
+    compliance
+        .frameworks
+        .iter()
+        .map(|framework| {
+            let framework_row = framework
+                .satisfied_by
+                .as_ref()
+                .map(|s| control_row(spec, None, Some(s)));
+            let controls = framework
+                .controls
+                .iter()
+                .map(|c| control_row(spec, Some(&c.id), c.satisfied_by.as_ref()));
+            FrameworkRows {
+                name: framework.name.clone(),
+                level: framework.level.clone(),
+                controls: framework_row.into_iter().chain(controls).collect(),
+            }
+        })
+        .collect()
+}
+
+fn control_row(spec: &Spec, id: Option<&str>, satisfied_by: Option<&SatisfiedBy>) -> ControlRow {
+    let satisfied_by: Vec<Statement> = cited(satisfied_by)
+        .map(|(kind, text)| Statement {
+            kind,
+            text: text.to_string(),
+            present: statements(spec, kind).iter().any(|s| s == text),
+        })
+        .collect();
+    ControlRow {
+        id: id.map(str::to_string),
+        satisfied: satisfied_by.iter().any(|s| s.present),
+        satisfied_by,
+    }
+}
+
+fn cited(satisfied_by: Option<&SatisfiedBy>) -> impl Iterator<Item = (StatementKind, &str)> {
+    satisfied_by.into_iter().flat_map(|s| {
+        let invariants = s
+            .invariants
+            .iter()
+            .map(|t| (StatementKind::Invariant, t.as_str()));
+        let constraints = s
+            .constraints
+            .iter()
+            .map(|t| (StatementKind::Constraint, t.as_str()));
+        invariants.chain(constraints)
+    })
+}
+
+fn statements(spec: &Spec, kind: StatementKind) -> &[String] {
+    match kind {
+        StatementKind::Invariant => &spec.invariants,
+        StatementKind::Constraint => &spec.constraints,
+    }
+}
+
+/// Render the matrices of `specs` as Markdown: one table per framework.
+pub fn to_markdown(specs: &[(String, Vec<FrameworkRows>)]) -> String {
+    let mut out = String::from("# Compliance matrix\n");
+    for (spec_path, frameworks) in specs {
+        out.push_str(&format!("\n## {spec_path}\n"));
+        for framework in frameworks {
+            match &framework.level {
+                Some(level) => out.push_str(&format!("\n### {} ({level})\n\n", framework.name)),
+                None => out.push_str(&format!("\n### {}\n\n", framework.name)),
+            }
+            out.push_str("| Control | Satisfied by | Status |\n");
+            out.push_str("| ------- | ------------ | ------ |\n");
+            for control in &framework.controls {
+                let cited: Vec<String> = control
+                    .satisfied_by
+                    .iter()
+                    .map(|s| {
+                        let missing = if s.present { "" } else { " **(missing)**" };
+                        format!("{}: {}{missing}", s.kind.as_str(), escape(&s.text))
+                    })
+                    .collect();
+                let cited = if cited.is_empty() {
+                    "—".to_string()
+                } else {
+                    cited.join("<br>")
+                };
+                let status = if control.satisfied {
+                    "satisfied"
+                } else {
+                    "**UNSATISFIED**"
+                };
+                let id = control.id.as_deref().map_or("*(framework)*", |id| id);
+                out.push_str(&format!("| {} | {cited} | {status} |\n", escape(id)));
+            }
+        }
+    }
+    out
+}
+
+fn escape(text: &str) -> String {
+    text.replace('|', "\\|")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Change {
+    Deleted,
+    Reworded,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Removal {
+    pub framework: String,
+    /// The control id, or `None` for a framework-level `satisfied_by`.
+    pub control: Option<String>,
+    pub kind: StatementKind,
+    pub change: Change,
+    /// The statement as it was at the base revision.
+    pub text: String,
+    /// The statement's new wording, for a rewording.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub now: Option<String>,
+}
+
+/// The statements cited by a control of `base` that `current` no longer has
+/// word for word. Statements the base revision cited but did not have are
+/// ignored. A removed statement counts as reworded when `current` has a
+/// similar statement of the same kind that `base` did not; `current` is
+/// `None` when the spec was deleted.
+pub fn removed_statements(base: &Spec, current: Option<&Spec>) -> Vec<Removal> {
+    let mut removals = Vec::new();
+    let Some(compliance) = &base.compliance else {
+        return removals;
+    };
+    for framework in &compliance.frameworks {
+        let controls = framework
+            .satisfied_by
+            .as_ref()
+            .map(|s| (None, s))
+            .into_iter()
+            .chain(
+                framework
+                    .controls
+                    .iter()
+                    .filter_map(|c| Some((Some(&c.id), c.satisfied_by.as_ref()?))),
+            );
+        for (control, satisfied_by) in controls {
+            for (kind, text) in cited(Some(satisfied_by)) {
+                let before = statements(base, kind);
+                if !before.iter().any(|s| s == text) {
+                    continue;
+                }
+                let after = current.map(|c| statements(c, kind)).unwrap_or_default();
+                if after.iter().any(|s| s == text) {
+                    continue;
+                }
+                let now = after
+                    .iter()
+                    .filter(|s| !before.contains(s))
+                    .map(|s| (TextDiff::from_chars(text, s.as_str()).ratio(), s))
+                    .filter(|(ratio, _)| *ratio >= REWORD_SIMILARITY)
+                    .max_by(|a, b| a.0.total_cmp(&b.0))
+                    .map(|(_, s)| s.clone());
+                removals.push(Removal {
+                    framework: framework.name.clone(),
+                    control: control.cloned(),
+                    kind,
+                    change: if now.is_some() {
+                        Change::Reworded
+                    } else {
+                        Change::Deleted
+                    },
+                    text: text.to_string(),
+                    now,
+                });
+            }
+        }
+    }
+    removals
+}
+
+/// The cited statements removed between git revision `base` and `head` (the
+/// working tree when `None`) from each spec that had compliance frameworks at
+/// `base`, restricted to `specs` (project-relative) when given. A spec in
+/// `specs` that does not exist at `base` is an error.
+pub fn removals(
+    project_root: &Path,
+    specs: &[String],
+    base: &str,
+    head: Option<&str>,
+) -> crate::Result<Vec<(String, Vec<Removal>)>> {
+    let at_base = specs_at(project_root, base)?;
+    let at_head = head.map(|rev| specs_at(project_root, rev)).transpose()?;
+    let selected: Vec<&str> = specs.iter().map(|s| normalize_rel(s)).collect();
+    if let Some(missing) = selected.iter().find(|s| !at_base.iter().any(|p| p == *s)) {
+        return Err(crate::Error::SpecNotAtRevision {
+            path: missing.to_string(),
+            rev: base.to_string(),
+        });
+    }
+    let parse = |content: &str, path: &str, rev: Option<&str>| {
+        Spec::parse(content).map_err(|e| crate::Error::InvalidSpec {
+            path: match rev {
+                Some(rev) => format!("{path} at {rev}"),
+                None => path.to_string(),
+            },
+            message: e.to_string(),
+        })
+    };
+    let mut reports = Vec::new();
+    for path in at_base {
+        if !selected.is_empty() && !selected.contains(&path.as_str()) {
+            continue;
+        }
+        let before = parse(&read_at(project_root, base, &path)?, &path, Some(base))?;
+        if !has_frameworks(&before) {
+            continue;
+        }
+        let content = match head.zip(at_head.as_ref()) {
+            Some((rev, at_head)) => at_head
+                .contains(&path)
+                .then(|| read_at(project_root, rev, &path))
+                .transpose()?,
+            None => {
+                let abs = project_root.join(&path);
+                match std::fs::read_to_string(&abs) {
+                    Ok(content) => Some(content),
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+                    Err(e) => return Err(crate::Error::io(&abs, e)),
+                }
+            }
+        };
+        let current = content
+            .map(|content| parse(&content, &path, head))
+            .transpose()?;
+        reports.push((path, removed_statements(&before, current.as_ref())));
+    }
+    Ok(reports)
+}
+
+/// True when the spec declares at least one compliance framework.
+pub fn has_frameworks(spec: &Spec) -> bool {
+    spec.compliance
+        .as_ref()
+        .is_some_and(|c| !c.frameworks.is_empty())
+}
+
+/// The spec files under `.notarai/` at git revision `rev`.
+pub fn specs_at(project_root: &Path, rev: &str) -> crate::Result<Vec<String>> {
+    let listing = git(
+        project_root,
+        &["ls-tree", "-r", "--name-only", rev, "--", ".notarai"],
+    )?;
+    Ok(listing
+        .lines()
+        .filter(|path| path.ends_with(".spec.yaml"))
+        .map(str::to_string)
+        .collect())
+}
+
+/// The content of `path` at git revision `rev`.
+pub fn read_at(project_root: &Path, rev: &str, path: &str) -> crate::Result<String> {
+    git(project_root, &["show", &format!("{rev}:./{path}")])
+}
+
+fn git(project_root: &Path, args: &[&str]) -> crate::Result<String> {
+    let failed = |message: String| crate::Error::Git {
+        command: args[0].to_string(),
+        message,
+    };
+    let output = std::process::Command::new("git")
+        .args(args)
+        .current_dir(project_root)
+        .output()
+        .map_err(|e| failed(e.to_string()))?;
+    if !output.status.success() {
+        return Err(failed(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::spec::test_spec;
+
+    const AUTH: &str = "invariants:\n  - 'no plaintext passwords stored anywhere'\n\
+        constraints:\n  - 'rate limit: 5 login attempts per minute per IP'\n\
+        compliance:\n  frameworks:\n    - name: SOC2\n      controls:\n        \
+        - id: CC6.1\n          satisfied_by:\n            \
+        invariants: ['no plaintext passwords stored anywhere']\n            \
+        constraints: ['rate limit: 5 login attempts per minute per IP']\n        \
+        - id: CC7.2\n        - id: CC8.1\n          satisfied_by:\n            \
+        invariants: ['every change is reviewed']\n    \
+        - name: WCAG\n      level: AA\n      satisfied_by:\n        \
+        constraints: ['rate limit: 5 login attempts per minute per IP']\n";
+
+    #[test]
+    fn matrix_flags_controls_without_a_present_statement() {
+        let frameworks = matrix(&test_spec(AUTH));
+        let status: Vec<(Option<&str>, bool)> = frameworks
+            .iter()
+            .flat_map(|f| &f.controls)
+            .map(|c| (c.id.as_deref(), c.satisfied))
+            .collect();
+        assert_eq!(
+            status,
+            [
+                (Some("CC6.1"), true),
+                (Some("CC7.2"), false),
+                (Some("CC8.1"), false),
+                (None, true),
+            ]
+        );
+        assert!(!frameworks[0].controls[2].satisfied_by[0].present);
+
+        let markdown = to_markdown(&[("a.spec.yaml".to_string(), frameworks)]);
+        assert!(markdown.contains("\n### WCAG (AA)\n"));
+        assert!(markdown.contains(
+            "| CC8.1 | invariant: every change is reviewed **(missing)** | **UNSATISFIED** |"
+        ));
+        assert!(markdown.contains("| CC7.2 | — | **UNSATISFIED** |"));
+    }
+
+    #[test]
+    fn guard_reports_deleted_and_reworded_statements() {
+        let base = test_spec(AUTH);
+        assert!(removed_statements(&base, Some(&base)).is_empty());
+
+        let current = test_spec(
+            &AUTH
+                .replace(
+                    "  - 'no plaintext passwords stored anywhere'\n",
+                    "  - 'no plaintext passwords are stored anywhere'\n",
+                )
+                .replacen(
+                    "  - 'rate limit: 5 login attempts per minute per IP'\n",
+                    "  - 'sessions expire after 30 minutes'\n",
+                    1,
+                ),
+        );
+        let removals = removed_statements(&base, Some(&current));
+        let summary: Vec<(Option<&str>, Change, Option<&str>)> = removals
+            .iter()
+            .map(|r| (r.control.as_deref(), r.change, r.now.as_deref()))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (
+                    Some("CC6.1"),
+                    Change::Reworded,
+                    Some("no plaintext passwords are stored anywhere")
+                ),
+                (Some("CC6.1"), Change::Deleted, None),
+                (None, Change::Deleted, None),
+            ]
+        );
+        assert_eq!(removed_statements(&base, None).len(), 3);
+    }
+}
@@ -0,0 +1,418 @@
+//! Effective-spec composition.
+//!
+//! A spec's `applies` references cross-cutting specs whose invariants and
+//! constraints apply to that spec and to every spec below it in the
+//! `subsystems` hierarchy. Composition walks up from a spec through the specs
+//! that list it (directly or transitively) as a subsystem, collects every
+//! spec referenced by their `applies` (following `applies` inside applied
+//! specs too), and returns the spec's own rules followed by the applied ones,
+//! each tagged with the spec it comes from.
+//!
+//! `$ref` paths are relative to the directory of the spec that contains them.
+//! Cycles are tolerated: each spec is visited at most once.
+
+use crate::core::spec::Spec;
+use crate::{Error, Result};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::path::{Component, Path, PathBuf};
+
+/// Every spec in a project, keyed by project-relative path.
+#[derive(Debug, Default)]
+pub struct SpecGraph {
+    /// Specs that failed to parse keep their error, which is only reported if
+    /// composition needs them.
+    specs: BTreeMap<String, Result<Spec>>,
+}
+
+/// An invariant or constraint with the spec that declares it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SourcedRule {
+    pub rule: String,
+    pub source: String,
+}
+
+/// A cross-cutting spec that applies to the composed spec.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AppliedSpec {
+    pub path: String,
+    /// The spec whose `applies` lists it: the composed spec itself, one of
+    /// its ancestors, or another applied spec.
+    pub via: String,
+}
+
+/// A spec's effective invariants and constraints.
+#[derive(Debug, Clone, Serialize)]
+pub struct Composition {
+    pub spec_path: String,
+    pub applied: Vec<AppliedSpec>,
+    pub invariants: Vec<SourcedRule>,
+    pub constraints: Vec<SourcedRule>,
+    /// `applies` references that do not resolve to a spec that parses. The
+    /// rules of everything that does resolve are still composed.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+    /// The failures behind `warnings`, for `into_complete`.
+    #[serde(skip)]
+    unresolved: Vec<Unresolved>,
+}
+
+/// An `applies` reference that could not be composed.
+#[derive(Debug, Clone, PartialEq)]
+enum Unresolved {
+    Missing {
+        from: String,
+        reference: String,
+    },
+    Unparsed {
+        from: String,
+        reference: String,
+        path: String,
+        message: String,
+    },
+}
+
+impl Composition {
+    /// The composition, or the first `applies` reference that could not be
+    /// composed as an error: `Error::UnresolvedApplies` for a reference to
+    /// no spec, `Error::InvalidSpec` for one to a spec that does not parse.
+    pub fn into_complete(mut self) -> Result<Composition> {
+        match std::mem::take(&mut self.unresolved).into_iter().next() {
+            None => Ok(self),
+            Some(Unresolved::Missing { from, reference }) => {
+                Err(Error::UnresolvedApplies { from, reference })
+            }
+            Some(Unresolved::Unparsed { path, message, .. }) => {
+                Err(Error::InvalidSpec { path, message })
+            }
+        }
+    }
+}
+
+impl SpecGraph {
+    /// Read and parse every spec in the project.
+    pub fn load(project_root: &Path) -> Result<SpecGraph> {
+        let project = crate::Project::open(project_root);
+        let mut specs = BTreeMap::new();
+        for abs in project.spec_files()? {
+            let content = std::fs::read_to_string(&abs).map_err(|e| Error::io(&abs, e))?;
+            let rel = abs
+                .strip_prefix(project_root)
+                .unwrap_or(&abs)
+                .to_string_lossy()
+                .to_string();
+            specs.insert(rel, Spec::parse(&content));
+        }
+        Ok(SpecGraph { specs })
+    }
+
+    /// Build a graph from already-parsed specs.
+    pub fn from_specs(specs: impl IntoIterator<Item = (String, Spec)>) -> SpecGraph {
+        SpecGraph {
+            specs: specs
+                .into_iter()
+                .map(|(path, spec)| (path, Ok(spec)))
+                .collect(),
+        }
+    }
+
+    /// Every spec with its parse result, ordered by path.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, Result<&Spec, &Error>)> {
+        self.specs
+            .iter()
+            .map(|(path, spec)| (path.as_str(), spec.as_ref()))
+    }
+
+    /// Compose the effective invariants and constraints of `spec_path`.
+    ///
+    /// Fails when the spec is not in the graph or does not parse. An `applies`
+    /// reference it depends on that does not resolve to a spec that parses is
+    /// reported in `warnings` and skipped.
+    pub fn compose(&self, spec_path: &str) -> Result<Composition> {
+        let spec_path = crate::core::mcp_tools::normalize_rel(spec_path);
+        let spec = self.get(spec_path)?;
+
+        let mut applied: Vec<AppliedSpec> = Vec::new();
+        let mut seen: BTreeSet<String> = BTreeSet::from([spec_path.to_string()]);
+        let mut unresolved: Vec<Unresolved> = Vec::new();
+        let mut queue: VecDeque<String> = self.scopes(spec_path).into();
+        while let Some(via) = queue.pop_front() {
+            for reference in &self.get(&via)?.applies {
+                let path = resolve_ref(&via, &reference.reference);
+                match self.specs.get(&path) {
+                    None => {
+                        unresolved.push(Unresolved::Missing {
+                            from: via.clone(),
+                            reference: reference.reference.clone(),
+                        });
+                        continue;
+                    }
+                    Some(Err(e)) => {
+                        if seen.insert(path.clone()) {
+                            unresolved.push(Unresolved::Unparsed {
+                                from: via.clone(),
+                                reference: reference.reference.clone(),
+                                path,
+                                message: e.to_string(),
+                            });
+                        }
+                        continue;
+                    }
+                    Some(Ok(_)) => {}
+                }
+                if seen.insert(path.clone()) {
+                    queue.push_back(path.clone());
+                    applied.push(AppliedSpec {
+                        path,
+                        via: via.clone(),
+                    });
+                }
+            }
+        }
+
+        let sourced = |rules: &[String], source: &str| -> Vec<SourcedRule> {
+            rules
+                .iter()
+                .map(|rule| SourcedRule {
+                    rule: rule.clone(),
+                    source: source.to_string(),
+                })
+                .collect()
+        };
+        let mut invariants = sourced(&spec.invariants, spec_path);
+        let mut constraints = sourced(&spec.constraints, spec_path);
+        for entry in &applied {
+            let applied_spec = self.get(&entry.path)?;
+            invariants.extend(sourced(&applied_spec.invariants, &entry.path));
+            constraints.extend(sourced(&applied_spec.constraints, &entry.path));
+        }
+
+        let warnings = unresolved
+            .iter()
+            .map(|u| match u {
+                Unresolved::Missing { from, reference } => Error::UnresolvedApplies {
+                    from: from.clone(),
+                    reference: reference.clone(),
+                }
+                .to_string(),
+                Unresolved::Unparsed {
+                    from,
+                    reference,
+                    message,
+                    ..
+                } => format!("{from}: applies reference '{reference}' does not parse: {message}"),
+            })
+            .collect();
+        Ok(Composition {
+            spec_path: spec_path.to_string(),
+            applied,
+            invariants,
+            constraints,
+            warnings,
+            unresolved,
+        })
+    }
+
+    fn get(&self, path: &str) -> Result<&Spec> {
+        match self.specs.get(path) {
+            Some(Ok(spec)) => Ok(spec),
+            Some(Err(e)) => Err(Error::InvalidSpec {
+                path: path.to_string(),
+                message: e.to_string(),
+            }),
+            None => Err(Error::SpecNotFound(path.to_string())),
+        }
+    }
+
+    /// `spec_path` followed by every spec that contains it through
+    /// `subsystems`, nearest first.
+    fn scopes(&self, spec_path: &str) -> Vec<String> {
+        let mut parents: BTreeMap<String, Vec<&str>> = BTreeMap::new();
+        for (path, spec) in &self.specs {
+            let Ok(spec) = spec else { continue };
+            for child in &spec.subsystems {
+                parents
+                    .entry(resolve_ref(path, &child.reference))
+                    .or_default()
+                    .push(path);
+            }
+        }
+
+        let mut scopes = vec![spec_path.to_string()];
+        let mut i = 0;
+        while i < scopes.len() {
+            for parent in parents.get(&scopes[i]).into_iter().flatten() {
+                if !scopes.iter().any(|s| s == parent) {
+                    scopes.push(parent.to_string());
+                }
+            }
+            i += 1;
+        }
+        scopes
+    }
+}
+
+/// Resolve a `$ref` found in the spec at `from` (project-relative) to a
+/// project-relative path.
+pub fn resolve_ref(from: &str, reference: &str) -> String {
+    let base = Path::new(from).parent().unwrap_or(Path::new(""));
+    let mut resolved = PathBuf::new();
+    for component in base.join(reference).components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                resolved.pop();
+            }
+            other => resolved.push(other),
+        }
+    }
+    resolved.to_string_lossy().replace('\\', "/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::spec::test_spec;
+
+    fn graph() -> SpecGraph {
+        SpecGraph::from_specs([
+            (
+                ".notarai/system.spec.yaml".to_string(),
+                test_spec(
+                    "invariants: ['system rule']\n\
+                     subsystems:\n  - $ref: './api/api.spec.yaml'\n\
+                     applies:\n  - $ref: './_shared/security.spec.yaml'\n",
+                ),
+            ),
+            (
+                ".notarai/api/api.spec.yaml".to_string(),
+                test_spec(
+                    "constraints: ['api constraint']\n\
+                     subsystems:\n  - $ref: './auth.spec.yaml'\n",
+                ),
+            ),
+            (
+                ".notarai/api/auth.spec.yaml".to_string(),
+                test_spec(
+                    "invariants: ['auth rule']\n\
+                     applies:\n  - $ref: '../_shared/logging.spec.yaml'\n",
+                ),
+            ),
+            (
+                ".notarai/_shared/security.spec.yaml".to_string(),
+                test_spec(
+                    "invariants: ['no secrets in logs']\n\
+                     applies:\n  - $ref: './logging.spec.yaml'\n",
+                ),
+            ),
+            (
+                ".notarai/_shared/logging.spec.yaml".to_string(),
+                test_spec("constraints: ['structured logs']\n"),
+            ),
+        ])
+    }
+
+    #[test]
+    fn resolves_refs_relative_to_the_referencing_spec() {
+        assert_eq!(
+            resolve_ref(".notarai/system.spec.yaml", "./cli.spec.yaml"),
+            ".notarai/cli.spec.yaml"
+        );
+        assert_eq!(
+            resolve_ref(".notarai/api/auth.spec.yaml", "../_shared/x.spec.yaml"),
+            ".notarai/_shared/x.spec.yaml"
+        );
+    }
+
+    #[test]
+    fn inherits_applies_from_ancestors_and_applied_specs() {
+        let c = graph().compose(".notarai/api/auth.spec.yaml").unwrap();
+        assert_eq!(
+            c.applied,
+            [
+                AppliedSpec {
+                    path: ".notarai/_shared/logging.spec.yaml".into(),
+                    via: ".notarai/api/auth.spec.yaml".into(),
+                },
+                AppliedSpec {
+                    path: ".notarai/_shared/security.spec.yaml".into(),
+                    via: ".notarai/system.spec.yaml".into(),
+                },
+            ]
+        );
+        let rules: Vec<(&str, &str)> = c
+            .invariants
+            .iter()
+            .map(|r| (r.rule.as_str(), r.source.as_str()))
+            .collect();
+        assert_eq!(
+            rules,
+            [
+                ("auth rule", ".notarai/api/auth.spec.yaml"),
+                ("no secrets in logs", ".notarai/_shared/security.spec.yaml"),
+            ]
+        );
+        assert_eq!(c.constraints.len(), 1);
+        assert_eq!(c.constraints[0].rule, "structured logs");
+    }
+
+    #[test]
+    fn ancestors_own_rules_do_not_propagate() {
+        let c = graph().compose(".notarai/api/api.spec.yaml").unwrap();
+        assert!(c.invariants.iter().all(|r| r.rule != "system rule"));
+        assert!(c.constraints.iter().any(|r| r.rule == "api constraint"));
+    }
+
+    #[test]
+    fn dangling_applies_is_a_warning() {
+        let graph = SpecGraph::from_specs([
+            (
+                ".notarai/a.spec.yaml".to_string(),
+                test_spec(
+                    "invariants: ['a rule']\n\
+                     applies:\n  - $ref: './missing.spec.yaml'\n  - $ref: './b.spec.yaml'\n",
+                ),
+            ),
+            (
+                ".notarai/b.spec.yaml".to_string(),
+                test_spec("invariants: ['b rule']\n"),
+            ),
+        ]);
+        let c = graph.compose(".notarai/a.spec.yaml").unwrap();
+        assert_eq!(
+            c.warnings,
+            [
+                ".notarai/a.spec.yaml: applies reference './missing.spec.yaml' does not resolve to a spec"
+            ]
+        );
+        assert_eq!(c.applied.len(), 1);
+        let rules: Vec<&str> = c.invariants.iter().map(|r| r.rule.as_str()).collect();
+        assert_eq!(rules, ["a rule", "b rule"]);
+
+        assert!(matches!(
+            c.into_complete(),
+            Err(Error::UnresolvedApplies { ref reference, .. }) if reference == "./missing.spec.yaml"
+        ));
+    }
+
+    #[test]
+    fn cycles_terminate() {
+        let graph = SpecGraph::from_specs([
+            (
+                ".notarai/a.spec.yaml".to_string(),
+                test_spec(
+                    "subsystems:\n  - $ref: './b.spec.yaml'\napplies:\n  - $ref: './b.spec.yaml'\n",
+                ),
+            ),
+            (
+                ".notarai/b.spec.yaml".to_string(),
+                test_spec(
+                    "subsystems:\n  - $ref: './a.spec.yaml'\napplies:\n  - $ref: './a.spec.yaml'\n",
+                ),
+            ),
+        ]);
+        let c = graph.compose(".notarai/a.spec.yaml").unwrap();
+        assert_eq!(c.applied.len(), 1);
+        assert_eq!(c.applied[0].path, ".notarai/b.spec.yaml");
+    }
+}
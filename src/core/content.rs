@@ -0,0 +1,370 @@
+//! Structural checks for a spec's `content.sections`.
+//!
+//! These are deterministic checks that need no LLM: references to sections
+//! that do not exist, duplicate ids, sections a `graph` structure cannot reach
+//! from its first section, cycles in `depends_on` prerequisites, prerequisites
+//! placed after their dependents in an `ordered` structure, and `content_ref`
+//! paths that match no file.
+
+use crate::core::spec::{Content, ContentSection, ContentStructure};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FindingKind {
+    DuplicateSection,
+    UnknownSection,
+    UnreachableSection,
+    DependencyCycle,
+    PrerequisiteOrder,
+    DanglingContentRef,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Finding {
+    pub kind: FindingKind,
+    /// The section the finding is about.
+    pub section: String,
+    pub message: String,
+}
+
+/// Run every check on `content`. `content_ref` paths are resolved against
+/// `project_root`.
+pub fn analyze(content: &Content, project_root: &Path) -> Vec<Finding> {
+    let sections = &content.sections;
+    let mut findings = Vec::new();
+    let mut index: BTreeMap<&str, usize> = BTreeMap::new();
+    for (i, section) in sections.iter().enumerate() {
+        if index.insert(&section.id, i).is_some() {
+            findings.push(finding(
+                FindingKind::DuplicateSection,
+                section,
+                format!("section id '{}' is used more than once", section.id),
+            ));
+        }
+    }
+
+    for section in sections {
+        for connection in &section.connections {
+            if !index.contains_key(connection.to.as_str()) {
+                findings.push(finding(
+                    FindingKind::UnknownSection,
+                    section,
+                    format!("connection to unknown section '{}'", connection.to),
+                ));
+            }
+        }
+        for prerequisite in &section.depends_on {
+            match index.get(prerequisite.id.as_str()) {
+                None => findings.push(finding(
+                    FindingKind::UnknownSection,
+                    section,
+                    format!("depends on unknown section '{}'", prerequisite.id),
+                )),
+                Some(&at) if content.structure() == ContentStructure::Ordered => {
+                    let own = index[section.id.as_str()];
+                    if at > own {
+                        findings.push(finding(
+                            FindingKind::PrerequisiteOrder,
+                            section,
+                            format!(
+                                "prerequisite '{}' comes after '{}' in an ordered structure",
+                                prerequisite.id, section.id
+                            ),
+                        ));
+                    }
+                }
+                Some(_) => {}
+            }
+        }
+        if let Some(path) = &section.content_ref
+            && !path_matches(project_root, path)
+        {
+            findings.push(finding(
+                FindingKind::DanglingContentRef,
+                section,
+                format!("content_ref '{path}' matches no file"),
+            ));
+        }
+    }
+
+    if content.structure() == ContentStructure::Graph {
+        findings.extend(unreachable(sections, &index));
+    }
+    findings.extend(cycles(sections, &index));
+    findings
+}
+
+fn finding(kind: FindingKind, section: &ContentSection, message: String) -> Finding {
+    Finding {
+        kind,
+        section: section.id.clone(),
+        message,
+    }
+}
+
+fn path_matches(project_root: &Path, path: &str) -> bool {
+    let path = crate::core::mcp_tools::normalize_rel(path);
+    if !path.contains(['*', '?', '[']) {
+        return project_root.join(path).exists();
+    }
+    let pattern = project_root.join(path).to_string_lossy().to_string();
+    glob::glob(&pattern).is_ok_and(|mut paths| paths.any(|p| p.is_ok()))
+}
+
+/// Sections a graph cannot reach from its first section by following
+/// `connections`.
+fn unreachable(sections: &[ContentSection], index: &BTreeMap<&str, usize>) -> Vec<Finding> {
+    let Some(entry) = sections.first() else {
+        return Vec::new();
+    };
+    let mut reached = vec![false; sections.len()];
+    reached[0] = true;
+    let mut frontier = vec![0];
+    while let Some(i) = frontier.pop() {
+        for connection in &sections[i].connections {
+            if let Some(&j) = index.get(connection.to.as_str())
+                && !reached[j]
+            {
+                reached[j] = true;
+                frontier.push(j);
+            }
+        }
+    }
+    sections
+        .iter()
+        .zip(reached)
+        .filter(|(_, reached)| !reached)
+        .map(|(section, _)| {
+            finding(
+                FindingKind::UnreachableSection,
+                section,
+                format!(
+                    "'{}' cannot be reached from the entry section '{}'",
+                    section.id, entry.id
+                ),
+            )
+        })
+        .collect()
+}
+
+/// Each distinct cycle in the `depends_on` graph, reported once on its
+/// smallest section id.
+fn cycles(sections: &[ContentSection], index: &BTreeMap<&str, usize>) -> Vec<Finding> {
+    #[derive(Clone, Copy, PartialEq)]
+    enum Mark {
+        New,
+        Active,
+        Done,
+    }
+
+    fn visit(
+        i: usize,
+        sections: &[ContentSection],
+        index: &BTreeMap<&str, usize>,
+        marks: &mut [Mark],
+        stack: &mut Vec<usize>,
+        found: &mut BTreeSet<Vec<usize>>,
+    ) {
+        marks[i] = Mark::Active;
+        stack.push(i);
+        for prerequisite in &sections[i].depends_on {
+            let Some(&j) = index.get(prerequisite.id.as_str()) else {
+                continue;
+            };
+            match marks[j] {
+                Mark::New => visit(j, sections, index, marks, stack, found),
+                Mark::Active => {
+                    let start = stack.iter().position(|&k| k == j).unwrap_or(0);
+                    let mut cycle = stack[start..].to_vec();
+                    let min = (0..cycle.len())
+                        .min_by_key(|&k| &sections[cycle[k]].id)
+                        .unwrap_or(0);
+                    cycle.rotate_left(min);
+                    found.insert(cycle);
+                }
+                Mark::Done => {}
+            }
+        }
+        stack.pop();
+        marks[i] = Mark::Done;
+    }
+
+    let mut marks = vec![Mark::New; sections.len()];
+    let mut found = BTreeSet::new();
+    for i in 0..sections.len() {
+        if marks[i] == Mark::New {
+            visit(i, sections, index, &mut marks, &mut Vec::new(), &mut found);
+        }
+    }
+    found
+        .into_iter()
+        .map(|cycle| {
+            let mut ids: Vec<&str> = cycle.iter().map(|&k| sections[k].id.as_str()).collect();
+            ids.push(ids[0]);
+            finding(
+                FindingKind::DependencyCycle,
+                &sections[cycle[0]],
+                format!("depends_on cycle: {}", ids.join(" -> ")),
+            )
+        })
+        .collect()
+}
+
+/// A Mermaid flowchart of the sections. `connections` are solid edges
+/// (labeled when they have a label), `depends_on` dashed edges from the
+/// prerequisite to its dependent, and an `ordered` structure without
+/// connections links consecutive sections.
+pub fn to_mermaid(content: &Content) -> String {
+    let sections = &content.sections;
+    let ids: BTreeMap<&str, String> = sections
+        .iter()
+        .enumerate()
+        .map(|(i, s)| (s.id.as_str(), format!("s{i}")))
+        .collect();
+    let text = |s: &str| format!("\"{}\"", s.replace('"', "#quot;"));
+    let direction = match content.structure() {
+        ContentStructure::Graph => "LR",
+        _ => "TD",
+    };
+
+    let mut out = format!("flowchart {direction}\n");
+    for section in sections {
+        out.push_str(&format!(
+            "  {}[{}]\n",
+            ids[section.id.as_str()],
+            text(&section.id)
+        ));
+    }
+    let has_connections = sections.iter().any(|s| !s.connections.is_empty());
+    if content.structure() == ContentStructure::Ordered && !has_connections {
+        for pair in sections.windows(2) {
+            out.push_str(&format!(
+                "  {} --> {}\n",
+                ids[pair[0].id.as_str()],
+                ids[pair[1].id.as_str()]
+            ));
+        }
+    }
+    for section in sections {
+        let from = &ids[section.id.as_str()];
+        for connection in &section.connections {
+            let Some(to) = ids.get(connection.to.as_str()) else {
+                continue;
+            };
+            match &connection.label {
+                Some(label) => out.push_str(&format!("  {from} -->|{}| {to}\n", text(label))),
+                None => out.push_str(&format!("  {from} --> {to}\n")),
+            }
+        }
+        for prerequisite in &section.depends_on {
+            if let Some(prereq) = ids.get(prerequisite.id.as_str()) {
+                out.push_str(&format!("  {prereq} -.->|prerequisite| {from}\n"));
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::spec::Spec;
+
+    fn content(yaml: &str) -> Content {
+        let spec = Spec::parse(&format!(
+            "schema_version: '0.7'\nintent: 'x'\ncontent:\n{yaml}"
+        ))
+        .unwrap();
+        spec.content.unwrap()
+    }
+
+    fn kinds(findings: &[Finding]) -> Vec<(FindingKind, &str)> {
+        findings
+            .iter()
+            .map(|f| (f.kind, f.section.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn clean_ordered_content_has_no_findings() {
+        let c = content(
+            "  structure: ordered\n  sections:\n    - id: a\n    \
+             - id: b\n      depends_on:\n        - id: a\n",
+        );
+        assert!(analyze(&c, Path::new(".")).is_empty());
+    }
+
+    #[test]
+    fn reports_order_unknown_and_duplicate_sections() {
+        let c = content(
+            "  structure: ordered\n  sections:\n    \
+             - id: a\n      depends_on:\n        - id: b\n        - id: ghost\n    \
+             - id: b\n    - id: b\n",
+        );
+        assert_eq!(
+            kinds(&analyze(&c, Path::new("."))),
+            [
+                (FindingKind::DuplicateSection, "b"),
+                (FindingKind::PrerequisiteOrder, "a"),
+                (FindingKind::UnknownSection, "a"),
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_unreachable_sections_and_cycles_in_graphs() {
+        let c = content(
+            "  structure: graph\n  sections:\n    \
+             - id: start\n      connections:\n        - to: level1\n    \
+             - id: level1\n      depends_on:\n        - id: level2\n    \
+             - id: level2\n      depends_on:\n        - id: level1\n    \
+             - id: secret\n",
+        );
+        let findings = analyze(&c, Path::new("."));
+        assert_eq!(
+            kinds(&findings),
+            [
+                (FindingKind::UnreachableSection, "level2"),
+                (FindingKind::UnreachableSection, "secret"),
+                (FindingKind::DependencyCycle, "level1"),
+            ]
+        );
+        assert_eq!(
+            findings[2].message,
+            "depends_on cycle: level1 -> level2 -> level1"
+        );
+    }
+
+    #[test]
+    fn reports_dangling_content_refs() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir_all(tmp.path().join("slides")).unwrap();
+        std::fs::write(tmp.path().join("slides/01.md"), "# Intro").unwrap();
+        let c = content(
+            "  sections:\n    - id: a\n      content_ref: 'slides/01.md'\n    \
+             - id: b\n      content_ref: 'slides/*.md'\n    \
+             - id: c\n      content_ref: 'slides/02.md'\n",
+        );
+        assert_eq!(
+            kinds(&analyze(&c, tmp.path())),
+            [(FindingKind::DanglingContentRef, "c")]
+        );
+    }
+
+    #[test]
+    fn mermaid_export() {
+        let c = content(
+            "  structure: graph\n  sections:\n    \
+             - id: start\n      connections:\n        - to: end\n          label: 'win'\n    \
+             - id: end\n      depends_on:\n        - id: start\n",
+        );
+        assert_eq!(
+            to_mermaid(&c),
+            "flowchart LR\n  s0[\"start\"]\n  s1[\"end\"]\n  s0 -->|\"win\"| s1\n  \
+             s0 -.->|prerequisite| s1\n"
+        );
+    }
+}
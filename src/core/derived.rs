@@ -0,0 +1,307 @@
+//! Staleness of derived artifacts.
+//!
+//! An artifact is derived when its ref sets `tier: 4`, or when it belongs to
+//! a spec with `tier: derived` and its ref does not set another tier. Derived
+//! files are generated, so they are checked against the files they were
+//! built from rather than reconciled: the pipeline step whose `output`
+//! matches a derived file supplies its inputs (the step's `input`), and the
+//! hash of each input is recorded in the cache whenever the step produces the
+//! file. A derived file is stale when an input was modified, added or removed
+//! since then.
+
+use crate::core::cache;
+use crate::core::compose::SpecGraph;
+use crate::core::pipeline;
+use crate::core::spec::{ArtifactRef, PipelineStep, Spec, Tier};
+use crate::{Error, Result};
+use rusqlite::{Connection, params};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// The artifact tier that marks a derived output.
+pub const DERIVED_TIER: u8 = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Staleness {
+    /// Every input is unchanged since the file was produced.
+    Fresh,
+    /// An input was modified, added or removed since the file was produced.
+    Stale,
+    /// No inputs have been recorded for the file.
+    Unrecorded,
+    /// The derived artifact glob matches no file.
+    Missing,
+    /// No pipeline step with an `input` declares the file as its `output`.
+    Unmapped,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DerivedStatus {
+    pub spec_path: String,
+    /// The derived file, or the artifact glob when it matches nothing.
+    pub path: String,
+    pub status: Staleness,
+    /// The pipeline step that produces the file.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub step: Option<String>,
+    /// Inputs that changed since the file was produced.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub changed_inputs: Vec<String>,
+}
+
+/// True when `artifact` of `spec` is a derived output.
+pub fn is_derived(spec: &Spec, artifact: &ArtifactRef) -> bool {
+    match artifact.tier {
+        Some(tier) => tier == DERIVED_TIER,
+        None => spec.tier() == Tier::Derived,
+    }
+}
+
+/// The artifact globs of `spec` that are derived outputs.
+pub fn derived_globs(spec: &Spec) -> Vec<&str> {
+    spec.artifact_refs()
+        .filter(|(_, r)| is_derived(spec, r))
+        .map(|(_, r)| r.path.as_str())
+        .collect()
+}
+
+/// Record the hashes of `step`'s inputs as the inputs of each of its
+/// outputs, replacing what was recorded before. Steps without both `input`
+/// and `output` record nothing. Returns the number of outputs recorded.
+pub fn record(conn: &Connection, project_root: &Path, step: &PipelineStep) -> Result<usize> {
+    let (Some(input), Some(output)) = (&step.input, &step.output) else {
+        return Ok(0);
+    };
+    let inputs = hash_inputs(project_root, input);
+    let outputs = pipeline::files(project_root, output);
+    let now = crate::core::time::now_secs();
+
+    let tx = cache::write_transaction(conn)?;
+    for output in &outputs {
+        tx.execute(
+            "DELETE FROM derived_inputs WHERE output_path = ?1",
+            params![output],
+        )
+        .map_err(Error::cache("could not replace derived inputs"))?;
+        for (input, hash) in &inputs {
+            tx.execute(
+                "INSERT INTO derived_inputs (output_path, input_path, blake3_hash, recorded_at)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![output, input, hash, now],
+            )
+            .map_err(Error::cache("could not record derived input"))?;
+        }
+    }
+    tx.commit().map_err(Error::cache("commit error"))?;
+    Ok(outputs.len())
+}
+
+/// Check every derived artifact in `specs`, in spec order. Each derived file
+/// is matched to the first step declaring it as output, looking at its own
+/// spec's pipeline before the others.
+pub fn check(
+    conn: &Connection,
+    project_root: &Path,
+    specs: &SpecGraph,
+) -> Result<Vec<DerivedStatus>> {
+    let steps: Vec<(&str, &PipelineStep)> = specs
+        .iter()
+        .filter_map(|(path, spec)| Some((path, spec.ok()?)))
+        .flat_map(|(path, spec)| {
+            spec.pipeline
+                .iter()
+                .flat_map(|p| &p.steps)
+                .map(move |step| (path, step))
+        })
+        .collect();
+    let mut step_outputs: BTreeMap<usize, Vec<String>> = BTreeMap::new();
+
+    let mut statuses = Vec::new();
+    for (spec_path, spec) in specs.iter() {
+        let Ok(spec) = spec else { continue };
+        for glob in derived_globs(spec) {
+            let files = pipeline::files(project_root, glob);
+            if files.is_empty() {
+                statuses.push(DerivedStatus {
+                    spec_path: spec_path.to_string(),
+                    path: glob.to_string(),
+                    status: Staleness::Missing,
+                    step: None,
+                    changed_inputs: Vec::new(),
+                });
+                continue;
+            }
+            for file in files {
+                let own_first = steps
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, (path, _))| *path == spec_path)
+                    .chain(
+                        steps
+                            .iter()
+                            .enumerate()
+                            .filter(|(_, (path, _))| *path != spec_path),
+                    );
+                let producer = own_first
+                    .filter(|(_, (_, step))| step.input.is_some())
+                    .find(|(i, (_, step))| {
+                        step_outputs
+                            .entry(*i)
+                            .or_insert_with(|| {
+                                step.output
+                                    .as_deref()
+                                    .map(|o| pipeline::files(project_root, o))
+                                    .unwrap_or_default()
+                            })
+                            .contains(&file)
+                    })
+                    .map(|(_, (_, step))| *step);
+                statuses.push(status_of(conn, project_root, spec_path, file, producer)?);
+            }
+        }
+    }
+    Ok(statuses)
+}
+
+fn status_of(
+    conn: &Connection,
+    project_root: &Path,
+    spec_path: &str,
+    file: String,
+    producer: Option<&PipelineStep>,
+) -> Result<DerivedStatus> {
+    let mut status = DerivedStatus {
+        spec_path: spec_path.to_string(),
+        path: file,
+        status: Staleness::Unmapped,
+        step: None,
+        changed_inputs: Vec::new(),
+    };
+    let Some(step) = producer else {
+        return Ok(status);
+    };
+    status.step = Some(step.name.clone());
+    let recorded = recorded_inputs(conn, &status.path)?;
+    if recorded.is_empty() {
+        status.status = Staleness::Unrecorded;
+        return Ok(status);
+    }
+
+    let current = hash_inputs(project_root, step.input.as_deref().unwrap_or_default());
+    let mut changed: Vec<String> = current
+        .iter()
+        .filter(|(path, hash)| recorded.get(*path) != Some(*hash))
+        .map(|(path, _)| path.clone())
+        .collect();
+    changed.extend(
+        recorded
+            .keys()
+            .filter(|path| !current.contains_key(*path))
+            .cloned(),
+    );
+    changed.sort();
+    status.status = if changed.is_empty() {
+        Staleness::Fresh
+    } else {
+        Staleness::Stale
+    };
+    status.changed_inputs = changed;
+    Ok(status)
+}
+
+fn hash_inputs(project_root: &Path, pattern: &str) -> BTreeMap<String, String> {
+    pipeline::files(project_root, pattern)
+        .into_iter()
+        .filter_map(|file| {
+            let hash = cache::hash_file(&project_root.join(&file)).ok()?;
+            Some((file, hash))
+        })
+        .collect()
+}
+
+fn recorded_inputs(conn: &Connection, output: &str) -> Result<BTreeMap<String, String>> {
+    let mut stmt = conn
+        .prepare("SELECT input_path, blake3_hash FROM derived_inputs WHERE output_path = ?1")
+        .map_err(Error::cache("prepare error"))?;
+    let rows = stmt
+        .query_map(params![output], |row| Ok((row.get(0)?, row.get(1)?)))
+        .map_err(Error::cache("query error"))?
+        .filter_map(|r| r.ok())
+        .collect();
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::spec::test_spec;
+    use tempfile::TempDir;
+
+    const DECK: &str = "artifacts:\n  slides:\n    - path: 'slides/*.md'\n  \
+        exports:\n    - path: 'dist/deck.pdf'\n      tier: 4\n    \
+        - path: 'dist/handout.pdf'\n      tier: 4\n\
+        pipeline:\n  steps:\n    - name: export\n      command: 'true'\n      \
+        input: 'slides/*.md'\n      output: 'dist/deck.pdf'\n";
+
+    fn statuses(conn: &Connection, root: &Path, graph: &SpecGraph) -> Vec<(String, Staleness)> {
+        check(conn, root, graph)
+            .unwrap()
+            .into_iter()
+            .map(|s| (s.path, s.status))
+            .collect()
+    }
+
+    #[test]
+    fn derived_refs_follow_artifact_tier_then_spec_tier() {
+        let s = test_spec(DECK);
+        assert_eq!(derived_globs(&s), ["dist/deck.pdf", "dist/handout.pdf"]);
+        let s = test_spec(
+            "tier: derived\nartifacts:\n  out:\n    - path: 'out/**'\n    \
+             - path: 'README.md'\n      tier: 1\n",
+        );
+        assert_eq!(derived_globs(&s), ["out/**"]);
+    }
+
+    #[test]
+    fn reports_fresh_stale_and_unrecorded_outputs() {
+        let tmp = TempDir::new().unwrap();
+        let root = tmp.path();
+        std::fs::create_dir_all(root.join("slides")).unwrap();
+        std::fs::create_dir_all(root.join("dist")).unwrap();
+        std::fs::write(root.join("slides/01.md"), "a").unwrap();
+        std::fs::write(root.join("dist/deck.pdf"), "pdf").unwrap();
+        let s = test_spec(DECK);
+        let step = s.pipeline.as_ref().unwrap().steps[0].clone();
+        let graph = SpecGraph::from_specs([(".notarai/deck.spec.yaml".to_string(), s)]);
+        let conn = cache::open_cache_db(root).unwrap();
+
+        assert_eq!(
+            statuses(&conn, root, &graph),
+            [
+                ("dist/deck.pdf".to_string(), Staleness::Unrecorded),
+                ("dist/handout.pdf".to_string(), Staleness::Missing),
+            ]
+        );
+
+        assert_eq!(record(&conn, root, &step).unwrap(), 1);
+        assert_eq!(
+            statuses(&conn, root, &graph)[0],
+            ("dist/deck.pdf".to_string(), Staleness::Fresh)
+        );
+
+        std::fs::write(root.join("slides/01.md"), "b").unwrap();
+        std::fs::write(root.join("slides/02.md"), "new").unwrap();
+        let report = check(&conn, root, &graph).unwrap();
+        assert_eq!(report[0].status, Staleness::Stale);
+        assert_eq!(report[0].step.as_deref(), Some("export"));
+        assert_eq!(report[0].changed_inputs, ["slides/01.md", "slides/02.md"]);
+
+        std::fs::write(root.join("dist/handout.pdf"), "pdf").unwrap();
+        assert_eq!(
+            statuses(&conn, root, &graph)[1],
+            ("dist/handout.pdf".to_string(), Staleness::Unmapped)
+        );
+    }
+}
@@ -0,0 +1,220 @@
+//! Evidence links of content sections.
+//!
+//! A section's `evidence` ties each claim to the `source` file backing it.
+//! Every source must exist and be governed by a spec's artifacts, so that
+//! changes to it are reconciled like any other artifact. A claim is flagged
+//! for review when its source changed since it was last marked reconciled for
+//! the spec making the claim (or was never marked).
+
+use crate::core::cache;
+use crate::core::compose::SpecGraph;
+use crate::core::mcp_tools::{is_spec_affected, normalize_rel};
+use crate::core::spec::Spec;
+use rusqlite::Connection;
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueKind {
+    /// The source file does not exist.
+    MissingSource,
+    /// No spec's artifacts govern the source file.
+    UngovernedSource,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Issue {
+    pub kind: IssueKind,
+    pub section: String,
+    pub claim: String,
+    pub source: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChangedClaim {
+    pub section: String,
+    pub claim: String,
+    pub source: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Report {
+    pub issues: Vec<Issue>,
+    /// Claims whose source changed since the last reconciliation.
+    pub changed_claims: Vec<ChangedClaim>,
+}
+
+impl Report {
+    pub fn is_clean(&self) -> bool {
+        self.issues.is_empty() && self.changed_claims.is_empty()
+    }
+}
+
+/// The reconciliation records a source is compared with: those made for
+/// `spec_path` at its current fingerprint.
+pub struct Records<'a> {
+    pub conn: &'a Connection,
+    pub spec_path: &'a str,
+    pub spec_fingerprint: &'a str,
+    pub verify_hashes: bool,
+}
+
+/// Check the evidence of `spec`'s content sections. Governance is looked up
+/// in `specs`. Without `records`, every existing source counts as changed.
+pub fn check(
+    project_root: &Path,
+    spec: &Spec,
+    specs: &SpecGraph,
+    records: Option<&Records>,
+) -> crate::Result<Report> {
+    let mut report = Report::default();
+    let linked: Vec<(&str, &str, &str)> = spec
+        .content
+        .iter()
+        .flat_map(|c| &c.sections)
+        .flat_map(|section| {
+            section.evidence.iter().filter_map(move |e| {
+                let source = normalize_rel(e.source.as_deref()?);
+                Some((section.id.as_str(), e.claim.as_str(), source))
+            })
+        })
+        .collect();
+
+    let mut existing = BTreeSet::new();
+    for &(section, claim, source) in &linked {
+        let kind = if !project_root.join(source).is_file() {
+            IssueKind::MissingSource
+        } else {
+            existing.insert(source);
+            let sources = [source.to_string()];
+            let governed = specs
+                .iter()
+                .filter_map(|(_, s)| s.ok())
+                .any(|s| is_spec_affected(s, &sources));
+            if governed {
+                continue;
+            }
+            IssueKind::UngovernedSource
+        };
+        report.issues.push(Issue {
+            kind,
+            section: section.to_string(),
+            claim: claim.to_string(),
+            source: source.to_string(),
+        });
+    }
+
+    let pairs: Vec<(String, PathBuf)> = existing
+        .iter()
+        .map(|s| (s.to_string(), project_root.join(s)))
+        .collect();
+    let changed: Vec<String> = match records {
+        Some(r) => {
+            cache::check_changed_batch(
+                r.conn,
+                r.spec_path,
+                r.spec_fingerprint,
+                &pairs,
+                r.verify_hashes,
+            )?
+            .0
+        }
+        None => pairs.into_iter().map(|(s, _)| s).collect(),
+    };
+    report.changed_claims = linked
+        .iter()
+        .filter(|(_, _, source)| changed.iter().any(|c| c == source))
+        .map(|&(section, claim, source)| ChangedClaim {
+            section: section.to_string(),
+            claim: claim.to_string(),
+            source: source.to_string(),
+        })
+        .collect();
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::cache::FileRecord;
+    use crate::core::normalize::Normalizer;
+    use tempfile::TempDir;
+
+    const REPORT: &str = "schema_version: '0.7'\nintent: 'x'\n\
+        artifacts:\n  data:\n    - path: 'data/*.csv'\n\
+        content:\n  sections:\n    - id: results\n      evidence:\n        \
+        - type: data\n          source: 'data/q1.csv'\n          claim: 'Revenue grew 12%'\n        \
+        - type: data\n          source: './data/q1.csv'\n          claim: 'Costs were flat'\n        \
+        - type: data\n          source: 'raw/q2.csv'\n          claim: 'Q2 beat Q1'\n        \
+        - type: citation\n          ref: 'Smith 2024'\n          claim: 'Markets grew'\n    \
+        - id: outlook\n      evidence:\n        \
+        - type: data\n          source: 'data/q3.csv'\n          claim: 'Q3 will grow'\n";
+
+    #[test]
+    fn flags_missing_and_ungoverned_sources_and_changed_claims() {
+        let tmp = TempDir::new().unwrap();
+        let root = tmp.path();
+        std::fs::create_dir_all(root.join("data")).unwrap();
+        std::fs::create_dir_all(root.join("raw")).unwrap();
+        std::fs::write(root.join("data/q1.csv"), "q,rev\n1,112\n").unwrap();
+        std::fs::write(root.join("raw/q2.csv"), "q,rev\n2,120\n").unwrap();
+        let spec = Spec::parse(REPORT).unwrap();
+        let graph =
+            SpecGraph::from_specs([(".notarai/report.spec.yaml".to_string(), spec.clone())]);
+
+        let report = check(root, &spec, &graph, None).unwrap();
+        let issues: Vec<(IssueKind, &str)> = report
+            .issues
+            .iter()
+            .map(|i| (i.kind, i.source.as_str()))
+            .collect();
+        assert_eq!(
+            issues,
+            [
+                (IssueKind::UngovernedSource, "raw/q2.csv"),
+                (IssueKind::MissingSource, "data/q3.csv"),
+            ]
+        );
+        let claims: Vec<&str> = report
+            .changed_claims
+            .iter()
+            .map(|c| c.claim.as_str())
+            .collect();
+        assert_eq!(
+            claims,
+            ["Revenue grew 12%", "Costs were flat", "Q2 beat Q1"]
+        );
+
+        let conn = cache::open_cache_db(root).unwrap();
+        let hash = cache::hash_file(&root.join("data/q1.csv")).unwrap();
+        let record = FileRecord {
+            path: "data/q1.csv".to_string(),
+            hash,
+            stat: None,
+            normalizer: Normalizer::Raw,
+        };
+        cache::upsert_batch(&conn, ".notarai/report.spec.yaml", "fp", &[record]).unwrap();
+        let records = Records {
+            conn: &conn,
+            spec_path: ".notarai/report.spec.yaml",
+            spec_fingerprint: "fp",
+            verify_hashes: false,
+        };
+        let report = check(root, &spec, &graph, Some(&records)).unwrap();
+        assert_eq!(
+            report.changed_claims,
+            [ChangedClaim {
+                section: "results".to_string(),
+                claim: "Q2 beat Q1".to_string(),
+                source: "raw/q2.csv".to_string(),
+            }]
+        );
+
+        std::fs::write(root.join("data/q1.csv"), "q,rev\n1,113\n").unwrap();
+        let report = check(root, &spec, &graph, Some(&records)).unwrap();
+        assert_eq!(report.changed_claims.len(), 3);
+        assert!(!report.is_clean());
+    }
+}
@@ -0,0 +1,606 @@
+//! Local evaluation of `feedback.metrics` and `feedback.triggers`.
+//!
+//! Metric values are read from the local CSV or JSON file named by the
+//! metric's `source`:
+//!
+//! - CSV: a header row, then one row per sample. The metric's values are in
+//!   the column named after the metric, or, in long format, in the `value`
+//!   column of the rows whose `metric` column names it.
+//! - JSON: an object mapping metric names to a number, an array of numbers,
+//!   or an array of `{value, timestamp}` objects; or an array of records that
+//!   is read like CSV rows.
+//!
+//! A `timestamp`, `date`, `time` or `at` column (or field) dates each sample.
+//! Thresholds such as `>= 0.7` or `< 5s` are compared numerically against the
+//! latest sample; units are not converted. Qualitative thresholds are not
+//! evaluated.
+
+use crate::core::spec::{
+    Duration, FeedbackMetric, FeedbackTrigger, Priority, Spec, TriggerAction, TriggerOperator,
+};
+use crate::core::time;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Column and field names that date a sample.
+const TIMESTAMP_COLUMNS: [&str; 4] = ["timestamp", "date", "time", "at"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sample {
+    pub value: f64,
+    /// Unix epoch seconds, when the source dates its samples.
+    pub at: Option<i64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MetricStatus {
+    /// The latest value meets the threshold.
+    Pass,
+    /// The latest value does not meet the threshold.
+    Fail,
+    /// The metric has a value but no numeric threshold to compare it with.
+    Unknown,
+    /// No value could be read for the metric.
+    Unavailable,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MetricResult {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub threshold: Option<String>,
+    /// The latest value.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<f64>,
+    /// When the latest value was measured, as ISO 8601 UTC.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub measured_at: Option<String>,
+    pub status: MetricStatus,
+    /// Why the metric is unavailable.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Triggered {
+    pub metric: String,
+    pub operator: TriggerOperator,
+    /// The trigger's `action`, `reconcile` when it has none.
+    pub action: TriggerAction,
+    /// The trigger's `priority`, `medium` when it has none.
+    pub priority: Priority,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Report {
+    pub spec_path: String,
+    pub metrics: Vec<MetricResult>,
+    pub triggered: Vec<Triggered>,
+    /// Triggers that could not be evaluated.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<String>,
+}
+
+impl Report {
+    /// True when a trigger with the `reconcile` action fired.
+    pub fn needs_reconciliation(&self) -> bool {
+        self.triggered
+            .iter()
+            .any(|t| t.action == TriggerAction::Reconcile)
+    }
+}
+
+/// Evaluate the metrics and triggers of `spec` at `now` (Unix epoch seconds).
+pub fn evaluate(project_root: &Path, spec_path: &str, spec: &Spec, now: i64) -> Report {
+    let mut report = Report {
+        spec_path: spec_path.to_string(),
+        metrics: Vec::new(),
+        triggered: Vec::new(),
+        errors: Vec::new(),
+    };
+    let Some(feedback) = &spec.feedback else {
+        return report;
+    };
+
+    let mut loaded = BTreeMap::new();
+    for metric in &feedback.metrics {
+        let samples = load_metric(project_root, metric);
+        report.metrics.push(metric_result(metric, &samples));
+        loaded.insert(metric.name.as_str(), (metric, samples));
+    }
+    for trigger in &feedback.triggers {
+        let name = &trigger.condition.metric;
+        let Some((metric, samples)) = loaded.get(name.as_str()) else {
+            report
+                .errors
+                .push(format!("trigger references unknown metric '{name}'"));
+            continue;
+        };
+        match fires(project_root, trigger, metric, samples, now) {
+            Ok(Some(message)) => report.triggered.push(Triggered {
+                metric: name.clone(),
+                operator: trigger.condition.operator,
+                action: trigger.action.unwrap_or(TriggerAction::Reconcile),
+                priority: trigger.priority.unwrap_or(Priority::Medium),
+                message,
+            }),
+            Ok(None) => {}
+            Err(e) => report.errors.push(format!(
+                "{name} {}: {e}",
+                operator_name(trigger.condition.operator)
+            )),
+        }
+    }
+    report
+}
+
+pub fn operator_name(operator: TriggerOperator) -> &'static str {
+    match operator {
+        TriggerOperator::BelowThreshold => "below_threshold",
+        TriggerOperator::AboveThreshold => "above_threshold",
+        TriggerOperator::Changed => "changed",
+        TriggerOperator::Stale => "stale",
+    }
+}
+
+fn metric_result(metric: &FeedbackMetric, samples: &Result<Vec<Sample>, String>) -> MetricResult {
+    let mut result = MetricResult {
+        name: metric.name.clone(),
+        threshold: metric.threshold.clone(),
+        value: None,
+        measured_at: None,
+        status: MetricStatus::Unavailable,
+        message: None,
+    };
+    let latest = match samples {
+        Ok(samples) => samples.last(),
+        Err(e) => {
+            result.message = Some(e.clone());
+            return result;
+        }
+    };
+    let Some(latest) = latest else {
+        result.message = Some("no values in source".to_string());
+        return result;
+    };
+    result.value = Some(latest.value);
+    result.measured_at = latest.at.map(time::format_utc);
+    result.status = match metric.threshold.as_deref().and_then(Threshold::parse) {
+        Some(threshold) if threshold.is_met(latest.value) => MetricStatus::Pass,
+        Some(_) => MetricStatus::Fail,
+        None => MetricStatus::Unknown,
+    };
+    result
+}
+
+/// Whether `trigger` fires, with a description of why it does.
+fn fires(
+    project_root: &Path,
+    trigger: &FeedbackTrigger,
+    metric: &FeedbackMetric,
+    samples: &Result<Vec<Sample>, String>,
+    now: i64,
+) -> Result<Option<String>, String> {
+    let condition = &trigger.condition;
+    let window = condition.duration.as_ref().map(seconds).transpose()?;
+    let samples = match (samples, condition.operator) {
+        (Ok(samples), _) => samples.as_slice(),
+        (Err(e), TriggerOperator::Stale) => return Ok(Some(format!("no data ({e})"))),
+        (Err(_), _) => return Ok(None),
+    };
+    let dated = samples.iter().all(|s| s.at.is_some());
+    if window.is_some() && !dated && condition.operator != TriggerOperator::Stale {
+        return Err("a duration needs timestamped samples".to_string());
+    }
+    let Some(latest) = samples.last() else {
+        return Ok((condition.operator == TriggerOperator::Stale)
+            .then(|| "no values in source".to_string()));
+    };
+
+    match condition.operator {
+        TriggerOperator::BelowThreshold | TriggerOperator::AboveThreshold => {
+            let threshold = metric
+                .threshold
+                .as_deref()
+                .ok_or("the metric has no threshold")?;
+            let threshold = Threshold::parse(threshold)
+                .ok_or_else(|| format!("threshold '{threshold}' is not numeric"))?;
+            let below = condition.operator == TriggerOperator::BelowThreshold;
+            let violates = |s: &Sample| {
+                !threshold.is_met(s.value)
+                    && if below {
+                        s.value <= threshold.bound
+                    } else {
+                        s.value >= threshold.bound
+                    }
+            };
+            // The condition must have held for the whole window: every sample
+            // since the start of the current violating streak violates, and
+            // that streak started at least `window` seconds ago.
+            let streak_start = samples
+                .iter()
+                .rev()
+                .take_while(|s| violates(s))
+                .last()
+                .map(|s| s.at);
+            let Some(start) = streak_start else {
+                return Ok(None);
+            };
+            let side = if below { "below" } else { "above" };
+            let raw = metric.threshold.as_deref().unwrap_or_default();
+            match (window, start) {
+                (None, _) => Ok(Some(format!("{} is {side} {raw}", latest.value))),
+                (Some(window), Some(start)) if now - start >= window => Ok(Some(format!(
+                    "{} has been {side} {raw} since {}",
+                    latest.value,
+                    time::format_utc(start)
+                ))),
+                _ => Ok(None),
+            }
+        }
+        TriggerOperator::Changed => {
+            let previous = match window {
+                None => samples.iter().rev().nth(1),
+                Some(window) => samples
+                    .iter()
+                    .rev()
+                    .find(|s| s.at.is_some_and(|at| at <= now - window)),
+            };
+            Ok(previous
+                .filter(|p| p.value != latest.value)
+                .map(|p| format!("changed from {} to {}", p.value, latest.value)))
+        }
+        TriggerOperator::Stale => {
+            let Some(window) = window else {
+                return Ok(None);
+            };
+            let measured = match latest.at {
+                Some(at) => at,
+                None => source_mtime(project_root, metric)?,
+            };
+            Ok((now - measured > window)
+                .then(|| format!("last measured {}", time::format_utc(measured))))
+        }
+    }
+}
+
+/// The mtime of a metric's source, dating samples that carry no timestamp.
+fn source_mtime(project_root: &Path, metric: &FeedbackMetric) -> Result<i64, String> {
+    let source = metric.source.as_deref().unwrap_or_default();
+    let modified = std::fs::metadata(project_root.join(source))
+        .and_then(|m| m.modified())
+        .map_err(|e| format!("could not read the mtime of {source}: {e}"))?;
+    Ok(modified
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64)
+}
+
+/// A numeric threshold: a comparison with a bound, like `>= 0.7`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Threshold {
+    op: Comparison,
+    bound: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Comparison {
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+}
+
+impl Threshold {
+    /// Parse `<op> <number>[unit]`; a bare number means equality. Returns
+    /// `None` for qualitative thresholds.
+    fn parse(threshold: &str) -> Option<Threshold> {
+        let threshold = threshold.trim();
+        let (op, rest) = [
+            (">=", Comparison::Ge),
+            ("<=", Comparison::Le),
+            ("==", Comparison::Eq),
+            (">", Comparison::Gt),
+            ("<", Comparison::Lt),
+            ("=", Comparison::Eq),
+        ]
+        .into_iter()
+        .find_map(|(prefix, op)| Some((op, threshold.strip_prefix(prefix)?)))
+        .unwrap_or((Comparison::Eq, threshold));
+        let rest = rest.trim();
+        let end = rest
+            .find(|c: char| !(c.is_ascii_digit() || matches!(c, '.' | '-' | '+')))
+            .unwrap_or(rest.len());
+        let (number, unit) = rest.split_at(end);
+        if !unit.trim().chars().all(|c| c.is_alphabetic() || c == '%') {
+            return None;
+        }
+        Some(Threshold {
+            op,
+            bound: number.parse().ok()?,
+        })
+    }
+
+    fn is_met(&self, value: f64) -> bool {
+        match self.op {
+            Comparison::Lt => value < self.bound,
+            Comparison::Le => value <= self.bound,
+            Comparison::Gt => value > self.bound,
+            Comparison::Ge => value >= self.bound,
+            Comparison::Eq => value == self.bound,
+        }
+    }
+}
+
+/// A trigger duration in seconds.
+fn seconds(duration: &Duration) -> Result<i64, String> {
+    let unit = match duration.unit.trim().to_ascii_lowercase().as_str() {
+        "s" | "sec" | "secs" | "second" | "seconds" => 1.0,
+        "m" | "min" | "mins" | "minute" | "minutes" => 60.0,
+        "h" | "hour" | "hours" => 3600.0,
+        "d" | "day" | "days" => 86_400.0,
+        "w" | "week" | "weeks" => 604_800.0,
+        other => return Err(format!("unknown duration unit '{other}'")),
+    };
+    Ok((duration.value * unit) as i64)
+}
+
+/// Read a metric's samples from its source, oldest first.
+fn load_metric(project_root: &Path, metric: &FeedbackMetric) -> Result<Vec<Sample>, String> {
+    let source = metric.source.as_deref().ok_or("no source")?;
+    if source.contains("://") {
+        return Err(format!("remote source {source} is not supported"));
+    }
+    let path = project_root.join(source);
+    let content =
+        std::fs::read_to_string(&path).map_err(|e| format!("could not read {source}: {e}"))?;
+    let extension = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_ascii_lowercase());
+    let mut samples = match extension.as_deref() {
+        Some("csv") => csv_samples(&content, &metric.name),
+        Some("json") => json_samples(&content, &metric.name),
+        _ => Err("unsupported source (expected .csv or .json)".to_string()),
+    }
+    .map_err(|e| format!("{source}: {e}"))?;
+    if samples.iter().all(|s| s.at.is_some()) {
+        samples.sort_by_key(|s| s.at);
+    }
+    Ok(samples)
+}
+
+fn csv_samples(content: &str, name: &str) -> Result<Vec<Sample>, String> {
+    let split = |line: &str| -> Vec<String> {
+        line.split(',')
+            .map(|cell| cell.trim().trim_matches('"').to_string())
+            .collect()
+    };
+    let mut lines = content.lines().filter(|l| !l.trim().is_empty());
+    let header = split(lines.next().ok_or("empty file")?);
+    let rows: Vec<BTreeMap<String, String>> = lines
+        .map(|line| header.iter().cloned().zip(split(line)).collect())
+        .collect();
+    rows_samples(&rows, name)
+}
+
+fn json_samples(content: &str, name: &str) -> Result<Vec<Sample>, String> {
+    let data: Value = serde_json::from_str(content).map_err(|e| format!("invalid JSON: {e}"))?;
+    let to_string = |v: &Value| match v {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    };
+    match data {
+        Value::Array(records) => {
+            let rows: Vec<BTreeMap<String, String>> = records
+                .iter()
+                .filter_map(Value::as_object)
+                .map(|r| r.iter().map(|(k, v)| (k.clone(), to_string(v))).collect())
+                .collect();
+            rows_samples(&rows, name)
+        }
+        Value::Object(metrics) => match metrics.get(name) {
+            None => Err(format!("no '{name}' key")),
+            Some(Value::Array(items)) => items
+                .iter()
+                .map(|item| match item {
+                    Value::Object(o) => {
+                        let row = o.iter().map(|(k, v)| (k.clone(), to_string(v))).collect();
+                        row_sample(&row, "value")
+                    }
+                    other => Ok(Sample {
+                        value: other.as_f64().ok_or("non-numeric value")?,
+                        at: None,
+                    }),
+                })
+                .collect(),
+            Some(value) => Ok(vec![Sample {
+                value: value.as_f64().ok_or("non-numeric value")?,
+                at: None,
+            }]),
+        },
+        _ => Err("expected an object or an array of records".to_string()),
+    }
+}
+
+/// Samples from table rows: the metric's own column, or the `value` column of
+/// the rows whose `metric` column names it.
+fn rows_samples(rows: &[BTreeMap<String, String>], name: &str) -> Result<Vec<Sample>, String> {
+    let wide = rows.iter().any(|r| r.contains_key(name));
+    if !wide && !rows.iter().any(|r| r.contains_key("metric")) {
+        return Err(format!("no '{name}' column"));
+    }
+    rows.iter()
+        .filter(|r| wide || r.get("metric").is_some_and(|m| m == name))
+        .filter(|r| {
+            r.get(if wide { name } else { "value" })
+                .is_some_and(|v| !v.is_empty())
+        })
+        .map(|r| row_sample(r, if wide { name } else { "value" }))
+        .collect()
+}
+
+fn row_sample(row: &BTreeMap<String, String>, column: &str) -> Result<Sample, String> {
+    let raw = row.get(column).map(String::as_str).unwrap_or_default();
+    let value = raw
+        .parse()
+        .map_err(|_| format!("non-numeric value '{raw}'"))?;
+    let at = match TIMESTAMP_COLUMNS.iter().find_map(|c| row.get(*c)) {
+        Some(at) => {
+            Some(time::parse_timestamp(at).ok_or_else(|| format!("invalid timestamp '{at}'"))?)
+        }
+        None => None,
+    };
+    Ok(Sample { value, at })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const DAY: i64 = 86_400;
+    /// 2024-03-10T00:00:00Z
+    const NOW: i64 = 1_710_028_800;
+
+    fn spec(feedback: &str) -> Spec {
+        Spec::parse(&format!(
+            "schema_version: '0.7'\nintent: 'x'\nfeedback:\n{feedback}"
+        ))
+        .unwrap()
+    }
+
+    fn triggered(report: &Report) -> Vec<(&str, &str)> {
+        report
+            .triggered
+            .iter()
+            .map(|t| (t.metric.as_str(), t.message.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn parses_thresholds() {
+        let t = Threshold::parse(">= 0.7").unwrap();
+        assert_eq!((t.op, t.bound), (Comparison::Ge, 0.7));
+        assert!(t.is_met(0.7) && !t.is_met(0.69));
+        let t = Threshold::parse("< 5s").unwrap();
+        assert_eq!((t.op, t.bound), (Comparison::Lt, 5.0));
+        assert_eq!(Threshold::parse("42").unwrap().op, Comparison::Eq);
+        assert_eq!(Threshold::parse("high engagement"), None);
+        assert_eq!(Threshold::parse(">= 0.7 or better"), None);
+    }
+
+    #[test]
+    fn reads_wide_and_long_csv_and_json_sources() {
+        let wide = "date,completion,views\n2024-03-02,0.9,10\n2024-03-01,0.8,\n";
+        assert_eq!(
+            csv_samples(wide, "views").unwrap(),
+            [Sample {
+                value: 10.0,
+                at: Some(NOW - 8 * DAY)
+            }]
+        );
+        assert_eq!(csv_samples(wide, "completion").unwrap().len(), 2);
+        let long = "metric,value\nviews,3\ncompletion,0.5\nviews,4\n";
+        let values: Vec<f64> = csv_samples(long, "views")
+            .unwrap()
+            .iter()
+            .map(|s| s.value)
+            .collect();
+        assert_eq!(values, [3.0, 4.0]);
+        assert!(csv_samples(wide, "missing").is_err());
+
+        let json =
+            r#"{"views": [1, 2], "rate": 0.5, "load": [{"value": 3, "timestamp": 1710028800}]}"#;
+        assert_eq!(json_samples(json, "views").unwrap().len(), 2);
+        assert_eq!(json_samples(json, "rate").unwrap()[0].value, 0.5);
+        assert_eq!(json_samples(json, "load").unwrap()[0].at, Some(NOW));
+        let records = r#"[{"date": "2024-03-01", "views": 7}]"#;
+        assert_eq!(json_samples(records, "views").unwrap()[0].value, 7.0);
+    }
+
+    #[test]
+    fn threshold_triggers_respect_their_duration() {
+        let tmp = TempDir::new().unwrap();
+        std::fs::write(
+            tmp.path().join("rates.csv"),
+            "date,rate\n2024-03-01,0.9\n2024-03-06,0.6\n2024-03-08,0.5\n2024-03-09,0.65\n",
+        )
+        .unwrap();
+        let s = spec(
+            "  metrics:\n    - name: rate\n      source: rates.csv\n      threshold: '>= 0.7'\n  \
+             triggers:\n    - condition: { metric: rate, operator: below_threshold }\n      \
+             action: warn\n    \
+             - condition: { metric: rate, operator: below_threshold, duration: { value: 3, unit: days } }\n      \
+             priority: high\n    \
+             - condition: { metric: rate, operator: below_threshold, duration: { value: 1, unit: weeks } }\n    \
+             - condition: { metric: rate, operator: above_threshold }\n",
+        );
+        let report = evaluate(tmp.path(), "s.spec.yaml", &s, NOW);
+        assert_eq!(report.metrics[0].status, MetricStatus::Fail);
+        assert_eq!(report.metrics[0].value, Some(0.65));
+        assert_eq!(
+            report.metrics[0].measured_at.as_deref(),
+            Some("2024-03-09T00:00:00Z")
+        );
+        assert_eq!(
+            triggered(&report),
+            [
+                ("rate", "0.65 is below >= 0.7"),
+                (
+                    "rate",
+                    "0.65 has been below >= 0.7 since 2024-03-06T00:00:00Z"
+                ),
+            ]
+        );
+        assert_eq!(report.triggered[0].action, TriggerAction::Warn);
+        assert_eq!(report.triggered[1].action, TriggerAction::Reconcile);
+        assert_eq!(report.triggered[1].priority, Priority::High);
+        assert!(report.needs_reconciliation());
+        assert!(report.errors.is_empty());
+    }
+
+    #[test]
+    fn changed_and_stale_triggers() {
+        let tmp = TempDir::new().unwrap();
+        std::fs::write(
+            tmp.path().join("m.json"),
+            r#"{"score": [{"value": 1, "date": "2024-03-01"}, {"value": 2, "date": "2024-03-05"}]}"#,
+        )
+        .unwrap();
+        let s = spec(
+            "  metrics:\n    - name: score\n      source: m.json\n    \
+             - name: speed\n      source: missing.csv\n  \
+             triggers:\n    - condition: { metric: score, operator: changed }\n    \
+             - condition: { metric: score, operator: stale, duration: { value: 7, unit: days } }\n    \
+             - condition: { metric: score, operator: stale, duration: { value: 2, unit: days } }\n    \
+             - condition: { metric: speed, operator: stale }\n    \
+             - condition: { metric: score, operator: stale, duration: { value: 2, unit: fortnights } }\n    \
+             - condition: { metric: nope, operator: changed }\n",
+        );
+        let report = evaluate(tmp.path(), "s.spec.yaml", &s, NOW);
+        assert_eq!(report.metrics[0].status, MetricStatus::Unknown);
+        assert_eq!(report.metrics[1].status, MetricStatus::Unavailable);
+        let fired = triggered(&report);
+        assert_eq!(fired[0], ("score", "changed from 1 to 2"));
+        assert_eq!(fired[1], ("score", "last measured 2024-03-05T00:00:00Z"));
+        assert_eq!(fired[2].0, "speed");
+        assert!(
+            fired[2]
+                .1
+                .starts_with("no data (could not read missing.csv")
+        );
+        assert_eq!(fired.len(), 3);
+        assert_eq!(
+            report.errors,
+            [
+                "score stale: unknown duration unit 'fortnights'",
+                "trigger references unknown metric 'nope'"
+            ]
+        );
+    }
+}
@@ -0,0 +1,414 @@
+//! The relationship graph of a project's specs, for `notarai graph`.
+//!
+//! Nodes are specs (plus, optionally, each spec's artifact categories as
+//! leaves). Edges are `subsystems` containment, `applies` cross-cutting
+//! references and `dependencies` labeled with their `relationship`. A `$ref`
+//! that does not resolve to a spec file becomes a `missing` node so broken
+//! references show up in the picture instead of disappearing.
+
+use crate::core::compose::{SpecGraph, resolve_ref};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NodeKind {
+    Spec,
+    Artifact,
+    Missing,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EdgeKind {
+    Subsystem,
+    Applies,
+    Dependency,
+    Artifact,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Node {
+    /// Project-relative spec path, or `<spec path>#<category>` for an
+    /// artifact category.
+    pub id: String,
+    pub kind: NodeKind,
+    pub label: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tier: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub domain: Option<&'static str>,
+    /// The artifact globs of an artifact category node.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub globs: Vec<String>,
+    /// Why a spec node has no edges: its file could not be parsed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Edge {
+    pub from: String,
+    pub to: String,
+    pub kind: EdgeKind,
+    /// The `relationship` of a dependency edge.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Graph {
+    pub nodes: Vec<Node>,
+    pub edges: Vec<Edge>,
+}
+
+/// Output format of `notarai graph`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Dot,
+    Mermaid,
+    Json,
+}
+
+impl std::str::FromStr for Format {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> crate::Result<Format> {
+        match s {
+            "dot" => Ok(Format::Dot),
+            "mermaid" => Ok(Format::Mermaid),
+            "json" => Ok(Format::Json),
+            _ => Err(crate::Error::UnknownValue {
+                what: "format",
+                value: s.to_string(),
+                expected: "dot, mermaid, json".to_string(),
+            }),
+        }
+    }
+}
+
+/// Build the graph of every spec in `specs`. With `artifacts`, each spec's
+/// artifact categories are added as leaf nodes.
+pub fn build(specs: &SpecGraph, artifacts: bool) -> Graph {
+    let mut graph = Graph::default();
+    let known: BTreeSet<&str> = specs.iter().map(|(path, _)| path).collect();
+    for (path, spec) in specs.iter() {
+        graph.nodes.push(Node {
+            id: path.to_string(),
+            kind: NodeKind::Spec,
+            label: spec_label(path),
+            tier: spec.ok().map(|s| s.tier().as_str()),
+            domain: spec.ok().map(|s| s.domain().as_str()),
+            globs: Vec::new(),
+            error: spec.err().map(|e| e.to_string()),
+        });
+    }
+
+    let mut missing: BTreeSet<String> = BTreeSet::new();
+    for (path, spec) in specs.iter() {
+        let Ok(spec) = spec else { continue };
+        let refs = spec
+            .subsystems
+            .iter()
+            .map(|r| (&r.reference, EdgeKind::Subsystem, None))
+            .chain(
+                spec.applies
+                    .iter()
+                    .map(|r| (&r.reference, EdgeKind::Applies, None)),
+            )
+            .chain(
+                spec.dependencies
+                    .iter()
+                    .map(|d| (&d.reference, EdgeKind::Dependency, Some(&d.relationship))),
+            );
+        for (reference, kind, label) in refs {
+            let to = resolve_ref(path, reference);
+            if !known.contains(to.as_str()) && missing.insert(to.clone()) {
+                graph.nodes.push(Node {
+                    id: to.clone(),
+                    kind: NodeKind::Missing,
+                    label: spec_label(&to),
+                    tier: None,
+                    domain: None,
+                    globs: Vec::new(),
+                    error: None,
+                });
+            }
+            graph.edges.push(Edge {
+                from: path.to_string(),
+                to,
+                kind,
+                label: label.cloned(),
+            });
+        }
+
+        if artifacts {
+            for (category, refs) in &spec.artifacts {
+                let id = format!("{path}#{category}");
+                graph.nodes.push(Node {
+                    id: id.clone(),
+                    kind: NodeKind::Artifact,
+                    label: category.clone(),
+                    tier: None,
+                    domain: None,
+                    globs: refs.iter().map(|r| r.path.clone()).collect(),
+                    error: None,
+                });
+                graph.edges.push(Edge {
+                    from: path.to_string(),
+                    to: id,
+                    kind: EdgeKind::Artifact,
+                    label: None,
+                });
+            }
+        }
+    }
+    graph
+}
+
+/// `.notarai/api/auth.spec.yaml` -> `api/auth`.
+fn spec_label(path: &str) -> String {
+    let path = path.strip_prefix(".notarai/").unwrap_or(path);
+    path.strip_suffix(".spec.yaml").unwrap_or(path).to_string()
+}
+
+impl Graph {
+    /// Keep `start` and every node reachable from it through `subsystems`
+    /// edges only (`subtree`) or through any reference (`!subtree`), with
+    /// their outgoing edges and the nodes those edges point to.
+    pub fn filter(&self, start: &str, subtree: bool) -> crate::Result<Graph> {
+        let start = crate::core::mcp_tools::normalize_rel(start);
+        if !self
+            .nodes
+            .iter()
+            .any(|n| n.id == start && n.kind == NodeKind::Spec)
+        {
+            return Err(crate::Error::SpecNotFound(start.to_string()));
+        }
+        let follow = |kind: EdgeKind| match kind {
+            EdgeKind::Subsystem => true,
+            EdgeKind::Applies | EdgeKind::Dependency => !subtree,
+            EdgeKind::Artifact => false,
+        };
+
+        let mut kept: BTreeSet<&str> = BTreeSet::from([start]);
+        let mut frontier = vec![start];
+        while let Some(id) = frontier.pop() {
+            for edge in &self.edges {
+                if edge.from == id && follow(edge.kind) && kept.insert(&edge.to) {
+                    frontier.push(&edge.to);
+                }
+            }
+        }
+
+        let edges: Vec<Edge> = self
+            .edges
+            .iter()
+            .filter(|e| kept.contains(e.from.as_str()))
+            .cloned()
+            .collect();
+        let shown: BTreeSet<&str> = kept
+            .iter()
+            .copied()
+            .chain(edges.iter().map(|e| e.to.as_str()))
+            .collect();
+        let nodes = self
+            .nodes
+            .iter()
+            .filter(|n| shown.contains(n.id.as_str()))
+            .cloned()
+            .collect();
+        Ok(Graph { nodes, edges })
+    }
+
+    pub fn render(&self, format: Format) -> String {
+        match format {
+            Format::Dot => self.to_dot(),
+            Format::Mermaid => self.to_mermaid(),
+            Format::Json => serde_json::to_string_pretty(self).expect("JSON serialization") + "\n",
+        }
+    }
+
+    /// Graphviz DOT. Subsystem edges are solid, `applies` dashed and
+    /// dependencies dotted with their relationship as the label.
+    fn to_dot(&self) -> String {
+        let quote = |s: &str| format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""));
+        let mut out = String::from("digraph notarai {\n  rankdir=LR;\n  node [shape=box];\n");
+        for node in &self.nodes {
+            let attrs = match node.kind {
+                NodeKind::Spec if node.error.is_some() => ", color=red",
+                NodeKind::Spec => "",
+                NodeKind::Artifact => ", shape=note",
+                NodeKind::Missing => ", style=dashed, color=red",
+            };
+            out.push_str(&format!(
+                "  {} [label={}{attrs}];\n",
+                quote(&node.id),
+                quote(&node.label)
+            ));
+        }
+        for edge in &self.edges {
+            let attrs = match (edge.kind, &edge.label) {
+                (EdgeKind::Subsystem, _) => String::new(),
+                (EdgeKind::Applies, _) => " [style=dashed, label=\"applies\"]".to_string(),
+                (EdgeKind::Dependency, label) => format!(
+                    " [style=dotted, label={}]",
+                    quote(label.as_deref().unwrap_or(""))
+                ),
+                (EdgeKind::Artifact, _) => " [arrowhead=none]".to_string(),
+            };
+            out.push_str(&format!(
+                "  {} -> {}{attrs};\n",
+                quote(&edge.from),
+                quote(&edge.to)
+            ));
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    /// Mermaid flowchart. Node ids are generated (`n0`, `n1`, ...) since spec
+    /// paths are not valid Mermaid identifiers.
+    fn to_mermaid(&self) -> String {
+        let ids: BTreeMap<&str, String> = self
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(i, n)| (n.id.as_str(), format!("n{i}")))
+            .collect();
+        let text = |s: &str| format!("\"{}\"", s.replace('"', "#quot;"));
+        let mut out = String::from("flowchart LR\n");
+        for node in &self.nodes {
+            let id = &ids[node.id.as_str()];
+            let label = text(&node.label);
+            match node.kind {
+                NodeKind::Spec => out.push_str(&format!("  {id}[{label}]\n")),
+                NodeKind::Artifact => out.push_str(&format!("  {id}[({label})]\n")),
+                NodeKind::Missing => out.push_str(&format!("  {id}{{{{{label}}}}}\n")),
+            }
+        }
+        for edge in &self.edges {
+            let (from, to) = (&ids[edge.from.as_str()], &ids[edge.to.as_str()]);
+            let arrow = match edge.kind {
+                EdgeKind::Subsystem => "-->".to_string(),
+                EdgeKind::Applies => "-.->|applies|".to_string(),
+                EdgeKind::Dependency => {
+                    format!("==>|{}|", text(edge.label.as_deref().unwrap_or("")))
+                }
+                EdgeKind::Artifact => "---".to_string(),
+            };
+            out.push_str(&format!("  {from} {arrow} {to}\n"));
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::spec::test_spec;
+
+    fn specs() -> SpecGraph {
+        SpecGraph::from_specs([
+            (
+                ".notarai/system.spec.yaml".to_string(),
+                test_spec(
+                    "subsystems:\n  - $ref: './api.spec.yaml'\n  - $ref: './web.spec.yaml'\n\
+                     applies:\n  - $ref: './style.spec.yaml'\n",
+                ),
+            ),
+            (
+                ".notarai/api.spec.yaml".to_string(),
+                test_spec(
+                    "dependencies:\n  - $ref: './db.spec.yaml'\n    relationship: 'stores \"users\"'\n\
+                     artifacts:\n  code:\n    - path: 'src/api/**'\n",
+                ),
+            ),
+            (".notarai/web.spec.yaml".to_string(), test_spec("")),
+            (".notarai/style.spec.yaml".to_string(), test_spec("")),
+            (
+                ".notarai/db.spec.yaml".to_string(),
+                test_spec("applies:\n  - $ref: './gone.spec.yaml'\n"),
+            ),
+        ])
+    }
+
+    #[test]
+    fn builds_edges_and_missing_nodes() {
+        let graph = build(&specs(), false);
+        let edges: Vec<(&str, &str, EdgeKind)> = graph
+            .edges
+            .iter()
+            .map(|e| (e.from.as_str(), e.to.as_str(), e.kind))
+            .collect();
+        assert!(edges.contains(&(
+            ".notarai/system.spec.yaml",
+            ".notarai/api.spec.yaml",
+            EdgeKind::Subsystem
+        )));
+        assert!(edges.contains(&(
+            ".notarai/api.spec.yaml",
+            ".notarai/db.spec.yaml",
+            EdgeKind::Dependency
+        )));
+        let gone = graph
+            .nodes
+            .iter()
+            .find(|n| n.id == ".notarai/gone.spec.yaml")
+            .unwrap();
+        assert_eq!(gone.kind, NodeKind::Missing);
+        assert!(graph.nodes.iter().all(|n| n.kind != NodeKind::Artifact));
+
+        let with_artifacts = build(&specs(), true);
+        let leaf = with_artifacts
+            .nodes
+            .iter()
+            .find(|n| n.id == ".notarai/api.spec.yaml#code")
+            .unwrap();
+        assert_eq!(leaf.globs, ["src/api/**"]);
+    }
+
+    #[test]
+    fn filters_subtree_and_reachable() {
+        let graph = build(&specs(), false);
+        let ids = |g: &Graph| -> Vec<String> { g.nodes.iter().map(|n| n.id.clone()).collect() };
+
+        let subtree = graph.filter(".notarai/api.spec.yaml", true).unwrap();
+        assert_eq!(
+            ids(&subtree),
+            [".notarai/api.spec.yaml", ".notarai/db.spec.yaml"]
+        );
+        assert_eq!(subtree.edges.len(), 1);
+
+        let reachable = graph.filter(".notarai/api.spec.yaml", false).unwrap();
+        assert_eq!(
+            ids(&reachable),
+            [
+                ".notarai/api.spec.yaml",
+                ".notarai/db.spec.yaml",
+                ".notarai/gone.spec.yaml"
+            ]
+        );
+
+        assert!(graph.filter(".notarai/nope.spec.yaml", true).is_err());
+    }
+
+    #[test]
+    fn renders_dot_and_mermaid() {
+        let graph = build(&specs(), false)
+            .filter(".notarai/api.spec.yaml", true)
+            .unwrap();
+        let dot = graph.render(Format::Dot);
+        assert!(dot.starts_with("digraph notarai {\n"));
+        assert!(dot.contains(
+            "  \".notarai/api.spec.yaml\" -> \".notarai/db.spec.yaml\" \
+             [style=dotted, label=\"stores \\\"users\\\"\"];\n"
+        ));
+
+        let mermaid = graph.render(Format::Mermaid);
+        assert_eq!(
+            mermaid,
+            "flowchart LR\n  n0[\"api\"]\n  n1[\"db\"]\n  n0 ==>|\"stores #quot;users#quot;\"| n1\n"
+        );
+    }
+}
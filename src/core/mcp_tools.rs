@@ -1,3 +1,5 @@
+use crate::core::compose::SpecGraph;
+use crate::core::spec::Spec;
 use std::path::{Path, PathBuf};
 
 /// A JSON-RPC error returned by an MCP tool.
@@ -6,17 +8,263 @@ pub struct McpError {
     pub message: String,
 }
 
+impl From<crate::Error> for McpError {
+    /// Library errors are internal errors of the tool call.
+    fn from(e: crate::Error) -> McpError {
+        McpError {
+            code: -32603,
+            message: e.to_string(),
+        }
+    }
+}
+
 /// Shorthand result type for MCP tool functions.
 pub type McpResult = Result<serde_json::Value, McpError>;
 
+/// The `tools/list` entry of every tool, with its input schema.
+pub fn tools() -> serde_json::Value {
+    serde_json::json!([
+        {
+            "name": "list_affected_specs",
+            "description": "List specs affected by changes on the current branch vs base branch",
+            "inputSchema": {
+                "type": "object",
+                "required": ["base_branch"],
+                "properties": {
+                    "base_branch": {"type": "string", "description": "The base branch to diff against"},
+                    "include_feedback": {
+                        "type": "boolean",
+                        "description": "If true, evaluate feedback triggers from local metric data: specs with a firing reconcile trigger are included even without changed files, and affected specs list their firing triggers in 'triggered'"
+                    }
+                }
+            }
+        },
+        {
+            "name": "get_spec_diff",
+            "description": "Get the git diff filtered to files governed by a specific spec. Files already reconciled (per cache) are skipped; the response includes a 'skipped' field listing them. A cold or absent cache causes all governed files to be diffed (safe fallback). Pass bypass_cache: true to force a full diff regardless of cache state. Spec files (.notarai/**/*.spec.yaml) in the governed set are split into a separate 'spec_changes' field with full file content (not diff hunks); the 'diff' field contains only non-spec artifact diffs. When spec_changes is non-empty, 'system_spec' is also included with the full content of the system spec (the spec with a subsystems key), even if the system spec itself did not change. Binary files (images, PPTX, PDF, etc.) are listed in 'binary_changes' and excluded from 'diff' since their diffs are uninformative. 'file_categories' maps each changed file path to its artifact category from the spec (e.g. 'code', 'docs', 'assets'). Derived (tier 4) artifacts are left out of the diff and listed in 'derived'. 'evidence' lists content evidence sources that are missing or not governed by any spec ('issues') and the claims whose source changed since the spec was last reconciled ('changed_claims', with section and claim text).",
+            "inputSchema": {
+                "type": "object",
+                "required": ["spec_path", "base_branch"],
+                "properties": {
+                    "spec_path": {"type": "string", "description": "Relative path to the spec file"},
+                    "base_branch": {"type": "string", "description": "The base branch to diff against"},
+                    "exclude_patterns": {
+                        "type": "array",
+                        "items": {"type": "string"},
+                        "description": "Glob patterns to exclude from the diff via git :(exclude) pathspecs (e.g. [\"Cargo.lock\", \"*.lock\"])"
+                    },
+                    "bypass_cache": {
+                        "type": "boolean",
+                        "description": "If true, skip cache filtering and diff all governed files regardless of prior reconciliation state"
+                    },
+                    "verify_hashes": {
+                        "type": "boolean",
+                        "description": "If true, rehash every cached file instead of trusting unchanged size and mtime"
+                    }
+                }
+            }
+        },
+        {
+            "name": "get_changed_artifacts",
+            "description": "Get artifacts governed by a spec that have changed since they were last marked with mark_reconciled",
+            "inputSchema": {
+                "type": "object",
+                "required": ["spec_path"],
+                "properties": {
+                    "spec_path": {"type": "string", "description": "Relative path to the spec file"},
+                    "artifact_type": {"type": "string", "description": "Optional artifact type filter (e.g. 'docs', 'code')"},
+                    "verify_hashes": {"type": "boolean", "description": "If true, rehash every cached file instead of trusting unchanged size and mtime"}
+                }
+            }
+        },
+        {
+            "name": "begin_reconciliation",
+            "description": "Start a reconciliation run. Subsequent mark_reconciled calls are logged against this run (visible via `notarai log`) until snapshot_state closes it.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "base_branch": {"type": "string", "description": "The base branch or ref being reconciled against"}
+                }
+            }
+        },
+        {
+            "name": "mark_reconciled",
+            "description": "Record files as reconciled against a spec. Records are scoped to the spec: they do not mark the files reconciled for any other spec, and they are discarded automatically when the spec file itself changes.",
+            "inputSchema": {
+                "type": "object",
+                "required": ["spec_path", "files"],
+                "properties": {
+                    "spec_path": {"type": "string", "description": "Relative path to the spec the files were reconciled against"},
+                    "files": {
+                        "type": "array",
+                        "items": {"type": "string"},
+                        "description": "Relative file paths to cache"
+                    }
+                }
+            }
+        },
+        {
+            "name": "clear_cache",
+            "description": "Delete every reconciliation record from the cache database, forcing the next get_spec_diff call to diff all governed files",
+            "inputSchema": {
+                "type": "object",
+                "properties": {}
+            }
+        },
+        {
+            "name": "snapshot_state",
+            "description": "Snapshot the current reconciliation cache into .notarai/reconciliation_state.json and close the open reconciliation run. Call this at the end of a successful reconciliation pass.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {},
+                "required": []
+            }
+        },
+        {
+            "name": "state_diff",
+            "description": "Report drift since the baseline in .notarai/reconciliation_state.json: hashes every governed file and spec and lists added, modified and removed artifacts and changed specs, grouped by spec. Works without git history.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {},
+                "required": []
+            }
+        },
+        {
+            "name": "resolve_variant",
+            "description": "Compute the effective spec for one variant of a spec with variants_resolved: true by applying the variant's dot-path overrides to the base spec (scalars and arrays replace, a '+' key prefix appends to an array, objects deep-merge, null clears). The result is validated against the schema; overrides whose path does not exist in the base spec are listed in 'unmatched_overrides' and not applied.",
+            "inputSchema": {
+                "type": "object",
+                "required": ["spec_path", "variant"],
+                "properties": {
+                    "spec_path": {"type": "string", "description": "Relative path to the spec file"},
+                    "variant": {"type": "string", "description": "The id of the variant to resolve"}
+                }
+            }
+        }
+    ])
+}
+
+/// Call the tool `name` with its `arguments` object. Unknown tools and
+/// missing required arguments are reported with their JSON-RPC error codes.
+pub fn call_tool(name: &str, args: &serde_json::Value, root: &Path) -> McpResult {
+    match name {
+        "list_affected_specs" => {
+            let base = args
+                .get("base_branch")
+                .and_then(|b| b.as_str())
+                .unwrap_or("main");
+            let include_feedback = args
+                .get("include_feedback")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false);
+            list_affected_specs(base, root, include_feedback)
+        }
+        "get_spec_diff" => {
+            let Some(spec) = args.get("spec_path").and_then(|s| s.as_str()) else {
+                return Err(invalid_params("Missing spec_path"));
+            };
+            let base = args
+                .get("base_branch")
+                .and_then(|b| b.as_str())
+                .unwrap_or("main");
+            let exclude_patterns: Vec<String> = args
+                .get("exclude_patterns")
+                .and_then(|v| v.as_array())
+                .map(|arr| {
+                    arr.iter()
+                        .filter_map(|v| v.as_str().map(String::from))
+                        .collect()
+                })
+                .unwrap_or_default();
+            let bypass_cache = args
+                .get("bypass_cache")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false);
+            let verify_hashes = args
+                .get("verify_hashes")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false);
+            get_spec_diff(
+                spec,
+                base,
+                &exclude_patterns,
+                bypass_cache,
+                verify_hashes,
+                root,
+            )
+        }
+        "get_changed_artifacts" => {
+            let Some(spec) = args.get("spec_path").and_then(|s| s.as_str()) else {
+                return Err(invalid_params("Missing spec_path"));
+            };
+            let art_type = args.get("artifact_type").and_then(|t| t.as_str());
+            let verify_hashes = args
+                .get("verify_hashes")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false);
+            get_changed_artifacts(spec, art_type, verify_hashes, root)
+        }
+        "begin_reconciliation" => {
+            let base = args.get("base_branch").and_then(|b| b.as_str());
+            begin_reconciliation(base, root)
+        }
+        "mark_reconciled" => {
+            let Some(spec) = args.get("spec_path").and_then(|s| s.as_str()) else {
+                return Err(invalid_params("Missing spec_path"));
+            };
+            let Some(arr) = args.get("files").and_then(|f| f.as_array()) else {
+                return Err(invalid_params("Missing files"));
+            };
+            let files: Vec<String> = arr
+                .iter()
+                .filter_map(|v| v.as_str().map(String::from))
+                .collect();
+            mark_reconciled(spec, &files, root)
+        }
+        "clear_cache" => clear_cache(root),
+        "snapshot_state" => snapshot_state(root),
+        "state_diff" => state_diff(root),
+        "resolve_variant" => {
+            let Some(spec) = args.get("spec_path").and_then(|s| s.as_str()) else {
+                return Err(invalid_params("Missing spec_path"));
+            };
+            let Some(variant) = args.get("variant").and_then(|v| v.as_str()) else {
+                return Err(invalid_params("Missing variant"));
+            };
+            resolve_variant(spec, variant, root)
+        }
+        _ => Err(McpError {
+            code: -32601,
+            message: format!("Unknown tool: {name}"),
+        }),
+    }
+}
+
+fn invalid_params(message: &str) -> McpError {
+    McpError {
+        code: -32602,
+        message: message.to_string(),
+    }
+}
+
 /// List specs whose governed files overlap with files changed since `base_branch`.
 ///
 /// Runs `git diff <base_branch> --name-only`, then cross-references each
 /// `.notarai/*.spec.yaml` artifact glob against the changed file list. Returns
 /// a JSON object with `changed_files` (all changed paths) and `affected_specs`
 /// (specs with at least one matching artifact, including their `behaviors`,
-/// `constraints`, and `invariants`).
-pub fn list_affected_specs(base_branch: &str, project_root: &Path) -> McpResult {
+/// `constraints`, and `invariants`, plus the `effective` invariants and
+/// constraints composed from the cross-cutting specs that apply to them).
+///
+/// With `include_feedback`, every spec's feedback triggers are evaluated too:
+/// specs with a firing `reconcile` trigger are affected even when none of
+/// their files changed, and affected specs with firing triggers list them in
+/// `triggered`.
+pub fn list_affected_specs(
+    base_branch: &str,
+    project_root: &Path,
+    include_feedback: bool,
+) -> McpResult {
     let output = std::process::Command::new("git")
         .args(["diff", base_branch, "--name-only"])
         .current_dir(project_root)
@@ -41,7 +289,7 @@ pub fn list_affected_specs(base_branch: &str, project_root: &Path) -> McpResult
 
     let specs = collect_specs(project_root)?;
 
-    let mut affected = Vec::new();
+    let mut parsed = Vec::new();
     for spec_path in &specs {
         let spec_rel = spec_path
             .strip_prefix(project_root)
@@ -53,32 +301,46 @@ pub fn list_affected_specs(base_branch: &str, project_root: &Path) -> McpResult
             message: format!("read error for {spec_rel}: {e}"),
         })?;
 
-        let spec_value = crate::core::yaml::parse_yaml(&content).map_err(|e| McpError {
+        let spec = Spec::parse(&content).map_err(|e| McpError {
             code: -32603,
-            message: e,
+            message: format!("{spec_rel}: {e}"),
         })?;
 
-        if is_spec_affected(&spec_value, &changed) {
-            let behaviors = spec_value
-                .get("behaviors")
-                .cloned()
-                .unwrap_or(serde_json::Value::Null);
-            let constraints = spec_value
-                .get("constraints")
-                .cloned()
-                .unwrap_or(serde_json::Value::Null);
-            let invariants = spec_value
-                .get("invariants")
-                .cloned()
-                .unwrap_or(serde_json::Value::Null);
-
-            affected.push(serde_json::json!({
-                "spec_path": spec_rel,
-                "behaviors": behaviors,
-                "constraints": constraints,
-                "invariants": invariants,
-            }));
+        parsed.push((spec_rel, spec));
+    }
+
+    let now = crate::core::time::now_secs();
+    let feedback: Vec<Option<crate::core::feedback::Report>> = parsed
+        .iter()
+        .map(|(spec_rel, spec)| {
+            (include_feedback && spec.feedback.is_some())
+                .then(|| crate::core::feedback::evaluate(project_root, spec_rel, spec, now))
+        })
+        .collect();
+    let affected_specs: Vec<(String, Spec, Option<crate::core::feedback::Report>)> = parsed
+        .iter()
+        .zip(feedback)
+        .filter(|((_, spec), report)| {
+            is_spec_affected(spec, &changed)
+                || report.as_ref().is_some_and(|r| r.needs_reconciliation())
+        })
+        .map(|((spec_rel, spec), report)| (spec_rel.clone(), spec.clone(), report))
+        .collect();
+    let graph = SpecGraph::from_specs(parsed);
+    let mut affected = Vec::new();
+    for (spec_rel, spec, report) in affected_specs {
+        let effective = compose(&graph, &spec_rel)?;
+        let mut entry = serde_json::json!({
+            "spec_path": spec_rel,
+            "behaviors": spec.behaviors,
+            "constraints": spec.constraints,
+            "invariants": spec.invariants,
+            "effective": effective,
+        });
+        if let Some(report) = report.filter(|r| !r.triggered.is_empty()) {
+            entry["triggered"] = serde_json::json!(report.triggered);
         }
+        affected.push(entry);
     }
 
     Ok(serde_json::json!({
@@ -94,20 +356,31 @@ pub fn list_affected_specs(base_branch: &str, project_root: &Path) -> McpResult
 ///   non-spec artifacts.
 /// - Unless `bypass_cache` is true, filters out files whose hash matches the
 ///   cache (these are listed in `"skipped"`). A cold or absent cache is treated
-///   as "include everything".
+///   as "include everything". `verify_hashes` disables the size/mtime fast
+///   path so every cached file is rehashed.
 /// - Returns full content (not a diff) for any changed spec files in
 ///   `"spec_changes"`. When `spec_changes` is non-empty, also includes
 ///   `"system_spec"` with the full content of the spec containing `subsystems`.
 /// - Runs `git diff <base_branch>` on the remaining non-spec artifacts,
 ///   applying `exclude_patterns` as `:(exclude)` pathspecs.
 ///
+/// - Diffs `.ipynb` artifacts cell by cell into `"notebook_changes"` instead of
+///   including them in `diff`.
+///
+/// - Leaves derived artifacts out of the diff, listing them in `"derived"`.
+/// - Checks the evidence of the spec's content sections into `"evidence"`:
+///   missing or ungoverned sources, and claims whose source changed since the
+///   spec was last reconciled (every claim with `bypass_cache`).
+///
 /// The returned JSON has keys: `diff`, `files`, `skipped`, `excluded`,
-/// `spec_changes`, `system_spec`.
+/// `spec_changes`, `system_spec`, `binary_changes`, `notebook_changes`,
+/// `file_categories`, `derived`, `evidence`, `effective`.
 pub fn get_spec_diff(
     spec_path: &str,
     base_branch: &str,
     exclude_patterns: &[String],
     bypass_cache: bool,
+    verify_hashes: bool,
     project_root: &Path,
 ) -> McpResult {
     let abs_spec = project_root.join(spec_path);
@@ -115,12 +388,41 @@ pub fn get_spec_diff(
         code: -32603,
         message: format!("read error: {e}"),
     })?;
-    let spec_value = crate::core::yaml::parse_yaml(&content).map_err(|e| McpError {
-        code: -32603,
-        message: e,
-    })?;
+    let spec = Spec::parse(&content).map_err(McpError::from)?;
 
-    let files = expand_artifact_globs(&spec_value, project_root);
+    let graph = SpecGraph::load(project_root).map_err(McpError::from)?;
+    let effective = compose(&graph, spec_path)?;
+
+    // Derived outputs are generated from other files; their staleness is
+    // checked by `notarai derived check`, so their content is not diffed.
+    let derived: Vec<String> = crate::core::derived::derived_globs(&spec)
+        .into_iter()
+        .flat_map(|glob| expand_glob(glob, project_root))
+        .collect();
+    let files: Vec<String> = expand_artifact_globs(&spec, project_root)
+        .into_iter()
+        .filter(|f| !derived.contains(f))
+        .collect();
+
+    // Content claims whose evidence source changed since the spec was last
+    // reconciled, and evidence sources that are missing or ungoverned.
+    let spec_key = normalize_rel(spec_path);
+    let spec_fingerprint = crate::core::cache::spec_fingerprint(content.as_bytes());
+    let evidence_conn = if bypass_cache {
+        None
+    } else {
+        crate::core::cache::open_cache_db(project_root).ok()
+    };
+    let records = evidence_conn
+        .as_ref()
+        .map(|conn| crate::core::evidence::Records {
+            conn,
+            spec_path: spec_key,
+            spec_fingerprint: &spec_fingerprint,
+            verify_hashes,
+        });
+    let evidence = crate::core::evidence::check(project_root, &spec, &graph, records.as_ref())
+        .map_err(McpError::from)?;
 
     if files.is_empty() {
         return Ok(serde_json::json!({
@@ -130,6 +432,9 @@ pub fn get_spec_diff(
             "excluded": exclude_patterns,
             "spec_changes": [],
             "system_spec": null,
+            "derived": derived,
+            "evidence": evidence,
+            "effective": effective,
         }));
     }
 
@@ -137,7 +442,8 @@ pub fn get_spec_diff(
     let (spec_files, artifact_files): (Vec<String>, Vec<String>) =
         files.into_iter().partition(|f| is_spec_file(f));
 
-    // Apply cache filtering to both groups independently.
+    // Apply cache filtering to both groups independently. Only records made
+    // for this spec, at its current fingerprint, count as reconciled.
     let (spec_to_show, artifact_to_diff, artifact_skipped) = if bypass_cache {
         (spec_files, artifact_files, vec![])
     } else {
@@ -150,11 +456,17 @@ pub fn get_spec_diff(
                         (rel, abs)
                     })
                     .collect();
-                let (s_show, _s_skip) = crate::core::cache::check_changed_batch(&conn, &spec_pairs)
-                    .unwrap_or_else(|_| {
-                        let all: Vec<String> = spec_pairs.into_iter().map(|(r, _)| r).collect();
-                        (all, vec![])
-                    });
+                let (s_show, _s_skip) = crate::core::cache::check_changed_batch(
+                    &conn,
+                    spec_key,
+                    &spec_fingerprint,
+                    &spec_pairs,
+                    verify_hashes,
+                )
+                .unwrap_or_else(|_| {
+                    let all: Vec<String> = spec_pairs.into_iter().map(|(r, _)| r).collect();
+                    (all, vec![])
+                });
 
                 let artifact_pairs: Vec<(String, std::path::PathBuf)> = artifact_files
                     .into_iter()
@@ -165,7 +477,10 @@ pub fn get_spec_diff(
                     .collect();
                 let (a_diff, a_skip) = crate::core::cache::check_changed_batch(
                     &conn,
+                    spec_key,
+                    &spec_fingerprint,
                     &artifact_pairs,
+                    verify_hashes,
                 )
                 .unwrap_or_else(|_| {
                     let all: Vec<String> = artifact_pairs.into_iter().map(|(r, _)| r).collect();
@@ -205,6 +520,28 @@ pub fn get_spec_diff(
         .cloned()
         .partition(|f| is_binary_by_extension(f));
 
+    // Notebooks get a cell-level diff instead of a JSON text diff. Any that
+    // cannot be parsed fall back to `git diff`.
+    let (notebooks, mut non_binary): (Vec<String>, Vec<String>) =
+        non_binary.into_iter().partition(|f| is_notebook(f));
+    let mut notebook_changes = Vec::new();
+    for path in notebooks {
+        if is_excluded(&path, exclude_patterns) {
+            continue;
+        }
+        match notebook_diff(&path, base_branch, project_root) {
+            Some(Ok(diff)) if !diff.is_empty() => {
+                notebook_changes.push(serde_json::json!({
+                    "path": path,
+                    "cells": diff.cells,
+                    "stale_outputs": diff.stale_outputs,
+                }));
+            }
+            Some(Err(_)) => non_binary.push(path),
+            Some(Ok(_)) | None => {}
+        }
+    }
+
     // Build :(exclude) pathspecs from caller-supplied patterns.
     // Git resolves these as globs, so patterns like "Cargo.lock" or "*.lock"
     // work without pre-expansion.
@@ -252,7 +589,7 @@ pub fn get_spec_diff(
     let diff = clean_lines.join("\n");
 
     // Build file_categories: map each changed artifact file to its spec category.
-    let file_categories = build_file_categories(&spec_value, &artifact_to_diff, project_root);
+    let file_categories = build_file_categories(&spec, &artifact_to_diff, project_root);
 
     Ok(serde_json::json!({
         "diff": diff,
@@ -262,38 +599,92 @@ pub fn get_spec_diff(
         "spec_changes": spec_changes,
         "system_spec": system_spec,
         "binary_changes": binary_changes,
+        "notebook_changes": notebook_changes,
         "file_categories": file_categories,
+        "derived": derived,
+        "evidence": evidence,
+        "effective": effective,
     }))
 }
 
-/// Delete the cache database file, if it exists.
+/// Compose a spec's effective invariants and constraints, as returned in the
+/// `effective` field of `list_affected_specs` and `get_spec_diff`.
+fn compose(graph: &SpecGraph, spec_path: &str) -> McpResult {
+    let composition = graph.compose(spec_path).map_err(McpError::from)?;
+    Ok(serde_json::to_value(composition).expect("JSON serialization"))
+}
+
+fn is_notebook(path: &str) -> bool {
+    Path::new(path)
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("ipynb"))
+}
+
+/// Whether `path` matches one of the caller's exclude patterns. Mirrors git's
+/// default pathspec matching, where `*` also matches `/`.
+fn is_excluded(path: &str, exclude_patterns: &[String]) -> bool {
+    exclude_patterns
+        .iter()
+        .any(|p| glob::Pattern::new(p).is_ok_and(|pattern| pattern.matches(path)))
+}
+
+/// Compare a notebook on disk with its revision at `base_branch`. Returns
+/// `None` when the two are byte-identical, and an error when either side is
+/// not a valid notebook.
+fn notebook_diff(
+    path: &str,
+    base_branch: &str,
+    project_root: &Path,
+) -> Option<crate::Result<crate::core::notebook::NotebookDiff>> {
+    let old = std::process::Command::new("git")
+        .args(["show", &format!("{base_branch}:./{path}")])
+        .current_dir(project_root)
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| String::from_utf8_lossy(&o.stdout).into_owned());
+    let new = std::fs::read_to_string(project_root.join(path)).ok();
+    if old == new {
+        return None;
+    }
+    Some(crate::core::notebook::diff_notebooks(
+        old.as_deref(),
+        new.as_deref(),
+    ))
+}
+
+/// Delete every reconciliation record from the cache database.
 ///
-/// Returns `{"cleared": true}` when the file was deleted, `{"cleared": false}`
-/// when it did not exist.
+/// Rows are deleted inside the database rather than removing the file, so
+/// other open connections stay valid. Returns `{"cleared": true, "removed": N}`
+/// when the database exists, `{"cleared": false}` when it does not.
 pub fn clear_cache(project_root: &Path) -> McpResult {
-    let path = crate::core::cache::db_path(project_root);
-    if path.exists() {
-        std::fs::remove_file(&path).map_err(|e| McpError {
+    if !crate::core::cache::db_path(project_root).exists() {
+        return Ok(serde_json::json!({"cleared": false}));
+    }
+    let removed = crate::core::cache::open_cache_db(project_root)
+        .and_then(|conn| crate::core::cache::clear(&conn))
+        .map_err(|e| McpError {
             code: -32603,
-            message: format!("could not delete cache: {e}"),
+            message: format!("could not clear cache: {e}"),
         })?;
-        Ok(serde_json::json!({"cleared": true}))
-    } else {
-        Ok(serde_json::json!({"cleared": false}))
-    }
+    Ok(serde_json::json!({"cleared": true, "removed": removed}))
 }
 
 /// Return artifact files governed by a spec that have changed since last cached.
 ///
 /// Expands the spec's artifact globs (optionally filtered to `artifact_type`),
-/// then checks each file against the hash cache. Files with a hash mismatch
-/// (or absent from the cache) are returned in `{"changed_artifacts": [...]}`.
+/// then checks each file against the spec's reconciliation records. Files with
+/// a hash mismatch (or no record for this spec) are returned in
+/// `{"changed_artifacts": [...]}`.
 ///
 /// Unlike `get_spec_diff`, this does not run `git diff` -- it compares against
 /// the local cache state, which is updated by `mark_reconciled`.
+/// `verify_hashes` rehashes every cached file instead of trusting size/mtime.
 pub fn get_changed_artifacts(
     spec_path: &str,
     artifact_type: Option<&str>,
+    verify_hashes: bool,
     project_root: &Path,
 ) -> McpResult {
     let abs_spec = project_root.join(spec_path);
@@ -301,20 +692,14 @@ pub fn get_changed_artifacts(
         code: -32603,
         message: format!("read error: {e}"),
     })?;
-    let spec_value = crate::core::yaml::parse_yaml(&content).map_err(|e| McpError {
-        code: -32603,
-        message: e,
-    })?;
+    let spec = Spec::parse(&content).map_err(McpError::from)?;
 
     let files = match artifact_type {
-        Some(art_type) => expand_artifact_type_globs(&spec_value, project_root, art_type),
-        None => expand_artifact_globs(&spec_value, project_root),
+        Some(art_type) => expand_artifact_type_globs(&spec, project_root, art_type),
+        None => expand_artifact_globs(&spec, project_root),
     };
 
-    let conn = crate::core::cache::open_cache_db(project_root).map_err(|e| McpError {
-        code: -32603,
-        message: e,
-    })?;
+    let conn = crate::core::cache::open_cache_db(project_root).map_err(McpError::from)?;
 
     let pairs: Vec<(String, std::path::PathBuf)> = files
         .into_iter()
@@ -324,62 +709,70 @@ pub fn get_changed_artifacts(
         })
         .collect();
 
-    let (changed, _unchanged) =
-        crate::core::cache::check_changed_batch(&conn, &pairs).map_err(|e| McpError {
-            code: -32603,
-            message: e,
-        })?;
+    let spec_fingerprint = crate::core::cache::spec_fingerprint(content.as_bytes());
+    let (changed, _unchanged) = crate::core::cache::check_changed_batch(
+        &conn,
+        normalize_rel(spec_path),
+        &spec_fingerprint,
+        &pairs,
+        verify_hashes,
+    )
+    .map_err(McpError::from)?;
 
     Ok(serde_json::json!({"changed_artifacts": changed}))
 }
 
-/// Record that the given files have been reconciled by hashing and caching them.
-///
-/// For each path in `files` that exists on disk, computes its BLAKE3 hash and
-/// upserts it into the cache. Files that do not exist are silently skipped.
-/// Returns `{"updated": N}` with the count of successfully cached files.
+/// Start a reconciliation run against `base_branch`.
 ///
-/// This is the correct way to seed or update the MCP cache -- not the CLI
-/// `cache update` subcommand, which uses absolute paths as keys instead of
-/// relative paths.
-pub fn mark_reconciled(files: &[String], project_root: &Path) -> McpResult {
-    let conn = crate::core::cache::open_cache_db(project_root).map_err(|e| McpError {
-        code: -32603,
-        message: e,
-    })?;
-
-    let mut to_upsert = Vec::new();
-    for f in files {
-        let abs = project_root.join(f);
-        if abs.exists() {
-            match crate::core::cache::hash_file(&abs) {
-                Ok(hash) => to_upsert.push((f.clone(), hash)),
-                Err(e) => eprintln!("Warning: {e}"),
-            }
-        }
-    }
+/// Subsequent `mark_reconciled` calls are recorded against this run until
+/// `snapshot_state` closes it. Returns `{"run_id": N}`.
+pub fn begin_reconciliation(base_branch: Option<&str>, project_root: &Path) -> McpResult {
+    let conn = crate::core::cache::open_cache_db(project_root).map_err(McpError::from)?;
+    let run_id = crate::core::runs::begin_run(&conn, base_branch).map_err(McpError::from)?;
+    Ok(serde_json::json!({"run_id": run_id}))
+}
 
-    let count = crate::core::cache::upsert_batch(&conn, &to_upsert).map_err(|e| McpError {
-        code: -32603,
-        message: e,
-    })?;
+/// Record that the given files have been reconciled against `spec_path`
+/// (see `Project::mark_reconciled`). Returns `{"updated": N, "run_id": N}`.
+///
+/// Records are keyed by relative paths, matching how `get_spec_diff` and
+/// `get_changed_artifacts` query the cache.
+pub fn mark_reconciled(spec_path: &str, files: &[String], project_root: &Path) -> McpResult {
+    let marked = crate::Project::open(project_root).mark_reconciled(spec_path, files)?;
+    let failed: Vec<serde_json::Value> = marked
+        .failed
+        .iter()
+        .map(|(path, error)| serde_json::json!({"path": path, "error": error}))
+        .collect();
+    Ok(serde_json::json!({
+        "updated": marked.updated,
+        "run_id": marked.run_id,
+        "failed": failed,
+    }))
+}
 
-    Ok(serde_json::json!({"updated": count}))
+/// Compare the governed files on disk with `reconciliation_state.json` (see
+/// `crate::core::state::drift`).
+///
+/// Returns `{"baseline", "clean", "specs", "removed_specs",
+/// "no_longer_governed", "unchanged_specs"}`, where each `specs` entry is
+/// `{spec_path, reconciled_at, spec_changed, added, modified, removed}` and
+/// only specs with drift are listed.
+pub fn state_diff(project_root: &Path) -> McpResult {
+    let diff = crate::core::state::drift(project_root)?;
+    Ok(serde_json::to_value(diff).expect("JSON serialization"))
 }
 
 /// Snapshot the current cache + git state into reconciliation_state.json.
 ///
-/// Called at the end of a reconciliation pass to persist the baseline.
+/// Called at the end of a reconciliation pass to persist the baseline; also
+/// closes the open reconciliation run, if any.
 /// Returns `{"state_path": "...", "files": N, "specs": N, "git_hash": "..."}`.
 pub fn snapshot_state(project_root: &Path) -> McpResult {
-    let state = crate::core::state::snapshot_from_cache(project_root).map_err(|e| McpError {
-        code: -32603,
-        message: e,
-    })?;
-    crate::core::state::save_state(project_root, &state).map_err(|e| McpError {
-        code: -32603,
-        message: e,
-    })?;
+    let project = crate::Project::open(project_root);
+    let state = project.snapshot_state()?;
+    project.save_state(&state)?;
+    project.finish_run()?;
     let state_path = crate::core::state::state_path(project_root)
         .to_string_lossy()
         .to_string();
@@ -391,12 +784,21 @@ pub fn snapshot_state(project_root: &Path) -> McpResult {
         .to_string();
     Ok(serde_json::json!({
         "state_path": state_path,
-        "files": state.file_fingerprints.len(),
-        "specs": state.spec_fingerprints.len(),
+        "files": state.artifact_fingerprints().len(),
+        "specs": state.specs.len(),
         "git_hash": git_hash,
     }))
 }
 
+/// Compute the effective spec of one variant (see `Project::resolve_variant`).
+///
+/// Returns `{"spec_path", "variant", "spec", "valid", "errors",
+/// "unmatched_overrides"}`.
+pub fn resolve_variant(spec_path: &str, variant: &str, project_root: &Path) -> McpResult {
+    let resolved = crate::Project::open(project_root).resolve_variant(spec_path, variant)?;
+    Ok(serde_json::to_value(resolved).expect("JSON serialization"))
+}
+
 /// Known binary file extensions whose unified diffs are uninformative noise.
 const BINARY_EXTENSIONS: &[&str] = &[
     ".png", ".jpg", ".jpeg", ".gif", ".webp", ".ico", ".pptx", ".docx", ".xlsx", ".pdf", ".zip",
@@ -413,38 +815,30 @@ fn is_binary_by_extension(path: &str) -> bool {
 /// Each file in `files` is matched against every category's glob patterns. The first
 /// matching category wins. Files that match no category are omitted.
 fn build_file_categories(
-    spec: &serde_json::Value,
+    spec: &Spec,
     files: &[String],
     project_root: &Path,
 ) -> serde_json::Map<String, serde_json::Value> {
     use std::collections::HashSet;
 
-    let mut map = serde_json::Map::new();
-    let Some(artifacts) = spec.get("artifacts").and_then(|a| a.as_object()) else {
-        return map;
-    };
-
     // Pre-expand all category globs once into HashSets for O(1) lookup.
-    let category_files: Vec<(String, HashSet<String>)> = artifacts
+    let category_files: Vec<(&String, HashSet<String>)> = spec
+        .artifacts
         .iter()
         .map(|(cat, refs)| {
             let expanded: HashSet<String> = refs
-                .as_array()
-                .map(|arr| {
-                    arr.iter()
-                        .filter_map(|item| item.get("path").and_then(|p| p.as_str()))
-                        .flat_map(|pattern| expand_glob(pattern, project_root))
-                        .collect()
-                })
-                .unwrap_or_default();
-            (cat.clone(), expanded)
+                .iter()
+                .flat_map(|r| expand_glob(&r.path, project_root))
+                .collect();
+            (cat, expanded)
         })
         .collect();
 
+    let mut map = serde_json::Map::new();
     for file in files {
         for (cat, cat_files) in &category_files {
             if cat_files.contains(file) {
-                map.insert(file.clone(), serde_json::Value::String(cat.clone()));
+                map.insert(file.clone(), serde_json::Value::String((*cat).clone()));
                 break;
             }
         }
@@ -453,7 +847,27 @@ fn build_file_categories(
     map
 }
 
-fn is_spec_file(path: &str) -> bool {
+/// Return the first artifact category of `spec` with a glob matching `rel_path`.
+pub(crate) fn artifact_category<'a>(spec: &'a Spec, rel_path: &str) -> Option<&'a str> {
+    let options = glob::MatchOptions {
+        require_literal_separator: true,
+        ..Default::default()
+    };
+    spec.artifact_refs()
+        .find(|(_, r)| {
+            glob::Pattern::new(normalize_rel(&r.path))
+                .is_ok_and(|p| p.matches_with(rel_path, options))
+        })
+        .map(|(category, _)| category)
+}
+
+/// Strip a leading `./` so `./.notarai/x.spec.yaml` and `.notarai/x.spec.yaml`
+/// share one cache key.
+pub fn normalize_rel(path: &str) -> &str {
+    path.strip_prefix("./").unwrap_or(path)
+}
+
+pub(crate) fn is_spec_file(path: &str) -> bool {
     path.starts_with(".notarai/") && path.ends_with(".spec.yaml")
 }
 
@@ -477,8 +891,7 @@ fn find_system_spec(
     let candidate = notarai_dir.join("system.spec.yaml");
     if candidate.exists()
         && let Ok(content) = std::fs::read_to_string(&candidate)
-        && let Ok(value) = crate::core::yaml::parse_yaml(&content)
-        && value.get("subsystems").is_some()
+        && Spec::parse(&content).is_ok_and(|spec| spec.is_system())
         && let Ok(rel) = candidate.strip_prefix(project_root)
     {
         system_spec_rel = Some(rel.to_string_lossy().to_string());
@@ -498,8 +911,7 @@ fn find_system_spec(
                 continue;
             }
             if let Ok(content) = std::fs::read_to_string(&path)
-                && let Ok(value) = crate::core::yaml::parse_yaml(&content)
-                && value.get("subsystems").is_some()
+                && Spec::parse(&content).is_ok_and(|spec| spec.is_system())
                 && let Ok(rel) = path.strip_prefix(project_root)
             {
                 system_spec_rel = Some(rel.to_string_lossy().to_string());
@@ -531,95 +943,41 @@ fn find_system_spec(
     }))
 }
 
-fn collect_specs(project_root: &Path) -> Result<Vec<PathBuf>, McpError> {
-    use walkdir::WalkDir;
-    let mut specs = Vec::new();
-    let notarai_dir = project_root.join(".notarai");
-    if !notarai_dir.exists() {
-        return Ok(specs);
-    }
-    for entry in WalkDir::new(&notarai_dir) {
-        let entry = entry.map_err(|e| McpError {
-            code: -32603,
-            message: format!("{e}"),
-        })?;
-        if entry.file_type().is_file() {
-            let name = entry.file_name().to_string_lossy();
-            if name.ends_with(".spec.yaml") {
-                specs.push(entry.into_path());
-            }
-        }
-    }
-    Ok(specs)
+/// Every `*.spec.yaml` file under `.notarai/`, in sorted order (see
+/// `Project::spec_files`).
+pub fn collect_specs(project_root: &Path) -> Result<Vec<PathBuf>, McpError> {
+    crate::Project::open(project_root)
+        .spec_files()
+        .map_err(McpError::from)
 }
 
-fn is_spec_affected(spec: &serde_json::Value, changed: &[String]) -> bool {
-    let Some(artifacts) = spec.get("artifacts") else {
-        return false;
-    };
-    let Some(obj) = artifacts.as_object() else {
-        return false;
-    };
-    for (_key, refs) in obj {
-        let Some(arr) = refs.as_array() else {
-            continue;
-        };
-        for item in arr {
-            let Some(pattern_str) = item.get("path").and_then(|p| p.as_str()) else {
-                continue;
-            };
-            if let Ok(pattern) = glob::Pattern::new(pattern_str) {
-                for changed_file in changed {
-                    if pattern.matches(changed_file) {
-                        return true;
-                    }
-                }
-            }
-        }
-    }
-    false
+/// True if any path in `changed` matches one of the spec's artifact globs.
+pub fn is_spec_affected(spec: &Spec, changed: &[String]) -> bool {
+    spec.artifact_refs().any(|(_, r)| {
+        glob::Pattern::new(&r.path).is_ok_and(|pattern| changed.iter().any(|f| pattern.matches(f)))
+    })
 }
 
-fn expand_artifact_globs(spec: &serde_json::Value, project_root: &Path) -> Vec<String> {
-    let mut files = Vec::new();
-    let Some(artifacts) = spec.get("artifacts").and_then(|a| a.as_object()) else {
-        return files;
-    };
-    for (_key, refs) in artifacts {
-        let Some(arr) = refs.as_array() else {
-            continue;
-        };
-        for item in arr {
-            if let Some(pattern_str) = item.get("path").and_then(|p| p.as_str()) {
-                files.extend(expand_glob(pattern_str, project_root));
-            }
-        }
-    }
-    files
+/// Expand every artifact glob in the spec to the project-relative paths of
+/// the files that currently exist.
+pub fn expand_artifact_globs(spec: &Spec, project_root: &Path) -> Vec<String> {
+    spec.artifact_refs()
+        .flat_map(|(_, r)| expand_glob(&r.path, project_root))
+        .collect()
 }
 
-fn expand_artifact_type_globs(
-    spec: &serde_json::Value,
-    project_root: &Path,
-    art_type: &str,
-) -> Vec<String> {
-    let mut files = Vec::new();
-    let Some(refs) = spec
-        .get("artifacts")
-        .and_then(|a| a.get(art_type))
-        .and_then(|r| r.as_array())
-    else {
-        return files;
-    };
-    for item in refs {
-        if let Some(pattern_str) = item.get("path").and_then(|p| p.as_str()) {
-            files.extend(expand_glob(pattern_str, project_root));
-        }
-    }
-    files
+fn expand_artifact_type_globs(spec: &Spec, project_root: &Path, art_type: &str) -> Vec<String> {
+    spec.artifacts
+        .get(art_type)
+        .into_iter()
+        .flatten()
+        .flat_map(|r| expand_glob(&r.path, project_root))
+        .collect()
 }
 
-fn expand_glob(pattern_str: &str, project_root: &Path) -> Vec<String> {
+/// Expand `pattern_str` (relative to `project_root`) to the project-relative
+/// paths that currently exist.
+pub fn expand_glob(pattern_str: &str, project_root: &Path) -> Vec<String> {
     let abs_pattern = project_root.join(pattern_str);
     let abs_pattern_str = abs_pattern.to_string_lossy();
     let mut result = Vec::new();
@@ -1,7 +1,24 @@
 pub mod cache;
+pub mod compliance;
+pub mod compose;
+pub mod content;
+pub mod derived;
+pub mod evidence;
+pub mod feedback;
+pub mod graph;
 pub mod mcp_tools;
+pub mod normalize;
+pub mod notebook;
+pub mod pipeline;
+pub mod runs;
+pub mod scaffold;
 pub mod schema;
+pub mod spec;
 pub mod state;
-pub mod update;
+pub mod state_machine;
+pub mod state_merge;
+pub mod status;
+pub mod time;
 pub mod validator;
+pub mod variants;
 pub mod yaml;
@@ -0,0 +1,487 @@
+//! Content normalizers applied before hashing.
+//!
+//! A formatting-only edit (CRLF conversion, trailing-whitespace cleanup, JSON
+//! key reordering, re-running a notebook) should not pull a file back into
+//! reconciliation. Each reconciliation record stores the name of the
+//! normalizer its hash was computed with, and later checks rehash with that
+//! same normalizer, so records stay comparable when the rules change.
+//!
+//! Which normalizer a file gets is decided by `NormalizerRules`: the file's
+//! artifact category first, then its extension, then `raw`. Only notebooks
+//! and JSON are normalized out of the box; the lossy `eol` and `text`
+//! normalizers are opted into in `.notarai/normalize.yaml`:
+//!
+//! ```yaml
+//! categories:
+//!   code: text
+//! extensions:
+//!   csv: eol
+//! ```
+//!
+//! `eol` and `text` are streamed, so large files are never read into memory.
+//! `json` and `notebook` need the parsed document and leave content above
+//! `MAX_PARSED_BYTES` as it is.
+
+use serde::Deserialize;
+use serde_json::Value;
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+/// Content larger than this is not parsed: `json` and `notebook` return it
+/// unchanged.
+pub const MAX_PARSED_BYTES: u64 = 32 * 1024 * 1024;
+
+/// How many leading bytes are searched for a NUL to detect binary content.
+const BINARY_PROBE: usize = 8000;
+
+/// A content transformation applied before hashing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Normalizer {
+    /// Hash the bytes as they are.
+    Raw,
+    /// Convert CRLF line endings to LF.
+    Eol,
+    /// `eol`, plus strip trailing whitespace from every line and at end of file.
+    Text,
+    /// Parse as JSON and hash a compact serialization with sorted keys.
+    Json,
+    /// `json` for Jupyter notebooks, with outputs and execution counts removed
+    /// and cell sources joined into single strings.
+    Notebook,
+}
+
+/// Every normalizer name, in the order they are documented.
+pub const NAMES: &[&str] = &["raw", "eol", "text", "json", "notebook"];
+
+impl Normalizer {
+    /// Name stored alongside each hash in the cache.
+    pub fn name(self) -> &'static str {
+        match self {
+            Normalizer::Raw => "raw",
+            Normalizer::Eol => "eol",
+            Normalizer::Text => "text",
+            Normalizer::Json => "json",
+            Normalizer::Notebook => "notebook",
+        }
+    }
+
+    /// Inverse of `name`. Returns `None` for names this version does not know.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "raw" => Some(Normalizer::Raw),
+            "eol" => Some(Normalizer::Eol),
+            "text" => Some(Normalizer::Text),
+            "json" => Some(Normalizer::Json),
+            "notebook" => Some(Normalizer::Notebook),
+            _ => None,
+        }
+    }
+
+    /// Apply the normalizer.
+    ///
+    /// Binary content (a NUL byte in the first 8000 bytes, the same heuristic
+    /// git uses) is returned unchanged by every normalizer. `json` and
+    /// `notebook` fall back to `text` when the content does not parse, and
+    /// leave content above `MAX_PARSED_BYTES` unchanged.
+    pub fn apply(self, bytes: &[u8]) -> Cow<'_, [u8]> {
+        if self == Normalizer::Raw || is_binary(bytes) {
+            return Cow::Borrowed(bytes);
+        }
+        let parsed = bytes.len() as u64 <= MAX_PARSED_BYTES;
+        match self {
+            Normalizer::Raw => Cow::Borrowed(bytes),
+            Normalizer::Eol => normalize_eol(bytes),
+            Normalizer::Text => Cow::Owned(normalize_text(bytes)),
+            Normalizer::Json | Normalizer::Notebook if !parsed => Cow::Borrowed(bytes),
+            Normalizer::Json => match serde_json::from_slice::<Value>(bytes) {
+                Ok(value) => Cow::Owned(canonical_json(&value)),
+                Err(_) => Cow::Owned(normalize_text(bytes)),
+            },
+            Normalizer::Notebook => match serde_json::from_slice::<Value>(bytes) {
+                Ok(mut value) => {
+                    strip_notebook(&mut value);
+                    Cow::Owned(canonical_json(&value))
+                }
+                Err(_) => Cow::Owned(normalize_text(bytes)),
+            },
+        }
+    }
+
+    /// Write the normalized content of `reader` to `out`; the bytes written
+    /// are the same as `apply` returns. `raw`, `eol` and `text` stream the
+    /// content in chunks, while `json` and `notebook` read it whole.
+    pub fn apply_to_writer(self, mut reader: impl Read, out: &mut impl Write) -> io::Result<()> {
+        let mut head = Vec::with_capacity(BINARY_PROBE);
+        (&mut reader)
+            .take(BINARY_PROBE as u64)
+            .read_to_end(&mut head)?;
+        if self == Normalizer::Raw || is_binary(&head) {
+            out.write_all(&head)?;
+            io::copy(&mut reader, out)?;
+            return Ok(());
+        }
+        let strip_whitespace = match self {
+            Normalizer::Eol => false,
+            Normalizer::Text => true,
+            _ => {
+                reader.read_to_end(&mut head)?;
+                return out.write_all(&self.apply(&head));
+            }
+        };
+
+        let mut lines = LineNormalizer::new(strip_whitespace);
+        let mut normalized = Vec::new();
+        lines.feed(&head, &mut normalized);
+        let mut buf = vec![0; 64 * 1024];
+        loop {
+            out.write_all(&normalized)?;
+            normalized.clear();
+            let n = match reader.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            lines.feed(&buf[..n], &mut normalized);
+        }
+        lines.finish(&mut normalized);
+        out.write_all(&normalized)
+    }
+}
+
+fn is_binary(bytes: &[u8]) -> bool {
+    bytes.iter().take(BINARY_PROBE).any(|&b| b == 0)
+}
+
+fn normalize_eol(bytes: &[u8]) -> Cow<'_, [u8]> {
+    if !bytes.windows(2).any(|w| w == b"\r\n") {
+        return Cow::Borrowed(bytes);
+    }
+    Cow::Owned(LineNormalizer::new(false).run(bytes))
+}
+
+fn normalize_text(bytes: &[u8]) -> Vec<u8> {
+    LineNormalizer::new(true).run(bytes)
+}
+
+/// The `eol` and `text` normalizers as a state machine over a byte stream.
+///
+/// `pending` holds bytes whose fate depends on what follows: a `\r` that may
+/// start a CRLF, or (for `text`) the whitespace after the last non-blank
+/// byte, which is dropped at a line end or at end of file.
+struct LineNormalizer {
+    strip_whitespace: bool,
+    pending: Vec<u8>,
+}
+
+impl LineNormalizer {
+    fn new(strip_whitespace: bool) -> Self {
+        LineNormalizer {
+            strip_whitespace,
+            pending: Vec::new(),
+        }
+    }
+
+    fn run(mut self, bytes: &[u8]) -> Vec<u8> {
+        let mut out = Vec::with_capacity(bytes.len());
+        self.feed(bytes, &mut out);
+        self.finish(&mut out);
+        out
+    }
+
+    fn feed(&mut self, chunk: &[u8], out: &mut Vec<u8>) {
+        for &b in chunk {
+            if self.strip_whitespace {
+                self.feed_text(b, out);
+            } else {
+                self.feed_eol(b, out);
+            }
+        }
+    }
+
+    fn feed_eol(&mut self, b: u8, out: &mut Vec<u8>) {
+        if self.pending.pop().is_some() && b != b'\n' {
+            out.push(b'\r');
+        }
+        if b == b'\r' {
+            self.pending.push(b);
+        } else {
+            out.push(b);
+        }
+    }
+
+    fn feed_text(&mut self, b: u8, out: &mut Vec<u8>) {
+        match b {
+            b' ' | b'\t' | b'\r' | b'\x0c' => self.pending.push(b),
+            b'\n' => {
+                // Trailing whitespace of the line ends here; a form feed is
+                // only dropped at end of file.
+                let end = self
+                    .pending
+                    .iter()
+                    .rposition(|&c| !matches!(c, b' ' | b'\t' | b'\r'))
+                    .map_or(0, |i| i + 1);
+                self.pending.truncate(end);
+                self.pending.push(b);
+            }
+            _ => {
+                out.append(&mut self.pending);
+                out.push(b);
+            }
+        }
+    }
+
+    fn finish(self, out: &mut Vec<u8>) {
+        // For `text` everything pending is trailing whitespace of the file.
+        if !self.strip_whitespace {
+            out.extend_from_slice(&self.pending);
+        }
+    }
+}
+
+/// Compact JSON with object keys in sorted order (`serde_json::Map` is a
+/// `BTreeMap` in this build).
+fn canonical_json(value: &Value) -> Vec<u8> {
+    serde_json::to_vec(value).unwrap_or_default()
+}
+
+fn strip_notebook(notebook: &mut Value) {
+    let Some(cells) = notebook.get_mut("cells").and_then(|c| c.as_array_mut()) else {
+        return;
+    };
+    for cell in cells {
+        let Some(cell) = cell.as_object_mut() else {
+            continue;
+        };
+        if cell.contains_key("outputs") {
+            cell.insert("outputs".to_string(), Value::Array(Vec::new()));
+        }
+        if cell.contains_key("execution_count") {
+            cell.insert("execution_count".to_string(), Value::Null);
+        }
+        if let Some(Value::Array(lines)) = cell.get("source") {
+            let joined: String = lines.iter().filter_map(|l| l.as_str()).collect();
+            cell.insert("source".to_string(), Value::String(joined));
+        }
+    }
+}
+
+/// Project overrides read from `.notarai/normalize.yaml`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RulesFile {
+    #[serde(default)]
+    categories: BTreeMap<String, Normalizer>,
+    #[serde(default)]
+    extensions: BTreeMap<String, Normalizer>,
+}
+
+/// Chooses a normalizer for a file from its artifact category and extension.
+#[derive(Debug)]
+pub struct NormalizerRules {
+    categories: BTreeMap<String, Normalizer>,
+    extensions: BTreeMap<String, Normalizer>,
+}
+
+impl Default for NormalizerRules {
+    fn default() -> Self {
+        let categories = BTreeMap::from([("notebooks".to_string(), Normalizer::Notebook)]);
+        let extensions = BTreeMap::from([
+            ("ipynb".to_string(), Normalizer::Notebook),
+            ("json".to_string(), Normalizer::Json),
+        ]);
+        NormalizerRules {
+            categories,
+            extensions,
+        }
+    }
+}
+
+/// Canonical path: `<root>/.notarai/normalize.yaml`
+pub fn rules_path(project_root: &Path) -> PathBuf {
+    project_root.join(".notarai").join("normalize.yaml")
+}
+
+impl NormalizerRules {
+    /// Built-in rules with any overrides from `.notarai/normalize.yaml` applied.
+    pub fn load(project_root: &Path) -> crate::Result<Self> {
+        let mut rules = NormalizerRules::default();
+        let path = rules_path(project_root);
+        if !path.exists() {
+            return Ok(rules);
+        }
+        let content = std::fs::read_to_string(&path).map_err(|e| crate::Error::io(&path, e))?;
+        let file: RulesFile =
+            serde_yaml_ng::from_str(&content).map_err(|e| crate::Error::InvalidConfig {
+                path: path.clone(),
+                message: e.to_string(),
+            })?;
+        rules.categories.extend(file.categories);
+        rules.extensions.extend(
+            file.extensions
+                .into_iter()
+                .map(|(ext, n)| (ext.trim_start_matches('.').to_ascii_lowercase(), n)),
+        );
+        Ok(rules)
+    }
+
+    /// Pick the normalizer for `rel_path`, governed under `category` if known.
+    /// Files no rule covers are hashed `raw`.
+    pub fn select(&self, rel_path: &str, category: Option<&str>) -> Normalizer {
+        if let Some(n) = category.and_then(|c| self.categories.get(c)) {
+            return *n;
+        }
+        let ext = Path::new(rel_path)
+            .extension()
+            .map(|e| e.to_string_lossy().to_ascii_lowercase());
+        ext.and_then(|e| self.extensions.get(&e).copied())
+            .unwrap_or(Normalizer::Raw)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn text_ignores_line_endings_and_trailing_whitespace() {
+        let a = Normalizer::Text.apply(b"fn main() {\n    run();\n}\n");
+        let b = Normalizer::Text.apply(b"fn main() {  \r\n    run();\t\r\n}\r\n\r\n");
+        assert_eq!(a, b);
+        let c = Normalizer::Text.apply(b"fn main() {\n    run2();\n}\n");
+        assert_ne!(a, c);
+    }
+
+    #[test]
+    fn text_keeps_form_feeds_within_the_file() {
+        assert_eq!(
+            &*Normalizer::Text.apply(b"a \x0c \nb\x0c\n\n"),
+            b"a \x0c\nb"
+        );
+        assert_eq!(&*Normalizer::Text.apply(b"a\rb \r\r\n"), b"a\rb");
+    }
+
+    #[test]
+    fn streaming_matches_apply_at_every_chunk_boundary() {
+        let inputs: [&[u8]; 4] = [
+            b"fn main() {  \r\n    run();\t\r\n}\r\n\r\n",
+            b"a\r\r\nb\rc \x0c \n\n  ",
+            b"{\"b\": 1,\r\n \"a\": 2}  \n",
+            b"\x00bin  \r\n",
+        ];
+        for input in inputs {
+            for n in [
+                Normalizer::Raw,
+                Normalizer::Eol,
+                Normalizer::Text,
+                Normalizer::Json,
+            ] {
+                let mut streamed = Vec::new();
+                n.apply_to_writer(input, &mut streamed).unwrap();
+                assert_eq!(streamed, &*n.apply(input), "{n:?} {input:?}");
+            }
+            if is_binary(input) {
+                continue;
+            }
+            for n in [Normalizer::Eol, Normalizer::Text] {
+                for split in 0..input.len() {
+                    let mut lines = LineNormalizer::new(n == Normalizer::Text);
+                    let mut out = Vec::new();
+                    lines.feed(&input[..split], &mut out);
+                    lines.feed(&input[split..], &mut out);
+                    lines.finish(&mut out);
+                    assert_eq!(out, &*n.apply(input), "{n:?} split at {split}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn oversized_json_is_left_alone() {
+        let mut big = b"{\"a\": \"".to_vec();
+        big.resize(MAX_PARSED_BYTES as usize + 1, b'x');
+        big.extend_from_slice(b"\"}");
+        assert!(matches!(Normalizer::Json.apply(&big), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn eol_keeps_trailing_whitespace() {
+        assert_eq!(&*Normalizer::Eol.apply(b"a  \r\nb\r\n"), b"a  \nb\n");
+    }
+
+    #[test]
+    fn binary_content_is_left_alone() {
+        let data = b"\x00\x01  \r\n";
+        assert_eq!(&*Normalizer::Text.apply(data), data);
+    }
+
+    #[test]
+    fn json_ignores_key_order_and_formatting() {
+        let a = Normalizer::Json.apply(br#"{"b": 1, "a": [1, 2]}"#);
+        let b = Normalizer::Json.apply(b"{\n  \"a\": [1,2],\n  \"b\": 1\n}\n");
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn notebook_ignores_outputs_and_execution_counts() {
+        let run1 = br#"{"cells":[{"cell_type":"code","execution_count":1,
+            "source":["x = 1\n","x"],"outputs":[{"data":{"text/plain":"1"}}],"metadata":{}}],
+            "metadata":{},"nbformat":4,"nbformat_minor":5}"#;
+        let run2 = br#"{"cells":[{"cell_type":"code","execution_count":7,
+            "source":"x = 1\nx","outputs":[],"metadata":{}}],
+            "metadata":{},"nbformat":4,"nbformat_minor":5}"#;
+        let edited = br#"{"cells":[{"cell_type":"code","execution_count":1,
+            "source":["x = 2\n","x"],"outputs":[],"metadata":{}}],
+            "metadata":{},"nbformat":4,"nbformat_minor":5}"#;
+        let n = Normalizer::Notebook;
+        assert_eq!(n.apply(run1), n.apply(run2));
+        assert_ne!(n.apply(run1), n.apply(edited));
+    }
+
+    #[test]
+    fn names_round_trip() {
+        for n in [
+            Normalizer::Raw,
+            Normalizer::Eol,
+            Normalizer::Text,
+            Normalizer::Json,
+            Normalizer::Notebook,
+        ] {
+            assert_eq!(Normalizer::from_name(n.name()), Some(n));
+        }
+        assert_eq!(Normalizer::from_name("nope"), None);
+    }
+
+    #[test]
+    fn category_rule_wins_over_extension() {
+        let tmp = TempDir::new().unwrap();
+        std::fs::create_dir_all(tmp.path().join(".notarai")).unwrap();
+        std::fs::write(
+            rules_path(tmp.path()),
+            "categories:\n  data: raw\nextensions:\n  .CSV: eol\n",
+        )
+        .unwrap();
+        let rules = NormalizerRules::load(tmp.path()).unwrap();
+        assert_eq!(rules.select("x.json", Some("data")), Normalizer::Raw);
+        assert_eq!(rules.select("x.json", Some("configs")), Normalizer::Json);
+        assert_eq!(rules.select("x.csv", None), Normalizer::Eol);
+        assert_eq!(
+            rules.select("a.nb", Some("notebooks")),
+            Normalizer::Notebook
+        );
+        assert_eq!(rules.select("src/main.rs", Some("code")), Normalizer::Raw);
+        assert_eq!(rules.select("README.md", None), Normalizer::Raw);
+    }
+
+    #[test]
+    fn unknown_normalizer_in_rules_is_an_error() {
+        let tmp = TempDir::new().unwrap();
+        std::fs::create_dir_all(tmp.path().join(".notarai")).unwrap();
+        std::fs::write(rules_path(tmp.path()), "extensions:\n  rs: rustfmt\n").unwrap();
+        assert!(NormalizerRules::load(tmp.path()).is_err());
+    }
+}
@@ -0,0 +1,481 @@
+//! Cell-level diffs for Jupyter notebooks.
+//!
+//! A plain `git diff` of an `.ipynb` file is mostly JSON structure, base64
+//! images and execution counts. `diff_notebooks` compares two revisions cell
+//! by cell instead: source changes as unified diffs, output changes as a short
+//! summary, and notebook/cell metadata ignored. It also flags code cells whose
+//! outputs look stale.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use similar::{DiffOp, TextDiff};
+
+/// How a cell differs between the two revisions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CellChangeKind {
+    Added,
+    Removed,
+    Modified,
+}
+
+/// One changed cell.
+#[derive(Debug, Serialize)]
+pub struct CellChange {
+    pub change: CellChangeKind,
+    pub cell_type: String,
+    /// Position in the new notebook; `None` for removed cells.
+    pub index: Option<usize>,
+    /// Position in the old notebook; `None` for added cells.
+    pub old_index: Option<usize>,
+    /// Full source of an added or removed cell.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    /// Unified diff of a modified cell's source, if it changed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source_diff: Option<String>,
+    /// `"<old outputs> -> <new outputs>"`, if the outputs changed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub outputs: Option<String>,
+}
+
+/// A code cell in the new notebook whose outputs may not reflect its source.
+#[derive(Debug, Serialize)]
+pub struct StaleOutput {
+    pub index: usize,
+    pub reason: String,
+}
+
+/// Cell-level comparison of two notebook revisions.
+#[derive(Debug, Serialize)]
+pub struct NotebookDiff {
+    pub cells: Vec<CellChange>,
+    pub stale_outputs: Vec<StaleOutput>,
+}
+
+impl NotebookDiff {
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty() && self.stale_outputs.is_empty()
+    }
+}
+
+struct Cell {
+    id: Option<String>,
+    cell_type: String,
+    source: String,
+    outputs: Vec<Value>,
+    execution_count: Option<i64>,
+}
+
+/// Compare two notebook revisions. `None` means the notebook does not exist
+/// on that side (added or deleted). Returns an error if either side is not a
+/// notebook.
+pub fn diff_notebooks(old: Option<&str>, new: Option<&str>) -> crate::Result<NotebookDiff> {
+    let old_cells = old.map(parse_cells).transpose()?.unwrap_or_default();
+    let new_cells = new.map(parse_cells).transpose()?.unwrap_or_default();
+
+    // Prefer stable cell ids (nbformat >= 4.5) so edited cells still pair up;
+    // otherwise match on type and source and pair up the leftovers by position.
+    let use_ids = !old_cells.is_empty()
+        && !new_cells.is_empty()
+        && old_cells.iter().chain(&new_cells).all(|c| c.id.is_some());
+    let key = |c: &Cell| -> String {
+        match (&c.id, use_ids) {
+            (Some(id), true) => id.clone(),
+            _ => format!("{}\u{0}{}", c.cell_type, c.source),
+        }
+    };
+    let old_keys: Vec<String> = old_cells.iter().map(key).collect();
+    let new_keys: Vec<String> = new_cells.iter().map(key).collect();
+
+    let mut cells = Vec::new();
+    let mut source_changed = vec![false; new_cells.len()];
+    for op in similar::capture_diff_slices(similar::Algorithm::Myers, &old_keys, &new_keys) {
+        match op {
+            DiffOp::Equal {
+                old_index,
+                new_index,
+                len,
+            } => {
+                for i in 0..len {
+                    let (o, n) = (old_index + i, new_index + i);
+                    if let Some(change) = compare(&old_cells[o], o, &new_cells[n], n) {
+                        source_changed[n] = change.source_diff.is_some();
+                        cells.push(change);
+                    }
+                }
+            }
+            DiffOp::Delete {
+                old_index, old_len, ..
+            } => {
+                cells.extend((old_index..old_index + old_len).map(|o| removed(&old_cells[o], o)));
+            }
+            DiffOp::Insert {
+                new_index, new_len, ..
+            } => {
+                cells.extend((new_index..new_index + new_len).map(|n| added(&new_cells[n], n)));
+            }
+            DiffOp::Replace {
+                old_index,
+                old_len,
+                new_index,
+                new_len,
+            } => {
+                for i in 0..old_len.max(new_len) {
+                    let (o, n) = (old_index + i, new_index + i);
+                    match (i < old_len, i < new_len) {
+                        (true, true) if old_cells[o].cell_type == new_cells[n].cell_type => {
+                            if let Some(change) = compare(&old_cells[o], o, &new_cells[n], n) {
+                                source_changed[n] = change.source_diff.is_some();
+                                cells.push(change);
+                            }
+                        }
+                        (true, true) => {
+                            cells.push(removed(&old_cells[o], o));
+                            cells.push(added(&new_cells[n], n));
+                        }
+                        (true, false) => cells.push(removed(&old_cells[o], o)),
+                        (false, true) => cells.push(added(&new_cells[n], n)),
+                        (false, false) => {}
+                    }
+                }
+            }
+        }
+    }
+
+    let stale_outputs = find_stale_outputs(&old_cells, &new_cells, &cells, &source_changed);
+    Ok(NotebookDiff {
+        cells,
+        stale_outputs,
+    })
+}
+
+/// The part of an `.ipynb` document the diff reads.
+#[derive(Deserialize)]
+struct Notebook {
+    cells: Vec<Value>,
+}
+
+fn parse_cells(content: &str) -> crate::Result<Vec<Cell>> {
+    let notebook: Notebook =
+        serde_json::from_str(content).map_err(crate::Error::model("notebook"))?;
+    Ok(notebook
+        .cells
+        .iter()
+        .map(|cell| Cell {
+            id: cell.get("id").and_then(|v| v.as_str()).map(String::from),
+            cell_type: cell
+                .get("cell_type")
+                .and_then(|v| v.as_str())
+                .unwrap_or("code")
+                .to_string(),
+            source: join_text(cell.get("source")),
+            outputs: cell
+                .get("outputs")
+                .and_then(|v| v.as_array())
+                .map(|outputs| outputs.iter().map(strip_output).collect())
+                .unwrap_or_default(),
+            execution_count: cell.get("execution_count").and_then(|v| v.as_i64()),
+        })
+        .collect())
+}
+
+/// nbformat stores multi-line strings either as one string or as a list of
+/// lines; treat both the same.
+fn join_text(value: Option<&Value>) -> String {
+    match value {
+        Some(Value::String(s)) => s.clone(),
+        Some(Value::Array(lines)) => lines.iter().filter_map(|l| l.as_str()).collect(),
+        _ => String::new(),
+    }
+}
+
+/// Drop the parts of an output that change on every run without changing
+/// what the output shows.
+fn strip_output(output: &Value) -> Value {
+    let mut output = output.clone();
+    if let Some(obj) = output.as_object_mut() {
+        obj.remove("execution_count");
+        obj.remove("metadata");
+    }
+    output
+}
+
+fn compare(old: &Cell, old_index: usize, new: &Cell, new_index: usize) -> Option<CellChange> {
+    let source_diff = (old.source != new.source).then(|| {
+        TextDiff::from_lines(&old.source, &new.source)
+            .unified_diff()
+            .context_radius(2)
+            .to_string()
+    });
+    let outputs = (old.outputs != new.outputs).then(|| {
+        format!(
+            "{} -> {}",
+            summarize_outputs(&old.outputs),
+            summarize_outputs(&new.outputs)
+        )
+    });
+    if source_diff.is_none() && outputs.is_none() {
+        return None;
+    }
+    Some(CellChange {
+        change: CellChangeKind::Modified,
+        cell_type: new.cell_type.clone(),
+        index: Some(new_index),
+        old_index: Some(old_index),
+        source: None,
+        source_diff,
+        outputs,
+    })
+}
+
+fn added(cell: &Cell, index: usize) -> CellChange {
+    CellChange {
+        change: CellChangeKind::Added,
+        cell_type: cell.cell_type.clone(),
+        index: Some(index),
+        old_index: None,
+        source: Some(cell.source.clone()),
+        source_diff: None,
+        outputs: (!cell.outputs.is_empty())
+            .then(|| format!("none -> {}", summarize_outputs(&cell.outputs))),
+    }
+}
+
+fn removed(cell: &Cell, old_index: usize) -> CellChange {
+    CellChange {
+        change: CellChangeKind::Removed,
+        cell_type: cell.cell_type.clone(),
+        index: None,
+        old_index: Some(old_index),
+        source: Some(cell.source.clone()),
+        source_diff: None,
+        outputs: None,
+    }
+}
+
+/// Describe outputs by type, e.g. `stream(stdout), display_data(image/png)`.
+/// Output payloads are never included.
+fn summarize_outputs(outputs: &[Value]) -> String {
+    if outputs.is_empty() {
+        return "none".to_string();
+    }
+    outputs
+        .iter()
+        .map(|output| {
+            let kind = output
+                .get("output_type")
+                .and_then(|v| v.as_str())
+                .unwrap_or("unknown");
+            let detail = match kind {
+                "stream" => output
+                    .get("name")
+                    .and_then(|v| v.as_str())
+                    .map(String::from),
+                "error" => output
+                    .get("ename")
+                    .and_then(|v| v.as_str())
+                    .map(String::from),
+                _ => output.get("data").and_then(|d| d.as_object()).map(|data| {
+                    data.keys()
+                        .map(String::as_str)
+                        .collect::<Vec<_>>()
+                        .join("+")
+                }),
+            };
+            match detail {
+                Some(detail) => format!("{kind}({detail})"),
+                None => kind.to_string(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Flag code cells in the new notebook whose outputs may not come from their
+/// current source:
+/// - the source changed but the outputs are identical to before;
+/// - the cell has outputs but no execution count;
+/// - the cell ran before a cell above it (execution counts out of order).
+fn find_stale_outputs(
+    old_cells: &[Cell],
+    new_cells: &[Cell],
+    changes: &[CellChange],
+    source_changed: &[bool],
+) -> Vec<StaleOutput> {
+    let mut stale = Vec::new();
+    for change in changes {
+        let (Some(n), Some(o)) = (change.index, change.old_index) else {
+            continue;
+        };
+        let (old, new) = (&old_cells[o], &new_cells[n]);
+        if source_changed[n]
+            && new.cell_type == "code"
+            && !new.outputs.is_empty()
+            && old.outputs == new.outputs
+        {
+            stale.push(StaleOutput {
+                index: n,
+                reason: "source changed but outputs are unchanged; re-run the cell".to_string(),
+            });
+        }
+    }
+
+    let mut highest: Option<(usize, i64)> = None;
+    for (i, cell) in new_cells.iter().enumerate() {
+        if cell.cell_type != "code" || cell.outputs.is_empty() {
+            continue;
+        }
+        if stale.iter().any(|s| s.index == i) {
+            continue;
+        }
+        match cell.execution_count {
+            None => stale.push(StaleOutput {
+                index: i,
+                reason: "has outputs but no execution count".to_string(),
+            }),
+            Some(count) => {
+                if let Some((above, max)) = highest
+                    && count < max
+                {
+                    stale.push(StaleOutput {
+                        index: i,
+                        reason: format!("executed before cell {above} above it"),
+                    });
+                }
+                if highest.is_none_or(|(_, max)| count > max) {
+                    highest = Some((i, count));
+                }
+            }
+        }
+    }
+    stale.sort_by_key(|s| s.index);
+    stale
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn notebook(cells: Value) -> String {
+        json!({"cells": cells, "metadata": {}, "nbformat": 4, "nbformat_minor": 4}).to_string()
+    }
+
+    fn code(source: &str, count: Option<i64>, text: Option<&str>) -> Value {
+        let outputs = match text {
+            Some(t) => json!([{"output_type": "stream", "name": "stdout", "text": [t]}]),
+            None => json!([]),
+        };
+        json!({"cell_type": "code", "source": source, "execution_count": count,
+               "metadata": {}, "outputs": outputs})
+    }
+
+    #[test]
+    fn identical_notebooks_have_no_changes() {
+        let nb = notebook(json!([code("x = 1", Some(1), Some("1"))]));
+        assert!(diff_notebooks(Some(&nb), Some(&nb)).unwrap().is_empty());
+    }
+
+    #[test]
+    fn metadata_and_execution_counts_are_ignored() {
+        let old = notebook(json!([code("x = 1", Some(1), None)]));
+        let mut cell = code("x = 1", Some(5), None);
+        cell["metadata"] = json!({"collapsed": true});
+        let new = json!({"cells": [cell], "metadata": {"kernelspec": {"name": "py"}},
+                         "nbformat": 4, "nbformat_minor": 4})
+        .to_string();
+        assert!(diff_notebooks(Some(&old), Some(&new)).unwrap().is_empty());
+    }
+
+    #[test]
+    fn edited_cell_is_modified_with_source_diff() {
+        let old = notebook(json!([
+            {"cell_type": "markdown", "source": "# Title", "metadata": {}},
+            code("x = 1\nprint(x)", Some(1), Some("1")),
+        ]));
+        let new = notebook(json!([
+            {"cell_type": "markdown", "source": "# Title", "metadata": {}},
+            code("x = 2\nprint(x)", Some(2), Some("2")),
+        ]));
+        let diff = diff_notebooks(Some(&old), Some(&new)).unwrap();
+        assert_eq!(diff.cells.len(), 1);
+        let change = &diff.cells[0];
+        assert_eq!(change.change, CellChangeKind::Modified);
+        assert_eq!(change.index, Some(1));
+        let source_diff = change.source_diff.as_deref().unwrap();
+        assert!(source_diff.contains("-x = 1"));
+        assert!(source_diff.contains("+x = 2"));
+        assert_eq!(
+            change.outputs.as_deref(),
+            Some("stream(stdout) -> stream(stdout)")
+        );
+        assert!(diff.stale_outputs.is_empty());
+    }
+
+    #[test]
+    fn added_and_removed_cells_are_reported() {
+        let old = notebook(json!([code("a", None, None), code("b", None, None)]));
+        let new = notebook(json!([
+            code("a", None, None),
+            {"cell_type": "markdown", "source": ["## Notes\n", "text"], "metadata": {}},
+        ]));
+        let diff = diff_notebooks(Some(&old), Some(&new)).unwrap();
+        let kinds: Vec<CellChangeKind> = diff.cells.iter().map(|c| c.change).collect();
+        assert_eq!(kinds, vec![CellChangeKind::Removed, CellChangeKind::Added]);
+        assert_eq!(diff.cells[1].source.as_deref(), Some("## Notes\ntext"));
+    }
+
+    #[test]
+    fn new_notebook_lists_every_cell_as_added() {
+        let new = notebook(json!([code("a", Some(1), Some("x"))]));
+        let diff = diff_notebooks(None, Some(&new)).unwrap();
+        assert_eq!(diff.cells.len(), 1);
+        assert_eq!(diff.cells[0].change, CellChangeKind::Added);
+        assert_eq!(
+            diff.cells[0].outputs.as_deref(),
+            Some("none -> stream(stdout)")
+        );
+    }
+
+    #[test]
+    fn cells_with_ids_pair_up_across_edits() {
+        let mut a = code("x = 1", None, None);
+        a["id"] = json!("cell-a");
+        let mut b = code("x = 2", None, None);
+        b["id"] = json!("cell-a");
+        let diff =
+            diff_notebooks(Some(&notebook(json!([a]))), Some(&notebook(json!([b])))).unwrap();
+        assert_eq!(diff.cells.len(), 1);
+        assert_eq!(diff.cells[0].change, CellChangeKind::Modified);
+    }
+
+    #[test]
+    fn source_edit_without_rerun_is_stale() {
+        let old = notebook(json!([code("x = 1", Some(1), Some("1"))]));
+        let new = notebook(json!([code("x = 2", Some(1), Some("1"))]));
+        let diff = diff_notebooks(Some(&old), Some(&new)).unwrap();
+        assert_eq!(diff.stale_outputs.len(), 1);
+        assert_eq!(diff.stale_outputs[0].index, 0);
+        assert!(diff.stale_outputs[0].reason.contains("source changed"));
+    }
+
+    #[test]
+    fn out_of_order_execution_is_stale() {
+        let new = notebook(json!([
+            code("a", Some(3), Some("a")),
+            code("b", Some(1), Some("b")),
+            code("c", None, Some("c")),
+        ]));
+        let diff = diff_notebooks(Some(&new), Some(&new)).unwrap();
+        let indexes: Vec<usize> = diff.stale_outputs.iter().map(|s| s.index).collect();
+        assert_eq!(indexes, vec![1, 2]);
+        assert!(diff.stale_outputs[0].reason.contains("before cell 0"));
+    }
+
+    #[test]
+    fn invalid_notebook_is_an_error() {
+        assert!(diff_notebooks(Some("not json"), None).is_err());
+        assert!(diff_notebooks(Some("{}"), None).is_err());
+    }
+}
@@ -0,0 +1,585 @@
+//! Pipeline runner for `pipeline.steps`.
+//!
+//! Steps run in declaration order from the project root, each through the
+//! shell (`command`, or `tool` when no command is given) with `pipeline.env`
+//! overlaid by the step's own `env`. A step that declares both `input` and
+//! `output` is skipped when its fingerprint -- a BLAKE3 hash of its command,
+//! environment and the contents of every input and output file -- matches
+//! the one recorded after its last successful run. After a step runs, its
+//! `output` must match at least one file, and the inputs its outputs were
+//! built from are recorded for staleness checks (see `core::derived`). Step
+//! output is sent to stderr.
+//!
+//! `condition` is evaluated when it is a single comparison of a spec field
+//! with a quoted literal (`output.format == 'pdf'`, `!=` also accepted);
+//! anything else is an error, since the runner cannot interpret free-form
+//! conditions.
+
+use crate::core::cache;
+use crate::core::spec::{OnFailure, Pipeline, PipelineStep, Spec};
+use crate::{Error, Result};
+use rusqlite::{Connection, params};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+/// What `run` should do.
+#[derive(Debug, Default)]
+pub struct RunOptions {
+    /// Report what would run without running anything or touching the cache.
+    pub dry_run: bool,
+    /// Run only the step with this name, ignoring its `depends_on`.
+    pub step: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    /// The step ran and produced its outputs.
+    Ran,
+    /// Inputs and outputs are unchanged since the last successful run.
+    UpToDate,
+    /// Dry run: the step would run.
+    WouldRun,
+    /// The step's condition is false.
+    ConditionFalse,
+    /// A step it depends on failed.
+    Blocked,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StepResult {
+    pub name: String,
+    pub outcome: Outcome,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+/// Progress reported while `run` executes.
+pub enum Event<'a> {
+    /// A step's command is about to run.
+    Starting {
+        step: &'a str,
+        command: &'a str,
+    },
+    Finished(&'a StepResult),
+}
+
+/// Run the pipeline of the spec at `spec_path`. Step failures are reported
+/// in the results; `Err` is returned when the pipeline cannot be run at all
+/// (no steps, an unknown `--step`, a `depends_on` naming an unknown or later
+/// step, or a cache error).
+pub fn run(
+    project_root: &Path,
+    spec_path: &str,
+    spec: &Spec,
+    options: &RunOptions,
+    mut on_event: impl FnMut(Event),
+) -> Result<Vec<StepResult>> {
+    let invalid = |message: String| Error::InvalidSpec {
+        path: spec_path.to_string(),
+        message,
+    };
+    let pipeline = spec
+        .pipeline
+        .as_ref()
+        .filter(|p| !p.steps.is_empty())
+        .ok_or_else(|| invalid("has no pipeline steps".to_string()))?;
+    check_dependencies(&pipeline.steps).map_err(invalid)?;
+    let selected: Vec<&PipelineStep> = match &options.step {
+        Some(name) => vec![
+            pipeline
+                .steps
+                .iter()
+                .find(|s| &s.name == name)
+                .ok_or_else(|| invalid(format!("has no pipeline step '{name}'")))?,
+        ],
+        None => pipeline.steps.iter().collect(),
+    };
+    let conn = if options.dry_run && !cache::db_path(project_root).exists() {
+        None
+    } else {
+        Some(cache::open_cache_db(project_root)?)
+    };
+    let spec_value = serde_json::to_value(spec).map_err(Error::model("spec"))?;
+
+    let mut results = Vec::new();
+    let mut incomplete: BTreeSet<&str> = BTreeSet::new();
+    for step in selected {
+        let result = run_step(
+            project_root,
+            spec_path,
+            &spec_value,
+            pipeline,
+            step,
+            &incomplete,
+            conn.as_ref(),
+            options.dry_run,
+            &mut on_event,
+        )?;
+        on_event(Event::Finished(&result));
+        let failed = result.outcome == Outcome::Failed;
+        if matches!(result.outcome, Outcome::Failed | Outcome::Blocked) {
+            incomplete.insert(&step.name);
+        }
+        results.push(result);
+        if failed && step.on_failure != Some(OnFailure::Skip) {
+            break;
+        }
+    }
+    Ok(results)
+}
+
+/// Every `depends_on` entry must name a step declared earlier.
+fn check_dependencies(steps: &[PipelineStep]) -> Result<(), String> {
+    for (i, step) in steps.iter().enumerate() {
+        for dependency in &step.depends_on {
+            match steps.iter().position(|s| &s.name == dependency) {
+                Some(at) if at < i => {}
+                Some(_) => {
+                    return Err(format!(
+                        "step '{}' depends on '{dependency}', which is declared after it",
+                        step.name
+                    ));
+                }
+                None => {
+                    return Err(format!(
+                        "step '{}' depends on unknown step '{dependency}'",
+                        step.name
+                    ));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn run_step(
+    project_root: &Path,
+    spec_path: &str,
+    spec_value: &Value,
+    pipeline: &Pipeline,
+    step: &PipelineStep,
+    incomplete: &BTreeSet<&str>,
+    conn: Option<&Connection>,
+    dry_run: bool,
+    on_event: &mut impl FnMut(Event),
+) -> Result<StepResult> {
+    let result = |outcome, message: Option<String>| StepResult {
+        name: step.name.clone(),
+        outcome,
+        message,
+    };
+
+    if let Some(dependency) = step
+        .depends_on
+        .iter()
+        .find(|d| incomplete.contains(d.as_str()))
+    {
+        return Ok(result(
+            Outcome::Blocked,
+            Some(format!("dependency '{dependency}' did not complete")),
+        ));
+    }
+    if let Some(condition) = &step.condition {
+        match evaluate_condition(spec_value, condition) {
+            Ok(true) => {}
+            Ok(false) => {
+                return Ok(result(
+                    Outcome::ConditionFalse,
+                    Some(format!("condition is false: {condition}")),
+                ));
+            }
+            Err(e) => return Ok(result(Outcome::Failed, Some(e))),
+        }
+    }
+    let Some(command) = step.command.as_deref().or(step.tool.as_deref()) else {
+        return Ok(result(
+            Outcome::Failed,
+            Some("step has no command or tool".to_string()),
+        ));
+    };
+    let env = step_env(pipeline, step);
+
+    if let (Some(before), Some(conn)) = (fingerprint(project_root, step, command, &env), conn)
+        && stored_fingerprint(conn, spec_path, &step.name)?.as_deref() == Some(before.as_str())
+    {
+        return Ok(result(Outcome::UpToDate, None));
+    }
+    if dry_run {
+        return Ok(result(Outcome::WouldRun, Some(command.to_string())));
+    }
+
+    let attempts = if step.on_failure == Some(OnFailure::Retry) {
+        2
+    } else {
+        1
+    };
+    let mut status = Ok(());
+    for _ in 0..attempts {
+        on_event(Event::Starting {
+            step: &step.name,
+            command,
+        });
+        status = execute(project_root, command, &env);
+        if status.is_ok() {
+            break;
+        }
+    }
+    if let Err(e) = status {
+        return Ok(result(Outcome::Failed, Some(e)));
+    }
+
+    if let Some(output) = &step.output
+        && files(project_root, output).is_empty()
+    {
+        return Ok(result(
+            Outcome::Failed,
+            Some(format!("did not produce its declared output '{output}'")),
+        ));
+    }
+    if let (Some(after), Some(conn)) = (fingerprint(project_root, step, command, &env), conn) {
+        record_fingerprint(conn, spec_path, &step.name, &after)?;
+        crate::core::derived::record(conn, project_root, step)?;
+    }
+    Ok(result(Outcome::Ran, None))
+}
+
+/// `pipeline.env` overlaid by the step's own `env`.
+fn step_env(pipeline: &Pipeline, step: &PipelineStep) -> BTreeMap<String, String> {
+    let mut env = pipeline.env.clone();
+    env.extend(step.env.clone());
+    env
+}
+
+fn execute(
+    project_root: &Path,
+    command: &str,
+    env: &BTreeMap<String, String>,
+) -> Result<(), String> {
+    let mut shell = if cfg!(windows) {
+        let mut c = std::process::Command::new("cmd");
+        c.arg("/C");
+        c
+    } else {
+        let mut c = std::process::Command::new("sh");
+        c.arg("-c");
+        c
+    };
+    let status = shell
+        .arg(command)
+        .current_dir(project_root)
+        .envs(env)
+        // Keep stdout for the run report; step output goes to stderr.
+        .stdout(std::io::stderr())
+        .status()
+        .map_err(|e| format!("could not start '{command}': {e}"))?;
+    if status.success() {
+        return Ok(());
+    }
+    Err(match status.code() {
+        Some(code) => format!("'{command}' exited with status {code}"),
+        None => format!("'{command}' was terminated by a signal"),
+    })
+}
+
+/// The files matched by `pattern`, with directories expanded to the files
+/// below them, sorted.
+pub(crate) fn files(project_root: &Path, pattern: &str) -> Vec<String> {
+    let pattern = crate::core::mcp_tools::normalize_rel(pattern);
+    let mut files = BTreeSet::new();
+    for path in crate::core::mcp_tools::expand_glob(pattern, project_root) {
+        let abs = project_root.join(&path);
+        if abs.is_dir() {
+            for entry in walkdir::WalkDir::new(&abs)
+                .into_iter()
+                .filter_map(|e| e.ok())
+                .filter(|e| e.file_type().is_file())
+            {
+                if let Ok(rel) = entry.path().strip_prefix(project_root) {
+                    files.insert(rel.to_string_lossy().replace('\\', "/"));
+                }
+            }
+        } else if abs.is_file() {
+            files.insert(path.replace('\\', "/"));
+        }
+    }
+    files.into_iter().collect()
+}
+
+/// Fingerprint of a step that declares both `input` and `output`, or `None`
+/// for a step that always runs. Missing outputs simply hash as absent, so
+/// deleting or editing an output makes the step run again.
+fn fingerprint(
+    project_root: &Path,
+    step: &PipelineStep,
+    command: &str,
+    env: &BTreeMap<String, String>,
+) -> Option<String> {
+    let (input, output) = (step.input.as_deref()?, step.output.as_deref()?);
+    let mut text = format!("command\0{command}\0");
+    for (key, value) in env {
+        text.push_str(&format!("env\0{key}\0{value}\0"));
+    }
+    for (kind, pattern) in [("input", input), ("output", output)] {
+        for file in files(project_root, pattern) {
+            let hash = cache::hash_file(&project_root.join(&file)).unwrap_or_default();
+            text.push_str(&format!("{kind}\0{file}\0{hash}\0"));
+        }
+    }
+    Some(cache::hash_bytes(text.as_bytes()))
+}
+
+fn stored_fingerprint(conn: &Connection, spec_path: &str, step: &str) -> Result<Option<String>> {
+    conn.query_row(
+        "SELECT fingerprint FROM pipeline_steps WHERE spec_path = ?1 AND step = ?2",
+        params![spec_path, step],
+        |row| row.get(0),
+    )
+    .map(Some)
+    .or_else(|e| match e {
+        rusqlite::Error::QueryReturnedNoRows => Ok(None),
+        e => Err(Error::cache("could not query pipeline step")(e)),
+    })
+}
+
+fn record_fingerprint(
+    conn: &Connection,
+    spec_path: &str,
+    step: &str,
+    fingerprint: &str,
+) -> Result<()> {
+    let tx = cache::write_transaction(conn)?;
+    tx.execute(
+        "INSERT OR REPLACE INTO pipeline_steps (spec_path, step, fingerprint, updated_at)
+         VALUES (?1, ?2, ?3, ?4)",
+        params![spec_path, step, fingerprint, crate::core::time::now_secs()],
+    )
+    .map_err(Error::cache("could not record pipeline step"))?;
+    tx.commit().map_err(Error::cache("commit error"))
+}
+
+/// Evaluate `<field path> == '<literal>'` or `!=` against the spec. The path
+/// is a bare dotted identifier (`output.format`) and the literal is quoted
+/// with `'` or `"`; anything else, such as `||` or a second comparison, is
+/// an error. A missing field compares unequal to every literal.
+fn evaluate_condition(spec: &Value, condition: &str) -> Result<bool, String> {
+    let (path, negate, literal) = parse_condition(condition).ok_or_else(|| {
+        format!(
+            "cannot evaluate condition '{condition}'; expected <field> == '<value>' or <field> != '<value>'"
+        )
+    })?;
+
+    let field = path.split('.').try_fold(spec, |node, segment| match node {
+        Value::Array(items) => items.get(segment.parse::<usize>().ok()?),
+        _ => node.get(segment),
+    });
+    let equal = match field {
+        Some(Value::String(s)) => s == literal,
+        Some(Value::Null) | None => false,
+        Some(other) => serde_json::from_str::<Value>(literal).is_ok_and(|v| &v == other),
+    };
+    Ok(equal != negate)
+}
+
+/// Split a condition into its field path, whether it is negated and its
+/// literal, if it is exactly one comparison of a dotted identifier with a
+/// quoted literal.
+fn parse_condition(condition: &str) -> Option<(&str, bool, &str)> {
+    let condition = condition.trim();
+    let path_len = condition
+        .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.')))
+        .unwrap_or(condition.len());
+    let (path, rest) = condition.split_at(path_len);
+    if path.split('.').any(str::is_empty) {
+        return None;
+    }
+    let rest = rest.trim_start();
+    let (negate, rest) = if let Some(rest) = rest.strip_prefix("!=") {
+        (true, rest)
+    } else {
+        (false, rest.strip_prefix("==")?)
+    };
+    let rest = rest.trim_start();
+    let quote = rest.chars().next().filter(|c| matches!(c, '\'' | '"'))?;
+    let (literal, tail) = rest[1..].split_once(quote)?;
+    tail.trim().is_empty().then_some((path, negate, literal))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    fn spec(steps: &str) -> Spec {
+        Spec::parse(&format!(
+            "schema_version: '0.7'\nintent: 'x'\noutput:\n  format: pdf\n\
+             pipeline:\n  env:\n    GREETING: hello\n  steps:\n{steps}"
+        ))
+        .unwrap()
+    }
+
+    fn outcomes(results: &[StepResult]) -> Vec<(&str, Outcome)> {
+        results
+            .iter()
+            .map(|r| (r.name.as_str(), r.outcome))
+            .collect()
+    }
+
+    fn run_all(root: &Path, spec: &Spec, options: &RunOptions) -> Vec<StepResult> {
+        run(root, ".notarai/x.spec.yaml", spec, options, |_| {}).unwrap()
+    }
+
+    #[test]
+    fn conditions_compare_spec_fields() {
+        let spec = json!({"output": {"format": "pdf"}, "variants_resolved": true});
+        assert!(evaluate_condition(&spec, "output.format == 'pdf'").unwrap());
+        assert!(!evaluate_condition(&spec, "output.format == \"html\"").unwrap());
+        assert!(evaluate_condition(&spec, "output.format != 'html'").unwrap());
+        assert!(!evaluate_condition(&spec, "output.runtime == 'browser'").unwrap());
+        assert!(evaluate_condition(&spec, "variants_resolved == 'true'").unwrap());
+        assert!(
+            evaluate_condition(&spec, "when the deck is final")
+                .unwrap_err()
+                .contains("cannot evaluate")
+        );
+    }
+
+    #[test]
+    fn conditions_must_be_a_single_comparison() {
+        let spec = json!({"output": {"format": "pdf"}, "tier": "full"});
+        for condition in [
+            "output.format != 'html' || tier == 'full'",
+            "output.format == 'pdf' && tier != 'full'",
+            "!(output.format == 'html')",
+            "output.format == 'pdf' == 'pdf'",
+            "output.format == pdf",
+            "output.format == 'pdf",
+            "output.format === 'pdf'",
+            "output..format == 'pdf'",
+            "== 'pdf'",
+        ] {
+            assert!(
+                evaluate_condition(&spec, condition).is_err(),
+                "{condition} should not evaluate"
+            );
+        }
+    }
+
+    #[test]
+    fn skips_unchanged_steps_and_reruns_on_change() {
+        let tmp = TempDir::new().unwrap();
+        std::fs::write(tmp.path().join("in.txt"), "a").unwrap();
+        let spec = spec(
+            "    - name: build\n      command: 'cat in.txt > out.txt; echo $GREETING >> out.txt'\n      \
+             input: 'in.txt'\n      output: 'out.txt'\n",
+        );
+        let options = RunOptions::default();
+        assert_eq!(
+            outcomes(&run_all(tmp.path(), &spec, &options)),
+            [("build", Outcome::Ran)]
+        );
+        assert_eq!(
+            std::fs::read_to_string(tmp.path().join("out.txt")).unwrap(),
+            "ahello\n"
+        );
+        assert_eq!(
+            outcomes(&run_all(tmp.path(), &spec, &options)),
+            [("build", Outcome::UpToDate)]
+        );
+
+        std::fs::write(tmp.path().join("in.txt"), "b").unwrap();
+        let dry = RunOptions {
+            dry_run: true,
+            step: None,
+        };
+        assert_eq!(
+            outcomes(&run_all(tmp.path(), &spec, &dry)),
+            [("build", Outcome::WouldRun)]
+        );
+        assert_eq!(
+            outcomes(&run_all(tmp.path(), &spec, &options)),
+            [("build", Outcome::Ran)]
+        );
+        std::fs::remove_file(tmp.path().join("out.txt")).unwrap();
+        assert_eq!(
+            outcomes(&run_all(tmp.path(), &spec, &options)),
+            [("build", Outcome::Ran)]
+        );
+    }
+
+    #[test]
+    fn failures_abort_skip_or_block_dependents() {
+        let tmp = TempDir::new().unwrap();
+        let spec = spec(
+            "    - name: lint\n      command: 'exit 3'\n      on_failure: skip\n    \
+             - name: build\n      command: 'true'\n      output: 'dist/*'\n    \
+             - name: publish\n      command: 'true'\n      depends_on: [lint]\n    \
+             - name: html\n      command: 'true'\n      condition: \"output.format == 'html'\"\n",
+        );
+        let results = run_all(tmp.path(), &spec, &RunOptions::default());
+        assert_eq!(
+            outcomes(&results),
+            [("lint", Outcome::Failed), ("build", Outcome::Failed)]
+        );
+        assert_eq!(
+            results[0].message.as_deref(),
+            Some("'exit 3' exited with status 3")
+        );
+        assert_eq!(
+            results[1].message.as_deref(),
+            Some("did not produce its declared output 'dist/*'")
+        );
+
+        std::fs::create_dir_all(tmp.path().join("dist")).unwrap();
+        std::fs::write(tmp.path().join("dist/deck.pdf"), "").unwrap();
+        assert_eq!(
+            outcomes(&run_all(tmp.path(), &spec, &RunOptions::default())),
+            [
+                ("lint", Outcome::Failed),
+                ("build", Outcome::Ran),
+                ("publish", Outcome::Blocked),
+                ("html", Outcome::ConditionFalse),
+            ]
+        );
+    }
+
+    #[test]
+    fn runs_a_single_step_and_rejects_bad_dependencies() {
+        let tmp = TempDir::new().unwrap();
+        let spec_ok = spec(
+            "    - name: a\n      command: 'exit 1'\n    \
+             - name: b\n      command: 'touch b.txt'\n      depends_on: [a]\n",
+        );
+        let only_b = RunOptions {
+            dry_run: false,
+            step: Some("b".to_string()),
+        };
+        assert_eq!(
+            outcomes(&run_all(tmp.path(), &spec_ok, &only_b)),
+            [("b", Outcome::Ran)]
+        );
+        assert!(tmp.path().join("b.txt").exists());
+
+        let unknown = RunOptions {
+            dry_run: false,
+            step: Some("c".to_string()),
+        };
+        let err = run(tmp.path(), "x", &spec_ok, &unknown, |_| {}).unwrap_err();
+        assert!(err.to_string().contains("no pipeline step 'c'"), "{err}");
+
+        let spec_bad = spec(
+            "    - name: a\n      command: 'true'\n      depends_on: [b]\n    \
+             - name: b\n      command: 'true'\n",
+        );
+        let err = run(tmp.path(), "x", &spec_bad, &RunOptions::default(), |_| {}).unwrap_err();
+        assert!(
+            matches!(err, Error::InvalidSpec { ref message, .. } if message.contains("declared after it")),
+            "{err}"
+        );
+    }
+}
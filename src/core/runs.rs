@@ -0,0 +1,205 @@
+//! Reconciliation run history stored in the cache database.
+//!
+//! A run starts with `begin_run` (the `begin_reconciliation` MCP tool) and is
+//! closed by `finish_current_run` when the state is snapshotted. Every
+//! `mark_reconciled` call in between is attached to the open run, recording
+//! which specs were touched and which files were marked for each. A
+//! `mark_reconciled` call with no open run starts one implicitly. A run left
+//! open by an abandoned session is closed when the next run starts.
+
+use crate::{Error, Result};
+use rusqlite::{Connection, params};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// One reconciliation run, as listed by `notarai log`.
+#[derive(Debug, Serialize)]
+pub struct RunSummary {
+    pub id: i64,
+    pub started_at: i64,
+    pub finished_at: Option<i64>,
+    pub base_ref: Option<String>,
+    /// Files marked in this run, keyed by the spec they were reconciled for.
+    /// A spec touched without marking any file maps to an empty list.
+    pub specs: BTreeMap<String, Vec<String>>,
+}
+
+/// Start a new run and return its id. Runs still open are closed first.
+pub fn begin_run(conn: &Connection, base_ref: Option<&str>) -> Result<i64> {
+    close_open_runs(conn)?;
+    conn.execute(
+        "INSERT INTO reconciliation_runs (started_at, base_ref) VALUES (?1, ?2)",
+        params![crate::core::time::now_secs(), base_ref],
+    )
+    .map_err(Error::cache("could not start run"))?;
+    Ok(conn.last_insert_rowid())
+}
+
+/// Return the id of the most recently started run that has not finished.
+pub fn current_run(conn: &Connection) -> Result<Option<i64>> {
+    conn.query_row(
+        "SELECT id FROM reconciliation_runs WHERE finished_at IS NULL
+         ORDER BY id DESC LIMIT 1",
+        [],
+        |row| row.get(0),
+    )
+    .map(Some)
+    .or_else(|e| match e {
+        rusqlite::Error::QueryReturnedNoRows => Ok(None),
+        e => Err(Error::cache("could not query current run")(e)),
+    })
+}
+
+/// Record that `files` were marked for `spec_path` in the open run, starting
+/// a run first if none is open. Returns the run id.
+pub fn record_marks(conn: &Connection, spec_path: &str, files: &[String]) -> Result<i64> {
+    let tx = crate::core::cache::write_transaction(conn)?;
+    let run_id = match current_run(&tx)? {
+        Some(id) => id,
+        None => begin_run(&tx, None)?,
+    };
+    tx.execute(
+        "INSERT OR IGNORE INTO run_specs (run_id, spec_path) VALUES (?1, ?2)",
+        params![run_id, spec_path],
+    )
+    .map_err(Error::cache("could not record spec"))?;
+    let mut stmt = tx
+        .prepare("INSERT OR IGNORE INTO run_files (run_id, spec_path, path) VALUES (?1, ?2, ?3)")
+        .map_err(Error::cache("prepare error"))?;
+    for file in files {
+        stmt.execute(params![run_id, spec_path, file])
+            .map_err(Error::cache("could not record file"))?;
+    }
+    drop(stmt);
+    tx.commit().map_err(Error::cache("commit error"))?;
+    Ok(run_id)
+}
+
+/// Close the open run, if any, along with any older run left open. Returns
+/// the id of the most recently started run that was closed.
+pub fn finish_current_run(conn: &Connection) -> Result<Option<i64>> {
+    let tx = crate::core::cache::write_transaction(conn)?;
+    let Some(run_id) = current_run(&tx)? else {
+        return Ok(None);
+    };
+    close_open_runs(&tx)?;
+    tx.commit().map_err(Error::cache("commit error"))?;
+    Ok(Some(run_id))
+}
+
+fn close_open_runs(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "UPDATE reconciliation_runs SET finished_at = ?1 WHERE finished_at IS NULL",
+        params![crate::core::time::now_secs()],
+    )
+    .map_err(Error::cache("could not finish run"))?;
+    Ok(())
+}
+
+/// List the `limit` most recent runs, newest first.
+pub fn list_runs(conn: &Connection, limit: usize) -> Result<Vec<RunSummary>> {
+    let mut runs_stmt = conn
+        .prepare(
+            "SELECT id, started_at, finished_at, base_ref FROM reconciliation_runs
+             ORDER BY id DESC LIMIT ?1",
+        )
+        .map_err(Error::cache("prepare error"))?;
+    let mut runs: Vec<RunSummary> = runs_stmt
+        .query_map(params![limit as i64], |row| {
+            Ok(RunSummary {
+                id: row.get(0)?,
+                started_at: row.get(1)?,
+                finished_at: row.get(2)?,
+                base_ref: row.get(3)?,
+                specs: BTreeMap::new(),
+            })
+        })
+        .map_err(Error::cache("query error"))?
+        .collect::<Result<_, _>>()
+        .map_err(Error::cache("row error"))?;
+
+    let mut specs_stmt = conn
+        .prepare("SELECT spec_path FROM run_specs WHERE run_id = ?1 ORDER BY spec_path")
+        .map_err(Error::cache("prepare error"))?;
+    let mut files_stmt = conn
+        .prepare("SELECT spec_path, path FROM run_files WHERE run_id = ?1 ORDER BY spec_path, path")
+        .map_err(Error::cache("prepare error"))?;
+    for run in &mut runs {
+        let specs = specs_stmt
+            .query_map(params![run.id], |row| row.get::<_, String>(0))
+            .map_err(Error::cache("query error"))?;
+        for spec in specs {
+            let spec = spec.map_err(Error::cache("row error"))?;
+            run.specs.entry(spec).or_default();
+        }
+        let files = files_stmt
+            .query_map(params![run.id], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })
+            .map_err(Error::cache("query error"))?;
+        for file in files {
+            let (spec, path) = file.map_err(Error::cache("row error"))?;
+            run.specs.entry(spec).or_default().push(path);
+        }
+    }
+    Ok(runs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::cache::open_cache_db;
+    use tempfile::TempDir;
+
+    #[test]
+    fn marks_without_open_run_start_one() {
+        let tmp = TempDir::new().unwrap();
+        let conn = open_cache_db(tmp.path()).unwrap();
+        assert_eq!(current_run(&conn).unwrap(), None);
+        let id = record_marks(&conn, "a.spec.yaml", &["x.txt".to_string()]).unwrap();
+        assert_eq!(current_run(&conn).unwrap(), Some(id));
+    }
+
+    #[test]
+    fn run_lifecycle_is_listed_newest_first() {
+        let tmp = TempDir::new().unwrap();
+        let conn = open_cache_db(tmp.path()).unwrap();
+
+        let first = begin_run(&conn, Some("main")).unwrap();
+        record_marks(&conn, "b.spec.yaml", &["y.txt".to_string()]).unwrap();
+        record_marks(&conn, "a.spec.yaml", &[]).unwrap();
+        assert_eq!(finish_current_run(&conn).unwrap(), Some(first));
+        let second = begin_run(&conn, None).unwrap();
+
+        let runs = list_runs(&conn, 10).unwrap();
+        assert_eq!(runs.len(), 2);
+        assert_eq!(runs[0].id, second);
+        assert!(runs[0].finished_at.is_none());
+        assert_eq!(runs[1].id, first);
+        assert_eq!(runs[1].base_ref.as_deref(), Some("main"));
+        assert!(runs[1].finished_at.is_some());
+        assert_eq!(runs[1].specs["b.spec.yaml"], vec!["y.txt".to_string()]);
+        assert!(runs[1].specs["a.spec.yaml"].is_empty());
+
+        assert_eq!(list_runs(&conn, 1).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn abandoned_runs_do_not_stay_open() {
+        let tmp = TempDir::new().unwrap();
+        let conn = open_cache_db(tmp.path()).unwrap();
+
+        let abandoned = begin_run(&conn, None).unwrap();
+        let second = begin_run(&conn, None).unwrap();
+        assert_eq!(current_run(&conn).unwrap(), Some(second));
+        let runs = list_runs(&conn, 10).unwrap();
+        assert_eq!(runs[1].id, abandoned);
+        assert!(runs[1].finished_at.is_some());
+
+        // Rows left open by an older notarai are closed at finish.
+        conn.execute("UPDATE reconciliation_runs SET finished_at = NULL", [])
+            .unwrap();
+        assert_eq!(finish_current_run(&conn).unwrap(), Some(second));
+        assert_eq!(current_run(&conn).unwrap(), None);
+    }
+}
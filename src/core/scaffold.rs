@@ -0,0 +1,587 @@
+//! Spec scaffolding for `notarai new`.
+//!
+//! Renders a schema-valid skeleton for a domain and tier, with the optional
+//! blocks that domain usually needs, and registers the new spec in the system
+//! spec's `subsystems`. Skeletons are rendered as text rather than serialized
+//! so they can carry placeholder values in the field order a person would
+//! write them.
+
+use crate::core::compose::resolve_ref;
+use crate::core::spec::{Domain, Spec, Tier};
+use crate::{Error, Result};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// What `create` wrote.
+#[derive(Debug)]
+pub struct NewSpec {
+    /// Project-relative path of the new spec.
+    pub path: String,
+    pub registration: Registration,
+}
+
+/// How a new spec was added to the system spec's `subsystems`.
+#[derive(Debug, PartialEq)]
+pub enum Registration {
+    /// The project has no system spec, or the new spec is the system spec.
+    NoSystemSpec,
+    /// The system spec already lists the new spec.
+    Already { system: String },
+    /// `reference` was appended to the system spec's `subsystems`.
+    Registered { system: String, reference: String },
+}
+
+/// Write a skeleton spec named `name` (see `spec_path_for`) and register it
+/// in the system spec.
+///
+/// Fails without writing anything when the spec already exists or the system
+/// spec cannot be edited safely.
+pub fn create(
+    project_root: &Path,
+    name: &str,
+    domain: Domain,
+    tier: Tier,
+    paths: &[String],
+) -> Result<NewSpec> {
+    let spec_rel = spec_path_for(name)?;
+    let spec_abs = project_root.join(&spec_rel);
+    if spec_abs.exists() {
+        return Err(Error::SpecExists(spec_rel));
+    }
+
+    // Work out the system spec edit first, so that a system spec that
+    // cannot be edited safely leaves the project untouched.
+    let (registration, updated) = registration(project_root, &spec_rel)?;
+
+    let stem = spec_rel
+        .trim_start_matches(".notarai/")
+        .trim_end_matches(".spec.yaml");
+    let globs = artifact_globs(paths, domain, project_root, stem);
+    let content = render_spec(stem, domain, tier, &globs);
+    if let Some(parent) = spec_abs.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| Error::io(parent, e))?;
+    }
+    std::fs::write(&spec_abs, content).map_err(|e| Error::io(&spec_abs, e))?;
+    if let (Registration::Registered { system, .. }, Some(updated)) = (&registration, updated) {
+        let system_abs = project_root.join(system);
+        std::fs::write(&system_abs, updated).map_err(|e| Error::io(&system_abs, e))?;
+    }
+    Ok(NewSpec {
+        path: spec_rel,
+        registration,
+    })
+}
+
+/// How the system spec must change to list `spec_rel` as a subsystem, with
+/// its edited source when it must.
+fn registration(project_root: &Path, spec_rel: &str) -> Result<(Registration, Option<String>)> {
+    let Some(system) = system_spec_path(project_root)?.filter(|s| s != spec_rel) else {
+        return Ok((Registration::NoSystemSpec, None));
+    };
+    let system_abs = project_root.join(&system);
+    let content = std::fs::read_to_string(&system_abs).map_err(|e| Error::io(&system_abs, e))?;
+    let already = Spec::parse(&content).is_ok_and(|spec| {
+        spec.subsystems
+            .iter()
+            .any(|r| resolve_ref(&system, &r.reference) == spec_rel)
+    });
+    if already {
+        return Ok((Registration::Already { system }, None));
+    }
+    let reference = relative_ref(&system, spec_rel);
+    let updated =
+        register_subsystem(&content, &reference).map_err(|message| Error::Registration {
+            spec: spec_rel.to_string(),
+            system: system.clone(),
+            message,
+        })?;
+    Ok((
+        Registration::Registered { system, reference },
+        Some(updated),
+    ))
+}
+
+/// Check a spec name (`auth`, `api/auth`) and return the project-relative path
+/// of its spec file.
+pub fn spec_path_for(name: &str) -> Result<String> {
+    let name = name.strip_suffix(".spec.yaml").unwrap_or(name);
+    let valid_segment = |s: &str| {
+        !s.is_empty()
+            && s != "."
+            && s != ".."
+            && s.chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+    };
+    if !name.split('/').all(valid_segment) {
+        return Err(Error::InvalidSpecName(name.to_string()));
+    }
+    Ok(format!(".notarai/{name}.spec.yaml"))
+}
+
+/// Artifact globs for the given paths, grouped by category.
+///
+/// A directory becomes `dir/**`; a file or glob is kept as given. Each path is
+/// filed under a category guessed from its extension or location (`tests`,
+/// `docs`, `slides`, ...), falling back to the domain's main category. With
+/// no paths, a placeholder glob is used.
+pub fn artifact_globs(
+    paths: &[String],
+    domain: Domain,
+    project_root: &Path,
+    name: &str,
+) -> BTreeMap<&'static str, Vec<String>> {
+    let mut globs: BTreeMap<&'static str, Vec<String>> = BTreeMap::new();
+    if paths.is_empty() {
+        globs
+            .entry(default_category(domain))
+            .or_default()
+            .push(format!("{name}/**"));
+        return globs;
+    }
+    for path in paths {
+        let path = crate::core::mcp_tools::normalize_rel(path).trim_end_matches('/');
+        let (glob, category) = if project_root.join(path).is_dir() {
+            (format!("{path}/**"), category_for_dir(path))
+        } else {
+            (path.to_string(), category_for_file(path))
+        };
+        globs
+            .entry(category.unwrap_or_else(|| default_category(domain)))
+            .or_default()
+            .push(glob);
+    }
+    globs
+}
+
+fn default_category(domain: Domain) -> &'static str {
+    match domain {
+        Domain::Software => "code",
+        Domain::Presentation => "slides",
+        Domain::Infrastructure => "configs",
+        Domain::Research => "notebooks",
+        Domain::Report | Domain::Course | Domain::Marketing | Domain::Legal | Domain::Education => {
+            "docs"
+        }
+    }
+}
+
+fn category_for_dir(path: &str) -> Option<&'static str> {
+    match path.rsplit('/').next() {
+        Some("tests" | "test" | "spec") => Some("tests"),
+        Some("docs" | "doc") => Some("docs"),
+        Some("data") => Some("data"),
+        Some("notebooks") => Some("notebooks"),
+        _ => None,
+    }
+}
+
+fn category_for_file(path: &str) -> Option<&'static str> {
+    let file = path.rsplit('/').next().unwrap_or(path);
+    let in_tests = path.split('/').any(|s| s == "tests" || s == "test");
+    if in_tests || file.contains("_test.") || file.contains(".test.") {
+        return Some("tests");
+    }
+    let ext = file
+        .rsplit_once('.')
+        .map(|(_, ext)| ext.to_ascii_lowercase())?;
+    match ext.as_str() {
+        "ipynb" => Some("notebooks"),
+        "pptx" | "key" | "odp" => Some("slides"),
+        "md" | "mdx" | "rst" | "adoc" | "docx" | "pdf" => Some("docs"),
+        "csv" | "tsv" | "parquet" | "jsonl" => Some("data"),
+        "toml" | "yaml" | "yml" | "tf" | "ini" | "cfg" => Some("configs"),
+        _ => None,
+    }
+}
+
+/// Render the skeleton spec.
+pub fn render_spec(
+    name: &str,
+    domain: Domain,
+    tier: Tier,
+    globs: &BTreeMap<&'static str, Vec<String>>,
+) -> String {
+    let mut out = String::new();
+    out.push_str("schema_version: '0.8'\n");
+    out.push_str(&format!("domain: {}\n", domain.as_str()));
+    out.push_str(&format!("tier: {}\n\n", tier.as_str()));
+    out.push_str(&format!(
+        "intent: {}\n",
+        quote(&format!(
+            "TODO: describe what {name} is for and why it exists"
+        ))
+    ));
+
+    match tier {
+        Tier::Full => {
+            out.push_str(
+                "\nbehaviors:\n  \
+                 - name: todo_behavior\n    \
+                 given: 'TODO: a precondition'\n    \
+                 then: 'TODO: the expected outcome'\n\n\
+                 constraints:\n  \
+                 - 'TODO: a rule the artifacts must follow'\n",
+            );
+            out.push_str(domain_blocks(domain));
+        }
+        Tier::Registered => {}
+        Tier::Derived => out.push_str(
+            "\npipeline:\n  \
+             steps:\n    \
+             - name: generate\n      \
+             command: 'TODO: the command that produces these outputs'\n",
+        ),
+    }
+
+    out.push_str("\nartifacts:\n");
+    for (category, paths) in globs {
+        out.push_str(&format!("  {category}:\n"));
+        for path in paths {
+            out.push_str(&format!("    - path: {}\n", quote(path)));
+        }
+    }
+    out
+}
+
+/// The optional blocks a full-tier spec of `domain` usually needs.
+fn domain_blocks(domain: Domain) -> &'static str {
+    match domain {
+        Domain::Software => "",
+        Domain::Presentation => {
+            "\noutput:\n  type: presentation\n  format: pptx\n\n\
+             audience:\n  role: 'TODO: who the deck is for'\n  tone: 'TODO: e.g. formal'\n\n\
+             content:\n  structure: ordered\n  sections:\n    \
+             - id: opening\n      intent: 'TODO: what this slide must convey'\n"
+        }
+        Domain::Report => {
+            "\noutput:\n  type: report\n  format: pdf\n\n\
+             audience:\n  role: 'TODO: who reads the report'\n\n\
+             content:\n  structure: hierarchical\n  sections:\n    \
+             - id: summary\n      intent: 'TODO: what this section must establish'\n"
+        }
+        Domain::Course | Domain::Education => {
+            "\noutput:\n  type: course\n\n\
+             audience:\n  role: 'TODO: who the learners are'\n  \
+             assumed_knowledge: 'TODO: what they already know'\n\n\
+             content:\n  structure: ordered\n  sections:\n    \
+             - id: module_1\n      intent: 'TODO: what learners can do after this module'\n"
+        }
+        Domain::Marketing => {
+            "\noutput:\n  type: campaign\n\n\
+             audience:\n  role: 'TODO: who the campaign targets'\n  tone: 'TODO: e.g. playful'\n\n\
+             feedback:\n  metrics:\n    \
+             - name: 'TODO: e.g. conversion_rate'\n"
+        }
+        Domain::Legal => {
+            "\ninvariants:\n  - 'TODO: a clause that must always hold'\n\n\
+             output:\n  type: document\n  format: pdf\n\n\
+             content:\n  structure: hierarchical\n  sections:\n    \
+             - id: definitions\n      intent: 'TODO: what this section must establish'\n\n\
+             compliance:\n  frameworks:\n    \
+             - name: 'TODO: e.g. GDPR'\n"
+        }
+        Domain::Infrastructure => {
+            "\ninvariants:\n  - 'TODO: a property the infrastructure must always keep'\n\n\
+             output:\n  type: infrastructure\n\n\
+             pipeline:\n  steps:\n    \
+             - name: plan\n      command: 'TODO: e.g. terraform plan'\n"
+        }
+        Domain::Research => {
+            "\nopen_questions:\n  - 'TODO: a question the research must answer'\n\n\
+             output:\n  type: report\n\n\
+             pipeline:\n  steps:\n    \
+             - name: analysis\n      command: 'TODO: the command that reproduces the results'\n"
+        }
+    }
+}
+
+fn quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "''"))
+}
+
+/// The spec with a `subsystems` key to register new specs in, preferring
+/// `.notarai/system.spec.yaml` (which is used even before it has
+/// subsystems). Specs that do not parse are skipped.
+pub fn system_spec_path(project_root: &Path) -> Result<Option<String>> {
+    let conventional = ".notarai/system.spec.yaml";
+    if project_root.join(conventional).is_file() {
+        return Ok(Some(conventional.to_string()));
+    }
+    for abs in crate::Project::open(project_root).spec_files()? {
+        let content = std::fs::read_to_string(&abs).map_err(|e| Error::io(&abs, e))?;
+        if Spec::parse(&content).is_ok_and(|spec| spec.is_system()) {
+            let rel = abs.strip_prefix(project_root).unwrap_or(&abs);
+            return Ok(Some(rel.to_string_lossy().to_string()));
+        }
+    }
+    Ok(None)
+}
+
+/// The `$ref` from the spec at `from` to the spec at `to` (both
+/// project-relative).
+pub fn relative_ref(from: &str, to: &str) -> String {
+    let from_dir: Vec<&str> = from.split('/').collect::<Vec<_>>();
+    let from_dir = &from_dir[..from_dir.len() - 1];
+    let to: Vec<&str> = to.split('/').collect();
+    let common = from_dir.iter().zip(&to).take_while(|(a, b)| a == b).count();
+    let mut parts: Vec<&str> = vec![".."; from_dir.len() - common];
+    if parts.is_empty() {
+        parts.push(".");
+    }
+    parts.extend(&to[common..]);
+    parts.join("/")
+}
+
+/// Add a `$ref` to `reference` to the `subsystems` list of a spec's source,
+/// keeping the rest of the file as written. Block lists get a new
+/// `- $ref:` item, single-line flow lists a new `{ $ref: }` element, and an
+/// empty or null value becomes a block list. Adds the key at the end when the
+/// spec has none.
+///
+/// Fails when the list is written in a form that cannot be edited in place,
+/// or when the result does not parse as a spec listing the new subsystem.
+fn register_subsystem(content: &str, reference: &str) -> Result<String, String> {
+    let out = insert_subsystem(content, reference)?;
+    let before = Spec::parse(content).map_or(0, |spec| spec.subsystems.len());
+    match Spec::parse(&out) {
+        Ok(spec)
+            if spec.subsystems.len() == before + 1
+                && spec
+                    .subsystems
+                    .last()
+                    .is_some_and(|r| r.reference == reference) =>
+        {
+            Ok(out)
+        }
+        Ok(_) => Err(format!(
+            "adding '{reference}' would not register it as a subsystem"
+        )),
+        Err(e) => Err(format!(
+            "adding '{reference}' would leave the spec unparseable: {e}"
+        )),
+    }
+}
+
+fn insert_subsystem(content: &str, reference: &str) -> Result<String, String> {
+    let lines: Vec<&str> = content.lines().collect();
+    let Some(key) = lines.iter().position(|l| {
+        l.strip_prefix("subsystems")
+            .is_some_and(|rest| rest.trim_start().starts_with(':'))
+    }) else {
+        let mut out = content.trim_end().to_string();
+        out.push_str(&format!(
+            "\n\nsubsystems:\n  - $ref: {}\n",
+            quote(reference)
+        ));
+        return Ok(out);
+    };
+
+    let mut out: Vec<String> = lines.iter().map(|l| l.to_string()).collect();
+    let line = lines[key];
+    let (code, comment) = split_comment(line);
+    let value = code[code.find(':').expect("key line has a colon") + 1..].trim();
+    match value {
+        "" => {
+            let (insert_at, indent) = block_end(&lines, key);
+            out.insert(insert_at, format!("{indent}- $ref: {}", quote(reference)));
+        }
+        "~" | "null" | "Null" | "NULL" => {
+            out[key] = format!("subsystems:{comment}");
+            out.insert(key + 1, format!("  - $ref: {}", quote(reference)));
+        }
+        flow if flow.starts_with('[') && flow.ends_with(']') => {
+            let items = flow[1..flow.len() - 1]
+                .trim()
+                .trim_end_matches(',')
+                .trim_end();
+            let entry = format!("{{ $ref: {} }}", quote(reference));
+            let list = if items.is_empty() {
+                format!("[{entry}]")
+            } else {
+                format!("[{items}, {entry}]")
+            };
+            out[key] = format!("subsystems: {list}{comment}");
+        }
+        other => {
+            return Err(format!(
+                "cannot add to subsystems written as '{other}'; add '{reference}' by hand"
+            ));
+        }
+    }
+    let mut out = out.join("\n");
+    out.push('\n');
+    Ok(out)
+}
+
+/// Split a line into its content and a trailing comment (with the whitespace
+/// before it), ignoring `#` inside quotes.
+fn split_comment(line: &str) -> (&str, &str) {
+    let mut quote: Option<char> = None;
+    let mut prev = ' ';
+    for (i, c) in line.char_indices() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '\'' || c == '"' => quote = Some(c),
+            None if c == '#' && prev.is_whitespace() => {
+                let start = line[..i].trim_end().len();
+                return (&line[..start], &line[start..]);
+            }
+            None => {}
+        }
+        prev = c;
+    }
+    (line, "")
+}
+
+/// Where a new item goes in the block list under the key at line `key`, and
+/// the indentation of its items. The block runs until the next line that
+/// starts a top-level key.
+fn block_end<'a>(lines: &[&'a str], key: usize) -> (usize, &'a str) {
+    let mut last_item = None;
+    let mut indent = "  ";
+    for (i, line) in lines.iter().enumerate().skip(key + 1) {
+        let trimmed = line.trim_start();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        if !line.starts_with([' ', '-']) {
+            break;
+        }
+        if trimmed.starts_with('-') {
+            indent = &line[..line.len() - trimmed.len()];
+        }
+        last_item = Some(i);
+    }
+    (last_item.unwrap_or(key) + 1, indent)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::validator::validate_spec;
+
+    #[test]
+    fn every_domain_and_tier_renders_a_valid_spec() {
+        let globs = BTreeMap::from([("code", vec!["src/x/**".to_string()])]);
+        for domain in Domain::ALL {
+            for tier in Tier::ALL {
+                let yaml = render_spec("x", domain, tier, &globs);
+                let result = validate_spec(&yaml);
+                assert!(
+                    result.valid,
+                    "{domain:?}/{tier:?}: {:?}\n{yaml}",
+                    result.errors
+                );
+                let spec = Spec::parse(&yaml).unwrap();
+                assert_eq!((spec.domain(), spec.tier()), (domain, tier));
+            }
+        }
+    }
+
+    #[test]
+    fn groups_paths_by_category() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir_all(tmp.path().join("src/auth")).unwrap();
+        let paths = [
+            "./src/auth/".to_string(),
+            "tests/auth_test.rs".to_string(),
+            "docs/*.md".to_string(),
+        ];
+        let globs = artifact_globs(&paths, Domain::Software, tmp.path(), "auth");
+        assert_eq!(globs["code"], ["src/auth/**"]);
+        assert_eq!(globs["tests"], ["tests/auth_test.rs"]);
+        assert_eq!(globs["docs"], ["docs/*.md"]);
+
+        let globs = artifact_globs(&[], Domain::Presentation, tmp.path(), "deck");
+        assert_eq!(globs["slides"], ["deck/**"]);
+    }
+
+    #[test]
+    fn rejects_unsafe_names() {
+        assert_eq!(
+            spec_path_for("api/auth").unwrap(),
+            ".notarai/api/auth.spec.yaml"
+        );
+        assert_eq!(
+            spec_path_for("auth.spec.yaml").unwrap(),
+            ".notarai/auth.spec.yaml"
+        );
+        for name in ["", "../x", "a//b", "/abs", "has space"] {
+            assert!(spec_path_for(name).is_err(), "{name}");
+        }
+    }
+
+    #[test]
+    fn relative_refs() {
+        let sys = ".notarai/system.spec.yaml";
+        assert_eq!(
+            relative_ref(sys, ".notarai/auth.spec.yaml"),
+            "./auth.spec.yaml"
+        );
+        assert_eq!(
+            relative_ref(sys, ".notarai/api/auth.spec.yaml"),
+            "./api/auth.spec.yaml"
+        );
+        assert_eq!(
+            relative_ref(".notarai/sys/system.spec.yaml", ".notarai/auth.spec.yaml"),
+            "../auth.spec.yaml"
+        );
+    }
+
+    #[test]
+    fn appends_to_existing_subsystems_block() {
+        let content = "intent: 'x'\n\nsubsystems:\n  - $ref: './a.spec.yaml'\n  \
+                       - $ref: './b.spec.yaml'\n\napplies:\n  - $ref: './s.spec.yaml'\n";
+        let out = register_subsystem(content, "./c.spec.yaml").unwrap();
+        assert_eq!(
+            out,
+            "intent: 'x'\n\nsubsystems:\n  - $ref: './a.spec.yaml'\n  \
+             - $ref: './b.spec.yaml'\n  - $ref: './c.spec.yaml'\n\n\
+             applies:\n  - $ref: './s.spec.yaml'\n"
+        );
+    }
+
+    #[test]
+    fn adds_subsystems_key_when_missing() {
+        let out = register_subsystem("intent: 'x'\n", "./c.spec.yaml").unwrap();
+        assert_eq!(
+            out,
+            "intent: 'x'\n\nsubsystems:\n  - $ref: './c.spec.yaml'\n"
+        );
+    }
+
+    #[test]
+    fn appends_to_flow_and_empty_subsystems() {
+        let out = register_subsystem("intent: 'x'\nsubsystems: []  # none yet\n", "./c.spec.yaml")
+            .unwrap();
+        assert_eq!(
+            out,
+            "intent: 'x'\nsubsystems: [{ $ref: './c.spec.yaml' }]  # none yet\n"
+        );
+
+        let out = register_subsystem(
+            "intent: 'x'\nsubsystems: [{ $ref: './a.spec.yaml' }]\n",
+            "./c.spec.yaml",
+        )
+        .unwrap();
+        assert_eq!(
+            out,
+            "intent: 'x'\nsubsystems: [{ $ref: './a.spec.yaml' }, { $ref: './c.spec.yaml' }]\n"
+        );
+
+        let out = register_subsystem("intent: 'x'\nsubsystems: ~\n", "./c.spec.yaml").unwrap();
+        assert_eq!(out, "intent: 'x'\nsubsystems:\n  - $ref: './c.spec.yaml'\n");
+    }
+
+    #[test]
+    fn refuses_edits_it_cannot_make_safely() {
+        let multiline = "intent: 'x'\nsubsystems: [\n  { $ref: './a.spec.yaml' }\n]\n";
+        let err = register_subsystem(multiline, "./c.spec.yaml").unwrap_err();
+        assert!(err.contains("add './c.spec.yaml' by hand"), "{err}");
+
+        let duplicate = "intent: 'x'\nsubsystems:\nsubsystems:\n  - $ref: './a.spec.yaml'\n";
+        let err = register_subsystem(duplicate, "./c.spec.yaml").unwrap_err();
+        assert!(err.contains("unparseable"), "{err}");
+    }
+}
@@ -0,0 +1,890 @@
+//! Typed data model for NotarAI spec files.
+//!
+//! Mirrors the bundled JSON Schema (`notarai.spec.json`). Specs are parsed
+//! from YAML through `yaml::parse_yaml`, so a value with the wrong shape (an
+//! `artifacts` list instead of a map, a `path` that is not a string) is a
+//! parse error rather than being skipped.
+//!
+//! Parsing is not validation: required fields that tools can do without
+//! (`schema_version`, `intent`) default to empty, and `validator` remains the
+//! authority on whether a spec conforms. Fields this version does not know
+//! are kept in each struct's `extra` map and written back on serialization,
+//! so a spec from a newer schema round-trips unchanged.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+/// Fields not described by this version of the model.
+pub type Extra = Map<String, Value>;
+
+/// A parsed `*.spec.yaml` file.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Spec {
+    #[serde(default)]
+    pub schema_version: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub domain: Option<Domain>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tier: Option<Tier>,
+    #[serde(default)]
+    pub intent: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub behaviors: Vec<Behavior>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub constraints: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub invariants: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub decisions: Vec<Decision>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub open_questions: Vec<String>,
+    /// Artifact category (`code`, `docs`, ... or any custom name) to the
+    /// globs it covers, ordered by category name.
+    #[serde(default)]
+    pub artifacts: BTreeMap<String, Vec<ArtifactRef>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub subsystems: Vec<SpecRef>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub applies: Vec<SpecRef>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dependencies: Vec<DependencyRef>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub coverage: Option<Coverage>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<Output>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<Content>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub states: Option<States>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub design: Option<Design>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audience: Option<Audience>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub variants: Vec<Variant>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub variants_resolved: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pipeline: Option<Pipeline>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub feedback: Option<Feedback>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compliance: Option<Compliance>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+impl Spec {
+    /// Parse a spec from YAML source.
+    pub fn parse(content: &str) -> crate::Result<Spec> {
+        let value = crate::core::yaml::parse_yaml(content)?;
+        Spec::from_value(value)
+    }
+
+    /// Build a spec from an already parsed JSON value.
+    pub fn from_value(value: Value) -> crate::Result<Spec> {
+        serde_json::from_value(value).map_err(crate::Error::model("spec"))
+    }
+
+    /// The declared tier, or the schema default (`full`).
+    pub fn tier(&self) -> Tier {
+        self.tier.unwrap_or_default()
+    }
+
+    /// The declared domain, or the schema default (`software`).
+    pub fn domain(&self) -> Domain {
+        self.domain.unwrap_or_default()
+    }
+
+    /// True for the top-level system spec, which lists `subsystems`.
+    pub fn is_system(&self) -> bool {
+        !self.subsystems.is_empty()
+    }
+
+    /// Every artifact reference with its category, ordered by category.
+    pub fn artifact_refs(&self) -> impl Iterator<Item = (&str, &ArtifactRef)> {
+        self.artifacts
+            .iter()
+            .flat_map(|(category, refs)| refs.iter().map(move |r| (category.as_str(), r)))
+    }
+}
+
+/// Test fixture: a spec with `yaml` appended to the required fields.
+#[cfg(test)]
+pub(crate) fn test_spec(yaml: &str) -> Spec {
+    Spec::parse(&format!("schema_version: '0.7'\nintent: 'x'\n{yaml}")).unwrap()
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Domain {
+    #[default]
+    Software,
+    Presentation,
+    Report,
+    Course,
+    Marketing,
+    Legal,
+    Education,
+    Infrastructure,
+    Research,
+}
+
+impl Domain {
+    pub const ALL: [Domain; 9] = [
+        Domain::Software,
+        Domain::Presentation,
+        Domain::Report,
+        Domain::Course,
+        Domain::Marketing,
+        Domain::Legal,
+        Domain::Education,
+        Domain::Infrastructure,
+        Domain::Research,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Domain::Software => "software",
+            Domain::Presentation => "presentation",
+            Domain::Report => "report",
+            Domain::Course => "course",
+            Domain::Marketing => "marketing",
+            Domain::Legal => "legal",
+            Domain::Education => "education",
+            Domain::Infrastructure => "infrastructure",
+            Domain::Research => "research",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Tier {
+    /// Behaviors and constraints.
+    #[default]
+    Full,
+    /// Intent and artifact mappings only.
+    Registered,
+    /// Generated outputs tracked for staleness.
+    Derived,
+}
+
+impl Tier {
+    pub const ALL: [Tier; 3] = [Tier::Full, Tier::Registered, Tier::Derived];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Tier::Full => "full",
+            Tier::Registered => "registered",
+            Tier::Derived => "derived",
+        }
+    }
+}
+
+impl std::str::FromStr for Domain {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> crate::Result<Domain> {
+        Domain::ALL
+            .into_iter()
+            .find(|d| d.as_str() == s)
+            .ok_or_else(|| crate::Error::UnknownValue {
+                what: "domain",
+                value: s.to_string(),
+                expected: Domain::ALL.map(Domain::as_str).join(", "),
+            })
+    }
+}
+
+impl std::str::FromStr for Tier {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> crate::Result<Tier> {
+        Tier::ALL
+            .into_iter()
+            .find(|t| t.as_str() == s)
+            .ok_or_else(|| crate::Error::UnknownValue {
+                what: "tier",
+                value: s.to_string(),
+                expected: Tier::ALL.map(Tier::as_str).join(", "),
+            })
+    }
+}
+
+/// A Given/Then behavior.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Behavior {
+    pub name: String,
+    pub given: String,
+    pub then: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub interaction: Option<Interaction>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state_transition: Option<StateTransition>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Interaction {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trigger: Option<InteractionTrigger>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sequence: Vec<String>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InteractionTrigger {
+    UserAction,
+    Timer,
+    SystemEvent,
+    DataChange,
+    Schedule,
+    ExternalSignal,
+    Threshold,
+    Manual,
+    Lifecycle,
+}
+
+/// The state machine edge a behavior exercises.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StateTransition {
+    pub from: String,
+    pub to: String,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Decision {
+    pub date: String,
+    pub choice: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rationale: Option<String>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+/// A glob of governed files.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ArtifactRef {
+    pub path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub role: Option<String>,
+    /// For generated artifacts: the input they are produced from.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tier: Option<u8>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+/// A `$ref` to another spec file, relative to the referencing spec.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SpecRef {
+    #[serde(rename = "$ref")]
+    pub reference: String,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DependencyRef {
+    #[serde(rename = "$ref")]
+    pub reference: String,
+    pub relationship: String,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Coverage {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub policy: Option<CoveragePolicy>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CoveragePolicy {
+    #[default]
+    Warn,
+    Strict,
+    Off,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Output {
+    #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+    pub output_type: Option<OutputType>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub runtime: Option<Runtime>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entry_point: Option<String>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OutputType {
+    App,
+    Presentation,
+    InteractiveDoc,
+    Game,
+    Dashboard,
+    Report,
+    Library,
+    Service,
+    Document,
+    Course,
+    Api,
+    Infrastructure,
+    Dataset,
+    DesignSystem,
+    Campaign,
+    Template,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Runtime {
+    Browser,
+    Native,
+    StaticFile,
+    Embedded,
+    Server,
+}
+
+/// The output's structure in content terms (slides, scenes, sections).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Content {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub structure: Option<ContentStructure>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sections: Vec<ContentSection>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+impl Content {
+    /// The declared structure, or the schema default (`ordered`).
+    pub fn structure(&self) -> ContentStructure {
+        self.structure.unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ContentStructure {
+    #[default]
+    Ordered,
+    Hierarchical,
+    Graph,
+    FreeForm,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ContentSection {
+    pub id: String,
+    /// Free-form section kind (`slide`, `chapter`, `scene`, ...).
+    #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+    pub section_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub intent: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_ref: Option<String>,
+    /// Artifact category to the paths that realize this section.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub artifacts: BTreeMap<String, Vec<String>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub connections: Vec<Connection>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration: Option<Duration>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub depends_on: Vec<SectionDependency>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<i64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub evidence: Vec<Evidence>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+/// An edge to another section, for `graph` structures.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Connection {
+    pub to: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub condition: Option<String>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Duration {
+    pub value: f64,
+    pub unit: String,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SectionDependency {
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub relationship: Option<String>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+/// Support for a claim made in a section.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Evidence {
+    #[serde(rename = "type")]
+    pub evidence_type: EvidenceType,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    #[serde(rename = "ref", default, skip_serializing_if = "Option::is_none")]
+    pub reference: Option<String>,
+    pub claim: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub relationship: Option<String>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EvidenceType {
+    Data,
+    Citation,
+    Reference,
+}
+
+/// A state machine for interactive artifacts.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct States {
+    pub initial: String,
+    pub definitions: Vec<StateDefinition>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StateDefinition {
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub transitions: Vec<TransitionRule>,
+    /// A final state, which is expected to have no outgoing transitions.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub terminal: bool,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TransitionRule {
+    pub to: String,
+    pub on: String,
+    /// Natural-language condition; interpreted during reconciliation, never
+    /// evaluated.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub guard: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub action: Option<String>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Design {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub style_guide: Vec<SpecRef>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub theme: Option<Theme>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layout: Option<Layout>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub assets: Vec<ArtifactRef>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub print: Option<Print>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub responsive: Option<Responsive>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Theme {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub palette: Vec<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub typography: BTreeMap<String, String>,
+    /// Named color modes (`dark`, `high-contrast`, ...).
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub modes: BTreeMap<String, ThemeMode>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ThemeMode {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub palette: Vec<String>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Layout {
+    #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+    pub layout_type: Option<LayoutType>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dimensions: Option<String>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LayoutType {
+    SlideDeck,
+    Scrolling,
+    Spatial,
+    Grid,
+    FreeForm,
+    Paginated,
+    Canvas,
+    Timeline,
+    Tabbed,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Print {
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub margins: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub headers: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub footers: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub page_numbers: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bleed: Option<String>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Responsive {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub breakpoints: Vec<Breakpoint>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Breakpoint {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_width: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_width: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layout_override: Option<String>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Audience {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub role: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub assumed_knowledge: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tone: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub locale: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub accessibility: Vec<String>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+/// A version of the artifact that overrides parts of the base spec.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Variant {
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Dot-path (`audience.role`) to replacement value.
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    pub overrides: Map<String, Value>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Pipeline {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub steps: Vec<PipelineStep>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preview: Option<Preview>,
+    /// Environment shared by every step.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PipelineStep {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub condition: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_failure: Option<OnFailure>,
+    /// Names of steps that must run first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub depends_on: Vec<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OnFailure {
+    Skip,
+    Abort,
+    Retry,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Preview {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Feedback {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub metrics: Vec<FeedbackMetric>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reconciliation_trigger: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub triggers: Vec<FeedbackTrigger>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FeedbackMetric {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    /// Natural-language expected value (`>= 0.7`, `< 5s`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub threshold: Option<String>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FeedbackTrigger {
+    pub condition: TriggerCondition,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub action: Option<TriggerAction>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<Priority>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TriggerCondition {
+    pub metric: String,
+    pub operator: TriggerOperator,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration: Option<Duration>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TriggerOperator {
+    BelowThreshold,
+    AboveThreshold,
+    Changed,
+    Stale,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TriggerAction {
+    Reconcile,
+    Warn,
+    Notify,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    Low,
+    Medium,
+    High,
+}
+
+/// Regulatory or standards frameworks the spec's rules satisfy.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Compliance {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub frameworks: Vec<Framework>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audit_trail: Option<bool>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Framework {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub level: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub controls: Vec<Control>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub satisfied_by: Option<SatisfiedBy>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Control {
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub satisfied_by: Option<SatisfiedBy>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+/// Invariants and constraints (by exact text) that satisfy a framework or
+/// control.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SatisfiedBy {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub invariants: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub constraints: Vec<String>,
+    #[serde(flatten)]
+    pub extra: Extra,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SPEC: &str = r#"
+schema_version: '0.7'
+intent: 'Deck'
+domain: presentation
+behaviors:
+  - name: advance
+    given: 'a slide is shown'
+    then: 'the next one follows'
+    state_transition: { from: intro, to: demo }
+artifacts:
+  slides:
+    - path: 'slides/*.md'
+      role: 'deck'
+  code:
+    - path: 'src/**'
+content:
+  structure: graph
+  sections:
+    - id: intro
+      connections: [{ to: demo }]
+      evidence:
+        - { type: citation, ref: 'https://example.com', claim: 'Fast' }
+pipeline:
+  steps:
+    - { name: build, command: 'make', on_failure: retry, depends_on: [fetch] }
+"#;
+
+    #[test]
+    fn parses_typed_fields() {
+        let spec = Spec::parse(SPEC).unwrap();
+        assert_eq!(spec.domain(), Domain::Presentation);
+        assert_eq!(spec.tier(), Tier::Full);
+        assert_eq!(
+            spec.behaviors[0].state_transition.as_ref().unwrap().to,
+            "demo"
+        );
+        let refs: Vec<_> = spec.artifact_refs().map(|(c, r)| (c, &*r.path)).collect();
+        assert_eq!(refs, [("code", "src/**"), ("slides", "slides/*.md")]);
+        let content = spec.content.as_ref().unwrap();
+        assert_eq!(content.structure, Some(ContentStructure::Graph));
+        let evidence = &content.sections[0].evidence[0];
+        assert_eq!(evidence.evidence_type, EvidenceType::Citation);
+        assert_eq!(evidence.reference.as_deref(), Some("https://example.com"));
+        let step = &spec.pipeline.as_ref().unwrap().steps[0];
+        assert_eq!(step.on_failure, Some(OnFailure::Retry));
+        assert_eq!(step.depends_on, ["fetch"]);
+    }
+
+    #[test]
+    fn unknown_fields_round_trip() {
+        let yaml = "schema_version: '0.9'\nintent: 'x'\nfuture_block: { a: 1 }\n\
+                    artifacts:\n  code:\n    - path: 'a.rs'\n      weight: 2\n";
+        let spec = Spec::parse(yaml).unwrap();
+        assert_eq!(spec.extra["future_block"]["a"], 1);
+        assert_eq!(spec.artifacts["code"][0].extra["weight"], 2);
+
+        let original = crate::core::yaml::parse_yaml(yaml).unwrap();
+        assert_eq!(serde_json::to_value(&spec).unwrap(), original);
+    }
+
+    #[test]
+    fn malformed_shapes_are_errors() {
+        let err = Spec::parse("intent: 'x'\nartifacts:\n  - path: 'a.rs'\n").unwrap_err();
+        assert!(
+            matches!(err, crate::Error::Model { what: "spec", .. }),
+            "{err}"
+        );
+        assert!(Spec::parse("artifacts:\n  code:\n    - path: 3\n").is_err());
+        assert!(Spec::parse("tier: partial\n").is_err());
+    }
+}
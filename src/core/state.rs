@@ -1,29 +1,142 @@
+//! The reconciliation state file, `.notarai/reconciliation_state.json`.
+//!
+//! Unlike the cache, the state file is meant to be committed: it gives
+//! collaborators and CI a baseline of what was last reconciled. Schema 2
+//! records, per spec, when it was last reconciled, at which commit, and the
+//! fingerprints of the artifacts checked. Schema 1 files are migrated when
+//! loaded and written back as schema 2 by the next snapshot.
+
+use crate::core::spec::Spec;
+use crate::error::{Error, Result};
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
-use std::time::SystemTime;
+
+/// Schema version written by this CLI.
+pub const STATE_SCHEMA_VERSION: &str = "2";
 
 /// On-disk reconciliation state, committed to the repo.
 #[derive(Debug, Serialize, Deserialize, PartialEq)]
 pub struct ReconciliationState {
-    pub schema_version: String, // always "1"
+    pub schema_version: String, // always STATE_SCHEMA_VERSION once loaded
     #[serde(skip_serializing_if = "Option::is_none", default)]
     pub cli_version: Option<String>,
+    /// The most recent snapshot.
     pub last_reconciliation: ReconciliationMeta,
-    pub file_fingerprints: BTreeMap<String, FileFingerprint>,
-    pub spec_fingerprints: BTreeMap<String, String>,
+    pub specs: BTreeMap<String, SpecState>,
+    /// Fingerprints migrated from a schema 1 file, which did not record which
+    /// spec checked each file. Entries are dropped once a spec records the file.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty", default)]
+    pub unattributed_fingerprints: BTreeMap<String, FileFingerprint>,
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct ReconciliationMeta {
-    pub timestamp: String,        // ISO 8601 (Unix epoch seconds)
+    pub timestamp: String,        // ISO 8601 UTC
     pub git_hash: Option<String>, // HEAD at reconciliation time
     pub branch: Option<String>,   // branch name at reconciliation time
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+/// What was last reconciled for one spec.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SpecState {
+    /// Fingerprint of the spec file (see `cache::spec_fingerprint`).
+    pub fingerprint: String,
+    /// When `mark_reconciled` last recorded the spec, ISO 8601 UTC.
+    pub reconciled_at: String,
+    /// HEAD at the snapshot that recorded this reconciliation.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub git_hash: Option<String>,
+    /// Artifacts checked against this revision of the spec.
+    #[serde(default)]
+    pub artifacts: BTreeMap<String, FileFingerprint>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct FileFingerprint {
     pub blake3: String,
+    /// Normalizer the hash was computed with (see `core::normalize`); absent
+    /// means `raw`.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub normalizer: Option<String>,
+}
+
+/// Schema 1 layout: one global timestamp and flat fingerprint maps.
+#[derive(Deserialize)]
+struct StateV1 {
+    #[serde(default)]
+    cli_version: Option<String>,
+    last_reconciliation: ReconciliationMeta,
+    file_fingerprints: BTreeMap<String, FileFingerprint>,
+    spec_fingerprints: BTreeMap<String, String>,
+}
+
+impl ReconciliationState {
+    /// Fingerprint of every artifact in the state. A file checked by several
+    /// specs takes its fingerprint from the most recently reconciled one;
+    /// unattributed fingerprints are used only for files no spec recorded.
+    pub fn artifact_fingerprints(&self) -> BTreeMap<&str, &FileFingerprint> {
+        let mut specs: Vec<&SpecState> = self.specs.values().collect();
+        specs.sort_by(|a, b| a.reconciled_at.cmp(&b.reconciled_at));
+        let mut out: BTreeMap<&str, &FileFingerprint> = self
+            .unattributed_fingerprints
+            .iter()
+            .map(|(p, fp)| (p.as_str(), fp))
+            .collect();
+        for spec in specs {
+            out.extend(spec.artifacts.iter().map(|(p, fp)| (p.as_str(), fp)));
+        }
+        out
+    }
+}
+
+/// Upgrade a schema 1 state. Every spec is given the global timestamp and
+/// commit, which is all schema 1 recorded.
+///
+/// Schema 1 fingerprinted specs by their raw bytes. A spec under
+/// `project_root` whose raw hash still matches gets its fingerprint rewritten
+/// to `cache::spec_fingerprint`, so an untouched spec is not reported as
+/// changed; any other fingerprint is kept and compares as changed.
+fn migrate_v1(v1: StateV1, project_root: &Path) -> ReconciliationState {
+    let meta = ReconciliationMeta {
+        timestamp: iso_timestamp(&v1.last_reconciliation.timestamp),
+        ..v1.last_reconciliation
+    };
+    let specs = v1
+        .spec_fingerprints
+        .into_iter()
+        .map(|(path, fingerprint)| {
+            let fingerprint = match std::fs::read(project_root.join(&path)) {
+                Ok(content) if crate::core::cache::hash_bytes(&content) == fingerprint => {
+                    crate::core::cache::spec_fingerprint(&content)
+                }
+                _ => fingerprint,
+            };
+            let spec = SpecState {
+                fingerprint,
+                reconciled_at: meta.timestamp.clone(),
+                git_hash: meta.git_hash.clone(),
+                artifacts: BTreeMap::new(),
+            };
+            (path, spec)
+        })
+        .collect();
+    ReconciliationState {
+        schema_version: STATE_SCHEMA_VERSION.to_string(),
+        cli_version: v1.cli_version,
+        last_reconciliation: meta,
+        specs,
+        unattributed_fingerprints: v1.file_fingerprints,
+    }
+}
+
+/// Schema 1 wrote epoch seconds with a `Z` suffix (`1700000000Z`). Convert
+/// those to ISO 8601 and leave anything else as is.
+fn iso_timestamp(timestamp: &str) -> String {
+    match timestamp.strip_suffix('Z').map(str::parse::<i64>) {
+        Some(Ok(secs)) => crate::core::time::format_utc(secs),
+        _ => timestamp.to_string(),
+    }
 }
 
 /// Canonical path: `<root>/.notarai/reconciliation_state.json`
@@ -34,73 +147,247 @@ pub fn state_path(project_root: &Path) -> PathBuf {
 }
 
 /// Load state from disk. Returns `None` if the file doesn't exist.
-/// Returns `Err` on read/parse failure.
-pub fn load_state(project_root: &Path) -> Result<Option<ReconciliationState>, String> {
+/// Returns `Err` on read/parse failure or an unsupported schema version.
+pub fn load_state(project_root: &Path) -> Result<Option<ReconciliationState>> {
     let path = state_path(project_root);
     if !path.exists() {
         return Ok(None);
     }
-    let content =
-        std::fs::read_to_string(&path).map_err(|e| format!("could not read state file: {e}"))?;
-    let state: ReconciliationState =
-        serde_json::from_str(&content).map_err(|e| format!("could not parse state file: {e}"))?;
-    Ok(Some(state))
+    let content = std::fs::read_to_string(&path).map_err(|e| Error::io(&path, e))?;
+    parse_state(&content, project_root).map(Some)
+}
+
+/// Parse a state file of any supported schema version, migrating it to the
+/// current one. Spec fingerprints of a schema 1 file are checked against the
+/// specs under `project_root` (see `migrate_v1`).
+pub fn parse_state(content: &str, project_root: &Path) -> Result<ReconciliationState> {
+    let parse_err = |e: serde_json::Error| Error::InvalidState(e.to_string());
+    let value: serde_json::Value = serde_json::from_str(content).map_err(parse_err)?;
+    match value.get("schema_version").and_then(|v| v.as_str()) {
+        Some("1") => Ok(migrate_v1(
+            serde_json::from_value(value).map_err(parse_err)?,
+            project_root,
+        )),
+        Some(STATE_SCHEMA_VERSION) => serde_json::from_value(value).map_err(parse_err),
+        Some(other) => Err(Error::UnsupportedVersion {
+            what: "state file",
+            found: other.to_string(),
+            supported: STATE_SCHEMA_VERSION.to_string(),
+        }),
+        None => Err(Error::InvalidState("missing schema_version".to_string())),
+    }
 }
 
 /// Write state to disk (pretty-printed JSON for diffability).
-pub fn save_state(project_root: &Path, state: &ReconciliationState) -> Result<(), String> {
-    let path = state_path(project_root);
+///
+/// The JSON is written to a temporary file in the same directory and renamed
+/// over the state file, so readers never see a partially written state.
+pub fn save_state(project_root: &Path, state: &ReconciliationState) -> Result<()> {
+    write_state(&state_path(project_root), state)
+}
+
+/// Atomically write `state` to `path` (see `save_state`).
+pub fn write_state(path: &Path, state: &ReconciliationState) -> Result<()> {
     if let Some(parent) = path.parent() {
-        std::fs::create_dir_all(parent)
-            .map_err(|e| format!("could not create .notarai directory: {e}"))?;
+        std::fs::create_dir_all(parent).map_err(|e| Error::io(parent, e))?;
     }
-    let mut json = serde_json::to_string_pretty(state)
-        .map_err(|e| format!("could not serialize state: {e}"))?;
+    let mut json = serde_json::to_string_pretty(state).expect("state serializes to JSON");
     json.push('\n');
-    std::fs::write(&path, json).map_err(|e| format!("could not write state file: {e}"))?;
-    Ok(())
+    let tmp = path.with_extension(format!("json.tmp.{}", std::process::id()));
+    std::fs::write(&tmp, json).map_err(|e| Error::io(&tmp, e))?;
+    std::fs::rename(&tmp, path).map_err(|e| {
+        let _ = std::fs::remove_file(&tmp);
+        Error::io(path, e)
+    })
 }
 
 /// Build a `ReconciliationState` from the current SQLite cache and git context.
 ///
-/// - Reads all (path, blake3_hash) rows from the cache.
-/// - Partitions: `.spec.yaml` paths go to `spec_fingerprints`, everything else
-///   to `file_fingerprints`.
-/// - Captures git HEAD hash and current branch.
-pub fn snapshot_from_cache(project_root: &Path) -> Result<ReconciliationState, String> {
+/// Starts from the existing state file, so specs that were not reconciled
+/// since it was written keep their timestamp and commit. A spec is replaced by
+/// its cache records when they were marked after its `reconciled_at`. Specs
+/// whose file no longer exists are dropped.
+///
+/// A state file that cannot be loaded (unparseable, or written by a newer
+/// notarai) is an error, since writing the snapshot would discard it. Pass
+/// `force` to start from scratch instead.
+pub fn snapshot_from_cache(project_root: &Path, force: bool) -> Result<ReconciliationState> {
+    let previous = match load_state(project_root) {
+        Ok(previous) => previous,
+        Err(_) if force => None,
+        Err(e) => return Err(e),
+    };
     let conn = crate::core::cache::open_cache_db(project_root)?;
-    let rows = crate::core::cache::read_all(&conn)?;
+    let cached = crate::core::cache::read_specs(&conn)?;
 
-    let mut file_fingerprints = BTreeMap::new();
-    let mut spec_fingerprints = BTreeMap::new();
+    let (mut specs, mut unattributed) = match previous {
+        Some(state) => (state.specs, state.unattributed_fingerprints),
+        None => (BTreeMap::new(), BTreeMap::new()),
+    };
 
-    for (path, hash) in rows {
-        if path.ends_with(".spec.yaml") {
-            spec_fingerprints.insert(path, hash);
-        } else {
-            file_fingerprints.insert(path, FileFingerprint { blake3: hash });
+    let git_hash = git_head(project_root);
+    let branch = git_branch(project_root);
+
+    for record in cached {
+        let reconciled_at = crate::core::time::format_utc(record.marked_at);
+        if specs
+            .get(&record.spec_path)
+            .is_some_and(|s: &SpecState| s.reconciled_at >= reconciled_at)
+        {
+            continue;
         }
+        let artifacts = record
+            .files
+            .into_iter()
+            .map(|f| {
+                let normalizer = (f.normalizer != crate::core::normalize::Normalizer::Raw.name())
+                    .then_some(f.normalizer);
+                let fp = FileFingerprint {
+                    blake3: f.hash,
+                    normalizer,
+                };
+                (f.path, fp)
+            })
+            .collect();
+        let spec = SpecState {
+            fingerprint: record.fingerprint,
+            reconciled_at,
+            git_hash: git_hash.clone(),
+            artifacts,
+        };
+        specs.insert(record.spec_path, spec);
     }
 
-    let git_hash = git_head(project_root);
-    let branch = git_branch(project_root);
+    specs.retain(|path, _| project_root.join(path).exists());
+    unattributed.retain(|path, _| {
+        project_root.join(path).exists() && !specs.values().any(|s| s.artifacts.contains_key(path))
+    });
 
     Ok(ReconciliationState {
-        schema_version: "1".to_string(),
+        schema_version: STATE_SCHEMA_VERSION.to_string(),
         cli_version: Some(env!("CARGO_PKG_VERSION").to_string()),
         last_reconciliation: ReconciliationMeta {
-            timestamp: utc_timestamp(),
+            timestamp: crate::core::time::format_utc(crate::core::time::now_secs()),
             git_hash,
             branch,
         },
-        file_fingerprints,
-        spec_fingerprints,
+        specs,
+        unattributed_fingerprints: unattributed,
     })
 }
 
+/// Outcome of `restore_cache`.
+#[derive(Debug, Default)]
+pub struct RestoreSummary {
+    /// Specs whose records were seeded.
+    pub specs: usize,
+    /// File records seeded.
+    pub files: usize,
+    /// Specs skipped because the local cache already has records for them.
+    pub kept_specs: usize,
+    /// Specs skipped because the spec file changed or was removed.
+    pub stale_specs: usize,
+    /// Files skipped because their content no longer matches the state.
+    pub stale_files: usize,
+}
+
+/// Seed the cache with the reconciliation records in `state`.
+///
+/// Only records that still hold are restored: a spec whose file no longer has
+/// the recorded fingerprint is skipped entirely, and each artifact is rehashed
+/// with its recorded normalizer and restored only if the hash matches.
+/// Unattributed fingerprints (from a schema 1 file) are restored for every
+/// spec that governs the file. Specs the cache already has records for are
+/// left alone. Restored specs keep their `reconciled_at` as their mark time,
+/// so the next snapshot does not treat them as reconciled again.
+pub fn restore_cache(
+    project_root: &Path,
+    conn: &rusqlite::Connection,
+    state: &ReconciliationState,
+) -> Result<RestoreSummary> {
+    use crate::core::cache;
+    use crate::core::normalize::Normalizer;
+
+    let cached: std::collections::BTreeSet<String> = cache::read_specs(conn)?
+        .into_iter()
+        .map(|s| s.spec_path)
+        .collect();
+    let mut summary = RestoreSummary::default();
+
+    for (spec_path, spec) in &state.specs {
+        if cached.contains(spec_path) {
+            summary.kept_specs += 1;
+            continue;
+        }
+        let content = match std::fs::read(project_root.join(spec_path)) {
+            Ok(c) if cache::spec_fingerprint(&c) == spec.fingerprint => c,
+            _ => {
+                summary.stale_specs += 1;
+                continue;
+            }
+        };
+
+        let mut candidates: Vec<(&String, &FileFingerprint)> = spec.artifacts.iter().collect();
+        let parsed = std::str::from_utf8(&content)
+            .ok()
+            .and_then(|c| crate::core::spec::Spec::parse(c).ok());
+        if let Some(value) = &parsed {
+            candidates.extend(state.unattributed_fingerprints.iter().filter(|(path, _)| {
+                !spec.artifacts.contains_key(*path)
+                    && crate::core::mcp_tools::is_spec_affected(value, std::slice::from_ref(*path))
+            }));
+        }
+
+        let considered = candidates.len();
+        // A normalizer this version does not know cannot reproduce the hash.
+        let candidates: Vec<(&String, &FileFingerprint, Normalizer, PathBuf)> = candidates
+            .into_iter()
+            .filter_map(|(path, fp)| {
+                let normalizer = match fp.normalizer.as_deref() {
+                    Some(name) => Normalizer::from_name(name)?,
+                    None => Normalizer::Raw,
+                };
+                Some((path, fp, normalizer, project_root.join(path)))
+            })
+            .collect();
+        // Stat before hashing, as `mark_reconciled` does.
+        let stats: Vec<Option<cache::FileStat>> = candidates
+            .iter()
+            .map(|(_, _, _, abs)| cache::stat_file(abs))
+            .collect();
+        let to_hash: Vec<(&Path, Normalizer)> = candidates
+            .iter()
+            .map(|(_, _, n, abs)| (abs.as_path(), *n))
+            .collect();
+        let hashes = cache::hash_files(&to_hash);
+        let records: Vec<cache::FileRecord> = candidates
+            .iter()
+            .zip(stats)
+            .zip(hashes)
+            .filter_map(|(((path, fp, normalizer, _), stat), hash)| {
+                let hash = hash.ok().filter(|h| *h == fp.blake3)?;
+                Some(cache::FileRecord {
+                    path: (*path).clone(),
+                    hash,
+                    stat,
+                    normalizer: *normalizer,
+                })
+            })
+            .collect();
+        summary.stale_files += considered - records.len();
+
+        let marked_at = crate::core::time::parse_utc(&spec.reconciled_at)
+            .unwrap_or_else(crate::core::time::now_secs);
+        summary.files +=
+            cache::seed_batch(conn, spec_path, &spec.fingerprint, &records, marked_at)?;
+        summary.specs += 1;
+    }
+    Ok(summary)
+}
+
 /// Compare current file hashes against a stored state.
 /// Returns lists of added/modified/removed/spec_changed/spec_unchanged paths.
-#[allow(dead_code)]
+#[derive(Debug)]
 pub struct StateDelta {
     pub added: Vec<String>,
     pub modified: Vec<String>,
@@ -109,7 +396,6 @@ pub struct StateDelta {
     pub spec_unchanged: Vec<String>,
 }
 
-#[allow(dead_code)]
 pub fn diff_against_state(
     state: &ReconciliationState,
     current_files: &[(String, String)], // (rel_path, blake3_hash)
@@ -131,15 +417,16 @@ pub fn diff_against_state(
         .map(|(p, h)| (p.as_str(), h.as_str()))
         .collect();
 
-    for (path, fp) in &state.file_fingerprints {
-        match current_map.get(path.as_str()) {
+    let stored = state.artifact_fingerprints();
+    for (path, fp) in &stored {
+        match current_map.get(path) {
             Some(h) if *h == fp.blake3 => {} // unchanged -- not reported
-            Some(_) => modified.push(path.clone()),
-            None => removed.push(path.clone()),
+            Some(_) => modified.push(path.to_string()),
+            None => removed.push(path.to_string()),
         }
     }
     for (path, hash) in &current_artifacts {
-        if !state.file_fingerprints.contains_key(path) {
+        if !stored.contains_key(path.as_str()) {
             let _ = hash;
             added.push(path.clone());
         }
@@ -151,15 +438,15 @@ pub fn diff_against_state(
         .map(|(p, h)| (p.as_str(), h.as_str()))
         .collect();
 
-    for (path, stored_hash) in &state.spec_fingerprints {
+    for (path, spec) in &state.specs {
         match current_spec_map.get(path.as_str()) {
-            Some(h) if *h == stored_hash => spec_unchanged.push(path.clone()),
+            Some(h) if *h == spec.fingerprint => spec_unchanged.push(path.clone()),
             Some(_) => spec_changed.push(path.clone()),
             None => spec_changed.push(path.clone()), // removed spec counts as changed
         }
     }
     for (path, _) in &current_specs {
-        if !state.spec_fingerprints.contains_key(path) {
+        if !state.specs.contains_key(path) {
             spec_changed.push(path.clone()); // new spec
         }
     }
@@ -173,11 +460,142 @@ pub fn diff_against_state(
     }
 }
 
-fn utc_timestamp() -> String {
-    let duration = SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap_or_default();
-    format!("{}Z", duration.as_secs())
+/// Drift of the governed files on disk from the reconciliation state, as
+/// computed by `drift`.
+#[derive(Debug, Serialize)]
+pub struct StateDiff {
+    /// The snapshot compared against.
+    pub baseline: ReconciliationMeta,
+    /// True when nothing below is reported.
+    pub clean: bool,
+    /// Specs with drift, in path order.
+    pub specs: Vec<SpecDrift>,
+    /// Specs in the state that no longer exist.
+    pub removed_specs: Vec<String>,
+    /// Files in the state that are gone and no spec governs any more.
+    pub no_longer_governed: Vec<String>,
+    pub unchanged_specs: Vec<String>,
+}
+
+/// Drift of one spec and the files it governs.
+#[derive(Debug, Serialize)]
+pub struct SpecDrift {
+    pub spec_path: String,
+    pub reconciled_at: Option<String>,
+    pub spec_changed: bool,
+    pub added: Vec<String>,
+    pub modified: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+/// Compare the governed files on disk with the state file.
+///
+/// Hashes every file governed by a spec in `.notarai/` (with the normalizer
+/// recorded for it in the state) and every spec, then reports drift from the
+/// stored fingerprints grouped by spec. A file governed by several specs is
+/// listed under each. Needs no git history, so it also works on a fresh clone.
+/// Fails with `Error::MissingState` when there is no state file.
+pub fn drift(project_root: &Path) -> Result<StateDiff> {
+    use crate::core::mcp_tools::{expand_artifact_globs, is_spec_affected, is_spec_file};
+    use crate::core::normalize::Normalizer;
+
+    let state = load_state(project_root)?.ok_or(Error::MissingState)?;
+    let project = crate::Project::open(project_root);
+
+    let mut specs = Vec::new();
+    let mut current: Vec<(String, String)> = Vec::new();
+    let mut governed = std::collections::BTreeSet::new();
+    for loaded in project.load_specs()? {
+        governed.extend(
+            expand_artifact_globs(&loaded.spec, project_root)
+                .into_iter()
+                .filter(|f| !is_spec_file(f)),
+        );
+        current.push((loaded.path.clone(), loaded.fingerprint.clone()));
+        specs.push(loaded);
+    }
+
+    // Hash with the normalizer the baseline used so fingerprints compare.
+    // A file new to the baseline is reported as added whatever its hash.
+    let governed: Vec<String> = governed.into_iter().collect();
+    let stored = state.artifact_fingerprints();
+    let normalizers: Vec<Option<Normalizer>> = governed
+        .iter()
+        .map(|f| match stored.get(f.as_str()) {
+            Some(fp) => fp
+                .normalizer
+                .as_deref()
+                .map_or(Some(Normalizer::Raw), Normalizer::from_name),
+            None => Some(Normalizer::Raw),
+        })
+        .collect();
+    let abs: Vec<PathBuf> = governed.iter().map(|f| project_root.join(f)).collect();
+    let to_hash: Vec<(&Path, Normalizer)> = abs
+        .iter()
+        .zip(&normalizers)
+        .map(|(p, n)| (p.as_path(), n.unwrap_or(Normalizer::Raw)))
+        .collect();
+    let hashes = crate::core::cache::hash_files(&to_hash);
+    for ((rel, normalizer), hash) in governed.iter().zip(&normalizers).zip(hashes) {
+        // An unknown normalizer cannot reproduce the stored hash: modified.
+        let hash = match normalizer {
+            Some(_) => hash?,
+            None => String::new(),
+        };
+        current.push((rel.clone(), hash));
+    }
+
+    let delta = diff_against_state(&state, &current);
+    let governs = |spec: &Spec, path: &String| is_spec_affected(spec, std::slice::from_ref(path));
+    let mut by_spec = Vec::new();
+    for loaded in &specs {
+        let pick = |paths: &[String]| -> Vec<String> {
+            paths
+                .iter()
+                .filter(|p| governs(&loaded.spec, p))
+                .cloned()
+                .collect()
+        };
+        let drift = SpecDrift {
+            spec_path: loaded.path.clone(),
+            reconciled_at: state
+                .specs
+                .get(&loaded.path)
+                .map(|s| s.reconciled_at.clone()),
+            spec_changed: delta.spec_changed.contains(&loaded.path),
+            added: pick(&delta.added),
+            modified: pick(&delta.modified),
+            removed: pick(&delta.removed),
+        };
+        if drift.spec_changed
+            || !drift.added.is_empty()
+            || !drift.modified.is_empty()
+            || !drift.removed.is_empty()
+        {
+            by_spec.push(drift);
+        }
+    }
+    let removed_specs: Vec<String> = delta
+        .spec_changed
+        .iter()
+        .filter(|s| !specs.iter().any(|l| &l.path == *s))
+        .cloned()
+        .collect();
+    let no_longer_governed: Vec<String> = delta
+        .removed
+        .iter()
+        .filter(|p| !specs.iter().any(|l| governs(&l.spec, p)))
+        .cloned()
+        .collect();
+
+    Ok(StateDiff {
+        baseline: state.last_reconciliation,
+        clean: by_spec.is_empty() && removed_specs.is_empty() && no_longer_governed.is_empty(),
+        specs: by_spec,
+        removed_specs,
+        no_longer_governed,
+        unchanged_specs: delta.spec_unchanged,
+    })
 }
 
 fn git_head(project_root: &Path) -> Option<String> {
@@ -217,25 +635,34 @@ mod tests {
     use tempfile::TempDir;
 
     fn make_state() -> ReconciliationState {
-        let mut file_fingerprints = BTreeMap::new();
-        file_fingerprints.insert(
+        let mut artifacts = BTreeMap::new();
+        artifacts.insert(
             "src/main.rs".to_string(),
             FileFingerprint {
                 blake3: "abc123".to_string(),
+                normalizer: None,
+            },
+        );
+        let mut specs = BTreeMap::new();
+        specs.insert(
+            ".notarai/cli.spec.yaml".to_string(),
+            SpecState {
+                fingerprint: "def456".to_string(),
+                reconciled_at: "2023-11-14T22:13:20Z".to_string(),
+                git_hash: Some("deadbeef".to_string()),
+                artifacts,
             },
         );
-        let mut spec_fingerprints = BTreeMap::new();
-        spec_fingerprints.insert(".notarai/cli.spec.yaml".to_string(), "def456".to_string());
         ReconciliationState {
-            schema_version: "1".to_string(),
+            schema_version: STATE_SCHEMA_VERSION.to_string(),
             cli_version: Some("0.3.2".to_string()),
             last_reconciliation: ReconciliationMeta {
-                timestamp: "1700000000Z".to_string(),
+                timestamp: "2023-11-14T22:13:20Z".to_string(),
                 git_hash: Some("deadbeef".to_string()),
                 branch: Some("main".to_string()),
             },
-            file_fingerprints,
-            spec_fingerprints,
+            specs,
+            unattributed_fingerprints: BTreeMap::new(),
         }
     }
 
@@ -263,13 +690,28 @@ mod tests {
         assert_eq!(state, loaded);
     }
 
+    #[test]
+    fn test_save_state_replaces_without_leaving_temp_files() {
+        let tmp = TempDir::new().unwrap();
+        let mut state = make_state();
+        save_state(tmp.path(), &state).unwrap();
+        state.last_reconciliation.branch = Some("other".to_string());
+        save_state(tmp.path(), &state).unwrap();
+        assert_eq!(load_state(tmp.path()).unwrap().unwrap(), state);
+        let entries: Vec<_> = std::fs::read_dir(tmp.path().join(".notarai"))
+            .unwrap()
+            .map(|e| e.unwrap().file_name())
+            .collect();
+        assert_eq!(entries, vec!["reconciliation_state.json"]);
+    }
+
     #[test]
     fn test_snapshot_deterministic_output() {
         // BTreeMap keys are always sorted
         let state = make_state();
         let json = serde_json::to_string_pretty(&state).unwrap();
         let parsed: serde_json::Value = serde_json::from_str(&json).unwrap();
-        let keys: Vec<&str> = parsed["file_fingerprints"]
+        let keys: Vec<&str> = parsed["specs"][".notarai/cli.spec.yaml"]["artifacts"]
             .as_object()
             .unwrap()
             .keys()
@@ -345,4 +787,244 @@ mod tests {
         let delta3 = diff_against_state(&state, &current_removed);
         assert!(delta3.removed.contains(&"src/main.rs".to_string()));
     }
+
+    #[test]
+    fn test_v1_state_is_migrated() {
+        use crate::core::cache;
+
+        let tmp = TempDir::new().unwrap();
+        let root = tmp.path();
+        std::fs::create_dir_all(root.join(".notarai")).unwrap();
+        let spec = "schema_version: '0.7'\nintent: 'x'\nartifacts:\n  code:\n    - path: '*.txt'\n";
+        std::fs::write(root.join(".notarai/cli.spec.yaml"), spec).unwrap();
+        std::fs::write(root.join("main.txt"), "main\n").unwrap();
+        // What a schema 1 snapshot recorded: raw hashes of both files.
+        let v1 = serde_json::json!({
+            "schema_version": "1",
+            "cli_version": "0.4.0",
+            "last_reconciliation": {"timestamp": "1700000000Z", "git_hash": "deadbeef", "branch": "main"},
+            "file_fingerprints": {"main.txt": {"blake3": cache::hash_bytes(b"main\n")}},
+            "spec_fingerprints": {".notarai/cli.spec.yaml": cache::hash_bytes(spec.as_bytes())}
+        });
+        std::fs::write(state_path(root), v1.to_string()).unwrap();
+
+        let state = load_state(root).unwrap().unwrap();
+        assert_eq!(state.schema_version, STATE_SCHEMA_VERSION);
+        assert_eq!(state.last_reconciliation.timestamp, "2023-11-14T22:13:20Z");
+        let migrated = &state.specs[".notarai/cli.spec.yaml"];
+        assert_eq!(
+            migrated.fingerprint,
+            cache::spec_fingerprint(spec.as_bytes())
+        );
+        assert_eq!(migrated.reconciled_at, "2023-11-14T22:13:20Z");
+        assert_eq!(migrated.git_hash.as_deref(), Some("deadbeef"));
+        assert!(migrated.artifacts.is_empty());
+
+        let diff = drift(root).unwrap();
+        assert!(diff.clean, "{:?}", diff.specs);
+
+        let conn = cache::open_cache_db_no_restore(root).unwrap();
+        let summary = restore_cache(root, &conn, &state).unwrap();
+        assert_eq!((summary.specs, summary.files), (1, 1));
+        assert_eq!(summary.stale_specs, 0);
+    }
+
+    #[test]
+    fn test_newer_schema_is_rejected() {
+        let err = parse_state(r#"{"schema_version": "3"}"#, Path::new(".")).unwrap_err();
+        assert!(
+            matches!(err, Error::UnsupportedVersion { ref found, .. } if found == "3"),
+            "{err:?}"
+        );
+        assert!(err.to_string().contains("newer than this notarai supports"));
+    }
+
+    #[test]
+    fn test_artifact_fingerprints_prefer_latest_spec() {
+        let mut state = make_state();
+        let mut newer = state.specs[".notarai/cli.spec.yaml"].clone();
+        newer.reconciled_at = "2024-01-01T00:00:00Z".to_string();
+        newer.artifacts.get_mut("src/main.rs").unwrap().blake3 = "newer".to_string();
+        state
+            .specs
+            .insert(".notarai/a.spec.yaml".to_string(), newer);
+        state.unattributed_fingerprints.insert(
+            "src/main.rs".to_string(),
+            FileFingerprint {
+                blake3: "legacy".to_string(),
+                normalizer: None,
+            },
+        );
+        assert_eq!(state.artifact_fingerprints()["src/main.rs"].blake3, "newer");
+    }
+
+    #[test]
+    fn test_snapshot_keeps_metadata_of_specs_not_reconciled_again() {
+        use crate::core::cache::{self, FileRecord};
+        use crate::core::normalize::Normalizer;
+
+        let tmp = TempDir::new().unwrap();
+        let root = tmp.path();
+        std::fs::create_dir_all(root.join(".notarai")).unwrap();
+        for f in [".notarai/cli.spec.yaml", ".notarai/docs.spec.yaml", "a.txt"] {
+            std::fs::write(root.join(f), f).unwrap();
+        }
+        let mut previous = make_state();
+        previous.specs.insert(
+            ".notarai/docs.spec.yaml".to_string(),
+            SpecState {
+                fingerprint: "docs".to_string(),
+                reconciled_at: "2023-01-01T00:00:00Z".to_string(),
+                git_hash: Some("0ld".to_string()),
+                artifacts: BTreeMap::new(),
+            },
+        );
+        previous.specs.insert(
+            ".notarai/gone.spec.yaml".to_string(),
+            previous.specs[".notarai/cli.spec.yaml"].clone(),
+        );
+        save_state(root, &previous).unwrap();
+
+        let conn = cache::open_cache_db(root).unwrap();
+        let record = FileRecord {
+            path: "a.txt".to_string(),
+            hash: "h".to_string(),
+            stat: None,
+            normalizer: Normalizer::Text,
+        };
+        cache::upsert_batch(&conn, ".notarai/cli.spec.yaml", "cli2", &[record]).unwrap();
+
+        let state = snapshot_from_cache(root, false).unwrap();
+        assert_eq!(state.specs.len(), 2, "{:?}", state.specs.keys());
+        assert_eq!(
+            state.specs[".notarai/docs.spec.yaml"],
+            previous.specs[".notarai/docs.spec.yaml"]
+        );
+        let cli = &state.specs[".notarai/cli.spec.yaml"];
+        assert_eq!(cli.fingerprint, "cli2");
+        assert!(cli.reconciled_at.as_str() > "2023-11-14T22:13:20Z");
+        assert_eq!(cli.artifacts["a.txt"].normalizer.as_deref(), Some("text"));
+    }
+
+    #[test]
+    fn test_restore_cache_seeds_only_matching_records() {
+        use crate::core::cache;
+        use crate::core::normalize::Normalizer;
+
+        let tmp = TempDir::new().unwrap();
+        let root = tmp.path();
+        std::fs::create_dir_all(root.join(".notarai")).unwrap();
+        let spec_content = "artifacts:\n  code:\n    - path: '*.txt'\n";
+        std::fs::write(root.join(".notarai/a.spec.yaml"), spec_content).unwrap();
+        std::fs::write(root.join(".notarai/b.spec.yaml"), "changed").unwrap();
+        for f in ["same.txt", "edited.txt", "legacy.txt"] {
+            std::fs::write(root.join(f), f).unwrap();
+        }
+        let text = |f: &str| FileFingerprint {
+            blake3: cache::hash_file_with(&root.join(f), Normalizer::Text).unwrap(),
+            normalizer: Some("text".to_string()),
+        };
+
+        let mut state = make_state();
+        state.specs.clear();
+        let a = SpecState {
+            fingerprint: cache::spec_fingerprint(spec_content.as_bytes()),
+            reconciled_at: "2026-01-01T00:00:00Z".to_string(),
+            git_hash: None,
+            artifacts: BTreeMap::from([
+                ("same.txt".to_string(), text("same.txt")),
+                ("edited.txt".to_string(), text("edited.txt")),
+            ]),
+        };
+        state
+            .specs
+            .insert(".notarai/a.spec.yaml".to_string(), a.clone());
+        state.specs.insert(
+            ".notarai/b.spec.yaml".to_string(),
+            SpecState {
+                fingerprint: "old".to_string(),
+                ..a.clone()
+            },
+        );
+        state.unattributed_fingerprints.insert(
+            "legacy.txt".to_string(),
+            FileFingerprint {
+                blake3: cache::hash_file(&root.join("legacy.txt")).unwrap(),
+                normalizer: None,
+            },
+        );
+        std::fs::write(root.join("edited.txt"), "something else").unwrap();
+
+        let conn = cache::open_cache_db_no_restore(root).unwrap();
+        let summary = restore_cache(root, &conn, &state).unwrap();
+        assert_eq!((summary.specs, summary.files), (1, 2));
+        assert_eq!((summary.stale_specs, summary.stale_files), (1, 1));
+
+        let specs = cache::read_specs(&conn).unwrap();
+        let files: Vec<_> = specs[0].files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(files, ["legacy.txt", "same.txt"]);
+        assert_eq!(
+            crate::core::time::format_utc(specs[0].marked_at),
+            a.reconciled_at
+        );
+
+        // A second restore keeps the local records.
+        let again = restore_cache(root, &conn, &state).unwrap();
+        assert_eq!((again.specs, again.kept_specs), (0, 1));
+    }
+
+    #[test]
+    fn test_imported_records_keep_their_reconciliation() {
+        use crate::core::cache;
+
+        let spec = "schema_version: '0.7'\nintent: 'x'\n";
+        let fingerprint = cache::spec_fingerprint(spec.as_bytes());
+        let project = || {
+            let tmp = TempDir::new().unwrap();
+            std::fs::create_dir_all(tmp.path().join(".notarai")).unwrap();
+            std::fs::write(tmp.path().join(".notarai/cli.spec.yaml"), spec).unwrap();
+            std::fs::write(tmp.path().join("main.txt"), "main").unwrap();
+            tmp
+        };
+        let reconciled_at = "2026-01-01T00:00:00Z";
+        let mut state = make_state();
+        state.specs.clear();
+        state.specs.insert(
+            ".notarai/cli.spec.yaml".to_string(),
+            SpecState {
+                fingerprint: fingerprint.clone(),
+                reconciled_at: reconciled_at.to_string(),
+                git_hash: Some("abc123".to_string()),
+                artifacts: BTreeMap::new(),
+            },
+        );
+
+        let src = project();
+        let conn = cache::open_cache_db(src.path()).unwrap();
+        let record = cache::FileRecord {
+            path: "main.txt".to_string(),
+            hash: cache::hash_file(&src.path().join("main.txt")).unwrap(),
+            stat: None,
+            normalizer: crate::core::normalize::Normalizer::Raw,
+        };
+        let marked_at = crate::core::time::parse_utc(reconciled_at).unwrap();
+        cache::seed_batch(
+            &conn,
+            ".notarai/cli.spec.yaml",
+            &fingerprint,
+            &[record],
+            marked_at,
+        )
+        .unwrap();
+        let export = cache::export(&conn).unwrap();
+
+        let dst = project();
+        save_state(dst.path(), &state).unwrap();
+        let conn = cache::open_cache_db_no_restore(dst.path()).unwrap();
+        assert_eq!(cache::import(&conn, &export).unwrap(), 1);
+        let snapshot = snapshot_from_cache(dst.path(), false).unwrap();
+        let spec = &snapshot.specs[".notarai/cli.spec.yaml"];
+        assert_eq!(spec.reconciled_at, reconciled_at);
+        assert_eq!(spec.git_hash.as_deref(), Some("abc123"));
+    }
 }
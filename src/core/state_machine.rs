@@ -0,0 +1,375 @@
+//! Checks and diagrams for a spec's `states` block.
+//!
+//! The state machine is checked structurally: duplicate or undefined states,
+//! states the initial state cannot reach, non-terminal states with no way
+//! out, transitions on the same event whose guards do not tell them apart,
+//! and behaviors whose `state_transition` names a state that is not defined.
+//! Guards are natural language and are only compared as text.
+
+use crate::core::graph::Format;
+use crate::core::spec::{Spec, States};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FindingKind {
+    DuplicateState,
+    UnknownState,
+    UnreachableState,
+    DeadEndState,
+    AmbiguousTransition,
+    UndefinedBehaviorState,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Finding {
+    pub kind: FindingKind,
+    /// The state, or for `undefined_behavior_state` the behavior, the finding
+    /// is about.
+    pub subject: String,
+    pub message: String,
+}
+
+impl Finding {
+    fn new(kind: FindingKind, subject: &str, message: String) -> Finding {
+        Finding {
+            kind,
+            subject: subject.to_string(),
+            message,
+        }
+    }
+}
+
+/// Run every check on `spec`'s state machine and on its behaviors'
+/// `state_transition`s. A spec without `states` only gets the behavior check.
+pub fn analyze(spec: &Spec) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let defined: BTreeSet<&str> = spec
+        .states
+        .iter()
+        .flat_map(|s| &s.definitions)
+        .map(|d| d.id.as_str())
+        .collect();
+
+    if let Some(states) = &spec.states {
+        findings.extend(check_states(states, &defined));
+    }
+
+    for behavior in &spec.behaviors {
+        let Some(transition) = &behavior.state_transition else {
+            continue;
+        };
+        for (end, state) in [("from", &transition.from), ("to", &transition.to)] {
+            if !defined.contains(state.as_str()) {
+                findings.push(Finding::new(
+                    FindingKind::UndefinedBehaviorState,
+                    &behavior.name,
+                    format!("state_transition.{end} '{state}' is not a defined state"),
+                ));
+            }
+        }
+    }
+    findings
+}
+
+fn check_states(states: &States, defined: &BTreeSet<&str>) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let mut seen = BTreeSet::new();
+    for state in &states.definitions {
+        if !seen.insert(state.id.as_str()) {
+            findings.push(Finding::new(
+                FindingKind::DuplicateState,
+                &state.id,
+                format!("state id '{}' is used more than once", state.id),
+            ));
+        }
+    }
+
+    if !defined.contains(states.initial.as_str()) {
+        findings.push(Finding::new(
+            FindingKind::UnknownState,
+            &states.initial,
+            format!("initial state '{}' is not defined", states.initial),
+        ));
+    }
+    for state in &states.definitions {
+        for transition in &state.transitions {
+            if !defined.contains(transition.to.as_str()) {
+                findings.push(Finding::new(
+                    FindingKind::UnknownState,
+                    &state.id,
+                    format!(
+                        "transition on '{}' targets undefined state '{}'",
+                        transition.on, transition.to
+                    ),
+                ));
+            }
+        }
+    }
+
+    let mut reached: BTreeSet<&str> = BTreeSet::new();
+    if defined.contains(states.initial.as_str()) {
+        let targets: BTreeMap<&str, Vec<&str>> = states
+            .definitions
+            .iter()
+            .map(|d| {
+                let to = d.transitions.iter().map(|t| t.to.as_str()).collect();
+                (d.id.as_str(), to)
+            })
+            .collect();
+        let mut frontier = vec![states.initial.as_str()];
+        while let Some(id) = frontier.pop() {
+            if reached.insert(id) {
+                frontier.extend(targets.get(id).into_iter().flatten());
+            }
+        }
+    }
+    let mut reported = BTreeSet::new();
+    for state in &states.definitions {
+        if !reached.contains(state.id.as_str()) && reported.insert(state.id.as_str()) {
+            findings.push(Finding::new(
+                FindingKind::UnreachableState,
+                &state.id,
+                format!(
+                    "'{}' cannot be reached from the initial state '{}'",
+                    state.id, states.initial
+                ),
+            ));
+        }
+    }
+
+    for state in &states.definitions {
+        if !state.terminal && state.transitions.is_empty() {
+            findings.push(Finding::new(
+                FindingKind::DeadEndState,
+                &state.id,
+                format!(
+                    "'{}' has no outgoing transitions and is not marked terminal",
+                    state.id
+                ),
+            ));
+        }
+
+        let mut by_event: BTreeMap<&str, Vec<Option<&str>>> = BTreeMap::new();
+        for transition in &state.transitions {
+            by_event
+                .entry(transition.on.as_str())
+                .or_default()
+                .push(transition.guard.as_deref().map(str::trim));
+        }
+        for (event, guards) in by_event {
+            let ambiguous = guards.iter().enumerate().any(|(i, a)| {
+                guards[i + 1..]
+                    .iter()
+                    .any(|b| a.is_none() || b.is_none() || a == b)
+            });
+            if ambiguous {
+                findings.push(Finding::new(
+                    FindingKind::AmbiguousTransition,
+                    &state.id,
+                    format!(
+                        "{} transitions on '{event}' are not distinguished by distinct guards",
+                        guards.len()
+                    ),
+                ));
+            }
+        }
+    }
+    findings
+}
+
+/// Render the state machine as a Mermaid `stateDiagram-v2`, Graphviz DOT, or
+/// JSON with one entry per transition. Transitions are labeled with their
+/// event, followed by the guard in brackets.
+pub fn render(states: &States, format: Format) -> String {
+    match format {
+        Format::Dot => to_dot(states),
+        Format::Mermaid => to_mermaid(states),
+        Format::Json => {
+            let transitions: Vec<serde_json::Value> = states
+                .definitions
+                .iter()
+                .flat_map(|d| {
+                    d.transitions.iter().map(|t| {
+                        serde_json::json!({
+                            "from": d.id,
+                            "to": t.to,
+                            "on": t.on,
+                            "guard": t.guard,
+                            "action": t.action,
+                        })
+                    })
+                })
+                .collect();
+            let report = serde_json::json!({
+                "initial": states.initial,
+                "states": states.definitions.iter().map(|d| serde_json::json!({
+                    "id": d.id,
+                    "terminal": d.terminal,
+                })).collect::<Vec<_>>(),
+                "transitions": transitions,
+            });
+            serde_json::to_string_pretty(&report).expect("JSON serialization") + "\n"
+        }
+    }
+}
+
+fn label(on: &str, guard: Option<&str>) -> String {
+    match guard {
+        Some(guard) => format!("{on} [{}]", guard.trim()),
+        None => on.to_string(),
+    }
+}
+
+/// Graphviz DOT. The initial state is entered from a point node and terminal
+/// states are drawn as double circles.
+fn to_dot(states: &States) -> String {
+    let quote = |s: &str| format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""));
+    let mut out = String::from(
+        "digraph states {\n  rankdir=LR;\n  node [shape=ellipse];\n  __start [shape=point];\n",
+    );
+    out.push_str(&format!("  __start -> {};\n", quote(&states.initial)));
+    for state in &states.definitions {
+        let shape = if state.terminal {
+            " [shape=doublecircle]"
+        } else {
+            ""
+        };
+        out.push_str(&format!("  {}{shape};\n", quote(&state.id)));
+    }
+    for state in &states.definitions {
+        for t in &state.transitions {
+            out.push_str(&format!(
+                "  {} -> {} [label={}];\n",
+                quote(&state.id),
+                quote(&t.to),
+                quote(&label(&t.on, t.guard.as_deref()))
+            ));
+        }
+    }
+    out.push_str("}\n");
+    out
+}
+
+/// Mermaid `stateDiagram-v2`. States get generated ids (`s0`, `s1`, ...) so
+/// that any state id can be used as a label.
+fn to_mermaid(states: &States) -> String {
+    let mut order: Vec<&str> = states.definitions.iter().map(|d| d.id.as_str()).collect();
+    order.push(&states.initial);
+    order.extend(
+        states
+            .definitions
+            .iter()
+            .flat_map(|d| d.transitions.iter().map(|t| t.to.as_str())),
+    );
+    let mut ids: BTreeMap<&str, String> = BTreeMap::new();
+    let mut out = String::from("stateDiagram-v2\n");
+    for id in order {
+        if ids.contains_key(id) {
+            continue;
+        }
+        let generated = format!("s{}", ids.len());
+        out.push_str(&format!(
+            "  state \"{}\" as {generated}\n",
+            id.replace('"', "#quot;")
+        ));
+        ids.insert(id, generated);
+    }
+
+    out.push_str(&format!("  [*] --> {}\n", ids[states.initial.as_str()]));
+    for state in &states.definitions {
+        let from = &ids[state.id.as_str()];
+        for t in &state.transitions {
+            let text = label(&t.on, t.guard.as_deref()).replace(':', "#58;");
+            out.push_str(&format!("  {from} --> {} : {text}\n", ids[t.to.as_str()]));
+        }
+        if state.terminal {
+            out.push_str(&format!("  {from} --> [*]\n"));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::spec::test_spec;
+
+    fn kinds(findings: &[Finding]) -> Vec<(FindingKind, &str)> {
+        findings
+            .iter()
+            .map(|f| (f.kind, f.subject.as_str()))
+            .collect()
+    }
+
+    const GAME: &str = "states:\n  initial: menu\n  definitions:\n    \
+        - id: menu\n      transitions:\n        - to: playing\n          on: start\n    \
+        - id: playing\n      transitions:\n        \
+        - to: won\n          on: finish\n          guard: 'score >= 100'\n        \
+        - to: lost\n          on: finish\n          guard: 'score < 100'\n    \
+        - id: won\n      terminal: true\n    - id: lost\n      terminal: true\n";
+
+    #[test]
+    fn well_formed_machine_has_no_findings() {
+        let s = test_spec(&format!(
+            "{GAME}behaviors:\n  - name: start\n    given: 'g'\n    then: 't'\n    \
+             state_transition: {{ from: menu, to: playing }}\n"
+        ));
+        assert!(analyze(&s).is_empty(), "{:?}", analyze(&s));
+    }
+
+    #[test]
+    fn reports_each_structural_problem() {
+        let s = test_spec(
+            "states:\n  initial: a\n  definitions:\n    \
+             - id: a\n      transitions:\n        - to: b\n          on: go\n        \
+             - to: c\n          on: go\n          guard: 'ready'\n        \
+             - to: ghost\n          on: jump\n    \
+             - id: b\n    - id: c\n      transitions:\n        - to: a\n          on: back\n    \
+             - id: island\n      terminal: true\n\
+             behaviors:\n  - name: warp\n    given: 'g'\n    then: 't'\n    \
+             state_transition: { from: a, to: nowhere }\n",
+        );
+        assert_eq!(
+            kinds(&analyze(&s)),
+            [
+                (FindingKind::UnknownState, "a"),
+                (FindingKind::UnreachableState, "island"),
+                (FindingKind::AmbiguousTransition, "a"),
+                (FindingKind::DeadEndState, "b"),
+                (FindingKind::UndefinedBehaviorState, "warp"),
+            ]
+        );
+    }
+
+    #[test]
+    fn identical_guards_are_ambiguous() {
+        let s = test_spec(
+            "states:\n  initial: a\n  definitions:\n    \
+             - id: a\n      transitions:\n        \
+             - to: b\n          on: go\n          guard: 'ready'\n        \
+             - to: a\n          on: go\n          guard: ' ready '\n    \
+             - id: b\n      terminal: true\n",
+        );
+        assert_eq!(
+            kinds(&analyze(&s)),
+            [(FindingKind::AmbiguousTransition, "a")]
+        );
+    }
+
+    #[test]
+    fn renders_mermaid_and_dot() {
+        let states = test_spec(GAME).states.unwrap();
+        let mermaid = render(&states, Format::Mermaid);
+        assert!(mermaid.starts_with("stateDiagram-v2\n  state \"menu\" as s0\n"));
+        assert!(mermaid.contains("  [*] --> s0\n"));
+        assert!(mermaid.contains("  s1 --> s2 : finish [score >= 100]\n"));
+        assert!(mermaid.contains("  s2 --> [*]\n"));
+
+        let dot = render(&states, Format::Dot);
+        assert!(dot.contains("  __start -> \"menu\";\n"));
+        assert!(dot.contains("  \"won\" [shape=doublecircle];\n"));
+        assert!(dot.contains("  \"playing\" -> \"lost\" [label=\"finish [score < 100]\"];\n"));
+    }
+}
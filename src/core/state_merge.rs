@@ -0,0 +1,309 @@
+//! Three-way merge of `reconciliation_state.json`, used as a git merge driver.
+//!
+//! Two branches that reconcile different specs both rewrite the state file,
+//! so a line-based merge always conflicts. This merge works on the parsed
+//! state instead: each spec entry (and each unattributed fingerprint) is
+//! merged against the common ancestor, so a spec changed on only one side
+//! takes that side's entry. When both sides reconciled the same spec, the
+//! artifact records are merged file by file and the newer reconciliation wins
+//! a disagreement.
+//!
+//! True conflicts still produce a merged state (the newer side is kept) but
+//! are reported, so the driver can make git stop for review:
+//!
+//! - both sides reconciled a spec against different revisions of the spec;
+//! - one side dropped a spec the other side reconciled again;
+//! - an unattributed fingerprint changed differently on both sides.
+
+use crate::core::state::{ReconciliationState, STATE_SCHEMA_VERSION, SpecState};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Result of `merge_states`.
+#[derive(Debug)]
+pub struct StateMerge {
+    pub state: ReconciliationState,
+    /// Human-readable description of each true conflict, ordered by path.
+    pub conflicts: Vec<String>,
+}
+
+/// Outcome of merging one map entry against its ancestor.
+enum Entry<T> {
+    /// The sides agree, or only one side changed: take this value.
+    Resolved(Option<T>),
+    /// Both sides changed the entry differently.
+    Both,
+}
+
+fn three_way<T: PartialEq + Clone>(
+    base: Option<&T>,
+    ours: Option<&T>,
+    theirs: Option<&T>,
+) -> Entry<T> {
+    if ours == theirs || theirs == base {
+        Entry::Resolved(ours.cloned())
+    } else if ours == base {
+        Entry::Resolved(theirs.cloned())
+    } else {
+        Entry::Both
+    }
+}
+
+fn keys<T>(maps: [&BTreeMap<String, T>; 3]) -> BTreeSet<&String> {
+    maps.into_iter().flat_map(|m| m.keys()).collect()
+}
+
+/// Merge `ours` and `theirs` against their common ancestor `base` (`None`
+/// when the file did not exist there).
+pub fn merge_states(
+    base: Option<&ReconciliationState>,
+    ours: &ReconciliationState,
+    theirs: &ReconciliationState,
+) -> StateMerge {
+    let empty = BTreeMap::new();
+    let base_specs = base.map_or(&empty, |b| &b.specs);
+    let mut conflicts = Vec::new();
+
+    let mut specs = BTreeMap::new();
+    for path in keys([base_specs, &ours.specs, &theirs.specs]) {
+        let (b, o, t) = (
+            base_specs.get(path),
+            ours.specs.get(path),
+            theirs.specs.get(path),
+        );
+        let merged = match three_way(b, o, t) {
+            Entry::Resolved(spec) => spec,
+            Entry::Both => match (o, t) {
+                (Some(o), Some(t)) => Some(merge_spec(path, b, o, t, &mut conflicts)),
+                (Some(kept), None) | (None, Some(kept)) => {
+                    conflicts.push(format!(
+                        "{path}: removed on one side and reconciled again on the other"
+                    ));
+                    Some(kept.clone())
+                }
+                (None, None) => None,
+            },
+        };
+        if let Some(spec) = merged {
+            specs.insert(path.clone(), spec);
+        }
+    }
+
+    let empty_fps = BTreeMap::new();
+    let base_fps = base.map_or(&empty_fps, |b| &b.unattributed_fingerprints);
+    let mut unattributed = BTreeMap::new();
+    for path in keys([
+        base_fps,
+        &ours.unattributed_fingerprints,
+        &theirs.unattributed_fingerprints,
+    ]) {
+        let (b, o, t) = (
+            base_fps.get(path),
+            ours.unattributed_fingerprints.get(path),
+            theirs.unattributed_fingerprints.get(path),
+        );
+        let merged = match three_way(b, o, t) {
+            Entry::Resolved(fp) => fp,
+            Entry::Both => {
+                conflicts.push(format!(
+                    "{path}: unattributed fingerprint changed on both sides"
+                ));
+                o.or(t).cloned()
+            }
+        };
+        // Drop it once a spec on either side records the file.
+        let recorded = specs.values().any(|s| s.artifacts.contains_key(path));
+        if let Some(fp) = merged.filter(|_| !recorded) {
+            unattributed.insert(path.clone(), fp);
+        }
+    }
+
+    let newer = if theirs.last_reconciliation.timestamp > ours.last_reconciliation.timestamp {
+        theirs
+    } else {
+        ours
+    };
+    StateMerge {
+        state: ReconciliationState {
+            schema_version: STATE_SCHEMA_VERSION.to_string(),
+            cli_version: newer.cli_version.clone(),
+            last_reconciliation: newer.last_reconciliation.clone(),
+            specs,
+            unattributed_fingerprints: unattributed,
+        },
+        conflicts,
+    }
+}
+
+/// Merge a spec reconciled on both sides. Artifact records are merged file by
+/// file against the ancestor; where both sides recorded a file differently,
+/// the record from the newer reconciliation is kept.
+fn merge_spec(
+    path: &str,
+    base: Option<&SpecState>,
+    ours: &SpecState,
+    theirs: &SpecState,
+    conflicts: &mut Vec<String>,
+) -> SpecState {
+    let (newer, older) = if theirs.reconciled_at > ours.reconciled_at {
+        (theirs, ours)
+    } else {
+        (ours, theirs)
+    };
+    if ours.fingerprint != theirs.fingerprint {
+        conflicts.push(format!(
+            "{path}: reconciled against different revisions of the spec \
+             ({} on one side, {} on the other); kept the newer",
+            newer.reconciled_at, older.reconciled_at
+        ));
+        return newer.clone();
+    }
+
+    let empty = BTreeMap::new();
+    let base_artifacts = base
+        .filter(|b| b.fingerprint == ours.fingerprint)
+        .map_or(&empty, |b| &b.artifacts);
+    let mut artifacts = BTreeMap::new();
+    for file in keys([base_artifacts, &ours.artifacts, &theirs.artifacts]) {
+        let (b, o, t) = (
+            base_artifacts.get(file),
+            ours.artifacts.get(file),
+            theirs.artifacts.get(file),
+        );
+        let merged = match three_way(b, o, t) {
+            Entry::Resolved(fp) => fp,
+            Entry::Both => newer
+                .artifacts
+                .get(file)
+                .or(older.artifacts.get(file))
+                .cloned(),
+        };
+        if let Some(fp) = merged {
+            artifacts.insert(file.clone(), fp);
+        }
+    }
+
+    SpecState {
+        fingerprint: newer.fingerprint.clone(),
+        reconciled_at: newer.reconciled_at.clone(),
+        git_hash: newer.git_hash.clone(),
+        artifacts,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::state::{FileFingerprint, ReconciliationMeta};
+
+    fn fp(hash: &str) -> FileFingerprint {
+        FileFingerprint {
+            blake3: hash.to_string(),
+            normalizer: None,
+        }
+    }
+
+    fn spec(fingerprint: &str, at: &str, files: &[(&str, &str)]) -> SpecState {
+        SpecState {
+            fingerprint: fingerprint.to_string(),
+            reconciled_at: at.to_string(),
+            git_hash: Some(format!("commit-{at}")),
+            artifacts: files.iter().map(|(p, h)| (p.to_string(), fp(h))).collect(),
+        }
+    }
+
+    fn state(at: &str, specs: &[(&str, SpecState)]) -> ReconciliationState {
+        ReconciliationState {
+            schema_version: STATE_SCHEMA_VERSION.to_string(),
+            cli_version: None,
+            last_reconciliation: ReconciliationMeta {
+                timestamp: at.to_string(),
+                git_hash: None,
+                branch: None,
+            },
+            specs: specs
+                .iter()
+                .map(|(p, s)| (p.to_string(), s.clone()))
+                .collect(),
+            unattributed_fingerprints: BTreeMap::new(),
+        }
+    }
+
+    const T0: &str = "2026-01-01T00:00:00Z";
+    const T1: &str = "2026-01-02T00:00:00Z";
+    const T2: &str = "2026-01-03T00:00:00Z";
+
+    #[test]
+    fn specs_reconciled_on_different_sides_both_survive() {
+        let cli0 = spec("c", T0, &[("src/main.rs", "m0")]);
+        let docs0 = spec("d", T0, &[("docs/a.md", "a0")]);
+        let base = state(T0, &[("cli", cli0.clone()), ("docs", docs0.clone())]);
+        let cli1 = spec("c", T1, &[("src/main.rs", "m1")]);
+        let docs2 = spec("d", T2, &[("docs/a.md", "a2")]);
+        let ours = state(T1, &[("cli", cli1.clone()), ("docs", docs0)]);
+        let theirs = state(T2, &[("cli", cli0), ("docs", docs2.clone())]);
+
+        let merged = merge_states(Some(&base), &ours, &theirs);
+        assert!(merged.conflicts.is_empty(), "{:?}", merged.conflicts);
+        assert_eq!(merged.state.specs["cli"], cli1);
+        assert_eq!(merged.state.specs["docs"], docs2);
+        assert_eq!(merged.state.last_reconciliation.timestamp, T2);
+    }
+
+    #[test]
+    fn same_spec_on_both_sides_merges_files_and_keeps_newer_record() {
+        let base = state(T0, &[("cli", spec("c", T0, &[("a", "a0"), ("b", "b0")]))]);
+        let ours = state(T1, &[("cli", spec("c", T1, &[("a", "a1"), ("b", "b0")]))]);
+        let theirs = state(
+            T2,
+            &[(
+                "cli",
+                spec("c", T2, &[("a", "a2"), ("b", "b0"), ("c", "c2")]),
+            )],
+        );
+
+        let merged = merge_states(Some(&base), &ours, &theirs);
+        assert!(merged.conflicts.is_empty(), "{:?}", merged.conflicts);
+        let cli = &merged.state.specs["cli"];
+        assert_eq!(cli.reconciled_at, T2);
+        assert_eq!(cli.artifacts["a"].blake3, "a2");
+        assert_eq!(cli.artifacts["b"].blake3, "b0");
+        assert_eq!(cli.artifacts["c"].blake3, "c2");
+    }
+
+    #[test]
+    fn different_spec_revisions_are_a_conflict() {
+        let base = state(T0, &[("cli", spec("c0", T0, &[]))]);
+        let ours = state(T1, &[("cli", spec("c1", T1, &[]))]);
+        let theirs = state(T2, &[("cli", spec("c2", T2, &[]))]);
+
+        let merged = merge_states(Some(&base), &ours, &theirs);
+        assert_eq!(merged.conflicts.len(), 1);
+        assert!(merged.conflicts[0].starts_with("cli: reconciled against different revisions"));
+        assert_eq!(merged.state.specs["cli"].fingerprint, "c2");
+    }
+
+    #[test]
+    fn removal_against_reconciliation_is_a_conflict() {
+        let base = state(T0, &[("cli", spec("c", T0, &[]))]);
+        let ours = state(T1, &[]);
+        let theirs = state(T2, &[("cli", spec("c", T2, &[]))]);
+
+        let merged = merge_states(Some(&base), &ours, &theirs);
+        assert_eq!(merged.conflicts.len(), 1);
+        assert!(merged.state.specs.contains_key("cli"));
+
+        // A removal on one side alone is taken as is.
+        let merged = merge_states(Some(&base), &ours, &base);
+        assert!(merged.conflicts.is_empty());
+        assert!(merged.state.specs.is_empty());
+    }
+
+    #[test]
+    fn no_ancestor_treats_specs_as_added_on_both_sides() {
+        let ours = state(T1, &[("cli", spec("c", T1, &[("a", "a1")]))]);
+        let theirs = state(T2, &[("docs", spec("d", T2, &[]))]);
+        let merged = merge_states(None, &ours, &theirs);
+        assert!(merged.conflicts.is_empty());
+        assert_eq!(merged.state.specs.len(), 2);
+    }
+}
@@ -0,0 +1,205 @@
+//! Per-spec reconciliation status, as shown by `notarai status`.
+//!
+//! Combines the spec walk, the local cache, and the committed state file
+//! into one row per spec. The cache is preferred as the record of the last
+//! reconciliation because it is updated as soon as a spec is marked; the
+//! state file supplies the commit, and covers specs reconciled elsewhere.
+
+use crate::core::spec::{Domain, Spec, Tier};
+use crate::core::state::{self, ReconciliationState};
+use crate::core::{cache, mcp_tools, time, validator};
+use crate::error::{Error, Result};
+use crate::project::Project;
+use rusqlite::Connection;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Reconciliation status of one spec.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    /// Spec and governed files match the last reconciliation.
+    Reconciled,
+    /// At least one governed file changed since the last reconciliation.
+    ArtifactsChanged,
+    /// The spec itself changed since it was last reconciled.
+    SpecChanged,
+    /// No reconciliation is recorded for the spec.
+    NeverReconciled,
+    /// The spec does not validate against the schema.
+    Invalid,
+}
+
+impl Status {
+    pub fn label(self) -> &'static str {
+        match self {
+            Status::Reconciled => "reconciled",
+            Status::ArtifactsChanged => "artifacts changed",
+            Status::SpecChanged => "spec changed",
+            Status::NeverReconciled => "never reconciled",
+            Status::Invalid => "invalid",
+        }
+    }
+}
+
+/// One row of `notarai status`.
+#[derive(Debug, Serialize)]
+pub struct SpecStatus {
+    pub spec_path: String,
+    pub tier: Option<Tier>,
+    pub domain: Option<Domain>,
+    pub status: Status,
+    /// Files the spec's artifact globs currently match.
+    pub governed_files: usize,
+    /// Governed files that changed since the last reconciliation. Empty when
+    /// the spec was never reconciled or is invalid.
+    pub changed_files: Vec<String>,
+    /// When the spec was last reconciled (ISO 8601 UTC).
+    pub reconciled_at: Option<String>,
+    /// Commit recorded for that reconciliation. `None` until a state snapshot
+    /// records it.
+    pub commit: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<String>,
+}
+
+/// The last recorded reconciliation of a spec.
+struct LastReconciliation {
+    fingerprint: String,
+    reconciled_at: String,
+    commit: Option<String>,
+}
+
+/// Compute the status of every spec in the project, ordered by path.
+pub fn collect_status(project_root: &Path) -> Result<Vec<SpecStatus>> {
+    let project = Project::open(project_root);
+    let specs = project.spec_files()?;
+    let state = project.load_state()?;
+    let conn = cache::open_cache_db(project_root)?;
+    // Specs reconciled elsewhere (only recorded in the state file) are seeded
+    // into the cache first, so their files can be checked like any other.
+    if let Some(state) = &state {
+        state::restore_cache(project_root, &conn, state)?;
+    }
+    let cached: BTreeMap<String, (String, i64)> = cache::read_specs(&conn)?
+        .into_iter()
+        .map(|s| (s.spec_path, (s.fingerprint, s.marked_at)))
+        .collect();
+
+    let mut rows = Vec::with_capacity(specs.len());
+    for spec_path in specs {
+        let spec_rel = spec_path
+            .strip_prefix(project_root)
+            .map(|p| p.to_string_lossy().to_string())
+            .unwrap_or_else(|_| spec_path.to_string_lossy().to_string());
+        let content = std::fs::read_to_string(&spec_path).map_err(|e| Error::io(&spec_path, e))?;
+        let last = last_reconciliation(&spec_rel, &cached, state.as_ref());
+        rows.push(spec_status(project_root, &conn, spec_rel, &content, last)?);
+    }
+    Ok(rows)
+}
+
+/// Prefer the cache's record; take the commit from the state file only when
+/// it describes the same reconciliation.
+fn last_reconciliation(
+    spec_rel: &str,
+    cached: &BTreeMap<String, (String, i64)>,
+    state: Option<&ReconciliationState>,
+) -> Option<LastReconciliation> {
+    let recorded = state.and_then(|s| s.specs.get(spec_rel));
+    match cached.get(spec_rel) {
+        Some((fingerprint, marked_at)) => {
+            let reconciled_at = time::format_utc(*marked_at);
+            let commit = recorded
+                .filter(|r| r.reconciled_at == reconciled_at)
+                .and_then(|r| r.git_hash.clone());
+            Some(LastReconciliation {
+                fingerprint: fingerprint.clone(),
+                reconciled_at,
+                commit,
+            })
+        }
+        None => recorded.map(|r| LastReconciliation {
+            fingerprint: r.fingerprint.clone(),
+            reconciled_at: r.reconciled_at.clone(),
+            commit: r.git_hash.clone(),
+        }),
+    }
+}
+
+fn spec_status(
+    project_root: &Path,
+    conn: &Connection,
+    spec_rel: String,
+    content: &str,
+    last: Option<LastReconciliation>,
+) -> Result<SpecStatus> {
+    let (reconciled_at, commit) = match &last {
+        Some(l) => (Some(l.reconciled_at.clone()), l.commit.clone()),
+        None => (None, None),
+    };
+    let mut row = SpecStatus {
+        spec_path: spec_rel,
+        tier: None,
+        domain: None,
+        status: Status::Invalid,
+        governed_files: 0,
+        changed_files: Vec::new(),
+        reconciled_at,
+        commit,
+        errors: Vec::new(),
+    };
+
+    let validation = validator::validate_spec(content);
+    let parsed = Spec::parse(content);
+    if let Ok(spec) = &parsed {
+        row.tier = Some(spec.tier());
+        row.domain = Some(spec.domain());
+    }
+    let spec = match parsed {
+        Ok(spec) if validation.valid => spec,
+        Err(e) if validation.valid => {
+            row.errors = vec![e.to_string()];
+            return Ok(row);
+        }
+        _ => {
+            row.errors = validation.errors;
+            return Ok(row);
+        }
+    };
+
+    let files = mcp_tools::expand_artifact_globs(&spec, project_root);
+    row.governed_files = files.len();
+    let Some(last) = last else {
+        row.status = Status::NeverReconciled;
+        return Ok(row);
+    };
+    let fingerprint = cache::spec_fingerprint(content.as_bytes());
+    if last.fingerprint != fingerprint {
+        row.status = Status::SpecChanged;
+        return Ok(row);
+    }
+
+    let pairs: Vec<_> = files
+        .into_iter()
+        .map(|rel| {
+            let abs = project_root.join(&rel);
+            (rel, abs)
+        })
+        .collect();
+    let (changed, _unchanged) = cache::check_changed_batch(
+        conn,
+        mcp_tools::normalize_rel(&row.spec_path),
+        &fingerprint,
+        &pairs,
+        false,
+    )?;
+    row.status = if changed.is_empty() {
+        Status::Reconciled
+    } else {
+        Status::ArtifactsChanged
+    };
+    row.changed_files = changed;
+    Ok(row)
+}
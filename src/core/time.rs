@@ -0,0 +1,153 @@
+//! Wall-clock helpers shared by the cache, run history, and state file.
+//!
+//! Timestamps are stored as Unix epoch seconds and rendered as ISO 8601 UTC
+//! (`2026-03-04T12:00:00Z`) for display. No timezone database is needed, so
+//! this avoids pulling in a date/time crate.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Current time as Unix epoch seconds.
+pub fn now_secs() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64
+}
+
+/// Format Unix epoch seconds as an ISO 8601 UTC timestamp.
+pub fn format_utc(secs: i64) -> String {
+    let days = secs.div_euclid(86_400);
+    let rem = secs.rem_euclid(86_400);
+    let (year, month, day) = civil_from_days(days);
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60
+    )
+}
+
+/// Parse an ISO 8601 UTC timestamp in the form written by `format_utc` back
+/// into Unix epoch seconds. Returns `None` for any other form.
+pub fn parse_utc(timestamp: &str) -> Option<i64> {
+    let b = timestamp.as_bytes();
+    if b.len() != 20 || b[4] != b'-' || b[7] != b'-' || b[10] != b'T' || b[13] != b':' {
+        return None;
+    }
+    if b[16] != b':' || b[19] != b'Z' {
+        return None;
+    }
+    let num = |range: std::ops::Range<usize>| timestamp.get(range)?.parse::<i64>().ok();
+    let (year, month, day) = (num(0..4)?, num(5..7)?, num(8..10)?);
+    let (hour, minute, second) = (num(11..13)?, num(14..16)?, num(17..19)?);
+    if !(1..=12).contains(&month)
+        || !(1..=31).contains(&day)
+        || hour > 23
+        || minute > 59
+        || second > 59
+    {
+        return None;
+    }
+    let days = days_from_civil(year, month as u32, day as u32);
+    Some(days * 86_400 + hour * 3600 + minute * 60 + second)
+}
+
+/// Parse a timestamp as found in metric data: Unix epoch seconds, a
+/// `YYYY-MM-DD` date (midnight UTC), or the ISO 8601 UTC form accepted by
+/// `parse_utc`.
+pub fn parse_timestamp(timestamp: &str) -> Option<i64> {
+    let timestamp = timestamp.trim();
+    if let Ok(secs) = timestamp.parse::<i64>() {
+        return Some(secs);
+    }
+    if timestamp.len() == 10 {
+        return parse_utc(&format!("{timestamp}T00:00:00Z"));
+    }
+    parse_utc(timestamp)
+}
+
+/// Render the time elapsed since `secs` compactly: `just now`, `5m ago`,
+/// `3h ago`, `2d ago`.
+pub fn format_age(secs: i64, now: i64) -> String {
+    let elapsed = now - secs;
+    match elapsed {
+        ..60 => "just now".to_string(),
+        60..3600 => format!("{}m ago", elapsed / 60),
+        3600..86_400 => format!("{}h ago", elapsed / 3600),
+        _ => format!("{}d ago", elapsed / 86_400),
+    }
+}
+
+/// Inverse of `civil_from_days` (Howard Hinnant's `days_from_civil`).
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y.rem_euclid(400);
+    let mp = i64::from((month + 9) % 12);
+    let doy = (153 * mp + 2) / 5 + i64::from(day) - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// Convert days since 1970-01-01 to a proleptic Gregorian (year, month, day).
+///
+/// Howard Hinnant's `civil_from_days` algorithm.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_epoch() {
+        assert_eq!(format_utc(0), "1970-01-01T00:00:00Z");
+    }
+
+    #[test]
+    fn formats_known_timestamps() {
+        assert_eq!(format_utc(1_700_000_000), "2023-11-14T22:13:20Z");
+        assert_eq!(format_utc(1_709_164_800), "2024-02-29T00:00:00Z");
+        assert_eq!(format_utc(951_782_400), "2000-02-29T00:00:00Z");
+    }
+
+    #[test]
+    fn parse_round_trips_format() {
+        for secs in [0, 951_782_400, 1_700_000_000, 1_709_164_800, 4_102_444_799] {
+            assert_eq!(parse_utc(&format_utc(secs)), Some(secs));
+        }
+        assert_eq!(parse_utc("1700000000Z"), None);
+        assert_eq!(parse_utc("2026-13-01T00:00:00Z"), None);
+    }
+
+    #[test]
+    fn parses_metric_timestamps() {
+        assert_eq!(parse_timestamp("1700000000"), Some(1_700_000_000));
+        assert_eq!(parse_timestamp("2024-02-29"), Some(1_709_164_800));
+        assert_eq!(
+            parse_timestamp(" 2023-11-14T22:13:20Z"),
+            Some(1_700_000_000)
+        );
+        assert_eq!(parse_timestamp("last week"), None);
+    }
+
+    #[test]
+    fn formats_age() {
+        assert_eq!(format_age(1000, 1030), "just now");
+        assert_eq!(format_age(1000, 1000 + 5 * 60), "5m ago");
+        assert_eq!(format_age(1000, 1000 + 3 * 3600 + 59), "3h ago");
+        assert_eq!(format_age(1000, 1000 + 2 * 86_400), "2d ago");
+        // Clock skew never renders a negative age.
+        assert_eq!(format_age(1000, 900), "just now");
+    }
+}
@@ -3,6 +3,7 @@ use crate::core::yaml;
 use jsonschema::Validator;
 use std::sync::OnceLock;
 
+#[derive(Debug, Clone)]
 pub struct ValidationResult {
     pub valid: bool,
     pub errors: Vec<String>,
@@ -39,8 +40,14 @@ pub fn validate_spec(content: &str) -> ValidationResult {
         }
     };
 
+    validate_value(&data)
+}
+
+/// Validate an already-parsed spec against the bundled schema. Used for specs
+/// computed in memory, such as a resolved variant.
+pub fn validate_value(data: &serde_json::Value) -> ValidationResult {
     let errors: Vec<String> = validator()
-        .iter_errors(&data)
+        .iter_errors(data)
         .map(|err| {
             let path = err.instance_path().to_string();
             let path = if path.is_empty() {
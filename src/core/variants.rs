@@ -0,0 +1,346 @@
+//! Variant override resolution.
+//!
+//! A spec with `variants_resolved: true` opts in to computing the effective
+//! spec of each variant by applying its dot-path `overrides` to the base
+//! spec:
+//!
+//! - a scalar replaces the targeted field;
+//! - an array replaces the targeted array, unless the key is prefixed with
+//!   `+` (`+constraints`), in which case its items are appended;
+//! - an object is deep-merged into the targeted object, with the same rules
+//!   applied to each of its keys;
+//! - `null` removes the targeted field.
+//!
+//! Path segments name object keys; a numeric segment indexes an array
+//! (`behaviors.0.then`). An override whose path does not exist in the base
+//! spec is not applied and is reported instead, since it is almost always a
+//! typo. The same goes for each key of an object override: `audience:
+//! {locale: en}` is reported like `audience.locale: en` when the base
+//! audience has no locale.
+//!
+//! Overrides are applied in the order they are written, so `audience: null`
+//! followed by `audience.role` reports the second as unmatched, while the
+//! reverse order applies both. Resolution therefore works on the YAML value,
+//! whose mappings keep document order.
+
+use crate::{Error, Result};
+use serde::Serialize;
+use serde_yaml_ng::{Mapping, Value};
+use std::path::Path;
+
+/// Result of `resolve_variant`.
+#[derive(Debug)]
+pub struct Resolution {
+    /// The base spec with the overrides applied and `variants` removed.
+    pub spec: serde_json::Value,
+    /// Override paths that do not exist in the base spec, with the reason
+    /// they were not applied.
+    pub unmatched: Vec<String>,
+}
+
+/// A variant's effective spec validated against the schema, as returned by
+/// `resolve`.
+#[derive(Debug, Serialize)]
+pub struct ResolvedVariant {
+    pub spec_path: String,
+    pub variant: String,
+    pub spec: serde_json::Value,
+    pub valid: bool,
+    /// Schema validation errors of the effective spec.
+    pub errors: Vec<String>,
+    /// Override paths that do not exist in the base spec, with the reason
+    /// they were not applied.
+    pub unmatched_overrides: Vec<String>,
+}
+
+/// Read the spec at `spec_path` (project-relative), resolve its variant
+/// `variant_id` and validate the result.
+pub fn resolve(project_root: &Path, spec_path: &str, variant_id: &str) -> Result<ResolvedVariant> {
+    let spec_path = crate::core::mcp_tools::normalize_rel(spec_path);
+    let abs = project_root.join(spec_path);
+    let content = std::fs::read_to_string(&abs).map_err(|e| Error::io(&abs, e))?;
+    let base: Value = serde_yaml_ng::from_str(&content).map_err(|e| Error::InvalidSpec {
+        path: spec_path.to_string(),
+        message: e.to_string(),
+    })?;
+    let resolution = resolve_variant(spec_path, &base, variant_id)?;
+    let validation = crate::core::validator::validate_value(&resolution.spec);
+    Ok(ResolvedVariant {
+        spec_path: spec_path.to_string(),
+        variant: variant_id.to_string(),
+        spec: resolution.spec,
+        valid: validation.valid,
+        errors: validation.errors,
+        unmatched_overrides: resolution.unmatched,
+    })
+}
+
+/// Apply the overrides of variant `variant_id` to `base`, the parsed YAML of
+/// the spec at `spec_path`.
+///
+/// Fails with `Error::InvalidSpec` when the spec has not opted in with
+/// `variants_resolved: true` or has no variant with that id.
+pub fn resolve_variant(spec_path: &str, base: &Value, variant_id: &str) -> Result<Resolution> {
+    let invalid = |message: String| Error::InvalidSpec {
+        path: spec_path.to_string(),
+        message,
+    };
+    if base.get("variants_resolved") != Some(&Value::Bool(true)) {
+        return Err(invalid(
+            "does not set variants_resolved: true; its variants are declarative only".to_string(),
+        ));
+    }
+    let variant = base
+        .get("variants")
+        .and_then(|v| v.as_sequence())
+        .into_iter()
+        .flatten()
+        .find(|v| v.get("id").and_then(|id| id.as_str()) == Some(variant_id))
+        .ok_or_else(|| invalid(format!("has no variant '{variant_id}'")))?;
+
+    let mut spec = base.clone();
+    if let Some(map) = spec.as_mapping_mut() {
+        map.shift_remove("variants");
+    }
+    let mut unmatched = Vec::new();
+    let empty = Mapping::new();
+    let overrides = variant
+        .get("overrides")
+        .and_then(|o| o.as_mapping())
+        .unwrap_or(&empty);
+    for (key, value) in overrides {
+        let key = key_name(key);
+        if let Err(reason) = apply_override(&mut spec, &key, value, &mut unmatched) {
+            unmatched.push(format!("{key}: {reason}"));
+        }
+    }
+    let spec = serde_json::to_value(spec).map_err(Error::model("spec"))?;
+    Ok(Resolution { spec, unmatched })
+}
+
+/// Apply one top-level override. Keys of an object override that do not
+/// exist are pushed to `unmatched` by `merge`.
+fn apply_override(
+    spec: &mut Value,
+    key: &str,
+    value: &Value,
+    unmatched: &mut Vec<String>,
+) -> Result<(), String> {
+    let (append, path) = match key.strip_prefix('+') {
+        Some(path) => (true, path),
+        None => (false, key),
+    };
+    let segments: Vec<&str> = path.split('.').collect();
+    let (last, parents) = segments
+        .split_last()
+        .filter(|(last, _)| !last.is_empty())
+        .ok_or_else(|| "empty path".to_string())?;
+
+    let mut node = spec;
+    for (depth, segment) in parents.iter().enumerate() {
+        node = child_mut(node, segment)
+            .ok_or_else(|| format!("'{}' does not exist", segments[..=depth].join(".")))?;
+    }
+    let Some(target) = child_mut(node, last) else {
+        return Err(format!("'{path}' does not exist"));
+    };
+
+    if append {
+        return append_to(target, value, path);
+    }
+    if value.is_null() {
+        match node {
+            Value::Mapping(map) => {
+                map.shift_remove(*last);
+            }
+            Value::Sequence(items) => {
+                items.remove(index(last).expect("index checked above"));
+            }
+            _ => unreachable!("child_mut only succeeds on mappings and sequences"),
+        }
+        return Ok(());
+    }
+    merge(target, value, path, unmatched);
+    Ok(())
+}
+
+fn append_to(target: &mut Value, value: &Value, path: &str) -> Result<(), String> {
+    let Value::Sequence(target) = target else {
+        return Err(format!("'{path}' is not an array; cannot append"));
+    };
+    match value {
+        Value::Sequence(items) => target.extend(items.iter().cloned()),
+        item => target.push(item.clone()),
+    }
+    Ok(())
+}
+
+fn index(segment: &str) -> Option<usize> {
+    segment.parse().ok()
+}
+
+fn child_mut<'a>(node: &'a mut Value, segment: &str) -> Option<&'a mut Value> {
+    match node {
+        Value::Mapping(map) => map.get_mut(segment),
+        Value::Sequence(items) => items.get_mut(index(segment)?),
+        _ => None,
+    }
+}
+
+/// A mapping key as it appears in an override path.
+fn key_name(key: &Value) -> String {
+    match key {
+        Value::String(s) => s.clone(),
+        other => serde_yaml_ng::to_string(other)
+            .map(|s| s.trim_end().to_string())
+            .unwrap_or_default(),
+    }
+}
+
+/// Deep-merge `value` into `target`, the value at `path`: objects merge key
+/// by key in document order, `null` removes a key, a `+`-prefixed key
+/// appends to an array, anything else replaces. Keys that do not exist in
+/// `target` are reported in `unmatched` as their dot path would be.
+fn merge(target: &mut Value, value: &Value, path: &str, unmatched: &mut Vec<String>) {
+    let (Value::Mapping(target_map), Value::Mapping(value_map)) = (&mut *target, value) else {
+        *target = value.clone();
+        return;
+    };
+    for (key, v) in value_map {
+        let name = key_name(key);
+        if let Some(field) = name.strip_prefix('+') {
+            let child = format!("{path}.{field}");
+            let result = match target_map.get_mut(field) {
+                Some(existing) => append_to(existing, v, &child),
+                None => Err(format!("'{child}' does not exist")),
+            };
+            if let Err(reason) = result {
+                unmatched.push(format!("+{child}: {reason}"));
+            }
+            continue;
+        }
+        let child = format!("{path}.{name}");
+        if !target_map.contains_key(key) {
+            unmatched.push(format!("{child}: '{child}' does not exist"));
+        } else if v.is_null() {
+            target_map.shift_remove(key);
+        } else if let Some(existing) = target_map.get_mut(key) {
+            merge(existing, v, &child, unmatched);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn base(overrides: &str) -> Value {
+        serde_yaml_ng::from_str(&format!(
+            "schema_version: '0.7'\n\
+             intent: Deck\n\
+             variants_resolved: true\n\
+             constraints: [a, b]\n\
+             audience: {{role: engineers, tone: casual, accessibility: [alt-text]}}\n\
+             behaviors: [{{name: x, given: g, then: t}}]\n\
+             variants:\n  - id: v\n    overrides: {overrides}\n"
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn applies_each_override_rule() {
+        let spec = base(
+            "{audience.role: investors, +constraints: [c], \
+             audience: {tone: null, +accessibility: captions}, \
+             behaviors.0.then: t2, intent: null}",
+        );
+        let resolved = resolve_variant("x", &spec, "v").unwrap();
+        assert!(resolved.unmatched.is_empty(), "{:?}", resolved.unmatched);
+        let s = &resolved.spec;
+        assert_eq!(s["audience"]["role"], "investors");
+        assert_eq!(s["constraints"], json!(["a", "b", "c"]));
+        assert!(s["audience"].get("tone").is_none());
+        assert_eq!(
+            s["audience"]["accessibility"],
+            json!(["alt-text", "captions"])
+        );
+        assert_eq!(s["behaviors"][0]["then"], "t2");
+        assert!(s.get("intent").is_none());
+        assert!(s.get("variants").is_none());
+    }
+
+    #[test]
+    fn arrays_are_replaced_without_plus() {
+        let spec = base("{constraints: [z]}");
+        let resolved = resolve_variant("x", &spec, "v").unwrap();
+        assert_eq!(resolved.spec["constraints"], json!(["z"]));
+    }
+
+    #[test]
+    fn reports_paths_missing_from_base() {
+        let spec = base("{audience.rol: typo, design.theme.palette: ['#000'], +intent: x}");
+        let resolved = resolve_variant("x", &spec, "v").unwrap();
+        assert_eq!(
+            resolved.unmatched,
+            [
+                "audience.rol: 'audience.rol' does not exist",
+                "design.theme.palette: 'design' does not exist",
+                "+intent: 'intent' is not an array; cannot append",
+            ]
+        );
+        assert_eq!(resolved.spec["audience"]["role"], "engineers");
+    }
+
+    #[test]
+    fn reports_keys_of_object_overrides_missing_from_base() {
+        let spec = base(
+            "{audience: {locale: en-US, +captions: yes, +role: x, region: null}, \
+             behaviors: [{name: y}]}",
+        );
+        let resolved = resolve_variant("x", &spec, "v").unwrap();
+        assert_eq!(
+            resolved.unmatched,
+            [
+                "audience.locale: 'audience.locale' does not exist",
+                "+audience.captions: 'audience.captions' does not exist",
+                "+audience.role: 'audience.role' is not an array; cannot append",
+                "audience.region: 'audience.region' does not exist",
+            ]
+        );
+        let audience = resolved.spec["audience"].as_object().unwrap();
+        assert!(audience.keys().all(|k| !k.starts_with('+')), "{audience:?}");
+        assert!(audience.get("locale").is_none());
+        assert_eq!(resolved.spec["behaviors"], json!([{"name": "y"}]));
+    }
+
+    #[test]
+    fn overrides_apply_in_document_order() {
+        let cleared_first =
+            resolve_variant("x", &base("{audience: null, audience.role: x}"), "v").unwrap();
+        assert_eq!(
+            cleared_first.unmatched,
+            ["audience.role: 'audience' does not exist"]
+        );
+        let cleared_last =
+            resolve_variant("x", &base("{audience.role: x, audience: null}"), "v").unwrap();
+        assert!(cleared_last.unmatched.is_empty());
+        assert!(cleared_last.spec.get("audience").is_none());
+    }
+
+    #[test]
+    fn requires_opt_in_and_known_variant() {
+        let mut spec = base("{}");
+        assert!(
+            resolve_variant("x", &spec, "nope")
+                .unwrap_err()
+                .to_string()
+                .contains("no variant")
+        );
+        spec["variants_resolved"] = Value::Bool(false);
+        assert!(matches!(
+            resolve_variant("x", &spec, "v"),
+            Err(Error::InvalidSpec { ref message, .. }) if message.contains("variants_resolved")
+        ));
+    }
+}
@@ -0,0 +1,153 @@
+//! Error type returned by the library API.
+
+use std::fmt;
+use std::path::PathBuf;
+
+/// Shorthand for results of library calls.
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// An error from loading specs or reading and writing reconciliation data.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// Reading or writing a file failed.
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    /// A spec file is not valid YAML or does not fit the spec model.
+    InvalidSpec { path: String, message: String },
+    /// Source text is not valid YAML.
+    Yaml(serde_yaml_ng::Error),
+    /// A parsed document (`what`: a spec, a notebook, a cache export) does
+    /// not have the expected shape.
+    Model {
+        what: &'static str,
+        source: serde_json::Error,
+    },
+    /// The reconciliation state file could not be parsed.
+    InvalidState(String),
+    /// The project has no reconciliation state file yet.
+    MissingState,
+    /// A file was written by a newer notarai with a schema this version
+    /// cannot read.
+    UnsupportedVersion {
+        what: &'static str,
+        found: String,
+        supported: String,
+    },
+    /// The cache database could not be opened, read or written.
+    Cache {
+        context: &'static str,
+        source: rusqlite::Error,
+    },
+    /// No spec exists at this project-relative path.
+    SpecNotFound(String),
+    /// A spec does not exist at the git revision `rev`.
+    SpecNotAtRevision { path: String, rev: String },
+    /// A name given for a new spec is not a valid spec path.
+    InvalidSpecName(String),
+    /// A spec already exists where a new one was to be written.
+    SpecExists(String),
+    /// A new spec could not be added to the system spec's `subsystems`.
+    Registration {
+        spec: String,
+        system: String,
+        message: String,
+    },
+    /// An `applies` reference in the spec at `from` does not resolve to a
+    /// spec.
+    UnresolvedApplies { from: String, reference: String },
+    /// A configuration file under `.notarai/` is invalid.
+    InvalidConfig { path: PathBuf, message: String },
+    /// A git command failed.
+    Git { command: String, message: String },
+    /// A value is not one of the names `what` accepts.
+    UnknownValue {
+        what: &'static str,
+        value: String,
+        expected: String,
+    },
+}
+
+impl Error {
+    pub(crate) fn io(path: impl Into<PathBuf>, source: std::io::Error) -> Error {
+        Error::Io {
+            path: path.into(),
+            source,
+        }
+    }
+
+    /// Wrap a database error with what was being done.
+    pub(crate) fn cache(context: &'static str) -> impl FnOnce(rusqlite::Error) -> Error {
+        move |source| Error::Cache { context, source }
+    }
+
+    /// Wrap a deserialization error with what was being read.
+    pub(crate) fn model(what: &'static str) -> impl FnOnce(serde_json::Error) -> Error {
+        move |source| Error::Model { what, source }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io { path, source } => write!(f, "{}: {source}", path.display()),
+            Error::InvalidSpec { path, message } => write!(f, "{path}: {message}"),
+            Error::Yaml(source) => write!(f, "{source}"),
+            Error::Model { what, source } => write!(f, "invalid {what}: {source}"),
+            Error::InvalidState(message) => write!(f, "could not parse state file: {message}"),
+            Error::MissingState => write!(
+                f,
+                "no reconciliation state found; run `notarai state snapshot` first"
+            ),
+            Error::UnsupportedVersion {
+                what,
+                found,
+                supported,
+            } => write!(
+                f,
+                "{what} schema version {found} is newer than this notarai supports \
+                 ({supported}); upgrade notarai"
+            ),
+            Error::Cache { context, source } => write!(f, "{context}: {source}"),
+            Error::SpecNotFound(path) => write!(f, "spec not found: {path}"),
+            Error::SpecNotAtRevision { path, rev } => write!(f, "{path} does not exist at {rev}"),
+            Error::InvalidSpecName(name) => write!(
+                f,
+                "invalid spec name '{name}': use letters, digits, '-', '_' and '.', with '/' between directories"
+            ),
+            Error::SpecExists(path) => write!(f, "{path} already exists"),
+            Error::Registration {
+                spec,
+                system,
+                message,
+            } => write!(f, "could not register {spec} in {system}: {message}"),
+            Error::UnresolvedApplies { from, reference } => write!(
+                f,
+                "{from}: applies reference '{reference}' does not resolve to a spec"
+            ),
+            Error::InvalidConfig { path, message } => {
+                write!(f, "invalid {}: {message}", path.display())
+            }
+            Error::Git { command, message } => write!(f, "git {command}: {message}"),
+            Error::UnknownValue {
+                what,
+                value,
+                expected,
+            } => write!(f, "unknown {what} '{value}' (expected one of: {expected})"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io { source, .. } => Some(source),
+            Error::Yaml(source) => Some(source),
+            Error::Model { source, .. } => Some(source),
+            Error::Cache { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,125 @@
+//! NotarAI as a library: load a spec project, validate its specs, resolve
+//! the files they govern, find the specs a change affects, and read and
+//! write reconciliation state.
+//!
+//! ```no_run
+//! use notarai::Project;
+//!
+//! # fn main() -> notarai::Result<()> {
+//! let project = Project::open(".");
+//! for (path, result) in project.validate()? {
+//!     println!("{path}: {}", if result.valid { "PASS" } else { "FAIL" });
+//! }
+//!
+//! let changed = vec!["src/main.rs".to_string()];
+//! for loaded in project.affected_specs(&changed)? {
+//!     let files = project.governed_files(&loaded.spec);
+//!     println!("{} governs {} file(s)", loaded.path, files.len());
+//! }
+//!
+//! if let Some(state) = project.load_state()? {
+//!     println!("last reconciled at {}", state.last_reconciliation.timestamp);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Everything public is re-exported here; the modules below group the
+//! result types of the `Project` methods by command. The implementation
+//! lives in a private module and may change between releases.
+
+mod core;
+mod error;
+mod project;
+
+pub use crate::core::compose::{AppliedSpec, Composition, SourcedRule};
+pub use crate::core::mcp_tools::normalize_rel;
+pub use crate::core::state::{
+    FileFingerprint, ReconciliationMeta, ReconciliationState, STATE_SCHEMA_VERSION, SpecState,
+};
+pub use crate::core::validator::{ValidationResult, validate_spec};
+pub use crate::core::{schema, spec, time};
+pub use error::{Error, Result};
+pub use project::{LoadedSpec, Marked, Project};
+
+/// Reconciliation cache contents, as exported and imported by
+/// `Project::export_cache` and `Project::import_cache`.
+pub mod cache {
+    pub use crate::core::cache::{CacheExport, ExportedFile, ExportedSpec};
+}
+
+/// Compliance statements and their removal between revisions.
+pub mod compliance {
+    pub use crate::core::compliance::{
+        Change, ControlRow, FrameworkRows, Removal, Statement, StatementKind, has_frameworks,
+        matrix, to_markdown,
+    };
+}
+
+/// Structural checks of `content` sections.
+pub mod content {
+    pub use crate::core::content::{Finding, FindingKind, analyze, to_mermaid};
+}
+
+/// Freshness of derived artifacts.
+pub mod derived {
+    pub use crate::core::derived::{DerivedStatus, Staleness};
+}
+
+/// Evidence checks of `content` sections.
+pub mod evidence {
+    pub use crate::core::evidence::{ChangedClaim, Issue, IssueKind, Report};
+}
+
+/// Feedback metrics and triggers.
+pub mod feedback {
+    pub use crate::core::feedback::{
+        MetricResult, MetricStatus, Report, Sample, Triggered, operator_name,
+    };
+}
+
+/// The spec dependency graph.
+pub mod graph {
+    pub use crate::core::graph::{Edge, EdgeKind, Format, Graph, Node, NodeKind};
+}
+
+/// The MCP tools served by `notarai mcp`.
+pub mod mcp {
+    pub use crate::core::mcp_tools::{McpError, McpResult, call_tool, tools};
+}
+
+/// Pipeline runs.
+pub mod pipeline {
+    pub use crate::core::pipeline::{Event, Outcome, RunOptions, StepResult};
+}
+
+/// Reconciliation run history.
+pub mod runs {
+    pub use crate::core::runs::RunSummary;
+}
+
+/// New spec skeletons.
+pub mod scaffold {
+    pub use crate::core::scaffold::{NewSpec, Registration};
+}
+
+/// Reconciliation state beyond the types re-exported at the crate root.
+pub mod state {
+    pub use crate::core::state::{RestoreSummary, SpecDrift, StateDiff, parse_state, write_state};
+    pub use crate::core::state_merge::{StateMerge, merge_states};
+}
+
+/// State machine checks of `states` sections.
+pub mod state_machine {
+    pub use crate::core::state_machine::{Finding, FindingKind, analyze, render};
+}
+
+/// Per-spec reconciliation status.
+pub mod status {
+    pub use crate::core::status::{SpecStatus, Status};
+}
+
+/// Spec variants.
+pub mod variants {
+    pub use crate::core::variants::ResolvedVariant;
+}
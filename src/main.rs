@@ -1,7 +1,9 @@
 mod commands;
-mod core;
+mod update;
 
 use clap::{Parser, Subcommand};
+use notarai::graph::Format;
+use notarai::spec::{Domain, Tier};
 use std::process;
 
 #[derive(Parser)]
@@ -36,8 +38,106 @@ enum Commands {
     },
     /// MCP server (stdio JSON-RPC 2.0 transport)
     Mcp,
+    /// Create a spec skeleton and register it in the system spec
+    New {
+        /// Spec name; the file is written to .notarai/<name>.spec.yaml
+        name: String,
+        /// Spec domain: software, presentation, report, course, marketing, legal, education, infrastructure or research
+        #[arg(long, default_value = "software")]
+        domain: Domain,
+        /// Coverage tier: full, registered or derived
+        #[arg(long, default_value = "full")]
+        tier: Tier,
+        /// Files, directories or globs the spec governs
+        paths: Vec<String>,
+    },
     /// Update schema version across all specs in the project
     SchemaBump,
+    /// Show the history of reconciliation runs (newest first)
+    Log {
+        /// Maximum number of runs to show
+        #[arg(short = 'n', long, default_value_t = 20)]
+        limit: usize,
+        /// List every marked file under its spec
+        #[arg(long)]
+        files: bool,
+        /// Print the history as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Show the reconciliation status of every spec
+    Status {
+        /// Print the status as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Print the effective spec of a variant (requires variants_resolved: true)
+    Resolve {
+        /// Path to the spec file
+        spec: String,
+        /// The id of the variant to resolve
+        #[arg(long)]
+        variant: String,
+        /// Print the resolution report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Render the relationship graph of all specs
+    Graph {
+        /// Output format: dot, mermaid or json
+        #[arg(long, default_value = "dot")]
+        format: Format,
+        /// Show each spec's artifact categories as leaf nodes
+        #[arg(long)]
+        artifacts: bool,
+        /// Only show this spec and its subsystems, recursively
+        #[arg(long, conflicts_with = "from")]
+        subtree: Option<String>,
+        /// Only show this spec and every spec it references, recursively
+        #[arg(long)]
+        from: Option<String>,
+    },
+    /// Show a spec's intent, behaviors, constraints and invariants
+    Show {
+        /// Path to the spec file
+        spec: String,
+        /// Include the invariants and constraints of cross-cutting specs that apply to it
+        #[arg(long)]
+        effective: bool,
+        /// Print the spec as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Check and export the structure of a spec's content sections
+    Content {
+        #[command(subcommand)]
+        action: commands::content::ContentAction,
+    },
+    /// Check and export the state machine in a spec's states block
+    States {
+        #[command(subcommand)]
+        action: commands::states::StatesAction,
+    },
+    /// Run the build steps declared in a spec's pipeline
+    Pipeline {
+        #[command(subcommand)]
+        action: commands::pipeline::PipelineAction,
+    },
+    /// Check derived artifacts for staleness against their pipeline inputs
+    Derived {
+        #[command(subcommand)]
+        action: commands::derived::DerivedAction,
+    },
+    /// Evaluate feedback metrics and triggers from local data
+    Feedback {
+        #[command(subcommand)]
+        action: commands::feedback::FeedbackAction,
+    },
+    /// Report compliance controls and guard the statements that satisfy them
+    Compliance {
+        #[command(subcommand)]
+        action: commands::compliance::ComplianceAction,
+    },
     /// Manage reconciliation state
     State {
         #[command(subcommand)]
@@ -68,7 +168,44 @@ fn main() {
         },
         Some(Commands::Cache { action }) => commands::cache::run(action),
         Some(Commands::Mcp) => commands::mcp::run(),
+        Some(Commands::New {
+            name,
+            domain,
+            tier,
+            paths,
+        }) => commands::new::run(&name, domain, tier, &paths),
         Some(Commands::SchemaBump) => commands::schema_bump::run(None),
+        Some(Commands::Log { limit, files, json }) => commands::log::run(limit, files, json),
+        Some(Commands::Status { json }) => commands::status::run(json),
+        Some(Commands::Resolve {
+            spec,
+            variant,
+            json,
+        }) => commands::resolve::run(&spec, &variant, json),
+        Some(Commands::Graph {
+            format,
+            artifacts,
+            subtree,
+            from,
+        }) => {
+            let selection = match (subtree, from) {
+                (Some(spec), _) => commands::graph::Selection::Subtree(spec),
+                (None, Some(spec)) => commands::graph::Selection::ReachableFrom(spec),
+                (None, None) => commands::graph::Selection::All,
+            };
+            commands::graph::run(format, artifacts, selection)
+        }
+        Some(Commands::Show {
+            spec,
+            effective,
+            json,
+        }) => commands::show::run(&spec, effective, json),
+        Some(Commands::Content { action }) => commands::content::run(action),
+        Some(Commands::States { action }) => commands::states::run(action),
+        Some(Commands::Pipeline { action }) => commands::pipeline::run(action),
+        Some(Commands::Derived { action }) => commands::derived::run(action),
+        Some(Commands::Feedback { action }) => commands::feedback::run(action),
+        Some(Commands::Compliance { action }) => commands::compliance::run(action),
         Some(Commands::State { action }) => commands::state::run(action),
         Some(Commands::Update { check }) => commands::update::run(check),
         None => {
@@ -0,0 +1,475 @@
+//! A NotarAI project on disk: its specs, their artifacts and its
+//! reconciliation state.
+
+use crate::core::cache::{self, CacheExport};
+use crate::core::compliance::{self, Removal};
+use crate::core::compose::{Composition, SpecGraph};
+use crate::core::derived::{self, DerivedStatus};
+use crate::core::evidence::{self, Records};
+use crate::core::feedback;
+use crate::core::graph::{self, Graph};
+use crate::core::normalize::{Normalizer, NormalizerRules};
+use crate::core::pipeline::{self, Event, RunOptions, StepResult};
+use crate::core::runs::{self, RunSummary};
+use crate::core::scaffold::{self, NewSpec};
+use crate::core::spec::{Domain, Spec, Tier};
+use crate::core::state::{self, ReconciliationState, RestoreSummary, StateDiff};
+use crate::core::status::{self, SpecStatus};
+use crate::core::validator::{self, ValidationResult};
+use crate::core::variants::{self, ResolvedVariant};
+use crate::core::{mcp_tools, time};
+use crate::error::{Error, Result};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// A project rooted at the directory that contains `.notarai/`.
+#[derive(Debug, Clone)]
+pub struct Project {
+    root: PathBuf,
+}
+
+/// A spec file read from a project.
+#[derive(Debug, Clone)]
+pub struct LoadedSpec {
+    /// Path relative to the project root (`.notarai/cli.spec.yaml`).
+    pub path: String,
+    /// Fingerprint of the spec's content, as recorded by reconciliation (see
+    /// `cache::spec_fingerprint`). Line endings and trailing whitespace are
+    /// ignored, so it is not a hash of the raw file bytes.
+    pub fingerprint: String,
+    pub spec: Spec,
+}
+
+/// Outcome of `Project::mark_reconciled`.
+#[derive(Debug, Clone, Serialize)]
+pub struct Marked {
+    /// Records written.
+    pub updated: usize,
+    /// The reconciliation run the files were logged against.
+    pub run_id: i64,
+    /// Files that exist but could not be hashed, with the reason. They were
+    /// not recorded.
+    pub failed: Vec<(String, String)>,
+}
+
+impl Project {
+    /// A project rooted at `root`. Nothing is read until a method needs it.
+    pub fn open(root: impl Into<PathBuf>) -> Project {
+        Project { root: root.into() }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Every `*.spec.yaml` file under `.notarai/`, as absolute paths in sorted
+    /// order. Empty when the project has no `.notarai/` directory.
+    pub fn spec_files(&self) -> Result<Vec<PathBuf>> {
+        let notarai_dir = self.root.join(".notarai");
+        let mut specs = Vec::new();
+        if !notarai_dir.exists() {
+            return Ok(specs);
+        }
+        for entry in walkdir::WalkDir::new(&notarai_dir) {
+            let entry = entry.map_err(|e| {
+                let path = e.path().unwrap_or(&notarai_dir).to_path_buf();
+                Error::io(path, e.into())
+            })?;
+            if entry.file_type().is_file()
+                && entry.file_name().to_string_lossy().ends_with(".spec.yaml")
+            {
+                specs.push(entry.into_path());
+            }
+        }
+        specs.sort();
+        Ok(specs)
+    }
+
+    /// Read and parse one spec. `path` is relative to the project root.
+    pub fn load_spec(&self, path: &str) -> Result<LoadedSpec> {
+        let path = mcp_tools::normalize_rel(path);
+        let abs = self.root.join(path);
+        let content = std::fs::read_to_string(&abs).map_err(|e| Error::io(&abs, e))?;
+        let spec = Spec::parse(&content).map_err(|e| Error::InvalidSpec {
+            path: path.to_string(),
+            message: e.to_string(),
+        })?;
+        Ok(LoadedSpec {
+            path: path.to_string(),
+            fingerprint: cache::spec_fingerprint(content.as_bytes()),
+            spec,
+        })
+    }
+
+    /// Read and parse every spec, failing on the first one that cannot be
+    /// parsed.
+    pub fn load_specs(&self) -> Result<Vec<LoadedSpec>> {
+        self.spec_files()?
+            .iter()
+            .map(|abs| self.load_spec(&self.relative(abs)))
+            .collect()
+    }
+
+    /// Validate every spec against the bundled schema. Returns each spec's
+    /// relative path with its result.
+    pub fn validate(&self) -> Result<Vec<(String, ValidationResult)>> {
+        self.spec_files()?
+            .iter()
+            .map(|abs| {
+                let content = std::fs::read_to_string(abs).map_err(|e| Error::io(abs, e))?;
+                Ok((self.relative(abs), validator::validate_spec(&content)))
+            })
+            .collect()
+    }
+
+    /// Project-relative paths of the files a spec's artifact globs match.
+    pub fn governed_files(&self, spec: &Spec) -> Vec<String> {
+        mcp_tools::expand_artifact_globs(spec, &self.root)
+    }
+
+    /// Specs with an artifact glob matching at least one of `changed`
+    /// (project-relative paths).
+    pub fn affected_specs(&self, changed: &[String]) -> Result<Vec<LoadedSpec>> {
+        let mut specs = self.load_specs()?;
+        specs.retain(|s| mcp_tools::is_spec_affected(&s.spec, changed));
+        Ok(specs)
+    }
+
+    /// A spec's effective invariants and constraints: its own, followed by
+    /// those of every cross-cutting spec that `applies` to it directly or
+    /// through the specs that contain it as a subsystem. Fails when an
+    /// `applies` reference it depends on does not resolve to a spec that
+    /// parses.
+    pub fn effective_spec(&self, path: &str) -> Result<Composition> {
+        let path = mcp_tools::normalize_rel(path);
+        SpecGraph::load(&self.root)?.compose(path)?.into_complete()
+    }
+
+    /// The dependency graph of every spec, with artifact globs as nodes when
+    /// `artifacts` is set.
+    pub fn graph(&self, artifacts: bool) -> Result<Graph> {
+        Ok(graph::build(&SpecGraph::load(&self.root)?, artifacts))
+    }
+
+    /// Write a skeleton spec named `name` (`auth`, `api/auth`) for the given
+    /// domain and tier, governing `paths`, and register it in the system
+    /// spec's `subsystems`. Nothing is written when the spec already exists
+    /// or the system spec cannot be edited in place.
+    pub fn new_spec(
+        &self,
+        name: &str,
+        domain: Domain,
+        tier: Tier,
+        paths: &[String],
+    ) -> Result<NewSpec> {
+        scaffold::create(&self.root, name, domain, tier, paths)
+    }
+
+    /// The effective spec of one of a spec's variants, validated against the
+    /// bundled schema.
+    pub fn resolve_variant(&self, path: &str, variant: &str) -> Result<ResolvedVariant> {
+        variants::resolve(&self.root, path, variant)
+    }
+
+    /// Reconciliation status of every spec: whether its governed files
+    /// changed since it was last reconciled.
+    pub fn status(&self) -> Result<Vec<SpecStatus>> {
+        status::collect_status(&self.root)
+    }
+
+    /// Check the evidence sources of each spec's content sections, comparing
+    /// them with the records made for the spec. `verify` rehashes every
+    /// source instead of trusting unchanged size and mtime. Reports are in
+    /// the order of `specs`.
+    pub fn check_evidence(
+        &self,
+        specs: &[LoadedSpec],
+        verify: bool,
+    ) -> Result<Vec<evidence::Report>> {
+        let graph = SpecGraph::load(&self.root)?;
+        let conn = cache::open_cache_db(&self.root)?;
+        specs
+            .iter()
+            .map(|l| {
+                let records = Records {
+                    conn: &conn,
+                    spec_path: &l.path,
+                    spec_fingerprint: &l.fingerprint,
+                    verify_hashes: verify,
+                };
+                evidence::check(&self.root, &l.spec, &graph, Some(&records))
+            })
+            .collect()
+    }
+
+    /// Evaluate a spec's feedback metrics and triggers now.
+    pub fn check_feedback(&self, spec: &LoadedSpec) -> feedback::Report {
+        feedback::evaluate(&self.root, &spec.path, &spec.spec, time::now_secs())
+    }
+
+    /// Run a spec's pipeline steps (see `RunOptions`), calling `on_event` as
+    /// each step starts and finishes. Step failures are reported in the
+    /// results; `Err` means the pipeline could not be run at all.
+    pub fn run_pipeline(
+        &self,
+        spec: &LoadedSpec,
+        options: &RunOptions,
+        on_event: impl FnMut(Event),
+    ) -> Result<Vec<StepResult>> {
+        pipeline::run(&self.root, &spec.path, &spec.spec, options, on_event)
+    }
+
+    /// Freshness of every derived artifact, in spec order.
+    pub fn derived_status(&self) -> Result<Vec<DerivedStatus>> {
+        let graph = SpecGraph::load(&self.root)?;
+        let conn = cache::open_cache_db(&self.root)?;
+        derived::check(&conn, &self.root, &graph)
+    }
+
+    /// Record the current inputs of the existing outputs of every pipeline
+    /// step in `specs` (every spec when empty), without running the steps.
+    /// Returns the number of outputs recorded.
+    pub fn record_derived_inputs(&self, specs: &[String]) -> Result<usize> {
+        let graph = SpecGraph::load(&self.root)?;
+        let selected: Vec<&str> = specs.iter().map(|s| mcp_tools::normalize_rel(s)).collect();
+        if let Some(unknown) = selected
+            .iter()
+            .find(|s| !graph.iter().any(|(path, _)| path == **s))
+        {
+            return Err(Error::SpecNotFound(unknown.to_string()));
+        }
+        let conn = cache::open_cache_db(&self.root)?;
+        let mut recorded = 0;
+        for (path, spec) in graph.iter() {
+            if !selected.is_empty() && !selected.contains(&path) {
+                continue;
+            }
+            let spec = spec.map_err(|e| Error::InvalidSpec {
+                path: path.to_string(),
+                message: e.to_string(),
+            })?;
+            for step in spec.pipeline.iter().flat_map(|p| &p.steps) {
+                recorded += derived::record(&conn, &self.root, step)?;
+            }
+        }
+        Ok(recorded)
+    }
+
+    /// The cited compliance statements removed between git revision `base`
+    /// and `head` (the working tree when `None`), for each spec in `specs`
+    /// (every spec with compliance frameworks at `base` when empty).
+    pub fn compliance_removals(
+        &self,
+        specs: &[String],
+        base: &str,
+        head: Option<&str>,
+    ) -> Result<Vec<(String, Vec<Removal>)>> {
+        compliance::removals(&self.root, specs, base, head)
+    }
+
+    /// Path of the reconciliation cache database. It is created on first
+    /// use.
+    pub fn cache_path(&self) -> PathBuf {
+        cache::db_path(&self.root)
+    }
+
+    /// The number of file records in the cache and when the newest was
+    /// written, or `None` when there is no cache yet.
+    pub fn cache_status(&self) -> Result<Option<(usize, Option<i64>)>> {
+        if !self.cache_path().exists() {
+            return Ok(None);
+        }
+        cache::status(&cache::open_cache_db(&self.root)?).map(Some)
+    }
+
+    /// Delete every reconciliation record, keeping run history. Returns the
+    /// number of file records removed, or `None` when there is no cache.
+    pub fn clear_cache(&self) -> Result<Option<usize>> {
+        if !self.cache_path().exists() {
+            return Ok(None);
+        }
+        cache::clear(&cache::open_cache_db(&self.root)?).map(Some)
+    }
+
+    /// Remove records for files and specs that no longer exist. Returns
+    /// `(files, specs)` removed, or `None` when there is no cache.
+    pub fn prune_cache(&self) -> Result<Option<(usize, usize)>> {
+        if !self.cache_path().exists() {
+            return Ok(None);
+        }
+        cache::prune(&cache::open_cache_db(&self.root)?, &self.root).map(Some)
+    }
+
+    /// Every spec fingerprint and its records, in a portable form.
+    pub fn export_cache(&self) -> Result<CacheExport> {
+        cache::export(&cache::open_cache_db(&self.root)?)
+    }
+
+    /// Load an export into the cache. Returns the number of file records
+    /// written.
+    pub fn import_cache(&self, export: &CacheExport) -> Result<usize> {
+        cache::import(&cache::open_cache_db(&self.root)?, export)
+    }
+
+    /// Seed the cache from the state file with the records that still hold.
+    /// Fails with `Error::MissingState` when there is no state file.
+    pub fn restore_cache(&self) -> Result<RestoreSummary> {
+        let state = self.load_state()?.ok_or(Error::MissingState)?;
+        let conn = cache::open_cache_db_no_restore(&self.root)?;
+        state::restore_cache(&self.root, &conn, &state)
+    }
+
+    /// Record `files` (project-relative) as reconciled against the spec at
+    /// `spec_path`, logging them against the open reconciliation run.
+    ///
+    /// Each file that exists is hashed with the normalizer chosen for its
+    /// artifact category in the spec and its extension; missing files are
+    /// skipped. Records made against an earlier revision of the spec are
+    /// discarded first.
+    pub fn mark_reconciled(&self, spec_path: &str, files: &[String]) -> Result<Marked> {
+        let spec_path = mcp_tools::normalize_rel(spec_path);
+        let spec_abs = self.root.join(spec_path);
+        let content = std::fs::read_to_string(&spec_abs).map_err(|e| Error::io(&spec_abs, e))?;
+        let spec_fingerprint = cache::spec_fingerprint(content.as_bytes());
+        let spec = Spec::parse(&content)?;
+        let rules = NormalizerRules::load(&self.root)?;
+        let conn = cache::open_cache_db(&self.root)?;
+
+        // Stat before hashing: if a file is rewritten mid-hash, its recorded
+        // mtime is older than the content, so the next check rehashes it.
+        let existing: Vec<(String, PathBuf, Option<cache::FileStat>)> = files
+            .iter()
+            .map(|f| mcp_tools::normalize_rel(f).to_string())
+            .map(|f| {
+                let abs = self.root.join(&f);
+                (f, abs)
+            })
+            .filter(|(_, abs)| abs.exists())
+            .map(|(f, abs)| {
+                let stat = cache::stat_file(&abs);
+                (f, abs, stat)
+            })
+            .collect();
+        let paths: Vec<(&Path, Normalizer)> = existing
+            .iter()
+            .map(|(f, abs, _)| {
+                let category = mcp_tools::artifact_category(&spec, f);
+                (abs.as_path(), rules.select(f, category))
+            })
+            .collect();
+        let hashes = cache::hash_files(&paths);
+
+        let mut records = Vec::new();
+        let mut failed = Vec::new();
+        for (((f, _, stat), (_, normalizer)), hash) in existing.iter().zip(&paths).zip(hashes) {
+            match hash {
+                Ok(hash) => records.push(cache::FileRecord {
+                    path: f.clone(),
+                    hash,
+                    stat: *stat,
+                    normalizer: *normalizer,
+                }),
+                Err(e) => failed.push((f.clone(), e.to_string())),
+            }
+        }
+
+        let updated = cache::upsert_batch(&conn, spec_path, &spec_fingerprint, &records)?;
+        let marked: Vec<String> = records.into_iter().map(|r| r.path).collect();
+        let run_id = runs::record_marks(&conn, spec_path, &marked)?;
+        Ok(Marked {
+            updated,
+            run_id,
+            failed,
+        })
+    }
+
+    /// Which of `files` (project-relative) changed since they were last
+    /// marked for the spec at `spec_path`. Returns each normalized path with
+    /// `true` when it changed, in input order. `verify` rehashes every file
+    /// instead of trusting unchanged size and mtime.
+    pub fn changed_files(
+        &self,
+        spec_path: &str,
+        files: &[String],
+        verify: bool,
+    ) -> Result<Vec<(String, bool)>> {
+        let spec_path = mcp_tools::normalize_rel(spec_path);
+        let spec_abs = self.root.join(spec_path);
+        let content = std::fs::read(&spec_abs).map_err(|e| Error::io(&spec_abs, e))?;
+        let fingerprint = cache::spec_fingerprint(&content);
+        let pairs: Vec<(String, PathBuf)> = files
+            .iter()
+            .map(|f| {
+                let rel = mcp_tools::normalize_rel(f).to_string();
+                let abs = self.root.join(&rel);
+                (rel, abs)
+            })
+            .collect();
+        let conn = cache::open_cache_db(&self.root)?;
+        let (changed, _unchanged) =
+            cache::check_changed_batch(&conn, spec_path, &fingerprint, &pairs, verify)?;
+        Ok(pairs
+            .into_iter()
+            .map(|(rel, _)| {
+                let is_changed = changed.contains(&rel);
+                (rel, is_changed)
+            })
+            .collect())
+    }
+
+    /// The most recent reconciliation runs, newest first. Empty when there
+    /// is no cache yet.
+    pub fn runs(&self, limit: usize) -> Result<Vec<RunSummary>> {
+        if !self.cache_path().exists() {
+            return Ok(Vec::new());
+        }
+        runs::list_runs(&cache::open_cache_db(&self.root)?, limit)
+    }
+
+    /// Close the open reconciliation run, if any. Returns its id.
+    pub fn finish_run(&self) -> Result<Option<i64>> {
+        runs::finish_current_run(&cache::open_cache_db(&self.root)?)
+    }
+
+    /// Path of the reconciliation state file.
+    pub fn state_path(&self) -> PathBuf {
+        state::state_path(&self.root)
+    }
+
+    /// The committed reconciliation state, or `None` if there is none yet.
+    /// Older state schemas are migrated on read.
+    pub fn load_state(&self) -> Result<Option<ReconciliationState>> {
+        state::load_state(&self.root)
+    }
+
+    /// Atomically write the reconciliation state file.
+    pub fn save_state(&self, state: &ReconciliationState) -> Result<()> {
+        state::save_state(&self.root, state)
+    }
+
+    /// Build a new state from the local cache, merged with the existing state
+    /// file. Nothing is written; pass the result to `save_state`. Fails if the
+    /// existing state file cannot be loaded.
+    pub fn snapshot_state(&self) -> Result<ReconciliationState> {
+        state::snapshot_from_cache(&self.root, false)
+    }
+
+    /// `snapshot_state`, starting over from the cache alone when the
+    /// existing state file cannot be loaded.
+    pub fn force_snapshot_state(&self) -> Result<ReconciliationState> {
+        state::snapshot_from_cache(&self.root, true)
+    }
+
+    /// Drift of the governed files on disk from the state file, grouped by
+    /// spec. Fails with `Error::MissingState` when there is no state file.
+    pub fn state_diff(&self) -> Result<StateDiff> {
+        state::drift(&self.root)
+    }
+
+    fn relative(&self, abs: &Path) -> String {
+        abs.strip_prefix(&self.root)
+            .unwrap_or(abs)
+            .to_string_lossy()
+            .to_string()
+    }
+}
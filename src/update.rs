@@ -325,7 +325,7 @@ mod tests {
         assert!(v2 > v1);
 
         let v3 = Version::parse("0.3.0").unwrap();
-        assert!(!(v3 > v1));
+        assert!(v3 <= v1);
     }
 
     #[test]
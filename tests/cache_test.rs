@@ -50,14 +50,14 @@ fn clear_prints_not_initialized_when_no_db() {
 }
 
 #[test]
-fn clear_removes_db_when_it_exists() {
-    let tmp = TempDir::new().unwrap();
-    let db_dir = tmp.path().join(".notarai/.cache");
-    fs::create_dir_all(&db_dir).unwrap();
-    let db = db_dir.join("notarai.db");
-    // Write a placeholder file to simulate an existing cache DB
-    fs::write(&db, b"placeholder").unwrap();
-
+fn clear_empties_db_when_it_exists() {
+    let tmp = project_with_spec();
+    notarai()
+        .args(["cache", "mark", "--spec", ".notarai/a.spec.yaml", "a.txt"])
+        .current_dir(tmp.path())
+        .assert()
+        .success();
+    let db = tmp.path().join(".notarai/.cache/notarai.db");
     assert!(db.exists());
 
     notarai()
@@ -67,5 +67,199 @@ fn clear_removes_db_when_it_exists() {
         .success()
         .stdout(predicate::str::contains("cleared"));
 
-    assert!(!db.exists());
+    // The file stays in place for other open connections; only rows go.
+    assert!(db.exists());
+    notarai()
+        .args(["cache", "status"])
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Entries: 0"));
+}
+
+fn project_with_spec() -> TempDir {
+    let tmp = TempDir::new().unwrap();
+    fs::create_dir_all(tmp.path().join(".notarai")).unwrap();
+    fs::write(
+        tmp.path().join(".notarai/a.spec.yaml"),
+        "schema_version: '0.8'\n",
+    )
+    .unwrap();
+    fs::write(tmp.path().join("a.txt"), "alpha").unwrap();
+    fs::write(tmp.path().join("b.txt"), "beta").unwrap();
+    tmp
+}
+
+#[test]
+fn mark_then_check_reports_unchanged() {
+    let tmp = project_with_spec();
+    notarai()
+        .args(["cache", "mark", "--spec", ".notarai/a.spec.yaml", "./a.txt"])
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Marked 1 file(s)"));
+
+    notarai()
+        .args(["cache", "check", "--spec", ".notarai/a.spec.yaml", "a.txt"])
+        .current_dir(tmp.path())
+        .assert()
+        .code(0)
+        .stdout(predicate::str::contains("unchanged  a.txt"));
+}
+
+#[test]
+fn check_exits_1_when_a_file_changed() {
+    let tmp = project_with_spec();
+    notarai()
+        .args(["cache", "mark", "--spec", ".notarai/a.spec.yaml", "a.txt"])
+        .current_dir(tmp.path())
+        .assert()
+        .success();
+
+    notarai()
+        .args([
+            "cache",
+            "check",
+            "--spec",
+            ".notarai/a.spec.yaml",
+            "a.txt",
+            "b.txt",
+        ])
+        .current_dir(tmp.path())
+        .assert()
+        .code(1)
+        .stdout(predicate::str::contains("unchanged  a.txt"))
+        .stdout(predicate::str::contains("changed    b.txt"));
+}
+
+#[test]
+fn prune_drops_records_for_deleted_files() {
+    let tmp = project_with_spec();
+    notarai()
+        .args([
+            "cache",
+            "mark",
+            "--spec",
+            ".notarai/a.spec.yaml",
+            "a.txt",
+            "b.txt",
+        ])
+        .current_dir(tmp.path())
+        .assert()
+        .success();
+    fs::remove_file(tmp.path().join("b.txt")).unwrap();
+
+    notarai()
+        .args(["cache", "prune"])
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Pruned 1 record(s) and 0 spec(s)"));
+}
+
+#[test]
+fn export_then_import_starts_another_checkout_warm() {
+    let tmp = project_with_spec();
+    notarai()
+        .args(["cache", "mark", "--spec", ".notarai/a.spec.yaml", "a.txt"])
+        .current_dir(tmp.path())
+        .assert()
+        .success();
+    let export = tmp.path().join("cache.json");
+    notarai()
+        .args(["cache", "export"])
+        .arg(&export)
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Exported 1 record(s) for 1 spec(s)",
+        ));
+
+    let other = project_with_spec();
+    notarai()
+        .args(["cache", "import"])
+        .arg(&export)
+        .current_dir(other.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Imported 1 record(s)"));
+    notarai()
+        .args(["cache", "check", "--spec", ".notarai/a.spec.yaml", "a.txt"])
+        .current_dir(other.path())
+        .assert()
+        .code(0);
+}
+
+/// Mark both files, snapshot the state, then drop the cache as a fresh clone
+/// would have it and edit `b.txt`.
+fn fresh_clone_with_state() -> TempDir {
+    let tmp = project_with_spec();
+    notarai()
+        .args([
+            "cache",
+            "mark",
+            "--spec",
+            ".notarai/a.spec.yaml",
+            "a.txt",
+            "b.txt",
+        ])
+        .current_dir(tmp.path())
+        .assert()
+        .success();
+    notarai()
+        .args(["state", "snapshot"])
+        .current_dir(tmp.path())
+        .assert()
+        .success();
+    fs::remove_dir_all(tmp.path().join(".notarai/.cache")).unwrap();
+    fs::write(tmp.path().join("b.txt"), "beta, edited").unwrap();
+    tmp
+}
+
+#[test]
+fn cold_cache_is_seeded_from_state() {
+    let tmp = fresh_clone_with_state();
+    notarai()
+        .args([
+            "cache",
+            "check",
+            "--spec",
+            ".notarai/a.spec.yaml",
+            "a.txt",
+            "b.txt",
+        ])
+        .current_dir(tmp.path())
+        .assert()
+        .code(1)
+        .stdout(predicate::str::contains("unchanged  a.txt"))
+        .stdout(predicate::str::contains("changed    b.txt"));
+}
+
+#[test]
+fn restore_seeds_matching_files_only() {
+    let tmp = fresh_clone_with_state();
+    notarai()
+        .args(["cache", "restore"])
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Restored 1 record(s) for 1 spec(s)",
+        ))
+        .stdout(predicate::str::contains(
+            "Skipped 1 file(s) changed since they were reconciled",
+        ));
+}
+
+#[test]
+fn restore_without_state_fails() {
+    let tmp = project_with_spec();
+    notarai()
+        .args(["cache", "restore"])
+        .current_dir(tmp.path())
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("no reconciliation state found"));
 }
@@ -0,0 +1,191 @@
+use assert_cmd::cargo_bin_cmd;
+use predicates::prelude::*;
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn notarai() -> assert_cmd::Command {
+    cargo_bin_cmd!("notarai")
+}
+
+fn git(dir: &Path, args: &[&str]) {
+    std::process::Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .unwrap();
+}
+
+const AUTH_SPEC: &str = r#"schema_version: '0.7'
+intent: 'Authentication'
+invariants:
+  - 'no plaintext passwords stored anywhere'
+  - 'sessions expire after 30 minutes'
+constraints:
+  - 'rate limit: 5 login attempts per minute per IP'
+compliance:
+  frameworks:
+    - name: SOC2
+      controls:
+        - id: CC6.1
+          satisfied_by:
+            invariants: ['no plaintext passwords stored anywhere']
+            constraints: ['rate limit: 5 login attempts per minute per IP']
+        - id: CC7.2
+    - name: HIPAA
+      satisfied_by:
+        invariants: ['sessions expire after 30 minutes']
+"#;
+
+/// A git repo whose base commit has `AUTH_SPEC`.
+fn project() -> TempDir {
+    let tmp = TempDir::new().unwrap();
+    let root = tmp.path();
+    git(root, &["init", "-b", "main"]);
+    git(root, &["config", "user.email", "test@notarai.dev"]);
+    git(root, &["config", "user.name", "NotarAI Test"]);
+    git(root, &["config", "commit.gpgsign", "false"]);
+    fs::create_dir_all(root.join(".notarai")).unwrap();
+    fs::write(root.join(".notarai/auth.spec.yaml"), AUTH_SPEC).unwrap();
+    git(root, &["add", "."]);
+    git(root, &["commit", "-m", "base"]);
+    tmp
+}
+
+#[test]
+fn compliance_report_prints_markdown_matrix() {
+    let tmp = project();
+    notarai()
+        .args(["compliance", "report"])
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with(
+            "# Compliance matrix\n\n## .notarai/auth.spec.yaml\n\n### SOC2\n",
+        ))
+        .stdout(predicate::str::contains(
+            "| CC6.1 | invariant: no plaintext passwords stored anywhere<br>\
+             constraint: rate limit: 5 login attempts per minute per IP | satisfied |",
+        ))
+        .stdout(predicate::str::contains("| CC7.2 | — | **UNSATISFIED** |"))
+        .stdout(predicate::str::contains(
+            "| *(framework)* | invariant: sessions expire after 30 minutes | satisfied |",
+        ));
+}
+
+#[test]
+fn compliance_report_json() {
+    let tmp = project();
+    let output = notarai()
+        .args(["compliance", "report", ".notarai/auth.spec.yaml", "--json"])
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let report: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    let soc2 = &report[0]["frameworks"][0];
+    assert_eq!(report[0]["spec_path"], ".notarai/auth.spec.yaml");
+    assert_eq!(soc2["name"], "SOC2");
+    assert_eq!(soc2["controls"][0]["id"], "CC6.1");
+    assert_eq!(soc2["controls"][0]["satisfied"], true);
+    assert_eq!(soc2["controls"][0]["satisfied_by"][1]["kind"], "constraint");
+    assert_eq!(soc2["controls"][1]["satisfied"], false);
+    assert_eq!(
+        report[0]["frameworks"][1]["controls"][0]["id"],
+        serde_json::Value::Null
+    );
+}
+
+#[test]
+fn compliance_check_passes_when_cited_statements_are_unchanged() {
+    let tmp = project();
+    notarai()
+        .args(["compliance", "check"])
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stdout("PASS .notarai/auth.spec.yaml\n");
+}
+
+#[test]
+fn compliance_check_fails_on_deleted_or_reworded_statements() {
+    let tmp = project();
+    let edited = AUTH_SPEC
+        .replace(
+            "  - 'no plaintext passwords stored anywhere'\n",
+            "  - 'no plaintext passwords are stored anywhere'\n",
+        )
+        .replace("  - 'sessions expire after 30 minutes'\n", "");
+    fs::write(tmp.path().join(".notarai/auth.spec.yaml"), edited).unwrap();
+    notarai()
+        .args(["compliance", "check", "--base", "main"])
+        .current_dir(tmp.path())
+        .assert()
+        .code(1)
+        .stdout(
+            "FAIL .notarai/auth.spec.yaml\n  \
+             - [SOC2 CC6.1] invariant reworded: 'no plaintext passwords stored anywhere' -> \
+             'no plaintext passwords are stored anywhere'\n  \
+             - [HIPAA] invariant deleted: 'sessions expire after 30 minutes'\n",
+        );
+
+    let output = notarai()
+        .args(["compliance", "check", "--json"])
+        .current_dir(tmp.path())
+        .assert()
+        .code(1)
+        .get_output()
+        .stdout
+        .clone();
+    let report: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(report[0]["findings"][0]["change"], "reworded");
+    assert_eq!(report[0]["findings"][1]["change"], "deleted");
+    assert_eq!(report[0]["findings"][1]["framework"], "HIPAA");
+}
+
+#[test]
+fn compliance_check_errors_on_unknown_revision() {
+    let tmp = project();
+    notarai()
+        .args(["compliance", "check", "--base", "nope"])
+        .current_dir(tmp.path())
+        .assert()
+        .code(2)
+        .stderr(predicate::str::starts_with("Error: "));
+}
+
+#[test]
+fn compliance_check_compares_against_a_head_revision() {
+    let tmp = project();
+    let edited = AUTH_SPEC.replace("  - 'sessions expire after 30 minutes'\n", "");
+    fs::write(tmp.path().join(".notarai/auth.spec.yaml"), edited).unwrap();
+    git(tmp.path(), &["commit", "-am", "drop session rule"]);
+    fs::write(tmp.path().join(".notarai/auth.spec.yaml"), AUTH_SPEC).unwrap();
+
+    notarai()
+        .args(["compliance", "check"])
+        .current_dir(tmp.path())
+        .assert()
+        .success();
+    notarai()
+        .args(["compliance", "check", "--base", "HEAD~1", "--head", "HEAD"])
+        .current_dir(tmp.path())
+        .assert()
+        .code(1)
+        .stdout(predicate::str::contains(
+            "[HIPAA] invariant deleted: 'sessions expire after 30 minutes'",
+        ));
+}
+
+#[test]
+fn compliance_check_errors_on_a_spec_missing_at_base() {
+    let tmp = project();
+    notarai()
+        .args(["compliance", "check", ".notarai/billing.spec.yaml"])
+        .current_dir(tmp.path())
+        .assert()
+        .code(2)
+        .stderr("Error: .notarai/billing.spec.yaml does not exist at main\n");
+}
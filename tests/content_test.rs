@@ -0,0 +1,205 @@
+use assert_cmd::cargo_bin_cmd;
+use predicates::prelude::*;
+use std::fs;
+use tempfile::TempDir;
+
+fn notarai() -> assert_cmd::Command {
+    cargo_bin_cmd!("notarai")
+}
+
+fn spec(content: &str) -> String {
+    format!(
+        "schema_version: '0.7'\nintent: 'x'\ndomain: presentation\n\
+         artifacts:\n  slides:\n    - path: 'slides/**'\ncontent:\n{content}"
+    )
+}
+
+fn project(content: &str) -> TempDir {
+    let tmp = TempDir::new().unwrap();
+    let dir = tmp.path().join(".notarai");
+    fs::create_dir_all(&dir).unwrap();
+    fs::create_dir_all(tmp.path().join("slides")).unwrap();
+    fs::write(tmp.path().join("slides/intro.md"), "# Intro").unwrap();
+    fs::write(dir.join("deck.spec.yaml"), spec(content)).unwrap();
+    fs::write(
+        dir.join("cli.spec.yaml"),
+        "schema_version: '0.7'\nintent: 'x'\nartifacts:\n  code:\n    - path: 'src/**'\n",
+    )
+    .unwrap();
+    tmp
+}
+
+#[test]
+fn content_check_passes_clean_specs() {
+    let tmp = project(
+        "  sections:\n    - id: intro\n      content_ref: 'slides/intro.md'\n    \
+         - id: demo\n      depends_on:\n        - id: intro\n",
+    );
+    notarai()
+        .args(["content", "check"])
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stdout("PASS .notarai/deck.spec.yaml\n");
+}
+
+#[test]
+fn content_check_reports_findings() {
+    let tmp = project(
+        "  structure: graph\n  sections:\n    \
+         - id: intro\n      content_ref: 'slides/missing.md'\n      \
+         connections:\n        - to: demo\n    \
+         - id: demo\n    - id: bonus\n",
+    );
+    notarai()
+        .args(["content", "check", ".notarai/deck.spec.yaml"])
+        .current_dir(tmp.path())
+        .assert()
+        .code(1)
+        .stdout(predicate::str::contains("FAIL .notarai/deck.spec.yaml"))
+        .stdout(predicate::str::contains(
+            "[intro] content_ref 'slides/missing.md' matches no file",
+        ))
+        .stdout(predicate::str::contains(
+            "[bonus] 'bonus' cannot be reached from the entry section 'intro'",
+        ));
+}
+
+#[test]
+fn content_check_json() {
+    let tmp = project(
+        "  sections:\n    - id: a\n      depends_on:\n        - id: b\n    \
+         - id: b\n      depends_on:\n        - id: a\n",
+    );
+    let output = notarai()
+        .args(["content", "check", "--json"])
+        .current_dir(tmp.path())
+        .assert()
+        .code(1)
+        .get_output()
+        .stdout
+        .clone();
+    let report: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(report[0]["spec_path"], ".notarai/deck.spec.yaml");
+    let kinds: Vec<&str> = report[0]["findings"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|f| f["kind"].as_str().unwrap())
+        .collect();
+    assert_eq!(kinds, ["prerequisite_order", "dependency_cycle"]);
+}
+
+#[test]
+fn content_check_missing_spec_is_an_error() {
+    let tmp = project("  sections:\n    - id: a\n");
+    notarai()
+        .args(["content", "check", ".notarai/nope.spec.yaml"])
+        .current_dir(tmp.path())
+        .assert()
+        .code(2);
+}
+
+#[test]
+fn content_graph_prints_mermaid() {
+    let tmp = project("  sections:\n    - id: intro\n    - id: demo\n");
+    notarai()
+        .args(["content", "graph", ".notarai/deck.spec.yaml"])
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stdout("flowchart TD\n  s0[\"intro\"]\n  s1[\"demo\"]\n  s0 --> s1\n");
+
+    notarai()
+        .args(["content", "graph", ".notarai/cli.spec.yaml"])
+        .current_dir(tmp.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("has no content sections"));
+}
+
+const EVIDENCE: &str = "  sections:\n    - id: results\n      evidence:\n        \
+    - type: data\n          source: 'slides/intro.md'\n          claim: 'Intro is short'\n        \
+    - type: data\n          source: 'data/raw.csv'\n          claim: 'Raw data backs it'\n        \
+    - type: data\n          source: 'data/gone.csv'\n          claim: 'Gone data backs it'\n";
+
+#[test]
+fn content_evidence_reports_sources_and_changed_claims() {
+    let tmp = project(EVIDENCE);
+    fs::create_dir_all(tmp.path().join("data")).unwrap();
+    fs::write(tmp.path().join("data/raw.csv"), "a\n1\n").unwrap();
+    notarai()
+        .args(["content", "evidence"])
+        .current_dir(tmp.path())
+        .assert()
+        .code(1)
+        .stdout(
+            "FAIL .notarai/deck.spec.yaml\n  \
+             - [results] source data/raw.csv is not governed by any spec\n  \
+             - [results] source data/gone.csv does not exist\n  \
+             - [results] slides/intro.md changed: 'Intro is short'\n  \
+             - [results] data/raw.csv changed: 'Raw data backs it'\n",
+        );
+
+    notarai()
+        .args([
+            "cache",
+            "mark",
+            "--spec",
+            ".notarai/deck.spec.yaml",
+            "slides/intro.md",
+        ])
+        .current_dir(tmp.path())
+        .assert()
+        .success();
+    let output = notarai()
+        .args(["content", "evidence", ".notarai/deck.spec.yaml", "--json"])
+        .current_dir(tmp.path())
+        .assert()
+        .code(1)
+        .get_output()
+        .stdout
+        .clone();
+    let report: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(report[0]["issues"][0]["kind"], "ungoverned_source");
+    assert_eq!(report[0]["issues"][1]["kind"], "missing_source");
+    let changed = report[0]["changed_claims"].as_array().unwrap();
+    assert_eq!(changed.len(), 1);
+    assert_eq!(changed[0]["section"], "results");
+    assert_eq!(changed[0]["claim"], "Raw data backs it");
+
+    fs::write(tmp.path().join("slides/intro.md"), "# Intro v2").unwrap();
+    notarai()
+        .args(["content", "evidence"])
+        .current_dir(tmp.path())
+        .assert()
+        .code(1)
+        .stdout(predicate::str::contains(
+            "slides/intro.md changed: 'Intro is short'",
+        ));
+}
+
+#[test]
+fn content_evidence_passes_reconciled_sources() {
+    let tmp = project(
+        "  sections:\n    - id: intro\n      evidence:\n        \
+         - type: data\n          source: 'slides/intro.md'\n          claim: 'Intro is short'\n",
+    );
+    notarai()
+        .args([
+            "cache",
+            "mark",
+            "--spec",
+            ".notarai/deck.spec.yaml",
+            "slides/intro.md",
+        ])
+        .current_dir(tmp.path())
+        .assert()
+        .success();
+    notarai()
+        .args(["content", "evidence"])
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stdout("PASS .notarai/deck.spec.yaml\n");
+}
@@ -0,0 +1,99 @@
+use assert_cmd::cargo_bin_cmd;
+use predicates::prelude::*;
+use std::fs;
+use tempfile::TempDir;
+
+fn notarai() -> assert_cmd::Command {
+    cargo_bin_cmd!("notarai")
+}
+
+const DECK_SPEC: &str = "schema_version: '0.7'\nintent: 'x'\ndomain: presentation\n\
+    artifacts:\n  slides:\n    - path: 'slides/**'\n  \
+    exports:\n    - path: 'dist/deck.txt'\n      tier: 4\n\
+    pipeline:\n  steps:\n    - name: build\n      \
+    command: 'mkdir -p dist && cat slides/*.md > dist/deck.txt'\n      \
+    input: 'slides/*.md'\n      output: 'dist/deck.txt'\n";
+
+fn project() -> TempDir {
+    let tmp = TempDir::new().unwrap();
+    let dir = tmp.path().join(".notarai");
+    fs::create_dir_all(&dir).unwrap();
+    fs::create_dir_all(tmp.path().join("slides")).unwrap();
+    fs::write(tmp.path().join("slides/01.md"), "# Deck\n").unwrap();
+    fs::write(dir.join("deck.spec.yaml"), DECK_SPEC).unwrap();
+    tmp
+}
+
+#[test]
+fn derived_check_reports_fresh_then_stale_outputs() {
+    let tmp = project();
+    notarai()
+        .args(["pipeline", "run", ".notarai/deck.spec.yaml"])
+        .current_dir(tmp.path())
+        .assert()
+        .success();
+    notarai()
+        .args(["derived", "check"])
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "fresh       dist/deck.txt (step build)",
+        ));
+
+    fs::write(tmp.path().join("slides/01.md"), "# Deck v2\n").unwrap();
+    notarai()
+        .args(["derived", "check"])
+        .current_dir(tmp.path())
+        .assert()
+        .code(1)
+        .stdout("STALE       dist/deck.txt (step build)\n  changed: slides/01.md\n");
+}
+
+#[test]
+fn derived_mark_records_existing_outputs() {
+    let tmp = project();
+    fs::create_dir_all(tmp.path().join("dist")).unwrap();
+    fs::write(tmp.path().join("dist/deck.txt"), "# Deck\n").unwrap();
+    notarai()
+        .args(["derived", "check"])
+        .current_dir(tmp.path())
+        .assert()
+        .code(1)
+        .stdout(predicate::str::contains("unrecorded  dist/deck.txt"))
+        .stderr(predicate::str::contains("notarai derived mark"));
+
+    notarai()
+        .args(["derived", "mark", ".notarai/deck.spec.yaml"])
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stdout("Recorded inputs for 1 output(s)\n");
+
+    let output = notarai()
+        .args(["derived", "check", "--json"])
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let report: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(report[0]["spec_path"], ".notarai/deck.spec.yaml");
+    assert_eq!(report[0]["path"], "dist/deck.txt");
+    assert_eq!(report[0]["status"], "fresh");
+    assert_eq!(report[0]["step"], "build");
+}
+
+#[test]
+fn derived_mark_rejects_unknown_spec() {
+    let tmp = project();
+    notarai()
+        .args(["derived", "mark", ".notarai/nope.spec.yaml"])
+        .current_dir(tmp.path())
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "spec not found: .notarai/nope.spec.yaml",
+        ));
+}
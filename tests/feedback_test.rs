@@ -0,0 +1,93 @@
+use assert_cmd::cargo_bin_cmd;
+use predicates::prelude::*;
+use std::fs;
+use tempfile::TempDir;
+
+fn notarai() -> assert_cmd::Command {
+    cargo_bin_cmd!("notarai")
+}
+
+const COURSE_SPEC: &str = "schema_version: '0.7'\nintent: 'x'\n\
+    artifacts:\n  docs:\n    - path: 'lessons/**'\n\
+    feedback:\n  metrics:\n    - name: completion_rate\n      \
+    source: analytics/completion.csv\n      threshold: '>= 0.7'\n    \
+    - name: views\n      source: analytics/views.json\n  \
+    triggers:\n    - condition:\n        metric: completion_rate\n        \
+    operator: below_threshold\n        duration: { value: 3, unit: days }\n      \
+    action: reconcile\n      priority: high\n    \
+    - condition: { metric: views, operator: stale, duration: { value: 30, unit: days } }\n      \
+    action: notify\n";
+
+fn project(completion: &str) -> TempDir {
+    let tmp = TempDir::new().unwrap();
+    fs::create_dir_all(tmp.path().join(".notarai")).unwrap();
+    fs::create_dir_all(tmp.path().join("analytics")).unwrap();
+    fs::write(tmp.path().join(".notarai/course.spec.yaml"), COURSE_SPEC).unwrap();
+    fs::write(tmp.path().join("analytics/completion.csv"), completion).unwrap();
+    fs::write(tmp.path().join("analytics/views.json"), r#"{"views": 120}"#).unwrap();
+    tmp
+}
+
+#[test]
+fn feedback_check_passes_when_no_trigger_fires() {
+    let tmp = project("date,completion_rate\n2020-01-01,0.8\n");
+    notarai()
+        .args(["feedback", "check"])
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stdout(
+            ".notarai/course.spec.yaml\n  \
+             pass         completion_rate = 0.8 (>= 0.7)\n  \
+             unknown      views = 120\n",
+        );
+}
+
+#[test]
+fn feedback_check_reports_triggered_actions() {
+    let tmp = project("date,completion_rate\n2020-01-01,0.6\n2020-01-02,0.5\n");
+    notarai()
+        .args(["feedback", "check"])
+        .current_dir(tmp.path())
+        .assert()
+        .code(1)
+        .stdout(predicate::str::contains(
+            "FAIL         completion_rate = 0.5",
+        ))
+        .stdout(predicate::str::contains(
+            "TRIGGERED    reconcile [high] completion_rate below_threshold: \
+             0.5 has been below >= 0.7 since 2020-01-01T00:00:00Z",
+        ));
+
+    let output = notarai()
+        .args(["feedback", "check", ".notarai/course.spec.yaml", "--json"])
+        .current_dir(tmp.path())
+        .assert()
+        .code(1)
+        .get_output()
+        .stdout
+        .clone();
+    let report: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(report[0]["spec_path"], ".notarai/course.spec.yaml");
+    assert_eq!(report[0]["metrics"][0]["status"], "fail");
+    assert_eq!(
+        report[0]["metrics"][0]["measured_at"],
+        "2020-01-02T00:00:00Z"
+    );
+    assert_eq!(report[0]["triggered"][0]["action"], "reconcile");
+    assert_eq!(report[0]["triggered"][0]["priority"], "high");
+    assert_eq!(report[0]["triggered"][0]["operator"], "below_threshold");
+}
+
+#[test]
+fn feedback_check_errors_on_unevaluable_triggers() {
+    let tmp = project("completion_rate\n0.5\n");
+    notarai()
+        .args(["feedback", "check"])
+        .current_dir(tmp.path())
+        .assert()
+        .code(2)
+        .stdout(predicate::str::contains(
+            "error        completion_rate below_threshold: a duration needs timestamped samples",
+        ));
+}
@@ -0,0 +1,130 @@
+use assert_cmd::cargo_bin_cmd;
+use predicates::prelude::*;
+use std::fs;
+use tempfile::TempDir;
+
+fn notarai() -> assert_cmd::Command {
+    cargo_bin_cmd!("notarai")
+}
+
+fn spec(extra: &str) -> String {
+    format!(
+        "schema_version: '0.7'\nintent: 'x'\nbehaviors:\n  \
+         - name: b\n    given: 'g'\n    then: 't'\n{extra}\
+         artifacts:\n  code:\n    - path: 'src/**'\n"
+    )
+}
+
+fn project() -> TempDir {
+    let tmp = TempDir::new().unwrap();
+    let dir = tmp.path().join(".notarai");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(
+        dir.join("system.spec.yaml"),
+        spec(
+            "subsystems:\n  - $ref: './api.spec.yaml'\n  - $ref: './web.spec.yaml'\n\
+             applies:\n  - $ref: './style.spec.yaml'\n",
+        ),
+    )
+    .unwrap();
+    fs::write(
+        dir.join("web.spec.yaml"),
+        spec("dependencies:\n  - $ref: './api.spec.yaml'\n    relationship: 'calls the API'\n"),
+    )
+    .unwrap();
+    fs::write(dir.join("api.spec.yaml"), spec("")).unwrap();
+    fs::write(dir.join("style.spec.yaml"), spec("")).unwrap();
+    tmp
+}
+
+#[test]
+fn graph_renders_dot_by_default() {
+    let tmp = project();
+    notarai()
+        .arg("graph")
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("digraph notarai {"))
+        .stdout(predicate::str::contains(
+            "\".notarai/system.spec.yaml\" -> \".notarai/api.spec.yaml\";",
+        ))
+        .stdout(predicate::str::contains(
+            "\".notarai/system.spec.yaml\" -> \".notarai/style.spec.yaml\" [style=dashed, label=\"applies\"];",
+        ))
+        .stdout(predicate::str::contains("[style=dotted, label=\"calls the API\"]"));
+}
+
+#[test]
+fn graph_mermaid_with_artifacts() {
+    let tmp = project();
+    notarai()
+        .args(["graph", "--format", "mermaid", "--artifacts"])
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("flowchart LR\n"))
+        .stdout(predicate::str::contains("[(\"code\")]"))
+        .stdout(predicate::str::contains("-.->|applies|"))
+        .stdout(predicate::str::contains("==>|\"calls the API\"|"));
+}
+
+#[test]
+fn graph_json_filters_reachable_specs() {
+    let tmp = project();
+    let output = notarai()
+        .args([
+            "graph",
+            "--format",
+            "json",
+            "--from",
+            ".notarai/web.spec.yaml",
+        ])
+        .current_dir(tmp.path())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let graph: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let ids: Vec<&str> = graph["nodes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|n| n["id"].as_str().unwrap())
+        .collect();
+    assert_eq!(ids, [".notarai/api.spec.yaml", ".notarai/web.spec.yaml"]);
+    assert_eq!(graph["edges"][0]["kind"], "dependency");
+    assert_eq!(graph["edges"][0]["label"], "calls the API");
+}
+
+#[test]
+fn graph_subtree_follows_subsystems_only() {
+    let tmp = project();
+    notarai()
+        .args(["graph", "--subtree", ".notarai/web.spec.yaml"])
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("system").not())
+        .stdout(predicate::str::contains("calls the API"));
+
+    notarai()
+        .args(["graph", "--subtree", ".notarai/nope.spec.yaml"])
+        .current_dir(tmp.path())
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("spec not found"));
+}
+
+#[test]
+fn graph_marks_missing_references() {
+    let tmp = project();
+    fs::remove_file(tmp.path().join(".notarai/style.spec.yaml")).unwrap();
+    notarai()
+        .arg("graph")
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "\".notarai/style.spec.yaml\" [label=\"style\", style=dashed, color=red];",
+        ));
+}
@@ -286,3 +286,35 @@ fn mcp_json_created() {
     assert!(content.contains("notarai"));
     assert!(content.contains("mcp"));
 }
+
+#[test]
+fn registers_state_merge_driver_in_git_repo() {
+    let tmp = TempDir::new().unwrap();
+    std::process::Command::new("git")
+        .arg("init")
+        .current_dir(tmp.path())
+        .output()
+        .unwrap();
+    for _ in 0..2 {
+        notarai()
+            .arg("init")
+            .current_dir(tmp.path())
+            .assert()
+            .success();
+    }
+
+    let attributes = fs::read_to_string(tmp.path().join(".gitattributes")).unwrap();
+    assert_eq!(
+        attributes,
+        ".notarai/reconciliation_state.json merge=notarai-state\n"
+    );
+    let driver = std::process::Command::new("git")
+        .args(["config", "--local", "merge.notarai-state.driver"])
+        .current_dir(tmp.path())
+        .output()
+        .unwrap();
+    assert_eq!(
+        String::from_utf8_lossy(&driver.stdout).trim(),
+        "notarai state merge %O %A %B"
+    );
+}
@@ -0,0 +1,111 @@
+use notarai::{Error, Project};
+use std::fs;
+use tempfile::TempDir;
+
+const SPEC: &str = "schema_version: '0.7'\nintent: 'Test'\nbehaviors:\n  \
+                    - name: b\n    given: 'g'\n    then: 't'\n\
+                    artifacts:\n  code:\n    - path: 'src/*.rs'\n";
+
+fn project() -> TempDir {
+    let tmp = TempDir::new().unwrap();
+    fs::create_dir_all(tmp.path().join(".notarai")).unwrap();
+    fs::create_dir_all(tmp.path().join("src")).unwrap();
+    fs::write(tmp.path().join(".notarai/cli.spec.yaml"), SPEC).unwrap();
+    fs::write(tmp.path().join("src/main.rs"), "fn main() {}").unwrap();
+    tmp
+}
+
+#[test]
+fn loads_validates_and_resolves_artifacts() {
+    let tmp = project();
+    let project = Project::open(tmp.path());
+
+    let results = project.validate().unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].0, ".notarai/cli.spec.yaml");
+    assert!(results[0].1.valid, "{:?}", results[0].1.errors);
+
+    let specs = project.load_specs().unwrap();
+    assert_eq!(project.governed_files(&specs[0].spec), ["src/main.rs"]);
+
+    let affected = project
+        .affected_specs(&["src/main.rs".to_string()])
+        .unwrap();
+    assert_eq!(affected.len(), 1);
+    assert!(
+        project
+            .affected_specs(&["README.md".to_string()])
+            .unwrap()
+            .is_empty()
+    );
+}
+
+#[test]
+fn state_round_trips_through_the_project() {
+    let tmp = project();
+    let project = Project::open(tmp.path());
+    assert!(project.load_state().unwrap().is_none());
+
+    let state = project.snapshot_state().unwrap();
+    project.save_state(&state).unwrap();
+    let loaded = project.load_state().unwrap().unwrap();
+    assert_eq!(loaded.schema_version, notarai::STATE_SCHEMA_VERSION);
+}
+
+#[test]
+fn errors_are_typed() {
+    let tmp = project();
+    fs::write(
+        tmp.path().join(".notarai/cli.spec.yaml"),
+        "intent: 'x'\nartifacts: ['src/*.rs']\n",
+    )
+    .unwrap();
+    let project = Project::open(tmp.path());
+    assert!(matches!(project.state_diff(), Err(Error::MissingState)));
+    assert!(matches!(
+        project.record_derived_inputs(&[".notarai/nope.spec.yaml".to_string()]),
+        Err(Error::SpecNotFound(path)) if path == ".notarai/nope.spec.yaml"
+    ));
+    match project.load_spec(".notarai/cli.spec.yaml") {
+        Err(Error::InvalidSpec { path, .. }) => assert_eq!(path, ".notarai/cli.spec.yaml"),
+        other => panic!("expected InvalidSpec, got {other:?}"),
+    }
+    assert!(matches!(
+        project.load_spec(".notarai/missing.spec.yaml"),
+        Err(Error::Io { .. })
+    ));
+
+    fs::write(
+        tmp.path().join(".notarai/reconciliation_state.json"),
+        r#"{"schema_version": "9"}"#,
+    )
+    .unwrap();
+    assert!(matches!(
+        project.load_state(),
+        Err(Error::UnsupportedVersion { .. })
+    ));
+}
+
+#[test]
+fn effective_spec_includes_applied_rules() {
+    let tmp = project();
+    fs::write(
+        tmp.path().join(".notarai/system.spec.yaml"),
+        "schema_version: '0.7'\nintent: 'System'\n\
+         subsystems:\n  - $ref: './cli.spec.yaml'\n\
+         applies:\n  - $ref: './style.spec.yaml'\n",
+    )
+    .unwrap();
+    fs::write(
+        tmp.path().join(".notarai/style.spec.yaml"),
+        "schema_version: '0.7'\nintent: 'Style'\nconstraints: ['ASCII only']\n",
+    )
+    .unwrap();
+    let effective = Project::open(tmp.path())
+        .effective_spec(".notarai/cli.spec.yaml")
+        .unwrap();
+    assert_eq!(effective.applied.len(), 1);
+    assert_eq!(effective.applied[0].via, ".notarai/system.spec.yaml");
+    assert_eq!(effective.constraints[0].rule, "ASCII only");
+    assert_eq!(effective.constraints[0].source, ".notarai/style.spec.yaml");
+}
@@ -0,0 +1,64 @@
+use assert_cmd::cargo_bin_cmd;
+use predicates::prelude::*;
+use std::fs;
+use tempfile::TempDir;
+
+fn notarai() -> assert_cmd::Command {
+    cargo_bin_cmd!("notarai")
+}
+
+const SPEC: &str = "schema_version: '0.7'\nintent: 'test'\n";
+
+#[test]
+fn log_with_no_cache_reports_no_runs() {
+    let tmp = TempDir::new().unwrap();
+    notarai()
+        .arg("log")
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No reconciliation runs recorded."));
+}
+
+#[test]
+fn log_shows_run_recorded_over_mcp() {
+    let tmp = TempDir::new().unwrap();
+    let root = tmp.path();
+    fs::create_dir_all(root.join(".notarai")).unwrap();
+    fs::write(root.join(".notarai/cli.spec.yaml"), SPEC).unwrap();
+    fs::write(root.join("main.rs"), "fn main() {}").unwrap();
+
+    let begin = r#"{"jsonrpc":"2.0","id":0,"method":"tools/call","params":{"name":"begin_reconciliation","arguments":{"base_branch":"main"}}}"#;
+    let mark = r#"{"jsonrpc":"2.0","id":1,"method":"tools/call","params":{"name":"mark_reconciled","arguments":{"spec_path":".notarai/cli.spec.yaml","files":["main.rs"]}}}"#;
+    let snap = r#"{"jsonrpc":"2.0","id":2,"method":"tools/call","params":{"name":"snapshot_state","arguments":{}}}"#;
+    notarai()
+        .arg("mcp")
+        .write_stdin(format!("{begin}\n{mark}\n{snap}\n"))
+        .current_dir(root)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("run_id"));
+
+    notarai()
+        .args(["log", "--files"])
+        .current_dir(root)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Run 1"))
+        .stdout(predicate::str::contains("Base:     main"))
+        .stdout(predicate::str::contains(
+            ".notarai/cli.spec.yaml (1 file(s))",
+        ))
+        .stdout(predicate::str::contains("    main.rs"))
+        .stdout(predicate::str::contains("in progress").not());
+
+    let output = notarai()
+        .args(["log", "--json"])
+        .current_dir(root)
+        .output()
+        .unwrap();
+    let parsed: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(parsed[0]["base_ref"], "main");
+    assert!(parsed[0]["finished_at"].is_i64());
+    assert_eq!(parsed[0]["specs"][".notarai/cli.spec.yaml"][0], "main.rs");
+}
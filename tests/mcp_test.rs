@@ -291,7 +291,7 @@ fn get_spec_diff_skips_file_matching_cache() {
     git_commit_all(root, "changes");
 
     // Seed beta.txt via mark_reconciled (uses relative path keys, same as get_spec_diff).
-    let seed_msg = r#"{"jsonrpc":"2.0","id":0,"method":"tools/call","params":{"name":"mark_reconciled","arguments":{"files":["beta.txt"]}}}"#;
+    let seed_msg = r#"{"jsonrpc":"2.0","id":0,"method":"tools/call","params":{"name":"mark_reconciled","arguments":{"spec_path":".notarai/test.spec.yaml","files":["beta.txt"]}}}"#;
     let diff_msg = r#"{"jsonrpc":"2.0","id":1,"method":"tools/call","params":{"name":"get_spec_diff","arguments":{"spec_path":".notarai/test.spec.yaml","base_branch":"HEAD~1"}}}"#;
 
     notarai()
@@ -324,7 +324,7 @@ fn get_spec_diff_skips_all_when_all_files_cached() {
     git_commit_all(root, "changes");
 
     // Seed both files via mark_reconciled - both should be skipped.
-    let seed_msg = r#"{"jsonrpc":"2.0","id":0,"method":"tools/call","params":{"name":"mark_reconciled","arguments":{"files":["alpha.txt","beta.txt"]}}}"#;
+    let seed_msg = r#"{"jsonrpc":"2.0","id":0,"method":"tools/call","params":{"name":"mark_reconciled","arguments":{"spec_path":".notarai/test.spec.yaml","files":["alpha.txt","beta.txt"]}}}"#;
     let diff_msg = r#"{"jsonrpc":"2.0","id":1,"method":"tools/call","params":{"name":"get_spec_diff","arguments":{"spec_path":".notarai/test.spec.yaml","base_branch":"HEAD~1"}}}"#;
 
     notarai()
@@ -355,7 +355,7 @@ fn get_spec_diff_bypass_cache_includes_all_files() {
     git_commit_all(root, "changes");
 
     // Seed both files via mark_reconciled, then bypass_cache should still show full diff.
-    let seed_msg = r#"{"jsonrpc":"2.0","id":0,"method":"tools/call","params":{"name":"mark_reconciled","arguments":{"files":["alpha.txt","beta.txt"]}}}"#;
+    let seed_msg = r#"{"jsonrpc":"2.0","id":0,"method":"tools/call","params":{"name":"mark_reconciled","arguments":{"spec_path":".notarai/test.spec.yaml","files":["alpha.txt","beta.txt"]}}}"#;
     let diff_msg = r#"{"jsonrpc":"2.0","id":1,"method":"tools/call","params":{"name":"get_spec_diff","arguments":{"spec_path":".notarai/test.spec.yaml","base_branch":"HEAD~1","bypass_cache":true}}}"#;
 
     notarai()
@@ -385,7 +385,7 @@ fn clear_cache_allows_full_diff_on_next_call() {
     git_commit_all(root, "changes");
 
     // Seed both files via mark_reconciled, then clear cache, then verify full diff.
-    let seed_msg = r#"{"jsonrpc":"2.0","id":0,"method":"tools/call","params":{"name":"mark_reconciled","arguments":{"files":["alpha.txt","beta.txt"]}}}"#;
+    let seed_msg = r#"{"jsonrpc":"2.0","id":0,"method":"tools/call","params":{"name":"mark_reconciled","arguments":{"spec_path":".notarai/test.spec.yaml","files":["alpha.txt","beta.txt"]}}}"#;
     let clear_msg = r#"{"jsonrpc":"2.0","id":1,"method":"tools/call","params":{"name":"clear_cache","arguments":{}}}"#;
     let diff_msg = r#"{"jsonrpc":"2.0","id":2,"method":"tools/call","params":{"name":"get_spec_diff","arguments":{"spec_path":".notarai/test.spec.yaml","base_branch":"HEAD~1"}}}"#;
 
@@ -566,7 +566,7 @@ fn get_spec_diff_cache_filtering_applies_to_spec_files() {
 
     // Seed the child spec into the cache with its current (changed) hash --
     // the cache now considers it reconciled.
-    let seed_msg = r#"{"jsonrpc":"2.0","id":0,"method":"tools/call","params":{"name":"mark_reconciled","arguments":{"files":[".notarai/child.spec.yaml"]}}}"#;
+    let seed_msg = r#"{"jsonrpc":"2.0","id":0,"method":"tools/call","params":{"name":"mark_reconciled","arguments":{"spec_path":".notarai/governing.spec.yaml","files":[".notarai/child.spec.yaml"]}}}"#;
     let diff_msg = r#"{"jsonrpc":"2.0","id":1,"method":"tools/call","params":{"name":"get_spec_diff","arguments":{"spec_path":".notarai/governing.spec.yaml","base_branch":"HEAD~1"}}}"#;
 
     notarai()
@@ -581,6 +581,133 @@ fn get_spec_diff_cache_filtering_applies_to_spec_files() {
         .stdout(predicate::str::contains("Child spec v2").not());
 }
 
+// -- mark_reconciled: per-spec records ------------------------------------------
+
+#[test]
+fn mark_reconciled_requires_spec_path() {
+    let msg = r#"{"jsonrpc":"2.0","id":1,"method":"tools/call","params":{"name":"mark_reconciled","arguments":{"files":["alpha.txt"]}}}"#;
+    notarai()
+        .arg("mcp")
+        .write_stdin(format!("{msg}\n"))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("-32602"))
+        .stdout(predicate::str::contains("Missing spec_path"));
+}
+
+#[test]
+fn get_spec_diff_ignores_records_from_other_specs() {
+    let tmp = TempDir::new().unwrap();
+    let root = tmp.path();
+
+    setup_git_repo(root);
+    fs::create_dir_all(root.join(".notarai")).unwrap();
+    fs::write(root.join(".notarai/a.spec.yaml"), TXT_SPEC).unwrap();
+    fs::write(root.join(".notarai/b.spec.yaml"), TXT_SPEC).unwrap();
+    fs::write(root.join("alpha.txt"), "initial").unwrap();
+    git_commit_all(root, "base");
+
+    fs::write(root.join("alpha.txt"), "changed").unwrap();
+    git_commit_all(root, "changes");
+
+    // alpha.txt is reconciled for spec A only.
+    let seed_msg = r#"{"jsonrpc":"2.0","id":0,"method":"tools/call","params":{"name":"mark_reconciled","arguments":{"spec_path":".notarai/a.spec.yaml","files":["alpha.txt"]}}}"#;
+    let diff_a = r#"{"jsonrpc":"2.0","id":1,"method":"tools/call","params":{"name":"get_spec_diff","arguments":{"spec_path":".notarai/a.spec.yaml","base_branch":"HEAD~1"}}}"#;
+    let diff_b = r#"{"jsonrpc":"2.0","id":2,"method":"tools/call","params":{"name":"get_spec_diff","arguments":{"spec_path":".notarai/b.spec.yaml","base_branch":"HEAD~1"}}}"#;
+
+    let output = notarai()
+        .arg("mcp")
+        .write_stdin(format!("{seed_msg}\n{diff_a}\n{diff_b}\n"))
+        .current_dir(root)
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert!(!lines[1].contains("diff --git a/alpha.txt"));
+    assert!(lines[2].contains("diff --git a/alpha.txt"));
+}
+
+#[test]
+fn get_spec_diff_invalidates_records_when_spec_changes() {
+    let tmp = TempDir::new().unwrap();
+    let root = tmp.path();
+
+    setup_git_repo(root);
+    fs::create_dir_all(root.join(".notarai")).unwrap();
+    fs::write(root.join(".notarai/test.spec.yaml"), TXT_SPEC).unwrap();
+    fs::write(root.join("alpha.txt"), "initial").unwrap();
+    git_commit_all(root, "base");
+
+    fs::write(root.join("alpha.txt"), "changed").unwrap();
+    git_commit_all(root, "changes");
+
+    let seed_msg = r#"{"jsonrpc":"2.0","id":0,"method":"tools/call","params":{"name":"mark_reconciled","arguments":{"spec_path":".notarai/test.spec.yaml","files":["alpha.txt"]}}}"#;
+    notarai()
+        .arg("mcp")
+        .write_stdin(format!("{seed_msg}\n"))
+        .current_dir(root)
+        .assert()
+        .success();
+
+    // Editing the spec's artifact globs changes its fingerprint.
+    fs::write(
+        root.join(".notarai/test.spec.yaml"),
+        TXT_SPEC.replace("'*.txt'", "'alpha.txt'"),
+    )
+    .unwrap();
+
+    let diff_msg = r#"{"jsonrpc":"2.0","id":1,"method":"tools/call","params":{"name":"get_spec_diff","arguments":{"spec_path":".notarai/test.spec.yaml","base_branch":"HEAD~1"}}}"#;
+    notarai()
+        .arg("mcp")
+        .write_stdin(format!("{diff_msg}\n"))
+        .current_dir(root)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("diff --git a/alpha.txt"));
+}
+
+#[test]
+fn get_spec_diff_skips_formatting_only_edits_after_mark() {
+    let tmp = TempDir::new().unwrap();
+    let root = tmp.path();
+
+    setup_git_repo(root);
+    fs::create_dir_all(root.join(".notarai")).unwrap();
+    fs::write(root.join(".notarai/test.spec.yaml"), TXT_SPEC).unwrap();
+    fs::write(
+        root.join(".notarai/normalize.yaml"),
+        "extensions:\n  txt: text\n",
+    )
+    .unwrap();
+    fs::write(root.join("alpha.txt"), "initial\n").unwrap();
+    git_commit_all(root, "base");
+
+    fs::write(root.join("alpha.txt"), "changed\n").unwrap();
+    git_commit_all(root, "changes");
+
+    let seed_msg = r#"{"jsonrpc":"2.0","id":0,"method":"tools/call","params":{"name":"mark_reconciled","arguments":{"spec_path":".notarai/test.spec.yaml","files":["alpha.txt"]}}}"#;
+    notarai()
+        .arg("mcp")
+        .write_stdin(format!("{seed_msg}\n"))
+        .current_dir(root)
+        .assert()
+        .success();
+
+    // CRLF line endings and trailing whitespace only.
+    fs::write(root.join("alpha.txt"), "changed  \r\n").unwrap();
+
+    let diff_msg = r#"{"jsonrpc":"2.0","id":1,"method":"tools/call","params":{"name":"get_spec_diff","arguments":{"spec_path":".notarai/test.spec.yaml","base_branch":"HEAD~1"}}}"#;
+    notarai()
+        .arg("mcp")
+        .write_stdin(format!("{diff_msg}\n"))
+        .current_dir(root)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("diff --git a/alpha.txt").not())
+        .stdout(predicate::str::contains("alpha.txt"));
+}
+
 // -- snapshot_state MCP tool --------------------------------------------------
 
 #[test]
@@ -595,7 +722,7 @@ fn test_snapshot_state_tool() {
     git_commit_all(root, "base");
 
     // Seed cache, then snapshot.
-    let seed_msg = r#"{"jsonrpc":"2.0","id":0,"method":"tools/call","params":{"name":"mark_reconciled","arguments":{"files":["alpha.txt"]}}}"#;
+    let seed_msg = r#"{"jsonrpc":"2.0","id":0,"method":"tools/call","params":{"name":"mark_reconciled","arguments":{"spec_path":".notarai/test.spec.yaml","files":["alpha.txt"]}}}"#;
     let snap_msg = r#"{"jsonrpc":"2.0","id":1,"method":"tools/call","params":{"name":"snapshot_state","arguments":{}}}"#;
 
     notarai()
@@ -637,7 +764,7 @@ fn test_snapshot_state_creates_file() {
     );
     let content = fs::read_to_string(&state_file).unwrap();
     let parsed: serde_json::Value = serde_json::from_str(&content).unwrap();
-    assert_eq!(parsed["schema_version"], "1");
+    assert_eq!(parsed["schema_version"], "2");
 }
 
 // -- get_spec_diff: file_categories and binary_changes -------------------------
@@ -730,3 +857,336 @@ fn get_changed_artifacts_works_with_nonstandard_category() {
         // and file wasn't modified -- that's fine, we're testing the key is present).
         .stdout(predicate::str::contains("changed_artifacts"));
 }
+
+#[test]
+fn get_spec_diff_rejects_malformed_artifacts() {
+    let tmp = TempDir::new().unwrap();
+    let root = tmp.path();
+
+    setup_git_repo(root);
+    fs::create_dir_all(root.join(".notarai")).unwrap();
+    // `artifacts` must map categories to lists, not be a list itself.
+    fs::write(
+        root.join(".notarai/test.spec.yaml"),
+        "schema_version: '0.7'\nintent: 'x'\nartifacts:\n  - path: '*.txt'\n",
+    )
+    .unwrap();
+    git_commit_all(root, "base");
+
+    let msg = r#"{"jsonrpc":"2.0","id":1,"method":"tools/call","params":{"name":"get_spec_diff","arguments":{"spec_path":".notarai/test.spec.yaml","base_branch":"HEAD"}}}"#;
+    notarai()
+        .arg("mcp")
+        .write_stdin(format!("{msg}\n"))
+        .current_dir(root)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("-32603"))
+        .stdout(predicate::str::contains("invalid spec:"));
+}
+
+// -- get_spec_diff: notebooks ---------------------------------------------------
+
+const NOTEBOOK_SPEC: &str = r#"schema_version: '0.4'
+intent: 'Test spec governing a notebook'
+artifacts:
+  notebooks:
+    - path: '*.ipynb'
+"#;
+
+fn notebook_json(source: &str, output: &str) -> String {
+    format!(
+        r#"{{"cells":[{{"cell_type":"code","execution_count":1,"metadata":{{}},"source":["{source}"],"outputs":[{{"output_type":"stream","name":"stdout","text":["{output}"]}}]}}],"metadata":{{}},"nbformat":4,"nbformat_minor":4}}"#
+    )
+}
+
+#[test]
+fn get_spec_diff_diffs_notebooks_by_cell_and_flags_stale_outputs() {
+    let tmp = TempDir::new().unwrap();
+    let root = tmp.path();
+
+    setup_git_repo(root);
+    fs::create_dir_all(root.join(".notarai")).unwrap();
+    fs::write(root.join(".notarai/test.spec.yaml"), NOTEBOOK_SPEC).unwrap();
+    fs::write(root.join("analysis.ipynb"), notebook_json("print(1)", "1")).unwrap();
+    git_commit_all(root, "base");
+
+    // Source edited, notebook not re-run.
+    fs::write(root.join("analysis.ipynb"), notebook_json("print(2)", "1")).unwrap();
+
+    let msg = r#"{"jsonrpc":"2.0","id":1,"method":"tools/call","params":{"name":"get_spec_diff","arguments":{"spec_path":".notarai/test.spec.yaml","base_branch":"HEAD"}}}"#;
+    notarai()
+        .arg("mcp")
+        .write_stdin(format!("{msg}\n"))
+        .current_dir(root)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("notebook_changes"))
+        .stdout(predicate::str::contains("+print(2)"))
+        .stdout(predicate::str::contains(
+            "source changed but outputs are unchanged",
+        ))
+        .stdout(predicate::str::contains("diff --git a/analysis.ipynb").not());
+}
+
+// -- effective composition ----------------------------------------------------
+
+/// Cross-cutting spec applied by `APPLYING_SYSTEM_SPEC` to its subsystems.
+const SHARED_SPEC: &str = r#"schema_version: '0.7'
+intent: 'Shared security rules'
+behaviors:
+  - name: audits
+    given: 'a change'
+    then: 'it is audited'
+invariants:
+  - 'Secrets never appear in logs'
+artifacts:
+  docs:
+    - path: 'SECURITY.md'
+"#;
+
+/// System spec with `TXT_SPEC` as a subsystem and `SHARED_SPEC` applied.
+const APPLYING_SYSTEM_SPEC: &str = r#"schema_version: '0.7'
+intent: 'System'
+behaviors:
+  - name: composes
+    given: 'subsystems exist'
+    then: 'they are composed'
+subsystems:
+  - $ref: './test.spec.yaml'
+applies:
+  - $ref: './shared.spec.yaml'
+artifacts:
+  docs:
+    - path: 'README.md'
+"#;
+
+fn project_with_applied_spec() -> TempDir {
+    let tmp = TempDir::new().unwrap();
+    let root = tmp.path();
+    setup_git_repo(root);
+    fs::create_dir_all(root.join(".notarai")).unwrap();
+    fs::write(root.join(".notarai/system.spec.yaml"), APPLYING_SYSTEM_SPEC).unwrap();
+    fs::write(root.join(".notarai/test.spec.yaml"), TXT_SPEC).unwrap();
+    fs::write(root.join(".notarai/shared.spec.yaml"), SHARED_SPEC).unwrap();
+    fs::write(root.join("alpha.txt"), "v1").unwrap();
+    git_commit_all(root, "base");
+    fs::write(root.join("alpha.txt"), "v2").unwrap();
+    tmp
+}
+
+const APPLIED_RULE: &str =
+    r#"{\"rule\":\"Secrets never appear in logs\",\"source\":\".notarai/shared.spec.yaml\"}"#;
+
+#[test]
+fn list_affected_specs_includes_effective_rules() {
+    let tmp = project_with_applied_spec();
+    let msg = r#"{"jsonrpc":"2.0","id":1,"method":"tools/call","params":{"name":"list_affected_specs","arguments":{"base_branch":"HEAD"}}}"#;
+    notarai()
+        .arg("mcp")
+        .write_stdin(format!("{msg}\n"))
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(APPLIED_RULE))
+        .stdout(predicate::str::contains(
+            r#"\"via\":\".notarai/system.spec.yaml\""#,
+        ));
+}
+
+#[test]
+fn get_spec_diff_includes_effective_rules() {
+    let tmp = project_with_applied_spec();
+    let msg = r#"{"jsonrpc":"2.0","id":1,"method":"tools/call","params":{"name":"get_spec_diff","arguments":{"spec_path":".notarai/test.spec.yaml","base_branch":"HEAD"}}}"#;
+    notarai()
+        .arg("mcp")
+        .write_stdin(format!("{msg}\n"))
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("alpha.txt"))
+        .stdout(predicate::str::contains(APPLIED_RULE));
+}
+
+#[test]
+fn dangling_applies_is_reported_as_a_warning_in_effective() {
+    let tmp = project_with_applied_spec();
+    let system = APPLYING_SYSTEM_SPEC.replace(
+        "applies:\n  - $ref: './shared.spec.yaml'\n",
+        "applies:\n  - $ref: './gone.spec.yaml'\n  - $ref: './shared.spec.yaml'\n",
+    );
+    fs::write(tmp.path().join(".notarai/system.spec.yaml"), system).unwrap();
+    let warning = r#"\"warnings\":[\".notarai/system.spec.yaml: applies reference './gone.spec.yaml' does not resolve to a spec\"]"#;
+    for msg in [
+        r#"{"jsonrpc":"2.0","id":1,"method":"tools/call","params":{"name":"list_affected_specs","arguments":{"base_branch":"HEAD"}}}"#,
+        r#"{"jsonrpc":"2.0","id":1,"method":"tools/call","params":{"name":"get_spec_diff","arguments":{"spec_path":".notarai/test.spec.yaml","base_branch":"HEAD"}}}"#,
+    ] {
+        notarai()
+            .arg("mcp")
+            .write_stdin(format!("{msg}\n"))
+            .current_dir(tmp.path())
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(APPLIED_RULE))
+            .stdout(predicate::str::contains(warning))
+            .stdout(predicate::str::contains("-32603").not());
+    }
+}
+
+// -- derived artifacts --------------------------------------------------------
+
+/// Spec whose `dist/` exports are derived (tier 4) outputs.
+const DERIVED_SPEC: &str = r#"schema_version: '0.7'
+intent: 'Spec with derived exports'
+behaviors:
+  - name: exports
+    given: 'txt files exist'
+    then: 'they are exported'
+artifacts:
+  code:
+    - path: '*.txt'
+  exports:
+    - path: 'dist/*.txt'
+      tier: 4
+"#;
+
+#[test]
+fn get_spec_diff_excludes_derived_outputs() {
+    let tmp = TempDir::new().unwrap();
+    let root = tmp.path();
+    setup_git_repo(root);
+    fs::create_dir_all(root.join(".notarai")).unwrap();
+    fs::create_dir_all(root.join("dist")).unwrap();
+    fs::write(root.join(".notarai/derived.spec.yaml"), DERIVED_SPEC).unwrap();
+    fs::write(root.join("alpha.txt"), "v1").unwrap();
+    fs::write(root.join("dist/alpha.txt"), "v1").unwrap();
+    git_commit_all(root, "base");
+    fs::write(root.join("alpha.txt"), "v2").unwrap();
+    fs::write(root.join("dist/alpha.txt"), "v2").unwrap();
+
+    let msg = r#"{"jsonrpc":"2.0","id":1,"method":"tools/call","params":{"name":"get_spec_diff","arguments":{"spec_path":".notarai/derived.spec.yaml","base_branch":"HEAD"}}}"#;
+    let output = notarai()
+        .arg("mcp")
+        .write_stdin(format!("{msg}\n"))
+        .current_dir(root)
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let response: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    let text = response["result"]["content"][0]["text"].as_str().unwrap();
+    let diff: serde_json::Value = serde_json::from_str(text).unwrap();
+    assert_eq!(diff["files"], serde_json::json!(["alpha.txt"]));
+    assert_eq!(diff["derived"], serde_json::json!(["dist/alpha.txt"]));
+    assert!(!diff["diff"].as_str().unwrap().contains("dist/alpha.txt"));
+}
+
+// -- feedback triggers --------------------------------------------------------
+
+/// Spec whose `rate` metric triggers a reconciliation when below threshold.
+const FEEDBACK_SPEC: &str = r#"schema_version: '0.7'
+intent: 'Spec with feedback triggers'
+behaviors:
+  - name: tracks
+    given: 'txt files exist'
+    then: 'they are tracked'
+artifacts:
+  docs:
+    - path: 'docs/*.md'
+feedback:
+  metrics:
+    - name: rate
+      source: 'rate.csv'
+      threshold: '>= 0.7'
+  triggers:
+    - condition: { metric: rate, operator: below_threshold }
+      action: reconcile
+"#;
+
+#[test]
+fn list_affected_specs_includes_feedback_flagged_specs() {
+    let tmp = TempDir::new().unwrap();
+    let root = tmp.path();
+    setup_git_repo(root);
+    fs::create_dir_all(root.join(".notarai")).unwrap();
+    fs::write(root.join(".notarai/feedback.spec.yaml"), FEEDBACK_SPEC).unwrap();
+    fs::write(root.join("rate.csv"), "rate\n0.5\n").unwrap();
+    git_commit_all(root, "base");
+
+    let call = |include_feedback: bool| {
+        let msg = format!(
+            r#"{{"jsonrpc":"2.0","id":1,"method":"tools/call","params":{{"name":"list_affected_specs","arguments":{{"base_branch":"HEAD","include_feedback":{include_feedback}}}}}}}"#
+        );
+        let output = notarai()
+            .arg("mcp")
+            .write_stdin(format!("{msg}\n"))
+            .current_dir(root)
+            .assert()
+            .success()
+            .get_output()
+            .stdout
+            .clone();
+        let response: serde_json::Value = serde_json::from_slice(&output).unwrap();
+        let text = response["result"]["content"][0]["text"].as_str().unwrap();
+        serde_json::from_str::<serde_json::Value>(text).unwrap()
+    };
+
+    assert_eq!(call(false)["affected_specs"], serde_json::json!([]));
+    let result = call(true);
+    let spec = &result["affected_specs"][0];
+    assert_eq!(spec["spec_path"], ".notarai/feedback.spec.yaml");
+    assert_eq!(spec["triggered"][0]["metric"], "rate");
+    assert_eq!(spec["triggered"][0]["action"], "reconcile");
+}
+
+// -- content evidence ---------------------------------------------------------
+
+/// Spec with a content claim backed by a governed data file.
+const EVIDENCE_SPEC: &str = r#"schema_version: '0.7'
+intent: 'Report backed by data'
+behaviors:
+  - name: reports
+    given: 'data exists'
+    then: 'claims cite it'
+artifacts:
+  data:
+    - path: '*.txt'
+content:
+  sections:
+    - id: results
+      evidence:
+        - type: data
+          source: 'alpha.txt'
+          claim: 'Alpha is up'
+"#;
+
+#[test]
+fn get_spec_diff_lists_claims_with_changed_evidence() {
+    let tmp = TempDir::new().unwrap();
+    let root = tmp.path();
+    setup_git_repo(root);
+    fs::create_dir_all(root.join(".notarai")).unwrap();
+    fs::write(root.join(".notarai/report.spec.yaml"), EVIDENCE_SPEC).unwrap();
+    fs::write(root.join("alpha.txt"), "v1").unwrap();
+    git_commit_all(root, "base");
+    fs::write(root.join("alpha.txt"), "v2").unwrap();
+
+    let msg = r#"{"jsonrpc":"2.0","id":1,"method":"tools/call","params":{"name":"get_spec_diff","arguments":{"spec_path":".notarai/report.spec.yaml","base_branch":"HEAD"}}}"#;
+    let output = notarai()
+        .arg("mcp")
+        .write_stdin(format!("{msg}\n"))
+        .current_dir(root)
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let response: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    let text = response["result"]["content"][0]["text"].as_str().unwrap();
+    let diff: serde_json::Value = serde_json::from_str(text).unwrap();
+    assert_eq!(diff["evidence"]["issues"], serde_json::json!([]));
+    assert_eq!(
+        diff["evidence"]["changed_claims"],
+        serde_json::json!([{"section": "results", "claim": "Alpha is up", "source": "alpha.txt"}])
+    );
+}
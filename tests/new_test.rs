@@ -0,0 +1,177 @@
+use assert_cmd::cargo_bin_cmd;
+use predicates::prelude::*;
+use std::fs;
+use tempfile::TempDir;
+
+fn notarai() -> assert_cmd::Command {
+    cargo_bin_cmd!("notarai")
+}
+
+const SYSTEM_SPEC: &str = "schema_version: '0.7'
+intent: 'System'
+behaviors:
+  - name: composes
+    given: 'subsystems exist'
+    then: 'they are composed'
+subsystems:
+  - $ref: './cli.spec.yaml'
+
+artifacts:
+  docs:
+    - path: 'README.md'
+";
+
+fn project() -> TempDir {
+    let tmp = TempDir::new().unwrap();
+    fs::create_dir_all(tmp.path().join(".notarai")).unwrap();
+    fs::create_dir_all(tmp.path().join("src/auth")).unwrap();
+    fs::write(tmp.path().join(".notarai/system.spec.yaml"), SYSTEM_SPEC).unwrap();
+    tmp
+}
+
+#[test]
+fn new_writes_valid_spec_and_registers_it() {
+    let tmp = project();
+    notarai()
+        .args(["new", "auth", "src/auth", "tests/auth_test.rs"])
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Created .notarai/auth.spec.yaml"))
+        .stdout(predicate::str::contains(
+            "Registered ./auth.spec.yaml in .notarai/system.spec.yaml subsystems",
+        ));
+
+    let spec = fs::read_to_string(tmp.path().join(".notarai/auth.spec.yaml")).unwrap();
+    assert!(
+        spec.contains("  code:\n    - path: 'src/auth/**'\n"),
+        "{spec}"
+    );
+    assert!(
+        spec.contains("  tests:\n    - path: 'tests/auth_test.rs'\n"),
+        "{spec}"
+    );
+
+    let system = fs::read_to_string(tmp.path().join(".notarai/system.spec.yaml")).unwrap();
+    assert!(system.contains(
+        "subsystems:\n  - $ref: './cli.spec.yaml'\n  - $ref: './auth.spec.yaml'\n\nartifacts:"
+    ));
+
+    notarai()
+        .args(["validate", ".notarai/auth.spec.yaml"])
+        .current_dir(tmp.path())
+        .assert()
+        .success();
+}
+
+#[test]
+fn new_uses_domain_blocks_and_tier() {
+    let tmp = project();
+    notarai()
+        .args(["new", "decks/q3", "--domain", "presentation", "q3.pptx"])
+        .current_dir(tmp.path())
+        .assert()
+        .success();
+    let spec = fs::read_to_string(tmp.path().join(".notarai/decks/q3.spec.yaml")).unwrap();
+    assert!(spec.contains("domain: presentation\n"));
+    assert!(spec.contains("content:\n  structure: ordered\n"));
+    assert!(spec.contains("  slides:\n    - path: 'q3.pptx'\n"));
+    let system = fs::read_to_string(tmp.path().join(".notarai/system.spec.yaml")).unwrap();
+    assert!(system.contains("  - $ref: './decks/q3.spec.yaml'\n"));
+
+    notarai()
+        .args(["new", "generated", "--tier", "derived", "dist"])
+        .current_dir(tmp.path())
+        .assert()
+        .success();
+    let spec = fs::read_to_string(tmp.path().join(".notarai/generated.spec.yaml")).unwrap();
+    assert!(spec.contains("tier: derived\n"));
+    assert!(!spec.contains("behaviors:"));
+
+    notarai()
+        .arg("validate")
+        .current_dir(tmp.path())
+        .assert()
+        .success();
+}
+
+#[test]
+fn new_registers_in_a_flow_style_subsystems_list() {
+    let tmp = project();
+    let system = SYSTEM_SPEC.replace(
+        "subsystems:\n  - $ref: './cli.spec.yaml'\n",
+        "subsystems: []\n",
+    );
+    fs::write(tmp.path().join(".notarai/system.spec.yaml"), system).unwrap();
+    notarai()
+        .args(["new", "auth"])
+        .current_dir(tmp.path())
+        .assert()
+        .success();
+    let system = fs::read_to_string(tmp.path().join(".notarai/system.spec.yaml")).unwrap();
+    assert!(
+        system.contains("subsystems: [{ $ref: './auth.spec.yaml' }]\n"),
+        "{system}"
+    );
+    assert_eq!(system.matches("subsystems:").count(), 1);
+}
+
+#[test]
+fn new_leaves_the_project_untouched_when_the_system_spec_cannot_be_edited() {
+    let tmp = project();
+    let system = SYSTEM_SPEC.replace("subsystems:\n", "subsystems:\nsubsystems:\n");
+    fs::write(tmp.path().join(".notarai/system.spec.yaml"), &system).unwrap();
+    notarai()
+        .args(["new", "auth"])
+        .current_dir(tmp.path())
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "could not register .notarai/auth.spec.yaml in .notarai/system.spec.yaml",
+        ));
+    assert!(!tmp.path().join(".notarai/auth.spec.yaml").exists());
+    assert_eq!(
+        fs::read_to_string(tmp.path().join(".notarai/system.spec.yaml")).unwrap(),
+        system
+    );
+}
+
+#[test]
+fn new_refuses_to_overwrite_an_existing_spec() {
+    let tmp = project();
+    fs::write(tmp.path().join(".notarai/cli.spec.yaml"), "intent: 'x'\n").unwrap();
+    notarai()
+        .args(["new", "cli"])
+        .current_dir(tmp.path())
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("already exists"));
+    assert_eq!(
+        fs::read_to_string(tmp.path().join(".notarai/cli.spec.yaml")).unwrap(),
+        "intent: 'x'\n"
+    );
+}
+
+#[test]
+fn new_without_system_spec_only_creates_the_spec() {
+    let tmp = TempDir::new().unwrap();
+    fs::create_dir_all(tmp.path().join(".notarai")).unwrap();
+    notarai()
+        .args(["new", "auth"])
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No system spec found"));
+    assert!(tmp.path().join(".notarai/auth.spec.yaml").exists());
+}
+
+#[test]
+fn new_rejects_unknown_domain() {
+    let tmp = project();
+    notarai()
+        .args(["new", "auth", "--domain", "slides"])
+        .current_dir(tmp.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("unknown domain 'slides'"));
+}
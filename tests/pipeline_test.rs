@@ -0,0 +1,133 @@
+use assert_cmd::cargo_bin_cmd;
+use predicates::prelude::*;
+use std::fs;
+use tempfile::TempDir;
+
+fn notarai() -> assert_cmd::Command {
+    cargo_bin_cmd!("notarai")
+}
+
+fn project(steps: &str) -> TempDir {
+    let tmp = TempDir::new().unwrap();
+    let dir = tmp.path().join(".notarai");
+    fs::create_dir_all(&dir).unwrap();
+    fs::create_dir_all(tmp.path().join("slides")).unwrap();
+    fs::write(tmp.path().join("slides/deck.md"), "# Deck\n").unwrap();
+    fs::write(
+        dir.join("deck.spec.yaml"),
+        format!(
+            "schema_version: '0.7'\nintent: 'x'\ndomain: presentation\n\
+             artifacts:\n  slides:\n    - path: 'slides/**'\n\
+             pipeline:\n  env:\n    TITLE: 'Quarterly'\n  steps:\n{steps}"
+        ),
+    )
+    .unwrap();
+    tmp
+}
+
+const BUILD: &str = "    - name: build\n      \
+    command: 'mkdir -p dist && cat slides/deck.md > dist/deck.txt && echo $TITLE >> dist/deck.txt'\n      \
+    input: 'slides/**/*.md'\n      output: 'dist/deck.txt'\n    \
+    - name: announce\n      command: 'echo built'\n      depends_on: [build]\n";
+
+#[test]
+fn pipeline_run_executes_steps_then_skips_unchanged_ones() {
+    let tmp = project(BUILD);
+    notarai()
+        .args(["pipeline", "run", ".notarai/deck.spec.yaml"])
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stdout("build: ok\nannounce: ok\n")
+        .stderr(predicate::str::contains(
+            "==> announce: echo built\nbuilt\n",
+        ));
+    assert_eq!(
+        fs::read_to_string(tmp.path().join("dist/deck.txt")).unwrap(),
+        "# Deck\nQuarterly\n"
+    );
+
+    notarai()
+        .args(["pipeline", "run", ".notarai/deck.spec.yaml"])
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stdout("build: up to date\nannounce: ok\n");
+
+    fs::write(tmp.path().join("slides/deck.md"), "# Deck v2\n").unwrap();
+    notarai()
+        .args(["pipeline", "run", ".notarai/deck.spec.yaml", "--dry-run"])
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("build: would run ("))
+        .stderr("");
+}
+
+#[test]
+fn pipeline_run_single_step_json() {
+    let tmp = project(BUILD);
+    let output = notarai()
+        .args([
+            "pipeline",
+            "run",
+            ".notarai/deck.spec.yaml",
+            "--step",
+            "announce",
+            "--json",
+        ])
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let report: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(report["spec_path"], ".notarai/deck.spec.yaml");
+    assert_eq!(report["steps"].as_array().unwrap().len(), 1);
+    assert_eq!(report["steps"][0]["name"], "announce");
+    assert_eq!(report["steps"][0]["outcome"], "ran");
+}
+
+#[test]
+fn pipeline_run_fails_when_outputs_are_missing() {
+    let tmp = project(
+        "    - name: render\n      command: 'true'\n      output: 'dist/*.pdf'\n    \
+         - name: publish\n      command: 'echo never'\n",
+    );
+    notarai()
+        .args(["pipeline", "run", ".notarai/deck.spec.yaml"])
+        .current_dir(tmp.path())
+        .assert()
+        .code(1)
+        .stdout("render: FAILED (did not produce its declared output 'dist/*.pdf')\n");
+}
+
+#[test]
+fn pipeline_run_errors() {
+    let tmp = project(BUILD);
+    notarai()
+        .args([
+            "pipeline",
+            "run",
+            ".notarai/deck.spec.yaml",
+            "--step",
+            "nope",
+        ])
+        .current_dir(tmp.path())
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("has no pipeline step 'nope'"));
+
+    fs::write(
+        tmp.path().join(".notarai/empty.spec.yaml"),
+        "schema_version: '0.7'\nintent: 'x'\nartifacts:\n  code:\n    - path: 'src/**'\n",
+    )
+    .unwrap();
+    notarai()
+        .args(["pipeline", "run", ".notarai/empty.spec.yaml"])
+        .current_dir(tmp.path())
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("has no pipeline steps"));
+}
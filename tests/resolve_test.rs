@@ -0,0 +1,125 @@
+use assert_cmd::cargo_bin_cmd;
+use predicates::prelude::*;
+use std::fs;
+use tempfile::TempDir;
+
+fn notarai() -> assert_cmd::Command {
+    cargo_bin_cmd!("notarai")
+}
+
+const DECK_SPEC: &str = r#"schema_version: '0.7'
+intent: 'Quarterly update deck'
+domain: presentation
+variants_resolved: true
+audience:
+  role: 'engineers'
+  tone: 'casual'
+constraints:
+  - 'No more than 20 slides'
+behaviors:
+  - name: opens_with_summary
+    given: 'the deck is presented'
+    then: 'the first slide summarizes the quarter'
+artifacts:
+  slides:
+    - path: 'deck.pptx'
+variants:
+  - id: investors
+    overrides:
+      audience.role: 'Series B investors'
+      +constraints: ['Every figure cites its source']
+      audience:
+        tone: null
+  - id: broken
+    overrides:
+      audience.rol: 'typo'
+      intent: null
+"#;
+
+fn project(spec: &str) -> TempDir {
+    let tmp = TempDir::new().unwrap();
+    fs::create_dir_all(tmp.path().join(".notarai")).unwrap();
+    fs::write(tmp.path().join(".notarai/deck.spec.yaml"), spec).unwrap();
+    tmp
+}
+
+#[test]
+fn prints_effective_spec_for_variant() {
+    let tmp = project(DECK_SPEC);
+    notarai()
+        .args([
+            "resolve",
+            ".notarai/deck.spec.yaml",
+            "--variant",
+            "investors",
+        ])
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("role: Series B investors"))
+        .stdout(predicate::str::contains("- Every figure cites its source"))
+        .stdout(predicate::str::contains("- No more than 20 slides"))
+        .stdout(predicate::str::contains("tone").not())
+        .stdout(predicate::str::contains("variants:").not());
+}
+
+#[test]
+fn reports_unmatched_overrides_and_invalid_result() {
+    let tmp = project(DECK_SPEC);
+    let output = notarai()
+        .args(["resolve", ".notarai/deck.spec.yaml", "--variant", "broken"])
+        .args(["--json"])
+        .current_dir(tmp.path())
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["variant"], "broken");
+    assert_eq!(report["valid"], false);
+    assert_eq!(
+        report["unmatched_overrides"],
+        serde_json::json!(["audience.rol: 'audience.rol' does not exist"])
+    );
+    assert!(report["spec"].get("intent").is_none());
+}
+
+#[test]
+fn declarative_variants_are_not_resolved() {
+    let tmp = project(&DECK_SPEC.replace("variants_resolved: true\n", ""));
+    notarai()
+        .args([
+            "resolve",
+            ".notarai/deck.spec.yaml",
+            "--variant",
+            "investors",
+        ])
+        .current_dir(tmp.path())
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("variants_resolved"));
+}
+
+#[test]
+fn unknown_variant_is_an_error() {
+    let tmp = project(DECK_SPEC);
+    notarai()
+        .args(["resolve", ".notarai/deck.spec.yaml", "--variant", "nope"])
+        .current_dir(tmp.path())
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("no variant 'nope'"));
+}
+
+#[test]
+fn mcp_tool_resolves_variant() {
+    let tmp = project(DECK_SPEC);
+    let msg = r#"{"jsonrpc":"2.0","id":1,"method":"tools/call","params":{"name":"resolve_variant","arguments":{"spec_path":".notarai/deck.spec.yaml","variant":"investors"}}}"#;
+    notarai()
+        .arg("mcp")
+        .write_stdin(format!("{msg}\n"))
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Series B investors"))
+        .stdout(predicate::str::contains(r#"\"valid\":true"#));
+}
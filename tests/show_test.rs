@@ -0,0 +1,131 @@
+use assert_cmd::cargo_bin_cmd;
+use predicates::prelude::*;
+use std::fs;
+use tempfile::TempDir;
+
+fn notarai() -> assert_cmd::Command {
+    cargo_bin_cmd!("notarai")
+}
+
+const SYSTEM_SPEC: &str = r#"schema_version: '0.7'
+intent: 'System'
+behaviors:
+  - name: composes
+    given: 'subsystems exist'
+    then: 'they are composed'
+invariants:
+  - 'System-only invariant'
+subsystems:
+  - $ref: './api.spec.yaml'
+applies:
+  - $ref: './_shared/security.spec.yaml'
+artifacts:
+  docs:
+    - path: 'README.md'
+"#;
+
+const API_SPEC: &str = r#"schema_version: '0.7'
+intent: 'API'
+behaviors:
+  - name: serves
+    given: 'a request'
+    then: 'a response is returned'
+constraints:
+  - 'Responses are JSON'
+artifacts:
+  code:
+    - path: 'api.txt'
+"#;
+
+const SECURITY_SPEC: &str = r#"schema_version: '0.7'
+intent: 'Security'
+behaviors:
+  - name: audits
+    given: 'a change'
+    then: 'it is audited'
+invariants:
+  - 'Secrets never appear in logs'
+artifacts:
+  docs:
+    - path: 'SECURITY.md'
+"#;
+
+fn project() -> TempDir {
+    let tmp = TempDir::new().unwrap();
+    let notarai_dir = tmp.path().join(".notarai");
+    fs::create_dir_all(notarai_dir.join("_shared")).unwrap();
+    fs::write(notarai_dir.join("system.spec.yaml"), SYSTEM_SPEC).unwrap();
+    fs::write(notarai_dir.join("api.spec.yaml"), API_SPEC).unwrap();
+    fs::write(
+        notarai_dir.join("_shared/security.spec.yaml"),
+        SECURITY_SPEC,
+    )
+    .unwrap();
+    tmp
+}
+
+#[test]
+fn show_lists_own_rules_only() {
+    let tmp = project();
+    notarai()
+        .args(["show", ".notarai/api.spec.yaml"])
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("- serves"))
+        .stdout(predicate::str::contains("- Responses are JSON"))
+        .stdout(predicate::str::contains("Secrets never appear in logs").not());
+}
+
+#[test]
+fn show_effective_includes_rules_applied_through_the_system_spec() {
+    let tmp = project();
+    notarai()
+        .args(["show", ".notarai/api.spec.yaml", "--effective"])
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "- Secrets never appear in logs  [.notarai/_shared/security.spec.yaml]",
+        ))
+        .stdout(predicate::str::contains(
+            "- .notarai/_shared/security.spec.yaml (via .notarai/system.spec.yaml)",
+        ))
+        .stdout(predicate::str::contains("System-only invariant").not());
+}
+
+#[test]
+fn show_effective_json_reports_sources() {
+    let tmp = project();
+    let output = notarai()
+        .args(["show", ".notarai/api.spec.yaml", "--effective", "--json"])
+        .current_dir(tmp.path())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["spec"]["intent"], "API");
+    assert_eq!(
+        report["effective"]["invariants"],
+        serde_json::json!([{
+            "rule": "Secrets never appear in logs",
+            "source": ".notarai/_shared/security.spec.yaml",
+        }])
+    );
+    assert_eq!(
+        report["effective"]["constraints"][0]["source"],
+        ".notarai/api.spec.yaml"
+    );
+}
+
+#[test]
+fn show_effective_fails_on_dangling_applies() {
+    let tmp = project();
+    fs::remove_file(tmp.path().join(".notarai/_shared/security.spec.yaml")).unwrap();
+    notarai()
+        .args(["show", ".notarai/api.spec.yaml", "--effective"])
+        .current_dir(tmp.path())
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("does not resolve to a spec"));
+}
@@ -102,6 +102,7 @@ fn test_state_snapshot_and_show() {
     let root = tmp.path();
     setup_git_repo(root);
     fs::create_dir_all(root.join(".notarai")).unwrap();
+    fs::write(root.join(".notarai/cli.spec.yaml"), "intent: 'cli'\n").unwrap();
     fs::write(root.join("src/main.rs"), "fn main() {}").unwrap_or_else(|_| {
         fs::create_dir_all(root.join("src")).unwrap();
         fs::write(root.join("src/main.rs"), "fn main() {}").unwrap();
@@ -109,7 +110,7 @@ fn test_state_snapshot_and_show() {
     git_commit_all(root, "initial");
 
     // Seed the cache by calling mark_reconciled via MCP, then snapshot.
-    let seed_msg = r#"{"jsonrpc":"2.0","id":0,"method":"tools/call","params":{"name":"mark_reconciled","arguments":{"files":["src/main.rs"]}}}"#;
+    let seed_msg = r#"{"jsonrpc":"2.0","id":0,"method":"tools/call","params":{"name":"mark_reconciled","arguments":{"spec_path":".notarai/cli.spec.yaml","files":["src/main.rs"]}}}"#;
     notarai()
         .arg("mcp")
         .write_stdin(format!("{seed_msg}\n"))
@@ -160,7 +161,265 @@ fn test_state_snapshot_produces_valid_json() {
 
     let content = fs::read_to_string(root.join(".notarai/reconciliation_state.json")).unwrap();
     let parsed: serde_json::Value = serde_json::from_str(&content).unwrap();
-    assert_eq!(parsed["schema_version"], "1");
-    assert!(parsed["file_fingerprints"].is_object());
-    assert!(parsed["spec_fingerprints"].is_object());
+    assert_eq!(parsed["schema_version"], "2");
+    assert!(parsed["specs"].is_object());
+    let timestamp = parsed["last_reconciliation"]["timestamp"].as_str().unwrap();
+    assert!(
+        timestamp.len() == 20 && timestamp.as_bytes()[10] == b'T' && timestamp.ends_with('Z'),
+        "timestamp must be ISO 8601 UTC: {timestamp}"
+    );
+}
+
+// -- notarai state diff --------------------------------------------------------
+
+#[test]
+fn test_state_diff_without_state_exits_2() {
+    let tmp = TempDir::new().unwrap();
+    fs::create_dir_all(tmp.path().join(".notarai")).unwrap();
+
+    notarai()
+        .args(["state", "diff"])
+        .current_dir(tmp.path())
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("no reconciliation state found"));
+}
+
+#[test]
+fn test_state_diff_reports_drift_grouped_by_spec() {
+    let tmp = TempDir::new().unwrap();
+    let root = tmp.path();
+    fs::create_dir_all(root.join(".notarai")).unwrap();
+    fs::create_dir_all(root.join("src")).unwrap();
+    fs::write(
+        root.join(".notarai/cli.spec.yaml"),
+        "schema_version: '0.8'\nintent: 'cli'\nartifacts:\n  code:\n    - path: 'src/*.rs'\n",
+    )
+    .unwrap();
+    fs::write(
+        root.join(".notarai/normalize.yaml"),
+        "categories:\n  code: text\n",
+    )
+    .unwrap();
+    fs::write(root.join("src/main.rs"), "fn main() {}\n").unwrap();
+    fs::write(root.join("src/lib.rs"), "pub fn lib() {}\n").unwrap();
+
+    let seed_msg = r#"{"jsonrpc":"2.0","id":0,"method":"tools/call","params":{"name":"mark_reconciled","arguments":{"spec_path":".notarai/cli.spec.yaml","files":["src/main.rs","src/lib.rs"]}}}"#;
+    notarai()
+        .arg("mcp")
+        .write_stdin(format!("{seed_msg}\n"))
+        .current_dir(root)
+        .assert()
+        .success();
+    notarai()
+        .args(["state", "snapshot"])
+        .current_dir(root)
+        .assert()
+        .success();
+
+    // No git history needed: a clean tree reports no drift.
+    notarai()
+        .args(["state", "diff"])
+        .current_dir(root)
+        .assert()
+        .code(0)
+        .stdout(predicate::str::contains("No drift since baseline."));
+
+    // Whitespace-only edits are not drift under the opted-in `text` normalizer.
+    fs::write(root.join("src/main.rs"), "fn main() {}  \r\n").unwrap();
+    notarai()
+        .args(["state", "diff"])
+        .current_dir(root)
+        .assert()
+        .code(0);
+
+    fs::write(root.join("src/main.rs"), "fn main() { run(); }\n").unwrap();
+    fs::write(root.join("src/new.rs"), "pub fn new() {}\n").unwrap();
+    fs::remove_file(root.join("src/lib.rs")).unwrap();
+
+    notarai()
+        .args(["state", "diff"])
+        .current_dir(root)
+        .assert()
+        .code(1)
+        .stdout(predicate::str::contains(".notarai/cli.spec.yaml"))
+        .stdout(predicate::str::contains("added:    src/new.rs"))
+        .stdout(predicate::str::contains("modified: src/main.rs"))
+        .stdout(predicate::str::contains("removed:  src/lib.rs"));
+
+    let output = notarai()
+        .args(["state", "diff", "--json"])
+        .current_dir(root)
+        .assert()
+        .code(1)
+        .get_output()
+        .stdout
+        .clone();
+    let report: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(report["clean"], false);
+    assert_eq!(report["specs"][0]["spec_changed"], false);
+    assert_eq!(report["specs"][0]["modified"][0], "src/main.rs");
+}
+
+// -- notarai state merge -------------------------------------------------------
+
+fn state_json(at: &str, specs: &[(&str, &str, &str)]) -> String {
+    let specs: serde_json::Map<String, serde_json::Value> = specs
+        .iter()
+        .map(|(path, fingerprint, reconciled_at)| {
+            let spec = serde_json::json!({
+                "fingerprint": fingerprint,
+                "reconciled_at": reconciled_at,
+                "artifacts": {},
+            });
+            (path.to_string(), spec)
+        })
+        .collect();
+    serde_json::json!({
+        "schema_version": "2",
+        "last_reconciliation": {"timestamp": at, "git_hash": null, "branch": null},
+        "specs": specs,
+    })
+    .to_string()
+}
+
+const T0: &str = "2026-01-01T00:00:00Z";
+const T1: &str = "2026-01-02T00:00:00Z";
+const T2: &str = "2026-01-03T00:00:00Z";
+
+#[test]
+fn test_state_merge_keeps_specs_reconciled_on_each_side() {
+    let tmp = TempDir::new().unwrap();
+    let dir = tmp.path();
+    let base = state_json(T0, &[("cli", "c", T0), ("docs", "d", T0)]);
+    fs::write(dir.join("base.json"), &base).unwrap();
+    fs::write(
+        dir.join("ours.json"),
+        state_json(T1, &[("cli", "c", T1), ("docs", "d", T0)]),
+    )
+    .unwrap();
+    fs::write(
+        dir.join("theirs.json"),
+        state_json(T2, &[("cli", "c", T0), ("docs", "d", T2)]),
+    )
+    .unwrap();
+
+    notarai()
+        .args(["state", "merge", "base.json", "ours.json", "theirs.json"])
+        .current_dir(dir)
+        .assert()
+        .code(0);
+
+    let merged: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(dir.join("ours.json")).unwrap()).unwrap();
+    assert_eq!(merged["specs"]["cli"]["reconciled_at"], T1);
+    assert_eq!(merged["specs"]["docs"]["reconciled_at"], T2);
+    assert_eq!(merged["last_reconciliation"]["timestamp"], T2);
+}
+
+#[test]
+fn test_state_merge_flags_conflicting_spec_revisions() {
+    let tmp = TempDir::new().unwrap();
+    let dir = tmp.path();
+    // An empty ancestor means the file was added on both sides.
+    fs::write(dir.join("base.json"), "").unwrap();
+    fs::write(dir.join("ours.json"), state_json(T1, &[("cli", "c1", T1)])).unwrap();
+    fs::write(
+        dir.join("theirs.json"),
+        state_json(T2, &[("cli", "c2", T2)]),
+    )
+    .unwrap();
+
+    notarai()
+        .args(["state", "merge", "base.json", "ours.json", "theirs.json"])
+        .current_dir(dir)
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "conflict: cli: reconciled against different revisions of the spec",
+        ));
+
+    let merged: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(dir.join("ours.json")).unwrap()).unwrap();
+    assert_eq!(merged["specs"]["cli"]["fingerprint"], "c2");
+}
+
+#[test]
+fn test_state_merge_driver_resolves_git_merge() {
+    let tmp = TempDir::new().unwrap();
+    let root = tmp.path();
+    setup_git_repo(root);
+    notarai().arg("init").current_dir(root).assert().success();
+    let state = root.join(".notarai/reconciliation_state.json");
+    fs::write(
+        &state,
+        state_json(T0, &[("cli", "c", T0), ("docs", "d", T0)]),
+    )
+    .unwrap();
+    git_commit_all(root, "base");
+
+    let git = |args: &[&str]| {
+        let bin_dir = Path::new(env!("CARGO_BIN_EXE_notarai")).parent().unwrap();
+        let path = format!("{}:{}", bin_dir.display(), std::env::var("PATH").unwrap());
+        std::process::Command::new("git")
+            .args(args)
+            .env("PATH", path)
+            .current_dir(root)
+            .output()
+            .unwrap()
+    };
+    git(&["checkout", "-q", "-b", "docs-work"]);
+    fs::write(
+        &state,
+        state_json(T2, &[("cli", "c", T0), ("docs", "d", T2)]),
+    )
+    .unwrap();
+    git_commit_all(root, "reconcile docs");
+    git(&["checkout", "-q", "-"]);
+    fs::write(
+        &state,
+        state_json(T1, &[("cli", "c", T1), ("docs", "d", T0)]),
+    )
+    .unwrap();
+    git_commit_all(root, "reconcile cli");
+
+    let merge = git(&["merge", "--no-edit", "docs-work"]);
+    assert!(
+        merge.status.success(),
+        "{}",
+        String::from_utf8_lossy(&merge.stdout)
+    );
+    let merged: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&state).unwrap()).unwrap();
+    assert_eq!(merged["specs"]["cli"]["reconciled_at"], T1);
+    assert_eq!(merged["specs"]["docs"]["reconciled_at"], T2);
+}
+
+#[test]
+fn test_snapshot_refuses_to_replace_a_newer_state_file() {
+    let tmp = TempDir::new().unwrap();
+    let root = tmp.path();
+    fs::create_dir_all(root.join(".notarai")).unwrap();
+    let newer = r#"{"schema_version": "3", "future_field": true}"#;
+    fs::write(root.join(".notarai/reconciliation_state.json"), newer).unwrap();
+
+    notarai()
+        .args(["state", "snapshot"])
+        .current_dir(root)
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("newer than this notarai supports"))
+        .stderr(predicate::str::contains("--force"));
+    assert_eq!(
+        fs::read_to_string(root.join(".notarai/reconciliation_state.json")).unwrap(),
+        newer
+    );
+
+    notarai()
+        .args(["state", "snapshot", "--force"])
+        .current_dir(root)
+        .assert()
+        .success();
+    let state = fs::read_to_string(root.join(".notarai/reconciliation_state.json")).unwrap();
+    assert!(state.contains(r#""schema_version": "2""#), "{state}");
 }
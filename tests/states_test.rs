@@ -0,0 +1,138 @@
+use assert_cmd::cargo_bin_cmd;
+use predicates::prelude::*;
+use std::fs;
+use tempfile::TempDir;
+
+fn notarai() -> assert_cmd::Command {
+    cargo_bin_cmd!("notarai")
+}
+
+fn project(states: &str) -> TempDir {
+    let tmp = TempDir::new().unwrap();
+    let dir = tmp.path().join(".notarai");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(
+        dir.join("game.spec.yaml"),
+        format!(
+            "schema_version: '0.8'\nintent: 'x'\nbehaviors:\n  \
+             - name: start\n    given: 'g'\n    then: 't'\n    \
+             state_transition: {{ from: menu, to: playing }}\n\
+             artifacts:\n  code:\n    - path: 'src/**'\n{states}"
+        ),
+    )
+    .unwrap();
+    fs::write(
+        dir.join("cli.spec.yaml"),
+        "schema_version: '0.7'\nintent: 'x'\nartifacts:\n  code:\n    - path: 'src/**'\n",
+    )
+    .unwrap();
+    tmp
+}
+
+const GAME: &str = "states:\n  initial: menu\n  definitions:\n    \
+    - id: menu\n      transitions:\n        - to: playing\n          on: start\n    \
+    - id: playing\n      transitions:\n        \
+    - to: over\n          on: die\n          guard: 'health <= 0'\n    \
+    - id: over\n      terminal: true\n";
+
+#[test]
+fn states_check_passes_clean_machines() {
+    let tmp = project(GAME);
+    notarai()
+        .args(["states", "check"])
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stdout("PASS .notarai/game.spec.yaml\n");
+}
+
+#[test]
+fn states_check_reports_findings() {
+    let tmp = project(
+        "states:\n  initial: menu\n  definitions:\n    \
+         - id: menu\n      transitions:\n        \
+         - to: paused\n          on: pause\n        - to: menu\n          on: pause\n    \
+         - id: paused\n    - id: credits\n      terminal: true\n",
+    );
+    notarai()
+        .args(["states", "check", ".notarai/game.spec.yaml"])
+        .current_dir(tmp.path())
+        .assert()
+        .code(1)
+        .stdout(predicate::str::contains("FAIL .notarai/game.spec.yaml"))
+        .stdout(predicate::str::contains(
+            "[credits] 'credits' cannot be reached from the initial state 'menu'",
+        ))
+        .stdout(predicate::str::contains(
+            "[paused] 'paused' has no outgoing transitions and is not marked terminal",
+        ))
+        .stdout(predicate::str::contains(
+            "[menu] 2 transitions on 'pause' are not distinguished by distinct guards",
+        ))
+        .stdout(predicate::str::contains(
+            "[start] state_transition.to 'playing' is not a defined state",
+        ));
+}
+
+#[test]
+fn states_check_json() {
+    let tmp = project("");
+    let output = notarai()
+        .args(["states", "check", "--json"])
+        .current_dir(tmp.path())
+        .assert()
+        .code(1)
+        .get_output()
+        .stdout
+        .clone();
+    let report: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(report.as_array().unwrap().len(), 1);
+    assert_eq!(report[0]["spec_path"], ".notarai/game.spec.yaml");
+    assert_eq!(report[0]["findings"][0]["kind"], "undefined_behavior_state");
+    assert_eq!(report[0]["findings"][0]["subject"], "start");
+}
+
+#[test]
+fn states_graph_formats() {
+    let tmp = project(GAME);
+    notarai()
+        .args(["states", "graph", ".notarai/game.spec.yaml"])
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("stateDiagram-v2\n"))
+        .stdout(predicate::str::contains(
+            "  s1 --> s2 : die [health <= 0]\n",
+        ));
+
+    notarai()
+        .args([
+            "states",
+            "graph",
+            ".notarai/game.spec.yaml",
+            "--format",
+            "dot",
+        ])
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("digraph states {"))
+        .stdout(predicate::str::contains("\"over\" [shape=doublecircle];"));
+
+    notarai()
+        .args(["states", "graph", ".notarai/cli.spec.yaml"])
+        .current_dir(tmp.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("has no states block"));
+}
+
+#[test]
+fn terminal_states_validate() {
+    let tmp = project(GAME);
+    notarai()
+        .args(["validate", ".notarai/game.spec.yaml"])
+        .current_dir(tmp.path())
+        .assert()
+        .success();
+}
@@ -0,0 +1,187 @@
+use assert_cmd::cargo_bin_cmd;
+use predicates::prelude::*;
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn notarai() -> assert_cmd::Command {
+    cargo_bin_cmd!("notarai")
+}
+
+fn spec(glob: &str, extra: &str) -> String {
+    format!(
+        "schema_version: '0.7'\nintent: 'Governs {glob}'\n{extra}behaviors:\n  \
+         - name: tracks\n    given: 'files exist'\n    then: 'they are tracked'\n\
+         artifacts:\n  code:\n    - path: '{glob}'\n"
+    )
+}
+
+fn mark(root: &Path, spec_path: &str, files: &[&str]) {
+    notarai()
+        .args(["cache", "mark", "--spec", spec_path])
+        .args(files)
+        .current_dir(root)
+        .assert()
+        .success();
+}
+
+/// One spec in each status: `clean` and `drift` are marked, then `drift.txt`
+/// is edited and `edited.spec.yaml` rewritten; `fresh` is never marked and
+/// `broken` does not validate.
+fn project_with_every_status() -> TempDir {
+    let tmp = TempDir::new().unwrap();
+    let root = tmp.path();
+    fs::create_dir_all(root.join(".notarai")).unwrap();
+    for name in ["clean", "drift", "edited", "fresh"] {
+        fs::write(root.join(format!("{name}.txt")), name).unwrap();
+    }
+    fs::write(
+        root.join(".notarai/clean.spec.yaml"),
+        spec("clean.txt", "tier: registered\n"),
+    )
+    .unwrap();
+    fs::write(
+        root.join(".notarai/drift.spec.yaml"),
+        spec("drift.txt", "domain: report\n"),
+    )
+    .unwrap();
+    fs::write(
+        root.join(".notarai/edited.spec.yaml"),
+        spec("edited.txt", ""),
+    )
+    .unwrap();
+    fs::write(root.join(".notarai/fresh.spec.yaml"), spec("fresh.txt", "")).unwrap();
+    fs::write(
+        root.join(".notarai/broken.spec.yaml"),
+        "intent: 'no version'\n",
+    )
+    .unwrap();
+
+    mark(root, ".notarai/clean.spec.yaml", &["clean.txt"]);
+    mark(root, ".notarai/drift.spec.yaml", &["drift.txt"]);
+    mark(root, ".notarai/edited.spec.yaml", &["edited.txt"]);
+    fs::write(root.join("drift.txt"), "drift, edited").unwrap();
+    fs::write(
+        root.join(".notarai/edited.spec.yaml"),
+        spec("edited.txt", "tier: derived\n"),
+    )
+    .unwrap();
+    tmp
+}
+
+#[test]
+fn status_lists_every_spec_with_its_status() {
+    let tmp = project_with_every_status();
+    notarai()
+        .arg("status")
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stdout(
+            predicate::str::is_match(
+                r"clean\.spec\.yaml\s+registered\s+software\s+reconciled\s+0/1\s+just now\s+-",
+            )
+            .unwrap(),
+        )
+        .stdout(
+            predicate::str::is_match(
+                r"drift\.spec\.yaml\s+full\s+report\s+artifacts changed\s+1/1",
+            )
+            .unwrap(),
+        )
+        .stdout(
+            predicate::str::is_match(r"edited\.spec\.yaml\s+derived\s+software\s+spec changed")
+                .unwrap(),
+        )
+        .stdout(
+            predicate::str::is_match(
+                r"fresh\.spec\.yaml\s+full\s+software\s+never reconciled\s+-\s+-\s+-",
+            )
+            .unwrap(),
+        )
+        .stdout(
+            predicate::str::is_match(r"broken\.spec\.yaml\s+full\s+software\s+invalid").unwrap(),
+        )
+        .stdout(predicate::str::contains("  error: "));
+}
+
+#[test]
+fn status_json_reports_changed_files() {
+    let tmp = project_with_every_status();
+    let output = notarai()
+        .args(["status", "--json"])
+        .current_dir(tmp.path())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let rows: Vec<serde_json::Value> = serde_json::from_slice(&output.stdout).unwrap();
+    let row = |name: &str| {
+        rows.iter()
+            .find(|r| r["spec_path"] == format!(".notarai/{name}.spec.yaml"))
+            .unwrap_or_else(|| panic!("no row for {name}"))
+    };
+
+    assert_eq!(rows.len(), 5);
+    assert_eq!(row("clean")["status"], "reconciled");
+    assert_eq!(row("drift")["status"], "artifacts_changed");
+    assert_eq!(
+        row("drift")["changed_files"],
+        serde_json::json!(["drift.txt"])
+    );
+    assert_eq!(row("edited")["status"], "spec_changed");
+    assert_eq!(row("fresh")["status"], "never_reconciled");
+    assert!(row("fresh")["reconciled_at"].is_null());
+    assert_eq!(row("broken")["status"], "invalid");
+    assert!(!row("broken")["errors"].as_array().unwrap().is_empty());
+}
+
+#[test]
+fn status_takes_commit_and_records_from_state_file() {
+    let tmp = project_with_every_status();
+    let root = tmp.path();
+    for args in [
+        vec!["init", "-q"],
+        vec!["config", "user.email", "test@notarai.dev"],
+        vec!["config", "user.name", "NotarAI Test"],
+        vec!["config", "commit.gpgsign", "false"],
+        vec!["add", "."],
+        vec!["commit", "-qm", "initial"],
+    ] {
+        std::process::Command::new("git")
+            .args(&args)
+            .current_dir(root)
+            .output()
+            .unwrap();
+    }
+    notarai()
+        .args(["state", "snapshot"])
+        .current_dir(root)
+        .assert()
+        .success();
+    // A fresh clone has the state file but no local cache.
+    fs::remove_dir_all(root.join(".notarai/.cache")).unwrap();
+
+    let output = notarai()
+        .args(["status", "--json"])
+        .current_dir(root)
+        .output()
+        .unwrap();
+    let rows: Vec<serde_json::Value> = serde_json::from_slice(&output.stdout).unwrap();
+    let clean = rows
+        .iter()
+        .find(|r| r["spec_path"] == ".notarai/clean.spec.yaml")
+        .unwrap();
+    assert_eq!(clean["status"], "reconciled");
+    assert_eq!(clean["commit"].as_str().unwrap().len(), 40);
+}
+
+#[test]
+fn status_without_specs() {
+    let tmp = TempDir::new().unwrap();
+    notarai()
+        .arg("status")
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No specs found in .notarai/"));
+}